@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS order_items;
+DROP TABLE IF EXISTS orders;
+DROP TABLE IF EXISTS cart_items;
+DROP TABLE IF EXISTS favorites;
+DROP TABLE IF EXISTS products;
+DROP TABLE IF EXISTS users;
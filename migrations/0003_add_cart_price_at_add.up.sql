@@ -0,0 +1,10 @@
+ALTER TABLE cart_items
+ADD COLUMN IF NOT EXISTS price_at_add BIGINT;
+
+UPDATE cart_items ci
+SET price_at_add = p.price
+FROM products p
+WHERE p.id = ci.product_id AND ci.price_at_add IS NULL;
+
+ALTER TABLE cart_items
+ALTER COLUMN price_at_add SET NOT NULL;
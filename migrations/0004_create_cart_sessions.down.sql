@@ -0,0 +1,12 @@
+-- Guest carts have no user to fall back to
+DELETE FROM cart_items WHERE user_id IS NULL;
+
+ALTER TABLE cart_items
+DROP CONSTRAINT IF EXISTS cart_items_owner_check,
+DROP CONSTRAINT IF EXISTS cart_items_session_token_product_id_key,
+DROP COLUMN IF EXISTS session_token;
+
+ALTER TABLE cart_items
+ALTER COLUMN user_id SET NOT NULL;
+
+DROP TABLE IF EXISTS cart_sessions;
@@ -0,0 +1,19 @@
+-- Guest cart sessions
+CREATE TABLE IF NOT EXISTS cart_sessions (
+    token uuid PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_cart_sessions_created_at ON cart_sessions(created_at);
+
+ALTER TABLE cart_items
+ALTER COLUMN user_id DROP NOT NULL;
+
+ALTER TABLE cart_items
+ADD COLUMN IF NOT EXISTS session_token uuid REFERENCES cart_sessions(token) ON DELETE CASCADE;
+
+ALTER TABLE cart_items
+ADD CONSTRAINT cart_items_session_token_product_id_key UNIQUE (session_token, product_id);
+
+ALTER TABLE cart_items
+ADD CONSTRAINT cart_items_owner_check CHECK (user_id IS NOT NULL OR session_token IS NOT NULL);
@@ -0,0 +1,12 @@
+-- Categories
+CREATE TABLE IF NOT EXISTS categories (
+    id uuid PRIMARY KEY,
+    name TEXT NOT NULL,
+    slug TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE products
+ADD COLUMN IF NOT EXISTS category_id uuid REFERENCES categories(id);
+
+CREATE INDEX IF NOT EXISTS idx_products_category_id ON products(category_id);
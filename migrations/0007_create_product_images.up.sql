@@ -0,0 +1,12 @@
+-- Product images
+CREATE TABLE IF NOT EXISTS product_images (
+    id uuid PRIMARY KEY,
+    product_id uuid NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    storage_key TEXT NOT NULL,
+    alt TEXT,
+    position INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_product_images_product_id ON product_images(product_id, position);
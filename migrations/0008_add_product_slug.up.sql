@@ -0,0 +1,28 @@
+-- Product slugs, backfilled from the name with a numeric suffix on collision
+ALTER TABLE products
+ADD COLUMN IF NOT EXISTS slug TEXT;
+
+WITH base AS (
+    SELECT
+        id,
+        created_at,
+        COALESCE(
+            NULLIF(trim(BOTH '-' FROM regexp_replace(lower(name), '[^a-z0-9]+', '-', 'g')), ''),
+            'product'
+        ) AS slug
+    FROM products
+    WHERE slug IS NULL
+),
+numbered AS (
+    SELECT id, slug, row_number() OVER (PARTITION BY slug ORDER BY created_at, id) AS n
+    FROM base
+)
+UPDATE products p
+SET slug = CASE WHEN numbered.n = 1 THEN numbered.slug ELSE numbered.slug || '-' || numbered.n END
+FROM numbered
+WHERE p.id = numbered.id;
+
+ALTER TABLE products
+ALTER COLUMN slug SET NOT NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_products_slug ON products(slug);
@@ -0,0 +1,6 @@
+-- Optional stock keeping unit, unique when present
+ALTER TABLE products
+ADD COLUMN IF NOT EXISTS sku TEXT;
+
+ALTER TABLE products
+ADD CONSTRAINT products_sku_key UNIQUE (sku);
@@ -0,0 +1,4 @@
+ALTER TABLE products
+DROP CONSTRAINT IF EXISTS products_price_non_negative,
+DROP CONSTRAINT IF EXISTS products_stock_non_negative,
+DROP CONSTRAINT IF EXISTS products_name_not_blank;
@@ -0,0 +1,5 @@
+-- Second line of defense behind the handler validation
+ALTER TABLE products
+ADD CONSTRAINT products_price_non_negative CHECK (price >= 0),
+ADD CONSTRAINT products_stock_non_negative CHECK (stock >= 0),
+ADD CONSTRAINT products_name_not_blank CHECK (btrim(name) <> '' AND char_length(name) <= 255);
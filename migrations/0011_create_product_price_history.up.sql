@@ -0,0 +1,16 @@
+-- Every price a product has had; old_price is NULL for the initial row
+CREATE TABLE IF NOT EXISTS product_price_history (
+    id uuid PRIMARY KEY,
+    product_id uuid NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    old_price BIGINT,
+    new_price BIGINT NOT NULL,
+    changed_by uuid REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_product_price_history_product_id
+ON product_price_history(product_id, created_at);
+
+INSERT INTO product_price_history (id, product_id, old_price, new_price, created_at)
+SELECT gen_random_uuid(), id, NULL, price, created_at
+FROM products;
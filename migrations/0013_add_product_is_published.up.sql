@@ -0,0 +1,3 @@
+-- Unpublished products are hidden from shoppers; existing rows stay live
+ALTER TABLE products
+ADD COLUMN IF NOT EXISTS is_published BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,4 @@
+ALTER TABLE order_items
+DROP CONSTRAINT IF EXISTS order_items_product_id_fkey,
+ADD CONSTRAINT order_items_product_id_fkey
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE;
@@ -0,0 +1,5 @@
+-- Deleting a product must not silently erase it from order history
+ALTER TABLE order_items
+DROP CONSTRAINT IF EXISTS order_items_product_id_fkey,
+ADD CONSTRAINT order_items_product_id_fkey
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE RESTRICT;
@@ -0,0 +1,7 @@
+-- Which request an audit entry came from; entries written before this stay NULL
+ALTER TABLE audit_log
+ADD COLUMN IF NOT EXISTS request_id TEXT,
+ADD COLUMN IF NOT EXISTS ip_address TEXT,
+ADD COLUMN IF NOT EXISTS user_agent TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_request_id ON audit_log(request_id);
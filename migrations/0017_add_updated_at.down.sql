@@ -0,0 +1,10 @@
+DROP TRIGGER IF EXISTS orders_set_updated_at ON orders;
+DROP TRIGGER IF EXISTS cart_items_set_updated_at ON cart_items;
+DROP TRIGGER IF EXISTS products_set_updated_at ON products;
+DROP TRIGGER IF EXISTS users_set_updated_at ON users;
+DROP FUNCTION IF EXISTS set_updated_at();
+
+ALTER TABLE orders DROP COLUMN IF EXISTS updated_at;
+ALTER TABLE cart_items DROP COLUMN IF EXISTS updated_at;
+ALTER TABLE products DROP COLUMN IF EXISTS updated_at;
+ALTER TABLE users DROP COLUMN IF EXISTS updated_at;
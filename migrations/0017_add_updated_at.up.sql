@@ -0,0 +1,29 @@
+-- Last modification time, maintained by trigger so no UPDATE can forget it; existing rows
+-- start at their creation time
+ALTER TABLE users ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE products ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE cart_items ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+UPDATE users SET updated_at = created_at;
+UPDATE products SET updated_at = created_at;
+UPDATE cart_items SET updated_at = created_at;
+UPDATE orders SET updated_at = created_at;
+
+-- Stamps updated_at on every update and keeps created_at as it was
+CREATE OR REPLACE FUNCTION set_updated_at() RETURNS trigger AS $$
+BEGIN
+    NEW.created_at := OLD.created_at;
+    NEW.updated_at := NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER users_set_updated_at BEFORE UPDATE ON users
+FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+CREATE TRIGGER products_set_updated_at BEFORE UPDATE ON products
+FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+CREATE TRIGGER cart_items_set_updated_at BEFORE UPDATE ON cart_items
+FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+CREATE TRIGGER orders_set_updated_at BEFORE UPDATE ON orders
+FOR EACH ROW EXECUTE FUNCTION set_updated_at();
@@ -0,0 +1,3 @@
+-- Only statuses `OrderStatus` knows; anything else would fail to load
+ALTER TABLE orders
+ADD CONSTRAINT orders_status_check CHECK (status IN ('pending', 'paid', 'completed', 'cancelled'));
@@ -0,0 +1,4 @@
+ALTER TABLE orders DROP COLUMN IF EXISTS recipient_phone;
+ALTER TABLE orders DROP COLUMN IF EXISTS recipient_name;
+ALTER TABLE users DROP COLUMN IF EXISTS phone;
+ALTER TABLE users DROP COLUMN IF EXISTS full_name;
@@ -0,0 +1,6 @@
+ALTER TABLE users ADD COLUMN full_name TEXT;
+ALTER TABLE users ADD COLUMN phone TEXT;
+
+-- Who receives the order, copied from the user's profile at checkout
+ALTER TABLE orders ADD COLUMN recipient_name TEXT;
+ALTER TABLE orders ADD COLUMN recipient_phone TEXT;
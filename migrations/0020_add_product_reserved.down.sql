@@ -0,0 +1,6 @@
+ALTER TABLE products DROP CONSTRAINT IF EXISTS products_reserved_check;
+
+-- Back to taking units out of stock at checkout
+UPDATE products SET stock = stock - reserved WHERE reserved > 0;
+
+ALTER TABLE products DROP COLUMN IF EXISTS reserved;
@@ -0,0 +1,19 @@
+-- Units held by pending orders: checkout reserves them, payment takes them out of stock and
+-- cancelling gives them back. What can still be sold is stock - reserved.
+ALTER TABLE products ADD COLUMN reserved INTEGER NOT NULL DEFAULT 0;
+
+-- Pending orders already took their units out of stock; hold them as reservations instead
+WITH held AS (
+    SELECT oi.product_id, SUM(oi.quantity)::int AS quantity
+    FROM order_items oi
+    JOIN orders o ON o.id = oi.order_id
+    WHERE o.status = 'pending'
+    GROUP BY oi.product_id
+)
+UPDATE products p
+SET stock = p.stock + held.quantity, reserved = held.quantity
+FROM held
+WHERE p.id = held.product_id;
+
+ALTER TABLE products
+ADD CONSTRAINT products_reserved_check CHECK (reserved >= 0 AND reserved <= stock);
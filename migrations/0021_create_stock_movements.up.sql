@@ -0,0 +1,20 @@
+-- Every change to a product's stock and why; a product's deltas sum to its stock
+CREATE TABLE IF NOT EXISTS stock_movements (
+    id uuid PRIMARY KEY,
+    product_id uuid NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    delta INTEGER NOT NULL CHECK (delta <> 0),
+    reason TEXT NOT NULL CHECK (reason IN ('restock', 'correction', 'damage', 'sale', 'return')),
+    -- what the change relates to, e.g. the paid order for a sale
+    reference_id uuid,
+    created_by uuid REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_stock_movements_product_id
+ON stock_movements(product_id, created_at);
+
+-- Opening balance, so the ledger of existing products sums to their stock
+INSERT INTO stock_movements (id, product_id, delta, reason)
+SELECT gen_random_uuid(), id, stock, 'correction'
+FROM products
+WHERE stock <> 0;
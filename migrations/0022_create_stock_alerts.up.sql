@@ -0,0 +1,20 @@
+-- Raised by the low-stock scan when a product's available units drop below the threshold.
+-- An alert is resolved once the product is back at the threshold; until then it is the
+-- product's only open alert, acknowledged or not.
+CREATE TABLE IF NOT EXISTS stock_alerts (
+    id uuid PRIMARY KEY,
+    product_id uuid NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    -- stock - reserved, and the threshold it fell below, when raised
+    available INTEGER NOT NULL,
+    threshold INTEGER NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    acknowledged_at TIMESTAMPTZ,
+    acknowledged_by uuid REFERENCES users(id) ON DELETE SET NULL,
+    resolved_at TIMESTAMPTZ
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_stock_alerts_open
+ON stock_alerts(product_id) WHERE resolved_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_stock_alerts_created_at
+ON stock_alerts(created_at);
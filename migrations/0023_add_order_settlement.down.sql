@@ -0,0 +1,3 @@
+ALTER TABLE orders DROP COLUMN IF EXISTS refund_needed;
+ALTER TABLE orders DROP COLUMN IF EXISTS completed_at;
+ALTER TABLE orders DROP COLUMN IF EXISTS paid_at;
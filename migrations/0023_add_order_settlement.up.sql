@@ -0,0 +1,9 @@
+-- When an order was paid and completed, and whether money has to go back to the buyer after
+-- an admin cancelled it post-payment
+ALTER TABLE orders ADD COLUMN paid_at TIMESTAMPTZ;
+ALTER TABLE orders ADD COLUMN completed_at TIMESTAMPTZ;
+ALTER TABLE orders ADD COLUMN refund_needed BOOLEAN NOT NULL DEFAULT false;
+
+-- Best guess for existing orders: their last change
+UPDATE orders SET paid_at = updated_at WHERE status IN ('paid', 'completed');
+UPDATE orders SET completed_at = updated_at WHERE status = 'completed';
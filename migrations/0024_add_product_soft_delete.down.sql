@@ -0,0 +1,16 @@
+-- Deleted products give up a slug or SKU a live one has taken since
+UPDATE products d
+SET slug = d.slug || '-' || left(d.id::text, 8)
+WHERE d.deleted_at IS NOT NULL
+    AND EXISTS (SELECT 1 FROM products p WHERE p.slug = d.slug AND p.id <> d.id AND p.deleted_at IS NULL);
+UPDATE products d
+SET sku = NULL
+WHERE d.deleted_at IS NOT NULL
+    AND EXISTS (SELECT 1 FROM products p WHERE p.sku = d.sku AND p.id <> d.id AND p.deleted_at IS NULL);
+
+DROP INDEX idx_products_slug;
+CREATE UNIQUE INDEX idx_products_slug ON products(slug);
+DROP INDEX products_sku_key;
+ALTER TABLE products ADD CONSTRAINT products_sku_key UNIQUE (sku);
+
+ALTER TABLE products DROP COLUMN IF EXISTS deleted_at;
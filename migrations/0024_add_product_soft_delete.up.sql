@@ -0,0 +1,9 @@
+-- Deleting a product only stamps it, so orders, ledger rows and reviews keep pointing at it and
+-- an admin can restore it
+ALTER TABLE products ADD COLUMN deleted_at TIMESTAMPTZ;
+
+-- Slugs and SKUs only need to be unique among live products
+ALTER TABLE products DROP CONSTRAINT products_sku_key;
+CREATE UNIQUE INDEX products_sku_key ON products(sku) WHERE deleted_at IS NULL;
+DROP INDEX idx_products_slug;
+CREATE UNIQUE INDEX idx_products_slug ON products(slug) WHERE deleted_at IS NULL;
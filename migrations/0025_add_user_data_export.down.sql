@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_audit_log_actor;
+ALTER TABLE users DROP COLUMN IF EXISTS last_data_export_at;
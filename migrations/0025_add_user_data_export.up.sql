@@ -0,0 +1,5 @@
+-- Users may download their data once an hour; the handler claims the slot by stamping this
+ALTER TABLE users ADD COLUMN last_data_export_at TIMESTAMPTZ;
+
+-- The export pages through a user's audit entries
+CREATE INDEX IF NOT EXISTS idx_audit_log_actor ON audit_log(actor_id, created_at, id);
@@ -0,0 +1,9 @@
+-- Orders of deleted accounts have no owner to go back to
+DELETE FROM orders WHERE user_id IS NULL;
+
+ALTER TABLE orders
+DROP CONSTRAINT IF EXISTS orders_user_id_fkey,
+ADD CONSTRAINT orders_user_id_fkey
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE;
+
+ALTER TABLE orders ALTER COLUMN user_id SET NOT NULL;
@@ -0,0 +1,7 @@
+-- Deleting an account keeps its orders for the books, no longer linked to anyone
+ALTER TABLE orders ALTER COLUMN user_id DROP NOT NULL;
+
+ALTER TABLE orders
+DROP CONSTRAINT IF EXISTS orders_user_id_fkey,
+ADD CONSTRAINT orders_user_id_fkey
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL;
@@ -0,0 +1,135 @@
+//! Live events for the back office, pushed to every admin connected to `GET /admin/ws`.
+//!
+//! Handlers publish to [`AdminEvents`] once their transaction has committed. Each socket has
+//! its own receiver, so all of them get every event; one that falls more than
+//! [`CHANNEL_CAPACITY`] events behind skips the ones it missed rather than holding others up.
+
+use std::time::Duration;
+
+use axum::extract::ws::{CloseFrame, Message, WebSocket, close_code};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::{
+    sync::broadcast::{self, Receiver, error::RecvError},
+    time::{Instant, MissedTickBehavior, interval},
+};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::money::{Money, serialize_display};
+
+/// Events buffered per socket before a slow one starts missing them.
+pub const CHANNEL_CAPACITY: usize = 256;
+
+/// How often the server pings a socket; one silent for two intervals is closed.
+pub const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long a freshly opened socket has to send its access token as the first message.
+pub const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A checkout that just went through, for the "new order" toast.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct NewOrderEvent {
+    pub order_id: Uuid,
+    #[schema(value_type = i64, example = 3750)]
+    pub total_amount: Money,
+    /// `total_amount` formatted in the shop's currency
+    #[serde(serialize_with = "serialize_display")]
+    #[schema(value_type = String, example = "$37.50")]
+    pub total_amount_display: Money,
+    #[schema(example = "buyer@example.com")]
+    pub customer_email: String,
+    /// Number of order lines
+    #[schema(example = 2)]
+    pub items: usize,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One message on the admin socket, a JSON object tagged by `type`.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AdminEvent {
+    /// Sent first, once the socket is authenticated and subscribed
+    Ready,
+    NewOrder(NewOrderEvent),
+}
+
+/// The channel admin sockets listen on.
+#[derive(Clone)]
+pub struct AdminEvents(broadcast::Sender<AdminEvent>);
+
+impl Default for AdminEvents {
+    fn default() -> Self {
+        Self(broadcast::channel(CHANNEL_CAPACITY).0)
+    }
+}
+
+impl AdminEvents {
+    /// Sends `event` to every connected admin; without any it goes nowhere.
+    pub fn publish(&self, event: AdminEvent) {
+        let _ = self.0.send(event);
+    }
+
+    pub fn subscribe(&self) -> Receiver<AdminEvent> {
+        self.0.subscribe()
+    }
+
+    /// Whether anyone is listening, to skip building events nobody gets.
+    pub fn has_subscribers(&self) -> bool {
+        self.0.receiver_count() > 0
+    }
+}
+
+/// Forwards `events` to `socket` as JSON text messages until either side goes away, pinging
+/// every [`KEEPALIVE_INTERVAL`] and closing the socket once the client stops answering.
+pub async fn forward(mut socket: WebSocket, mut events: Receiver<AdminEvent>) {
+    if send(&mut socket, &AdminEvent::Ready).await.is_err() {
+        return;
+    }
+    let mut keepalive = interval(KEEPALIVE_INTERVAL);
+    keepalive.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last_heard = Instant::now();
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    if send(&mut socket, &event).await.is_err() {
+                        return;
+                    }
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!(missed, "admin socket fell behind; events skipped");
+                }
+                Err(RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                // pings are answered by the socket itself
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => return,
+                Some(Ok(_)) => last_heard = Instant::now(),
+            },
+            _ = keepalive.tick() => {
+                if last_heard.elapsed() > 2 * KEEPALIVE_INTERVAL {
+                    break;
+                }
+                if socket.send(Message::Ping(Default::default())).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+    close(socket, close_code::AWAY, "closing").await;
+}
+
+/// Closes `socket` with `code` and `reason`, ignoring a client that is already gone.
+pub async fn close(mut socket: WebSocket, code: u16, reason: &str) {
+    let frame = CloseFrame {
+        code,
+        reason: reason.into(),
+    };
+    let _ = socket.send(Message::Close(Some(frame))).await;
+}
+
+async fn send(socket: &mut WebSocket, event: &AdminEvent) -> Result<(), axum::Error> {
+    let json = serde_json::to_string(event).expect("admin events serialize");
+    socket.send(Message::Text(json.into())).await
+}
@@ -0,0 +1,352 @@
+//! Audit trail of who changed what, in the `audit_log` table.
+//!
+//! Handlers call [`AuditLog::record`], which only queues the event; a background writer
+//! inserts queued events in batches, so auditing adds no database round trip to a request and
+//! a failing write never fails one.
+
+use std::{fmt, str::FromStr, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::Json;
+use tokio::sync::{mpsc, oneshot};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{db::DbPool, ids::new_id, middleware::request_context::RequestContext};
+
+/// Events queued before new ones are dropped, should the database fall behind.
+const QUEUE_CAPACITY: usize = 10_000;
+
+/// Events written per insert.
+const BATCH_SIZE: usize = 500;
+
+/// Longest an event waits in the writer before it is written.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Rows deleted per statement when pruning, so no single delete holds locks for long.
+pub const PRUNE_BATCH_SIZE: i64 = 10_000;
+
+/// What an audit entry records; stored in `audit_log.action` as [`AuditAction::as_str`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub enum AuditAction {
+    #[serde(rename = "user.register")]
+    UserRegister,
+    #[serde(rename = "user.login")]
+    UserLogin,
+    #[serde(rename = "user.login_failed")]
+    UserLoginFailed,
+    #[serde(rename = "user.profile_update")]
+    UserProfileUpdate,
+    /// An admin looked at a user's orders or cart.
+    #[serde(rename = "user.inspect")]
+    UserInspect,
+    /// A user downloaded a copy of their data.
+    #[serde(rename = "user.data_export")]
+    UserDataExport,
+    /// A user deleted their account; the entry keeps a hash of the email, not the email.
+    #[serde(rename = "user.delete")]
+    UserDelete,
+    #[serde(rename = "product.create")]
+    ProductCreate,
+    #[serde(rename = "product.update")]
+    ProductUpdate,
+    #[serde(rename = "product.delete")]
+    ProductDelete,
+    #[serde(rename = "product.inventory_adjust")]
+    InventoryAdjust,
+    #[serde(rename = "product.restore")]
+    ProductRestore,
+    /// An admin listed products through `/admin/products`, which can show deleted ones.
+    #[serde(rename = "product.admin_list")]
+    ProductAdminList,
+    /// An admin downloaded the product catalog as CSV.
+    #[serde(rename = "product.export")]
+    ProductExport,
+    #[serde(rename = "product.low_stock_export")]
+    LowStockExport,
+    #[serde(rename = "order.create")]
+    OrderCreate,
+    #[serde(rename = "order.pay")]
+    OrderPay,
+    #[serde(rename = "order.cancel")]
+    OrderCancel,
+    /// An admin moved an order to a status outside the normal transitions.
+    #[serde(rename = "order.force_status")]
+    OrderForceStatus,
+    #[serde(rename = "audit_log.prune")]
+    AuditLogPrune,
+    /// Any mutating HTTP request, recorded by middleware alongside the specific action.
+    #[serde(rename = "http.mutation")]
+    HttpMutation,
+}
+
+impl AuditAction {
+    /// Every action, in declaration order.
+    pub const ALL: [AuditAction; 21] = [
+        AuditAction::UserRegister,
+        AuditAction::UserLogin,
+        AuditAction::UserLoginFailed,
+        AuditAction::UserProfileUpdate,
+        AuditAction::UserInspect,
+        AuditAction::UserDataExport,
+        AuditAction::UserDelete,
+        AuditAction::ProductCreate,
+        AuditAction::ProductUpdate,
+        AuditAction::ProductDelete,
+        AuditAction::InventoryAdjust,
+        AuditAction::ProductRestore,
+        AuditAction::ProductAdminList,
+        AuditAction::ProductExport,
+        AuditAction::LowStockExport,
+        AuditAction::OrderCreate,
+        AuditAction::OrderPay,
+        AuditAction::OrderCancel,
+        AuditAction::OrderForceStatus,
+        AuditAction::AuditLogPrune,
+        AuditAction::HttpMutation,
+    ];
+
+    /// The stored form, the same as the serialized one; never change an existing one.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::UserRegister => "user.register",
+            AuditAction::UserLogin => "user.login",
+            AuditAction::UserLoginFailed => "user.login_failed",
+            AuditAction::UserProfileUpdate => "user.profile_update",
+            AuditAction::UserInspect => "user.inspect",
+            AuditAction::UserDataExport => "user.data_export",
+            AuditAction::UserDelete => "user.delete",
+            AuditAction::ProductCreate => "product.create",
+            AuditAction::ProductUpdate => "product.update",
+            AuditAction::ProductDelete => "product.delete",
+            AuditAction::InventoryAdjust => "product.inventory_adjust",
+            AuditAction::ProductRestore => "product.restore",
+            AuditAction::ProductAdminList => "product.admin_list",
+            AuditAction::ProductExport => "product.export",
+            AuditAction::LowStockExport => "product.low_stock_export",
+            AuditAction::OrderCreate => "order.create",
+            AuditAction::OrderPay => "order.pay",
+            AuditAction::OrderCancel => "order.cancel",
+            AuditAction::OrderForceStatus => "order.force_status",
+            AuditAction::AuditLogPrune => "audit_log.prune",
+            AuditAction::HttpMutation => "http.mutation",
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AuditAction::ALL
+            .into_iter()
+            .find(|action| action.as_str() == s)
+            .ok_or_else(|| format!("unknown audit action `{}`", s))
+    }
+}
+
+/// One audited action, e.g. `product.update` on a product by an admin.
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    /// Who did it; `None` for guests and failed logins.
+    pub actor_id: Option<Uuid>,
+    pub action: AuditAction,
+    pub entity_type: &'static str,
+    pub entity_id: Option<Uuid>,
+    pub details: Value,
+    /// The request that caused it; empty for events from outside a request.
+    pub context: RequestContext,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditEvent {
+    pub fn new(action: AuditAction, entity_type: &'static str) -> Self {
+        Self {
+            actor_id: None,
+            action,
+            entity_type,
+            entity_id: None,
+            details: Value::Object(Default::default()),
+            context: RequestContext::default(),
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn actor(mut self, actor_id: Uuid) -> Self {
+        self.actor_id = Some(actor_id);
+        self
+    }
+
+    pub fn entity(mut self, entity_id: Uuid) -> Self {
+        self.entity_id = Some(entity_id);
+        self
+    }
+
+    pub fn details(mut self, details: Value) -> Self {
+        self.details = details;
+        self
+    }
+
+    pub fn context(mut self, context: &RequestContext) -> Self {
+        self.context = context.clone();
+        self
+    }
+}
+
+enum Message {
+    Event(AuditEvent),
+    /// Write everything queued so far, then answer.
+    Flush(oneshot::Sender<()>),
+}
+
+/// Handle to the audit writer, kept in `AppState`.
+#[derive(Clone)]
+pub struct AuditLog {
+    sender: mpsc::Sender<Message>,
+}
+
+impl AuditLog {
+    /// Spawns the writer for `pool`; it runs until every handle is dropped.
+    pub fn start(pool: DbPool) -> Self {
+        let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(run_writer(pool, receiver));
+        Self { sender }
+    }
+
+    /// Queues `event` without waiting. A full queue drops it with a warning.
+    pub fn record(&self, event: AuditEvent) {
+        let action = event.action;
+        if self.sender.try_send(Message::Event(event)).is_err() {
+            tracing::warn!(%action, "audit queue full or closed; event dropped");
+        }
+    }
+
+    /// Waits until every event recorded before the call has been written (or failed to be).
+    /// Used on shutdown, and by tests that read `audit_log`.
+    pub async fn flush(&self) {
+        let (ack, done) = oneshot::channel();
+        if self.sender.send(Message::Flush(ack)).await.is_ok() {
+            done.await.ok();
+        }
+    }
+}
+
+async fn run_writer(pool: DbPool, mut receiver: mpsc::Receiver<Message>) {
+    let mut pending = Vec::new();
+    let mut ticker = tokio::time::interval(FLUSH_INTERVAL);
+    loop {
+        tokio::select! {
+            message = receiver.recv() => match message {
+                Some(Message::Event(event)) => {
+                    pending.push(event);
+                    if pending.len() >= BATCH_SIZE {
+                        write(&pool, &mut pending).await;
+                    }
+                }
+                Some(Message::Flush(ack)) => {
+                    write(&pool, &mut pending).await;
+                    ack.send(()).ok();
+                }
+                None => break,
+            },
+            _ = ticker.tick() => write(&pool, &mut pending).await,
+        }
+    }
+    write(&pool, &mut pending).await;
+}
+
+/// Inserts and clears `events`; a failed insert is logged and the batch dropped. Actors whose
+/// account was deleted while their events were queued are stored as unset.
+async fn write(pool: &DbPool, events: &mut Vec<AuditEvent>) {
+    if events.is_empty() {
+        return;
+    }
+    let ids: Vec<Uuid> = events.iter().map(|_| new_id()).collect();
+    let actors: Vec<Option<Uuid>> = events.iter().map(|e| e.actor_id).collect();
+    let actions: Vec<&str> = events.iter().map(|e| e.action.as_str()).collect();
+    let entity_types: Vec<&str> = events.iter().map(|e| e.entity_type).collect();
+    let entity_ids: Vec<Option<Uuid>> = events.iter().map(|e| e.entity_id).collect();
+    let details: Vec<Json<&Value>> = events.iter().map(|e| Json(&e.details)).collect();
+    let request_ids: Vec<Option<&str>> = events
+        .iter()
+        .map(|e| e.context.request_id.as_deref())
+        .collect();
+    let ips: Vec<Option<String>> = events
+        .iter()
+        .map(|e| e.context.ip_address.map(|ip| ip.to_string()))
+        .collect();
+    let user_agents: Vec<Option<&str>> = events
+        .iter()
+        .map(|e| e.context.user_agent.as_deref())
+        .collect();
+    let created: Vec<DateTime<Utc>> = events.iter().map(|e| e.created_at).collect();
+    let result = sqlx::query(
+        r#"
+        INSERT INTO audit_log (
+            id, actor_id, action, entity_type, entity_id, details,
+            request_id, ip_address, user_agent, created_at
+        )
+        SELECT
+            e.id, u.id, e.action, e.entity_type, e.entity_id, e.details,
+            e.request_id, e.ip_address, e.user_agent, e.created_at
+        FROM UNNEST(
+            $1::uuid[], $2::uuid[], $3::text[], $4::text[], $5::uuid[], $6::jsonb[],
+            $7::text[], $8::text[], $9::text[], $10::timestamptz[]
+        ) AS e(
+            id, actor_id, action, entity_type, entity_id, details,
+            request_id, ip_address, user_agent, created_at
+        )
+        LEFT JOIN users u ON u.id = e.actor_id
+        "#,
+    )
+    .bind(&ids)
+    .bind(&actors)
+    .bind(&actions)
+    .bind(&entity_types)
+    .bind(&entity_ids)
+    .bind(&details)
+    .bind(&request_ids)
+    .bind(&ips)
+    .bind(&user_agents)
+    .bind(&created)
+    .execute(pool)
+    .await;
+    if let Err(e) = result {
+        tracing::error!(error = %e, dropped = events.len(), "writing audit events failed");
+    }
+    events.clear();
+}
+
+/// Deletes entries created before `cutoff`, `batch_size` rows at a time, and returns how many
+/// went.
+pub async fn prune_older_than(
+    pool: &DbPool,
+    cutoff: DateTime<Utc>,
+    batch_size: i64,
+) -> sqlx::Result<u64> {
+    let mut deleted = 0;
+    loop {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM audit_log
+            WHERE id IN (SELECT id FROM audit_log WHERE created_at < $1 LIMIT $2)
+            "#,
+        )
+        .bind(cutoff)
+        .bind(batch_size)
+        .execute(pool)
+        .await?;
+        deleted += result.rows_affected();
+        if result.rows_affected() < batch_size as u64 {
+            return Ok(deleted);
+        }
+    }
+}
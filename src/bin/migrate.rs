@@ -0,0 +1,68 @@
+//! Schema migrations from `migrations/`, all through `db::MIGRATOR`:
+//!
+//! - `migrate [up]` applies pending migrations
+//! - `migrate down [VERSION]` reverts everything after `VERSION`, by default just the latest
+//! - `migrate status` (or `--status`) lists which are applied and which are not
+//! - `migrate fresh` drops the whole schema and migrates from scratch
+//!
+//! sqlx records each applied migration with its checksum in `_sqlx_migrations`, so files
+//! already applied are skipped and an applied file that was edited afterwards stops the run.
+
+use std::env;
+
+use anyhow::Context;
+use axum_ecommerce_api::{
+    config::AppConfig,
+    db::{DbPool, MIGRATOR, create_pool, migration_status, reset_database},
+};
+
+const USAGE: &str = "usage: migrate [up | down [VERSION] | status | fresh]";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let config = AppConfig::from_env()?;
+    let pool = create_pool(&config).await?;
+
+    match args[..] {
+        [] | ["up"] => MIGRATOR.run(&pool).await?,
+        ["down"] => {
+            let applied = applied_versions(&pool).await?;
+            let target = match applied[..] {
+                [] => anyhow::bail!("nothing to revert"),
+                [.., previous, _] => previous,
+                [_] => 0,
+            };
+            MIGRATOR.undo(&pool, target).await?;
+        }
+        ["down", version] => {
+            let target = version.parse().context(USAGE)?;
+            MIGRATOR.undo(&pool, target).await?;
+        }
+        ["status"] | ["--status"] => {}
+        ["fresh"] => reset_database(&pool).await?,
+        _ => anyhow::bail!(USAGE),
+    }
+
+    for m in migration_status(&pool).await? {
+        let state = match (m.applied, m.changed) {
+            (true, true) => "changed",
+            (true, false) => "applied",
+            (false, _) => "pending",
+        };
+        println!("{:<8} {:>4} {}", state, m.version, m.description);
+    }
+    Ok(())
+}
+
+async fn applied_versions(pool: &DbPool) -> anyhow::Result<Vec<i64>> {
+    Ok(migration_status(pool)
+        .await?
+        .into_iter()
+        .filter(|m| m.applied)
+        .map(|m| m.version)
+        .collect())
+}
@@ -0,0 +1,15 @@
+//! Writes the OpenAPI spec to the path given as the only argument, without a server or database.
+
+use std::{env, fs};
+
+use anyhow::Context;
+use axum_ecommerce_api::{config::public_url_from_env, routes::doc::openapi_spec};
+
+fn main() -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+    let path = env::args().nth(1).context("usage: openapi <output.json>")?;
+
+    let spec = openapi_spec(&public_url_from_env()).to_pretty_json()?;
+    fs::write(&path, spec).with_context(|| format!("cannot write {}", path))?;
+    Ok(())
+}
@@ -0,0 +1,88 @@
+//! Fills the database at `DATABASE_URL` with data for demos and load tests:
+//!
+//! - `seed bootstrap` creates `admin@example.com`, `user@example.com` and four products
+//! - `seed --products 1000 --users 50 --orders 200` generates fake data up to those counts;
+//!   running it again only adds what is missing
+//! - `--clean` deletes previously seeded data first
+//!
+//! Run `migrate` before seeding.
+
+use std::time::Instant;
+
+use axum_ecommerce_api::{
+    config::AppConfig,
+    db::create_pool,
+    seed::{self, SEED_PASSWORD, Volumes},
+};
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(about = "Seed the database with demo data")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Products to have, numbered SEED-000001 onwards
+    #[arg(long, default_value_t = 100)]
+    products: usize,
+
+    /// Users to have, all under @seed.example.com
+    #[arg(long, default_value_t = 10)]
+    users: usize,
+
+    /// Orders the seeded users should have between them
+    #[arg(long, default_value_t = 50)]
+    orders: usize,
+
+    /// Delete seeded users, their orders and carts, and seeded products first
+    #[arg(long)]
+    clean: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the admin and user accounts and a few products
+    Bootstrap,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+    let cli = Cli::parse();
+
+    let config = AppConfig::from_env()?;
+    let pool = create_pool(&config).await?;
+
+    if let Some(Command::Bootstrap) = cli.command {
+        seed::bootstrap(&pool).await?;
+        println!(
+            "admin@example.com and user@example.com, password {:?}",
+            SEED_PASSWORD
+        );
+        return Ok(());
+    }
+
+    let started = Instant::now();
+    if cli.clean {
+        seed::clean(&pool).await?;
+    }
+    let seeded = seed::seed(
+        &pool,
+        Volumes {
+            products: cli.products,
+            users: cli.users,
+            orders: cli.orders,
+        },
+    )
+    .await?;
+    println!(
+        "added {} products, {} users, {} orders and {} cart lines in {:.1?}; password {:?}",
+        seeded.products,
+        seeded.users,
+        seeded.orders,
+        seeded.cart_items,
+        started.elapsed(),
+        SEED_PASSWORD
+    );
+    Ok(())
+}
@@ -0,0 +1,8 @@
+//! What build is running, baked in at compile time by `build.rs`.
+
+/// Crate version from `Cargo.toml`.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Short hash of the commit the binary was built from, or `unknown` outside a git checkout.
+pub const GIT_COMMIT: &str = env!("BUILD_GIT_COMMIT");
+/// RFC 3339 build time in UTC.
+pub const BUILD_TIMESTAMP: &str = env!("BUILD_TIMESTAMP");
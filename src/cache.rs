@@ -0,0 +1,105 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use moka::future::Cache;
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{db::DbPool, error::AppResult, models::Product};
+
+/// Read-through cache for single-product reads, keyed by product id.
+#[derive(Clone)]
+pub struct ProductCache {
+    entries: Cache<Uuid, Product>,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: u64,
+}
+
+impl ProductCache {
+    pub fn new(capacity: u64, ttl: Duration) -> Self {
+        Self {
+            entries: Cache::builder()
+                .max_capacity(capacity)
+                .time_to_live(ttl)
+                .build(),
+            hits: Arc::new(AtomicU64::new(0)),
+            misses: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub async fn get(&self, id: Uuid) -> Option<Product> {
+        let product = self.entries.get(&id).await;
+        let counter = if product.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        product
+    }
+
+    pub async fn insert(&self, product: Product) {
+        self.entries.insert(product.id, product).await;
+    }
+
+    pub async fn invalidate(&self, id: Uuid) {
+        self.entries.invalidate(&id).await;
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.entry_count(),
+        }
+    }
+}
+
+/// Whether the account behind a token still exists, remembered for `ttl` so authenticated
+/// requests do not each look the user up. Deleting an account must go through
+/// [`UserCache::forget`], so this instance stops accepting its tokens at once.
+#[derive(Clone)]
+pub struct UserCache {
+    entries: Cache<Uuid, bool>,
+}
+
+impl UserCache {
+    pub fn new(capacity: u64, ttl: Duration) -> Self {
+        Self {
+            entries: Cache::builder()
+                .max_capacity(capacity)
+                .time_to_live(ttl)
+                .build(),
+        }
+    }
+
+    pub async fn exists(&self, pool: &DbPool, id: Uuid) -> AppResult<bool> {
+        if let Some(exists) = self.entries.get(&id).await {
+            return Ok(exists);
+        }
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE id = $1)")
+            .bind(id)
+            .fetch_one(pool)
+            .await?;
+        self.entries.insert(id, exists).await;
+        Ok(exists)
+    }
+
+    /// Marks `id` as deleted; ids are never reused, so this can stand until it expires.
+    pub async fn forget(&self, id: Uuid) {
+        self.entries.insert(id, false).await;
+    }
+}
@@ -1,10 +1,106 @@
-use sqlx::{PgPool, postgres::PgPoolOptions};
+use std::{str::FromStr, time::Duration};
+
+use anyhow::Context;
+use log::LevelFilter;
+use sqlx::{
+    ConnectOptions, PgPool,
+    migrate::Migrator,
+    postgres::{PgConnectOptions, PgPoolOptions},
+};
+
+use crate::config::AppConfig;
 
 pub type DbPool = PgPool;
-pub async fn create_pool(database_url: &str) -> anyhow::Result<DbPool> {
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(database_url)
-        .await?;
+
+/// The migrations in `migrations/`, embedded at build time. `/ready` compares them with what
+/// the database has applied.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Pool settings from the `db_*` fields of `config`.
+pub fn pool_options(config: &AppConfig) -> PgPoolOptions {
+    PgPoolOptions::new()
+        .max_connections(config.db_max_connections)
+        .min_connections(config.db_min_connections)
+        .acquire_timeout(Duration::from_secs(config.db_acquire_timeout_secs))
+        .idle_timeout(Duration::from_secs(config.db_idle_timeout_secs))
+}
+
+/// Connects right away, so an unreachable database stops startup instead of failing the first
+/// request.
+pub async fn create_pool(config: &AppConfig) -> anyhow::Result<DbPool> {
+    let mut options =
+        PgConnectOptions::from_str(&config.database_url).context("DATABASE_URL is not valid")?;
+    if !config.db_log_statements {
+        options = options.log_statements(LevelFilter::Off);
+    }
+    options = match config.db_slow_query_ms {
+        0 => options.log_slow_statements(LevelFilter::Off, Duration::ZERO),
+        ms => options.log_slow_statements(LevelFilter::Warn, Duration::from_millis(ms)),
+    };
+    let pool = pool_options(config)
+        .connect_with(options)
+        .await
+        .context("cannot connect to the database at DATABASE_URL")?;
     Ok(pool)
 }
+
+/// `fragment` with the `LIKE` wildcards escaped, so `jane_doe` matches only itself.
+pub fn escape_like(fragment: &str) -> String {
+    fragment
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// One embedded migration and where the database stands on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationState {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+    /// Applied, but the file has been edited since; `MIGRATOR.run` refuses to start then.
+    pub changed: bool,
+}
+
+/// Every migration this build ships with, in order, against `_sqlx_migrations`. A database
+/// that was never migrated has nothing applied.
+pub async fn migration_status(pool: &DbPool) -> sqlx::Result<Vec<MigrationState>> {
+    let table: Option<String> = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations')::text")
+        .fetch_one(pool)
+        .await?;
+    let applied: Vec<(i64, Vec<u8>)> = if table.is_some() {
+        sqlx::query_as("SELECT version, checksum FROM _sqlx_migrations WHERE success")
+            .fetch_all(pool)
+            .await?
+    } else {
+        Vec::new()
+    };
+
+    Ok(MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+        .map(|m| {
+            let checksum = applied
+                .iter()
+                .find(|(version, _)| *version == m.version)
+                .map(|(_, checksum)| checksum);
+            MigrationState {
+                version: m.version,
+                description: m.description.to_string(),
+                applied: checksum.is_some(),
+                changed: checksum.is_some_and(|c| c[..] != m.checksum[..]),
+            }
+        })
+        .collect())
+}
+
+/// Drops everything in the `public` schema, migration history included, and migrates from
+/// scratch. For development and tests only.
+pub async fn reset_database(pool: &DbPool) -> anyhow::Result<()> {
+    sqlx::raw_sql("DROP SCHEMA public CASCADE; CREATE SCHEMA public;")
+        .execute(pool)
+        .await
+        .context("cannot drop the public schema")?;
+    MIGRATOR.run(pool).await?;
+    Ok(())
+}
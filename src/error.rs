@@ -1,11 +1,12 @@
 use axum::{
-    http::StatusCode,
+    http::{HeaderValue, StatusCode, header},
     response::{IntoResponse, Response},
 };
 use serde::Serialize;
 use thiserror::Error;
+use utoipa::ToSchema;
 
-use crate::response::{ApiResponse, Meta};
+use crate::{middleware::request_id::current_request_id, response::ApiResponse};
 
 #[derive(Debug, Error)]
 pub enum AppError {
@@ -18,37 +19,268 @@ pub enum AppError {
     #[error("Forbidden")]
     Forbidden,
 
+    #[error("Conflict {0}")]
+    Conflict(String),
+
+    #[error("Payload Too Large")]
+    PayloadTooLarge,
+
+    /// Rate limit hit; the client may retry after this many seconds.
+    #[error("Too Many Requests")]
+    TooManyRequests(u64),
+
+    #[error("Validation failed")]
+    Validation(Vec<FieldError>),
+
+    /// Query string parameters that are malformed or out of range; a 400, unlike
+    /// `Validation`, which is about the request body.
+    #[error("Bad Request invalid query parameter {}", .0.iter().map(|e| e.field.as_str()).collect::<Vec<_>>().join(", "))]
+    InvalidQuery(Vec<FieldError>),
+
     #[error("Database error")]
     DbError(#[from] sqlx::Error),
 
     #[error("Internal Server Error")]
     Internal(#[from] anyhow::Error),
+
+    /// Any of the above with a specific `error_code`; status and message come from the inner error.
+    #[error("{1}")]
+    Coded(ErrorCode, Box<AppError>),
+}
+
+impl AppError {
+    /// Attaches a stable machine-readable code, e.g.
+    /// `AppError::BadRequest("Cart is empty".into()).with_code(ErrorCode::CartEmpty)`.
+    pub fn with_code(self, code: ErrorCode) -> Self {
+        AppError::Coded(code, Box::new(self))
+    }
+
+    /// Status, code and client-facing message. Database details never reach the message.
+    fn classify(&self) -> (StatusCode, ErrorCode, String) {
+        match self {
+            AppError::NotFound => (StatusCode::NOT_FOUND, ErrorCode::NotFound, self.to_string()),
+            AppError::BadRequest(_) => (
+                StatusCode::BAD_REQUEST,
+                ErrorCode::BadRequest,
+                self.to_string(),
+            ),
+            AppError::Forbidden => (
+                StatusCode::FORBIDDEN,
+                ErrorCode::Forbidden,
+                self.to_string(),
+            ),
+            AppError::Conflict(_) => (StatusCode::CONFLICT, ErrorCode::Conflict, self.to_string()),
+            AppError::PayloadTooLarge => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                ErrorCode::PayloadTooLarge,
+                self.to_string(),
+            ),
+            AppError::TooManyRequests(_) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                ErrorCode::RateLimited,
+                self.to_string(),
+            ),
+            AppError::Validation(_) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ErrorCode::ValidationFailed,
+                self.to_string(),
+            ),
+            AppError::InvalidQuery(_) => (
+                StatusCode::BAD_REQUEST,
+                ErrorCode::BadRequest,
+                self.to_string(),
+            ),
+            AppError::DbError(sqlx::Error::Database(e)) => {
+                classify_database_error(e.as_ref()).unwrap_or_else(internal_error)
+            }
+            AppError::DbError(_) | AppError::Internal(_) => internal_error(),
+            AppError::Coded(code, inner) => {
+                let (status, _, message) = inner.classify();
+                (status, *code, message)
+            }
+        }
+    }
+
+    fn into_field_errors(self) -> Vec<FieldError> {
+        match self {
+            AppError::Validation(errors) | AppError::InvalidQuery(errors) => errors,
+            AppError::Coded(_, inner) => inner.into_field_errors(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn retry_after(&self) -> Option<u64> {
+        match self {
+            AppError::TooManyRequests(secs) => Some(*secs),
+            AppError::Coded(_, inner) => inner.retry_after(),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Serialize)]
-struct ErrorData {
-    error: String,
+/// Every 5xx gets the same body; the cause only goes to the log.
+fn internal_error() -> (StatusCode, ErrorCode, String) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        ErrorCode::InternalError,
+        "Internal Server Error".to_string(),
+    )
+}
+
+/// Client errors surfaced by Postgres: constraint violations and malformed input text.
+fn classify_database_error(
+    e: &dyn sqlx::error::DatabaseError,
+) -> Option<(StatusCode, ErrorCode, String)> {
+    match e.code().as_deref() {
+        // unique_violation
+        Some("23505") => Some((
+            StatusCode::CONFLICT,
+            ErrorCode::Conflict,
+            "Conflict duplicate value".to_string(),
+        )),
+        // foreign_key_violation
+        Some("23503") => Some((
+            StatusCode::CONFLICT,
+            ErrorCode::Conflict,
+            "Conflict resource is still referenced".to_string(),
+        )),
+        // check_violation
+        Some("23514") => Some((
+            StatusCode::BAD_REQUEST,
+            ErrorCode::ConstraintViolation,
+            "Bad Request value out of range".to_string(),
+        )),
+        // numeric_value_out_of_range, e.g. a line total past bigint
+        Some("22003") => Some((
+            StatusCode::BAD_REQUEST,
+            ErrorCode::BadRequest,
+            "Bad Request number out of range".to_string(),
+        )),
+        // invalid_text_representation, e.g. a malformed uuid cast in SQL
+        Some("22P02") => Some((
+            StatusCode::BAD_REQUEST,
+            ErrorCode::BadRequest,
+            "Bad Request invalid input syntax".to_string(),
+        )),
+        _ => None,
+    }
+}
+
+/// Stable code in `ErrorData::error_code`; clients should branch on this, not on `message`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    NotFound,
+    BadRequest,
+    Forbidden,
+    Conflict,
+    ValidationFailed,
+    ConstraintViolation,
+    InternalError,
+    CartEmpty,
+    InsufficientStock,
+    PriceChanged,
+    OrderAlreadyPaid,
+    InvalidOrderStatus,
+    EmailTaken,
+    InvalidCredentials,
+    PayloadTooLarge,
+    RateLimited,
+    CheckoutInProgress,
+}
+
+/// One invalid input field, e.g. `{ field: "price", code: "negative", message: ... }`.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Collects field errors; `finish` turns a non-empty list into `AppError::Validation`.
+#[derive(Debug, Default)]
+pub struct FieldErrors(Vec<FieldError>);
+
+impl FieldErrors {
+    pub fn add(&mut self, field: &str, code: &str, message: impl Into<String>) {
+        self.0.push(FieldError::new(field, code, message));
+    }
+
+    pub fn finish(self) -> AppResult<()> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::Validation(self.0))
+        }
+    }
+
+    /// Like [`FieldErrors::finish`], for query parameters: `AppError::InvalidQuery`.
+    pub fn finish_query(self) -> AppResult<()> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::InvalidQuery(self.0))
+        }
+    }
+}
+
+/// `data` of every error response; `errors` is only present for validation failures and
+/// invalid query parameters.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorData {
+    pub error: String,
+    pub error_code: ErrorCode,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, message) = match &self {
-            AppError::NotFound => (StatusCode::NOT_FOUND, self.to_string()),
-            AppError::BadRequest(_) => (StatusCode::BAD_REQUEST, self.to_string()),
-            AppError::Forbidden => (StatusCode::FORBIDDEN, self.to_string()),
-            AppError::DbError(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
-            AppError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
-        };
-
-        let body = ApiResponse {
-            message,
-            data: Some(ErrorData {
-                error: self.to_string(),
-            }),
-            meta: Some(Meta::empty()),
-        };
-
-        (status, axum::Json(body)).into_response()
+        let (status, error_code, message) = self.classify();
+        if status.is_server_error() {
+            tracing::error!(
+                request_id = current_request_id().as_deref(),
+                error = ?self,
+                "request failed"
+            );
+        } else if let AppError::DbError(sqlx::Error::Database(e)) = &self {
+            // the client only gets a generic message; the details stay in the log
+            tracing::warn!(
+                request_id = current_request_id().as_deref(),
+                code = ?e.code(),
+                constraint = ?e.constraint(),
+                error = %e,
+                "database rejected request"
+            );
+        }
+
+        let retry_after = self.retry_after();
+        let body = ApiResponse::error(
+            message.clone(),
+            ErrorData {
+                error: message,
+                error_code,
+                errors: self.into_field_errors(),
+            },
+        );
+
+        let mut response = (status, axum::Json(body)).into_response();
+        if let Some(secs) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
     }
 }
 
@@ -0,0 +1,96 @@
+use std::error::Error;
+
+use axum::{
+    Json,
+    extract::{
+        FromRequest, FromRequestParts, OptionalFromRequest, Query, Request,
+        rejection::{JsonRejection, QueryRejection},
+    },
+    http::{StatusCode, request::Parts},
+};
+use serde::de::DeserializeOwned;
+
+use crate::error::{AppError, FieldError};
+
+/// Drop-in replacement for `axum::Json` as an extractor whose rejections are rendered in the
+/// usual `ApiResponse` error envelope instead of axum's plain-text body.
+#[derive(Debug, Default)]
+pub struct AppJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for AppJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = <Json<T> as FromRequest<S>>::from_request(req, state).await?;
+        Ok(Self(value))
+    }
+}
+
+/// Like `Option<Json<T>>`: no `Content-Type` means no body, anything else must parse.
+impl<T, S> OptionalFromRequest<S> for AppJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        let value = <Json<T> as OptionalFromRequest<S>>::from_request(req, state).await?;
+        Ok(value.map(|Json(value)| Self(value)))
+    }
+}
+
+impl From<JsonRejection> for AppError {
+    fn from(rejection: JsonRejection) -> Self {
+        match rejection {
+            // Well-formed JSON that doesn't fit the target type (missing field, wrong type).
+            JsonRejection::JsonDataError(e) => {
+                AppError::Validation(vec![FieldError::new("body", "invalid", e.body_text())])
+            }
+            // Body over `AppConfig::max_body_bytes`.
+            other if other.status() == StatusCode::PAYLOAD_TOO_LARGE => AppError::PayloadTooLarge,
+            other => AppError::BadRequest(other.body_text()),
+        }
+    }
+}
+
+/// Drop-in replacement for `axum::extract::Query` with enveloped rejections that name the
+/// offending parameter, e.g. `page` for `?page=abc`.
+#[derive(Debug)]
+pub struct AppQuery<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for AppQuery<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state).await?;
+        Ok(Self(value))
+    }
+}
+
+impl From<QueryRejection> for AppError {
+    fn from(rejection: QueryRejection) -> Self {
+        // axum deserializes through serde_path_to_error, which knows the parameter
+        let first: &(dyn Error + 'static) = &rejection;
+        let failed = std::iter::successors(Some(first), |&e| e.source())
+            .find_map(|e| e.downcast_ref::<serde_path_to_error::Error<serde::de::value::Error>>());
+        match failed {
+            Some(e) if e.path().iter().next().is_some() => {
+                AppError::InvalidQuery(vec![FieldError::new(
+                    &e.path().to_string(),
+                    "invalid",
+                    e.inner().to_string(),
+                )])
+            }
+            _ => AppError::BadRequest(rejection.body_text()),
+        }
+    }
+}
@@ -0,0 +1,10 @@
+//! Primary keys for new rows.
+
+use uuid::Uuid;
+
+/// A UUIDv7: ids from this process increase over time, so inserts append to the primary key
+/// index and ids sort in creation order. Rows from before v7 keep their v4 ids, which work the
+/// same in lookups. Not for secrets such as cart tokens, as it starts with a timestamp.
+pub fn new_id() -> Uuid {
+    Uuid::now_v7()
+}
@@ -0,0 +1,286 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::Serialize;
+use tokio::task::JoinSet;
+use tracing::Instrument;
+use utoipa::ToSchema;
+
+use crate::{
+    audit::{PRUNE_BATCH_SIZE, prune_older_than},
+    config::AppConfig,
+    routes::cart::prune_guest_carts,
+    state::AppState,
+    stock_alerts,
+};
+
+/// Periodic background work, run by a [`Scheduler`].
+#[async_trait]
+pub trait Job: Send + Sync + 'static {
+    /// Unique name, shown by `/admin/jobs` and on the job's tracing span.
+    fn name(&self) -> &'static str;
+
+    /// Pause between runs, before jitter.
+    fn interval(&self) -> Duration;
+
+    /// How long a run may take before it is abandoned; the interval unless overridden.
+    fn timeout(&self) -> Duration {
+        self.interval()
+    }
+
+    async fn run(&self, state: &AppState) -> anyhow::Result<()>;
+}
+
+/// What a job's runs so far came to.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct JobStatus {
+    #[schema(example = "prune_guest_carts")]
+    pub name: String,
+    #[schema(example = 3600)]
+    pub interval_secs: u64,
+    /// When the last run started; `None` until the first one
+    pub last_run_at: Option<DateTime<Utc>>,
+    #[schema(example = 12)]
+    pub last_duration_ms: Option<u64>,
+    /// Why the last run failed; cleared by the next success
+    pub last_error: Option<String>,
+    #[schema(example = 24)]
+    pub runs: u64,
+    #[schema(example = 0)]
+    pub failures: u64,
+}
+
+/// Status of every scheduled job, written by the scheduler and read by `/admin/jobs`.
+#[derive(Clone, Default)]
+pub struct JobRegistry(Arc<Mutex<BTreeMap<&'static str, JobStatus>>>);
+
+impl JobRegistry {
+    /// All registered jobs, by name.
+    pub fn statuses(&self) -> Vec<JobStatus> {
+        self.0.lock().unwrap().values().cloned().collect()
+    }
+
+    pub fn status(&self, name: &str) -> Option<JobStatus> {
+        self.0.lock().unwrap().get(name).cloned()
+    }
+
+    fn register(&self, job: &dyn Job) {
+        self.0.lock().unwrap().insert(
+            job.name(),
+            JobStatus {
+                name: job.name().to_string(),
+                interval_secs: job.interval().as_secs(),
+                last_run_at: None,
+                last_duration_ms: None,
+                last_error: None,
+                runs: 0,
+                failures: 0,
+            },
+        );
+    }
+
+    fn record(
+        &self,
+        name: &'static str,
+        started_at: DateTime<Utc>,
+        took: Duration,
+        result: Result<(), String>,
+    ) {
+        let mut jobs = self.0.lock().unwrap();
+        let Some(status) = jobs.get_mut(name) else {
+            return;
+        };
+        status.last_run_at = Some(started_at);
+        status.last_duration_ms = Some(took.as_millis() as u64);
+        status.runs += 1;
+        if result.is_err() {
+            status.failures += 1;
+        }
+        status.last_error = result.err();
+    }
+}
+
+/// Runs registered jobs, each in its own loop: a first run shortly after start, then one
+/// every interval. Runs get a random delay of up to a tenth of the interval, so instances
+/// started together do not hit the database at the same moment.
+#[derive(Default)]
+pub struct Scheduler {
+    jobs: Vec<Arc<dyn Job>>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_job(mut self, job: impl Job) -> Self {
+        self.jobs.push(Arc::new(job));
+        self
+    }
+
+    /// Starts every job, reporting to `state.jobs`. Dropping the returned set stops them.
+    pub fn start(self, state: AppState) -> JoinSet<()> {
+        let mut tasks = JoinSet::new();
+        for job in self.jobs {
+            state.jobs.register(job.as_ref());
+            let span = tracing::info_span!("job", name = job.name());
+            tasks.spawn(run_forever(job, state.clone()).instrument(span));
+        }
+        tasks
+    }
+}
+
+/// The jobs `main` runs.
+pub fn scheduler(config: &AppConfig) -> Scheduler {
+    let mut scheduler = Scheduler::new().with_job(PruneGuestCarts);
+    if config.audit_retention_days > 0 {
+        scheduler = scheduler.with_job(PruneAuditLog {
+            retention: chrono::Duration::days(i64::from(config.audit_retention_days)),
+        });
+    }
+    if config.low_stock_scan_secs > 0 {
+        scheduler = scheduler.with_job(LowStockAlerts {
+            threshold: config.low_stock_threshold,
+            interval: Duration::from_secs(config.low_stock_scan_secs),
+            webhook_url: config.low_stock_webhook_url.clone(),
+            client: reqwest::Client::new(),
+        });
+    }
+    scheduler
+}
+
+async fn run_forever(job: Arc<dyn Job>, state: AppState) {
+    tokio::time::sleep(jitter(job.interval())).await;
+    loop {
+        run_once(&job, &state).await;
+        tokio::time::sleep(job.interval() + jitter(job.interval())).await;
+    }
+}
+
+/// One run, on its own task so a panic fails the run instead of ending the loop.
+async fn run_once(job: &Arc<dyn Job>, state: &AppState) {
+    let started_at = Utc::now();
+    let started = Instant::now();
+    let timeout = job.timeout();
+    let task = {
+        let (job, state) = (job.clone(), state.clone());
+        tokio::spawn(
+            async move { tokio::time::timeout(timeout, job.run(&state)).await }.in_current_span(),
+        )
+    };
+    let result = match task.await {
+        Ok(Ok(Ok(()))) => Ok(()),
+        Ok(Ok(Err(e))) => Err(format!("{:#}", e)),
+        Ok(Err(_)) => Err(format!("timed out after {:?}", timeout)),
+        Err(e) if e.is_panic() => Err("panicked".to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    let took = started.elapsed();
+    match &result {
+        Ok(()) => tracing::debug!(took_ms = took.as_millis() as u64, "job finished"),
+        Err(e) => tracing::error!(error = %e, "job failed"),
+    }
+    state.jobs.record(job.name(), started_at, took, result);
+}
+
+fn jitter(interval: Duration) -> Duration {
+    interval.mul_f64(rand::thread_rng().gen_range(0.0..0.1))
+}
+
+/// Deletes guest carts that outlived their token, see [`prune_guest_carts`].
+pub struct PruneGuestCarts;
+
+#[async_trait]
+impl Job for PruneGuestCarts {
+    fn name(&self) -> &'static str {
+        "prune_guest_carts"
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(60 * 60)
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(60)
+    }
+
+    async fn run(&self, state: &AppState) -> anyhow::Result<()> {
+        let pruned = prune_guest_carts(&state.pool).await?;
+        if pruned > 0 {
+            tracing::info!("pruned {} expired guest carts", pruned);
+        }
+        Ok(())
+    }
+}
+
+/// Deletes audit log entries older than the retention period, see [`prune_older_than`].
+pub struct PruneAuditLog {
+    pub retention: chrono::Duration,
+}
+
+#[async_trait]
+impl Job for PruneAuditLog {
+    fn name(&self) -> &'static str {
+        "prune_audit_log"
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(24 * 60 * 60)
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(15 * 60)
+    }
+
+    async fn run(&self, state: &AppState) -> anyhow::Result<()> {
+        let cutoff = Utc::now() - self.retention;
+        let pruned = prune_older_than(&state.pool, cutoff, PRUNE_BATCH_SIZE).await?;
+        if pruned > 0 {
+            tracing::info!(%cutoff, "pruned {} audit log entries", pruned);
+        }
+        Ok(())
+    }
+}
+
+/// Raises alerts for products that ran low and sends new ones to the webhook, see
+/// [`stock_alerts::scan`].
+pub struct LowStockAlerts {
+    pub threshold: i32,
+    pub interval: Duration,
+    pub webhook_url: Option<String>,
+    pub client: reqwest::Client,
+}
+
+#[async_trait]
+impl Job for LowStockAlerts {
+    fn name(&self) -> &'static str {
+        "low_stock_alerts"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn run(&self, state: &AppState) -> anyhow::Result<()> {
+        let alerts = stock_alerts::scan(&state.pool, self.threshold).await?;
+        if alerts.is_empty() {
+            return Ok(());
+        }
+        tracing::warn!(
+            threshold = self.threshold,
+            "{} products ran low on stock",
+            alerts.len()
+        );
+        match &self.webhook_url {
+            // the alerts are stored either way, so a failed delivery is not retried
+            Some(url) => stock_alerts::notify(&self.client, url, &alerts).await,
+            None => Ok(()),
+        }
+    }
+}
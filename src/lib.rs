@@ -0,0 +1,148 @@
+use std::any::Any;
+
+use axum::{
+    Extension, Router,
+    body::Body,
+    extract::DefaultBodyLimit,
+    http::{HeaderValue, Request, header},
+    middleware as axum_middleware,
+    response::{IntoResponse, Response},
+};
+use tower::{limit::ConcurrencyLimitLayer, util::option_layer};
+use tower_http::{catch_panic::CatchPanicLayer, services::ServeDir, trace::TraceLayer};
+use tracing::Span;
+
+use crate::{
+    config::AppConfig,
+    error::AppError,
+    middleware::{
+        http_audit::{HttpAudit, audit_mutations},
+        rate_limit::{RateLimits, limit_default},
+        request_context::request_context,
+        request_id::request_id,
+    },
+    routes::{
+        create_api_router,
+        doc::{docs_router, with_server},
+        v1_router,
+    },
+    state::AppState,
+};
+
+pub mod admin_events;
+pub mod audit;
+pub mod build_info;
+pub mod cache;
+pub mod config;
+pub mod db;
+pub mod error;
+pub mod extract;
+pub mod ids;
+pub mod jobs;
+pub mod middleware;
+pub mod models;
+pub mod money;
+pub mod notifier;
+pub mod response;
+pub mod routes;
+pub mod seed;
+pub mod server;
+pub mod slug;
+pub mod state;
+pub mod stock_alerts;
+pub mod storage;
+
+/// The full application router with all layers, ready to serve.
+pub fn app(config: &AppConfig, state: AppState) -> Router {
+    let (mut router, v1_spec) = v1_router().split_for_parts();
+    if config.unversioned_api_alias {
+        // Same handlers as `/api/v1`, kept for clients built before versioning.
+        let (legacy, _) = create_api_router().split_for_parts();
+        router = router.nest(
+            "/api",
+            legacy.layer(axum_middleware::map_response(deprecated)),
+        );
+    }
+
+    let http_audit = HttpAudit {
+        log: state.audit.clone(),
+        sample_rate: config.audit_http_sample_rate,
+        jwt: state.jwt.clone(),
+    };
+    let router = router
+        // Only the API routes; uploads and docs are read-only.
+        .layer(axum_middleware::from_fn_with_state(
+            http_audit,
+            audit_mutations,
+        ))
+        .nest_service(&config.upload_base_url, ServeDir::new(&config.upload_dir))
+        .merge(docs_router(
+            vec![("v1", with_server(v1_spec, &config.public_url))],
+            config.docs_ui,
+        ))
+        .fallback(not_found)
+        .layer(axum_middleware::from_fn_with_state(
+            config.trust_proxy,
+            request_context,
+        ))
+        .layer(axum_middleware::from_fn(limit_default))
+        .layer(Extension(RateLimits::from_config(config)));
+
+    with_middleware(router, config).with_state(state)
+}
+
+/// Wraps `router` in the layers every request goes through. Panics are caught innermost so
+/// the 500 they turn into is still traced and carries the request id.
+pub fn with_middleware<S>(router: Router<S>, config: &AppConfig) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router
+        .layer(DefaultBodyLimit::max(config.max_body_bytes))
+        .layer(CatchPanicLayer::custom(panic_response))
+        .layer(TraceLayer::new_for_http().make_span_with(request_span))
+        .layer(axum_middleware::from_fn_with_state(
+            config.request_id_header.clone(),
+            request_id,
+        ))
+        .layer(option_layer(
+            config.max_concurrency.map(ConcurrencyLimitLayer::new),
+        ))
+}
+
+/// The span every request's log lines carry, tagged with the running build. Only the path is
+/// logged, since query strings can carry values that must not end up in the logs.
+fn request_span(req: &Request<Body>) -> Span {
+    tracing::info_span!(
+        "request",
+        method = %req.method(),
+        path = %req.uri().path(),
+        app_version = build_info::VERSION,
+        git_commit = build_info::GIT_COMMIT,
+    )
+}
+
+/// Marks responses from the unversioned `/api` alias and points at its replacement.
+async fn deprecated(mut response: Response) -> Response {
+    let headers = response.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static("true"));
+    headers.insert(
+        header::LINK,
+        HeaderValue::from_static("</api/v1>; rel=\"successor-version\""),
+    );
+    response
+}
+
+async fn not_found() -> AppError {
+    AppError::NotFound
+}
+
+/// Answers a panicked handler with the standard 500 envelope; the payload only goes to the log.
+fn panic_response(payload: Box<dyn Any + Send + 'static>) -> Response {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload");
+    AppError::Internal(anyhow::anyhow!("handler panicked: {}", message)).into_response()
+}
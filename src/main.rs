@@ -1,23 +1,16 @@
-use axum::{Router, routing::get};
-use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use std::net::SocketAddr;
+use std::{backtrace::Backtrace, net::SocketAddr, panic, path::Path};
 
-use crate::{
-    config::AppConfig,
-    db::create_pool,
-    routes::{create_api_router, doc::scalar_docs},
+use axum_ecommerce_api::{
+    app, build_info,
+    config::{AppConfig, Listen},
+    db::{MIGRATOR, create_pool},
+    jobs,
+    server::{self, load_tls, reload_tls_on_sighup, serve},
+    state::AppState,
 };
 
-mod config;
-mod db;
-mod error;
-mod middleware;
-mod models;
-mod response;
-mod routes;
-
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenvy::dotenv().ok();
@@ -28,25 +21,53 @@ async fn main() -> anyhow::Result<()> {
         )
         .with(tracing_subscriber::fmt::layer())
         .init();
+    // Handler panics become a 500 in `app`; this puts where they happened in the same log.
+    panic::set_hook(Box::new(|info| {
+        tracing::error!(panic = %info, backtrace = %Backtrace::force_capture(), "panicked");
+    }));
 
-    let config = AppConfig::from_env()?;
-    let pool = create_pool(&config.database_url).await?;
+    tracing::info!(
+        app_version = build_info::VERSION,
+        git_commit = build_info::GIT_COMMIT,
+        built_at = build_info::BUILD_TIMESTAMP,
+        "starting"
+    );
 
-    sqlx::migrate!("./migrations").run(&pool).await?;
+    let config = AppConfig::from_env()?;
+    // Bad key material should stop startup before anything else happens.
+    let tls = match &config.tls {
+        Some(paths) => {
+            let tls = load_tls(Path::new(&paths.cert_path), Path::new(&paths.key_path))?;
+            reload_tls_on_sighup(tls.clone(), paths.cert_path.clone(), paths.key_path.clone());
+            Some(tls)
+        }
+        None => None,
+    };
+    let pool = create_pool(&config).await?;
 
-    let api_router = create_api_router();
+    MIGRATOR.run(&pool).await?;
 
-    let app = Router::new()
-        .route("/health", get(routes::health::health_check))
-        .nest("/api", api_router)
-        .merge(scalar_docs())
-        .layer(TraceLayer::new_for_http())
-        .with_state(pool);
+    let state = AppState::new(pool, &config);
+    let jobs = jobs::scheduler(&config).start(state.clone());
+    let audit = state.audit.clone();
+    let app = app(&config, state);
 
-    let addr = SocketAddr::from((config.host.parse::<std::net::IpAddr>()?, config.port));
-    tracing::info!("listening on {}", addr);
+    match &config.listen {
+        Listen::Tcp => {
+            let addr = SocketAddr::from((config.host.parse::<std::net::IpAddr>()?, config.port));
+            let listener = std::net::TcpListener::bind(addr)?;
+            serve(listener, app, tls, server::shutdown_signal()).await?;
+        }
+        #[cfg(unix)]
+        Listen::Unix { path, mode } => {
+            server::serve_unix(Path::new(path), *mode, app, server::shutdown_signal()).await?;
+        }
+        #[cfg(not(unix))]
+        Listen::Unix { .. } => anyhow::bail!("LISTEN=unix needs a unix platform"),
+    }
 
-    axum::serve(tokio::net::TcpListener::bind(addr).await?, app).await?;
+    drop(jobs);
+    audit.flush().await;
 
     Ok(())
 }
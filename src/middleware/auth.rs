@@ -1,8 +1,40 @@
-use axum::{extract::FromRequestParts, http::header};
-use jsonwebtoken::{DecodingKey, Validation, decode};
+use std::sync::Arc;
+
+use axum::{
+    extract::{FromRef, FromRequestParts, OptionalFromRequestParts},
+    http::header,
+};
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
 use uuid::Uuid;
 
-use crate::{error::AppError, routes::auth::Claims};
+use crate::{cache::UserCache, db::DbPool, error::AppError, routes::auth::Claims};
+
+/// Signing and checking keys for access tokens, built once from `AppConfig::jwt_secret` and
+/// shared through the state instead of being derived on every request.
+pub struct JwtKeys {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    validation: Validation,
+}
+
+impl JwtKeys {
+    pub fn new(secret: &str) -> Self {
+        Self {
+            encoding: EncodingKey::from_secret(secret.as_bytes()),
+            decoding: DecodingKey::from_secret(secret.as_bytes()),
+            validation: Validation::default(),
+        }
+    }
+
+    pub fn encode(&self, claims: &Claims) -> jsonwebtoken::errors::Result<String> {
+        encode(&Header::default(), claims, &self.encoding)
+    }
+
+    /// The claims of a token signed with this key and not yet expired.
+    pub fn decode(&self, token: &str) -> jsonwebtoken::errors::Result<Claims> {
+        decode::<Claims>(token, &self.decoding, &self.validation).map(|data| data.claims)
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct AuthUser {
@@ -10,14 +42,51 @@ pub struct AuthUser {
     pub role: String,
 }
 
+impl AuthUser {
+    /// The user an access token belongs to, with or without the `Bearer ` prefix login hands
+    /// it out with.
+    pub fn from_token(keys: &JwtKeys, token: &str) -> Result<Self, AppError> {
+        let claims = keys
+            .decode(token.trim().trim_start_matches("Bearer ").trim())
+            .map_err(|_| AppError::BadRequest("Invalid or expired token".into()))?;
+
+        let user_id = Uuid::parse_str(&claims.sub)
+            .map_err(|_| AppError::BadRequest("Invalid user id in token".into()))?;
+
+        Ok(AuthUser {
+            user_id,
+            role: claims.role,
+        })
+    }
+
+    /// Like [`AuthUser::from_token`], but also turns away tokens of since-deleted accounts,
+    /// with the same error as any other bad token. Whether the account exists comes from
+    /// `users`, so the database is only asked once in a while per user.
+    pub async fn authenticate(
+        keys: &JwtKeys,
+        users: &UserCache,
+        pool: &DbPool,
+        token: &str,
+    ) -> Result<Self, AppError> {
+        let user = Self::from_token(keys, token)?;
+        if !users.exists(pool, user.user_id).await? {
+            return Err(AppError::BadRequest("Invalid or expired token".into()));
+        }
+        Ok(user)
+    }
+}
+
 impl<S> FromRequestParts<S> for AuthUser
 where
+    DbPool: FromRef<S>,
+    UserCache: FromRef<S>,
+    Arc<JwtKeys>: FromRef<S>,
     S: Send + Sync,
 {
     type Rejection = AppError;
     async fn from_request_parts(
         parts: &mut axum::http::request::Parts,
-        _state: &S,
+        state: &S,
     ) -> Result<Self, Self::Rejection> {
         let auth_header = parts
             .headers
@@ -33,22 +102,34 @@ where
         }
         let token = auth_str.trim_start_matches("Bearer ").trim();
 
-        let secret = std::env::var("JWT_SECRET")
-            .map_err(|_| AppError::Internal(anyhow::anyhow!("JWT_SECRET is not set")))?;
-
-        let decoded = decode::<Claims>(
+        AuthUser::authenticate(
+            &Arc::<JwtKeys>::from_ref(state),
+            &UserCache::from_ref(state),
+            &DbPool::from_ref(state),
             token,
-            &DecodingKey::from_secret(secret.as_bytes()),
-            &Validation::default(),
         )
-        .map_err(|_| AppError::BadRequest("Invalid or expired token".into()))?;
-
-        let user_id = Uuid::parse_str(&decoded.claims.sub)
-            .map_err(|_| AppError::BadRequest("Invalid user id in token".into()))?;
+        .await
+    }
+}
 
-        Ok(AuthUser {
-            user_id,
-            role: decoded.claims.role.clone(),
-        })
+/// `Option<AuthUser>`: `None` without an Authorization header, an error for a bad token.
+impl<S> OptionalFromRequestParts<S> for AuthUser
+where
+    DbPool: FromRef<S>,
+    UserCache: FromRef<S>,
+    Arc<JwtKeys>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        if !parts.headers.contains_key(header::AUTHORIZATION) {
+            return Ok(None);
+        }
+        <AuthUser as FromRequestParts<S>>::from_request_parts(parts, state)
+            .await
+            .map(Some)
     }
 }
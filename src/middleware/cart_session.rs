@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::header,
+};
+use uuid::Uuid;
+
+use crate::{
+    cache::UserCache,
+    db::DbPool,
+    error::AppError,
+    middleware::auth::{AuthUser, JwtKeys},
+};
+
+pub const CART_TOKEN_HEADER: &str = "x-cart-token";
+
+/// Guest cart sessions older than this are rejected and pruned.
+pub const GUEST_CART_TTL_DAYS: i32 = 30;
+
+/// Owner of a cart: an authenticated user, or a guest identified by `X-Cart-Token`.
+#[derive(Debug, Clone, Copy)]
+pub enum CartOwner {
+    User(Uuid),
+    Guest(Uuid),
+}
+
+impl CartOwner {
+    /// `cart_items` column that identifies this owner.
+    pub fn column(&self) -> &'static str {
+        match self {
+            CartOwner::User(_) => "user_id",
+            CartOwner::Guest(_) => "session_token",
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        match self {
+            CartOwner::User(id) | CartOwner::Guest(id) => *id,
+        }
+    }
+}
+
+pub fn cart_token_from_headers(headers: &axum::http::HeaderMap) -> Option<Uuid> {
+    headers
+        .get(CART_TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| Uuid::parse_str(v.trim()).ok())
+}
+
+impl<S> FromRequestParts<S> for CartOwner
+where
+    DbPool: FromRef<S>,
+    UserCache: FromRef<S>,
+    Arc<JwtKeys>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        if parts.headers.contains_key(header::AUTHORIZATION) {
+            let user = AuthUser::from_request_parts(parts, state).await?;
+            return Ok(CartOwner::User(user.user_id));
+        }
+
+        if !parts.headers.contains_key(CART_TOKEN_HEADER) {
+            return Err(AppError::BadRequest(
+                "Missing Authorization or X-Cart-Token header".into(),
+            ));
+        }
+        let token = cart_token_from_headers(&parts.headers)
+            .ok_or_else(|| AppError::BadRequest("Invalid X-Cart-Token header".into()))?;
+
+        let session: Option<(Uuid,)> = sqlx::query_as(
+            "SELECT token FROM cart_sessions WHERE token = $1 AND created_at > NOW() - make_interval(days => $2)",
+        )
+        .bind(token)
+        .bind(GUEST_CART_TTL_DAYS)
+        .fetch_optional(&DbPool::from_ref(state))
+        .await?;
+
+        if session.is_none() {
+            return Err(AppError::BadRequest("Unknown or expired cart token".into()));
+        }
+
+        Ok(CartOwner::Guest(token))
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{FromRef, MatchedPath, Request, State},
+    http::{Method, header},
+    middleware::Next,
+    response::Response,
+};
+use rand::Rng;
+
+use crate::{
+    audit::{AuditAction, AuditEvent, AuditLog},
+    middleware::{
+        auth::{AuthUser, JwtKeys},
+        request_context::RequestContext,
+    },
+};
+
+/// State for [`audit_mutations`]: where events go and which share of requests are recorded,
+/// from `AppConfig::audit_http_sample_rate`, plus the keys that identify the actor.
+#[derive(Clone, FromRef)]
+pub struct HttpAudit {
+    pub log: AuditLog,
+    pub sample_rate: f64,
+    pub jwt: Arc<JwtKeys>,
+}
+
+/// Records an `http.mutation` audit event for every (sampled) POST, PUT, PATCH or DELETE:
+/// method, route template and response status, never the body. This is the coarse trail;
+/// handlers still record their own, more specific actions.
+pub async fn audit_mutations(
+    State(audit): State<HttpAudit>,
+    context: RequestContext,
+    req: Request,
+    next: Next,
+) -> Response {
+    let method = req.method().clone();
+    if !matches!(
+        method,
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    ) || !rand::thread_rng().gen_bool(audit.sample_rate)
+    {
+        return next.run(req).await;
+    }
+    // only the token is checked; a since-deleted actor is dropped when the event is written
+    let user = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|token| AuthUser::from_token(&audit.jwt, token).ok());
+    let path = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| req.uri().path().to_string());
+
+    let response = next.run(req).await;
+
+    let mut event = AuditEvent::new(AuditAction::HttpMutation, "http_request")
+        .details(serde_json::json!({
+            "method": method.as_str(),
+            "path": path,
+            "status": response.status().as_u16(),
+        }))
+        .context(&context);
+    if let Some(user) = user {
+        event = event.actor(user.user_id);
+    }
+    audit.log.record(event);
+    response
+}
@@ -1 +1,6 @@
 pub mod auth;
+pub mod cart_session;
+pub mod http_audit;
+pub mod rate_limit;
+pub mod request_context;
+pub mod request_id;
@@ -0,0 +1,117 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    Extension,
+    extract::Request,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::{
+    config::{AppConfig, RateLimit},
+    error::AppError,
+    middleware::request_context::client_ip,
+};
+
+/// Buckets kept before idle ones are swept, so a flood of addresses cannot grow the map forever.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket per client IP.
+#[derive(Clone)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    buckets: Arc<Mutex<HashMap<IpAddr, Bucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            buckets: Arc::default(),
+        }
+    }
+
+    /// Takes a token for `ip`, or says how long until the next one is available.
+    pub fn check(&self, ip: IpAddr) -> Result<(), Duration> {
+        let now = Instant::now();
+        let burst = f64::from(self.limit.burst);
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_CLIENTS {
+            let full_after = burst / self.limit.per_second;
+            buckets.retain(|_, b| now.duration_since(b.updated).as_secs_f64() < full_after);
+        }
+
+        let bucket = buckets.entry(ip).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.limit.per_second).min(burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / self.limit.per_second,
+            ))
+        }
+    }
+}
+
+/// The limiters `app` installs as a request extension, shared by `/api/v1` and its alias.
+#[derive(Clone)]
+pub struct RateLimits {
+    pub default: RateLimiter,
+    pub auth: RateLimiter,
+    pub trust_proxy: bool,
+}
+
+impl RateLimits {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            default: RateLimiter::new(config.rate_limit),
+            auth: RateLimiter::new(config.auth_rate_limit),
+            trust_proxy: config.trust_proxy,
+        }
+    }
+}
+
+/// Applies the default limit to every request.
+pub async fn limit_default(
+    Extension(limits): Extension<RateLimits>,
+    req: Request,
+    next: Next,
+) -> Response {
+    limit(&limits.default, limits.trust_proxy, req, next).await
+}
+
+/// Applies the auth limit, on top of the default one.
+pub async fn limit_auth(
+    Extension(limits): Extension<RateLimits>,
+    req: Request,
+    next: Next,
+) -> Response {
+    limit(&limits.auth, limits.trust_proxy, req, next).await
+}
+
+async fn limit(limiter: &RateLimiter, trust_proxy: bool, req: Request, next: Next) -> Response {
+    // Without a known address (no connect info, e.g. in tests) there is nothing to key on.
+    if let Some(ip) = client_ip(req.headers(), req.extensions(), trust_proxy)
+        && let Err(wait) = limiter.check(ip)
+    {
+        return AppError::TooManyRequests(wait.as_secs_f64().ceil() as u64).into_response();
+    }
+    next.run(req).await
+}
@@ -0,0 +1,78 @@
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts, Request, State},
+    http::{Extensions, HeaderMap, header, request::Parts},
+    middleware::Next,
+    response::Response,
+};
+
+use super::request_id::current_request_id;
+
+/// Longest user agent kept; the rest is cut off.
+const MAX_USER_AGENT_LEN: usize = 512;
+
+/// Who sent the request being handled, for audit entries. Inserted by [`request_context`];
+/// extracting it outside that middleware gives an empty context.
+#[derive(Debug, Clone, Default)]
+pub struct RequestContext {
+    pub request_id: Option<String>,
+    pub ip_address: Option<IpAddr>,
+    pub user_agent: Option<String>,
+}
+
+/// Records the request id, client IP and user agent in the request extensions. Must run
+/// inside `request_id`; `trust_proxy` is `AppConfig::trust_proxy`.
+pub async fn request_context(
+    State(trust_proxy): State<bool>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let context = RequestContext {
+        request_id: current_request_id(),
+        ip_address: client_ip(req.headers(), req.extensions(), trust_proxy),
+        user_agent: req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.chars().take(MAX_USER_AGENT_LEN).collect()),
+    };
+    req.extensions_mut().insert(context);
+    next.run(req).await
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for RequestContext {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<RequestContext>()
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+/// The peer address, or behind a trusted proxy the last `X-Forwarded-For` hop, which is the
+/// one the proxy itself appended and the client cannot forge.
+pub fn client_ip(
+    headers: &HeaderMap,
+    extensions: &Extensions,
+    trust_proxy: bool,
+) -> Option<IpAddr> {
+    if trust_proxy
+        && let Some(ip) = headers
+            .get("x-forwarded-for")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit(',').next())
+            .and_then(|v| v.trim().parse().ok())
+    {
+        return Some(ip);
+    }
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+}
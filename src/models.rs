@@ -3,22 +3,117 @@ use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
+use crate::money::{Money, serialize_display};
+
 #[derive(Debug, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
 pub struct User {
     pub id: Uuid,
     pub email: String,
     pub password_hash: String,
     pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
     pub role: String,
+    #[schema(example = "Jane Doe")]
+    pub full_name: Option<String>,
+    /// Digits with an optional leading `+`, separators removed
+    #[schema(example = "+6281234567890")]
+    pub phone: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+/// A user as shown to themselves and to admins, without the password hash.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserProfile {
+    pub id: Uuid,
+    #[schema(example = "jane@example.com")]
+    pub email: String,
+    pub role: String,
+    #[schema(example = "Jane Doe")]
+    pub full_name: Option<String>,
+    #[schema(example = "+6281234567890")]
+    pub phone: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<User> for UserProfile {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            email: user.email,
+            role: user.role,
+            full_name: user.full_name,
+            phone: user.phone,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
 pub struct Product {
     pub id: Uuid,
+    #[schema(example = "Ceramic Mug")]
     pub name: String,
+    /// URL-safe name, unique across products
+    #[schema(example = "ceramic-mug")]
+    pub slug: String,
+    #[schema(example = "MUG-350-WHT")]
+    pub sku: Option<String>,
+    pub is_published: bool,
+    #[schema(example = "350 ml, dishwasher safe")]
     pub description: Option<String>,
-    pub price: i64,
+    /// Price in the smallest currency unit (cents)
+    #[schema(value_type = i64, example = 1250)]
+    pub price: Money,
+    /// `price` formatted in the shop's currency
+    #[sqlx(rename = "price")]
+    #[serde(serialize_with = "serialize_display", skip_deserializing)]
+    #[schema(value_type = String, example = "$12.50")]
+    pub price_display: Money,
+    #[schema(example = 10)]
     pub stock: i32,
+    /// Units held by pending orders; `stock - reserved` can still be sold
+    #[schema(example = 2)]
+    pub reserved: i32,
+    pub category_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    /// Last change to any column, stock included; set by the database
+    pub updated_at: DateTime<Utc>,
+    /// When an admin deleted the product; only deleted products carry it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    #[sqlx(skip)]
+    pub category: Option<Category>,
+    #[sqlx(skip)]
+    pub images: Vec<ProductImage>,
+    #[sqlx(skip)]
+    pub average_rating: Option<f64>,
+    #[sqlx(skip)]
+    pub review_count: i64,
+    #[sqlx(skip)]
+    pub favorite_count: i64,
+    /// Whether the requesting user has favorited the product; false for anonymous requests
+    #[sqlx(skip)]
+    pub is_favorited: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct ProductImage {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub url: String,
+    #[serde(skip)]
+    pub storage_key: String,
+    pub alt: Option<String>,
+    pub position: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct Category {
+    pub id: Uuid,
+    pub name: String,
+    pub slug: String,
     pub created_at: DateTime<Utc>,
 }
 
@@ -34,18 +129,72 @@ pub struct Favorite {
 pub struct CartItem {
     pub id: Uuid,
     pub product_id: Uuid,
-    pub user_id: Uuid,
-    pub quantity: i64,
+    pub user_id: Option<Uuid>,
+    pub session_token: Option<Uuid>,
+    #[schema(example = 2)]
+    pub quantity: i32,
+    /// Unit price in cents when the line was first added
+    #[schema(value_type = i64, example = 1250)]
+    pub price_at_add: Money,
+    /// Saved for later: kept out of the cart totals and checkout
+    pub saved: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct CartSession {
+    pub token: Uuid,
     pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
 pub struct Order {
     pub id: Uuid,
-    pub user_id: Uuid,
-    pub total_amount: i64,
-    pub status: String,
+    /// Unset once the customer has deleted their account
+    pub user_id: Option<Uuid>,
+    /// Sum of the order lines in cents
+    #[schema(value_type = i64, example = 2500)]
+    pub total_amount: Money,
+    /// `total_amount` formatted in the shop's currency
+    #[sqlx(rename = "total_amount")]
+    #[serde(serialize_with = "serialize_display", skip_deserializing)]
+    #[schema(value_type = String, example = "$25.00")]
+    pub total_amount_display: Money,
+    pub status: OrderStatus,
+    /// The buyer's `full_name` at checkout
+    #[schema(example = "Jane Doe")]
+    pub recipient_name: Option<String>,
+    /// The buyer's `phone` at checkout
+    #[schema(example = "+6281234567890")]
+    pub recipient_phone: Option<String>,
+    /// When the order was paid; unset until then
+    pub paid_at: Option<DateTime<Utc>>,
+    /// When the order was completed; unset until then
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Set when an admin cancels an order after payment, until the buyer is refunded
+    pub refund_needed: bool,
     pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Where an order stands, stored as text in `orders.status`. Checkout creates `pending`
+/// orders; `paid` and `completed` ones count as purchases, `cancelled` ones never do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum OrderStatus {
+    Pending,
+    Paid,
+    Completed,
+    Cancelled,
+}
+
+impl OrderStatus {
+    /// Whether the order counts as a purchase, for reviews and best sellers.
+    pub fn is_paid(self) -> bool {
+        matches!(self, OrderStatus::Paid | OrderStatus::Completed)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
@@ -53,6 +202,108 @@ pub struct OrderItem {
     pub id: Uuid,
     pub order_id: Uuid,
     pub product_id: Uuid,
-    pub quprice: i64,
+    pub quantity: i32,
+    /// Unit price in cents at checkout
+    #[schema(value_type = i64, example = 1250)]
+    pub price: Money,
+    /// `price` formatted in the shop's currency
+    #[sqlx(rename = "price")]
+    #[serde(serialize_with = "serialize_display", skip_deserializing)]
+    #[schema(value_type = String, example = "$12.50")]
+    pub price_display: Money,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct ProductPriceChange {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub old_price: Option<i64>,
+    pub new_price: i64,
+    /// Admin who set the price; unset for rows backfilled or seeded without one, or whose
+    /// admin has since been deleted
+    pub changed_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Why a product's stock changed, stored as text in `stock_movements.reason`. Sales are
+/// recorded when an order is paid; the rest come from inventory adjustments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum StockMovementReason {
+    Restock,
+    Correction,
+    Damage,
+    Sale,
+    Return,
+}
+
+/// One entry of a product's stock ledger; its `delta`s sum to the product's `stock`.
+#[derive(Debug, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct StockMovement {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    /// Units added, or taken out when negative
+    #[schema(example = -2)]
+    pub delta: i32,
+    pub reason: StockMovementReason,
+    /// What the change relates to, e.g. the paid order for a sale
+    pub reference_id: Option<Uuid>,
+    /// User who made the change; unset for opening balances and since-deleted users
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A product whose available units fell below the low-stock threshold, see `stock_alerts`.
+#[derive(Debug, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct StockAlert {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    #[schema(example = "Ceramic Mug")]
+    pub product_name: String,
+    /// `stock - reserved` when the alert was raised
+    #[schema(example = 3)]
+    pub available: i32,
+    #[schema(example = 5)]
+    pub threshold: i32,
+    pub created_at: DateTime<Utc>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    /// Admin who acknowledged it; unset until then, or once that admin is deleted
+    pub acknowledged_by: Option<Uuid>,
+    /// When the product was back at the threshold; the next drop raises a new alert
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// One entry of the audit trail, see `audit::AuditEvent`.
+#[derive(Debug, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct AuditLogEntry {
+    pub id: Uuid,
+    /// User who acted; unset for guests, failed logins and since-deleted users
+    pub actor_id: Option<Uuid>,
+    /// An `AuditAction`, as text so entries for retired actions still load
+    #[schema(example = "product.update")]
+    pub action: String,
+    #[schema(example = "product")]
+    pub entity_type: String,
+    pub entity_id: Option<Uuid>,
+    /// What the action changed, depending on the action
+    #[schema(value_type = Object)]
+    pub details: serde_json::Value,
+    /// Request that caused it, as in the request id response header; unset for background jobs
+    /// and entries older than request tracking
+    pub request_id: Option<String>,
+    #[schema(example = "203.0.113.7")]
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, sqlx::FromRow)]
+pub struct Review {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub product_id: Uuid,
+    pub rating: i32,
+    pub comment: Option<String>,
     pub created_at: DateTime<Utc>,
 }
@@ -0,0 +1,196 @@
+//! Amounts of money in the shop's currency.
+//!
+//! [`Money`] is a count of minor units (cents for USD), serialized as a bare integer as it
+//! always was. Responses add a formatted `*_display` field next to each amount, written with
+//! [`serialize_display`] in the currency set by [`set_currency`].
+
+use std::{fmt, str::FromStr, sync::OnceLock};
+
+use serde::{Deserialize, Serialize, Serializer};
+use utoipa::ToSchema;
+
+/// An amount in minor units of the shop's currency, e.g. `1250` for $12.50.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    ToSchema,
+    sqlx::Type,
+)]
+#[serde(transparent)]
+#[sqlx(transparent)]
+#[schema(value_type = i64, example = 1250)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub const fn from_minor(minor: i64) -> Self {
+        Money(minor)
+    }
+
+    /// The amount in minor units.
+    pub const fn minor(self) -> i64 {
+        self.0
+    }
+
+    pub const fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    /// `None` on overflow.
+    pub fn checked_add(self, other: Money) -> Option<Money> {
+        self.0.checked_add(other.0).map(Money)
+    }
+
+    /// This amount `quantity` times, e.g. a line total; `None` on overflow.
+    pub fn checked_mul(self, quantity: i64) -> Option<Money> {
+        self.0.checked_mul(quantity).map(Money)
+    }
+
+    /// The total of `amounts`; `None` on overflow.
+    pub fn checked_sum(amounts: impl IntoIterator<Item = Money>) -> Option<Money> {
+        amounts
+            .into_iter()
+            .try_fold(Money::ZERO, |total, amount| total.checked_add(amount))
+    }
+
+    /// E.g. `$1,234.50` or `Rp 550.000`.
+    pub fn format(self, currency: &Currency) -> String {
+        let minor = self.0.unsigned_abs();
+        let unit = 10u64.pow(currency.minor_digits);
+        let digits = (minor / unit).to_string();
+        let mut out = String::new();
+        if self.0 < 0 {
+            out.push('-');
+        }
+        out.push_str(currency.symbol);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                out.push(currency.thousands_separator);
+            }
+            out.push(digit);
+        }
+        if currency.minor_digits > 0 {
+            out.push(currency.decimal_separator);
+            let fraction = minor % unit;
+            out.push_str(&format!(
+                "{:0width$}",
+                fraction,
+                width = currency.minor_digits as usize
+            ));
+        }
+        out
+    }
+}
+
+impl fmt::Display for Money {
+    /// Formatted in the shop's currency.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format(currency()))
+    }
+}
+
+/// How a currency's amounts are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Currency {
+    /// ISO 4217 code, e.g. `USD`.
+    pub code: &'static str,
+    /// Written before the amount, with any space it needs.
+    pub symbol: &'static str,
+    /// Digits after the decimal separator; minor units per major unit are 10 to this power.
+    pub minor_digits: u32,
+    pub thousands_separator: char,
+    pub decimal_separator: char,
+}
+
+impl Currency {
+    pub const USD: Currency = Currency {
+        code: "USD",
+        symbol: "$",
+        minor_digits: 2,
+        thousands_separator: ',',
+        decimal_separator: '.',
+    };
+    pub const EUR: Currency = Currency {
+        code: "EUR",
+        symbol: "€",
+        minor_digits: 2,
+        thousands_separator: '.',
+        decimal_separator: ',',
+    };
+    pub const GBP: Currency = Currency {
+        code: "GBP",
+        symbol: "£",
+        minor_digits: 2,
+        thousands_separator: ',',
+        decimal_separator: '.',
+    };
+    /// Rupiah amounts are whole; prices are stored in rupiah, not sen.
+    pub const IDR: Currency = Currency {
+        code: "IDR",
+        symbol: "Rp ",
+        minor_digits: 0,
+        thousands_separator: '.',
+        decimal_separator: ',',
+    };
+    pub const JPY: Currency = Currency {
+        code: "JPY",
+        symbol: "¥",
+        minor_digits: 0,
+        thousands_separator: ',',
+        decimal_separator: '.',
+    };
+
+    const ALL: [Currency; 5] = [
+        Currency::USD,
+        Currency::EUR,
+        Currency::GBP,
+        Currency::IDR,
+        Currency::JPY,
+    ];
+}
+
+impl FromStr for Currency {
+    type Err = anyhow::Error;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        Currency::ALL
+            .into_iter()
+            .find(|currency| currency.code.eq_ignore_ascii_case(code.trim()))
+            .ok_or_else(|| {
+                let known: Vec<&str> = Currency::ALL.iter().map(|c| c.code).collect();
+                anyhow::anyhow!(
+                    "unknown currency {:?}, expected one of {}",
+                    code,
+                    known.join(", ")
+                )
+            })
+    }
+}
+
+static CURRENCY: OnceLock<Currency> = OnceLock::new();
+
+/// Sets the currency amounts are displayed in, from `AppConfig::currency`. The first call
+/// wins; later ones, e.g. from further test apps, are ignored.
+pub fn set_currency(currency: Currency) {
+    CURRENCY.get_or_init(|| currency);
+}
+
+/// The shop's currency; USD until [`set_currency`] is called.
+pub fn currency() -> &'static Currency {
+    CURRENCY.get().unwrap_or(&Currency::USD)
+}
+
+/// Serializes an amount as its formatted string, for `*_display` fields.
+pub fn serialize_display<S: Serializer>(money: &Money, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(money)
+}
@@ -1,31 +1,148 @@
-use serde::Serialize;
-use utoipa::ToSchema;
+use axum::{
+    Json,
+    http::{HeaderName, StatusCode, header},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    error::{AppError, AppResult, FieldError, FieldErrors},
+    middleware::request_id::current_request_id,
+};
 
 #[derive(Debug, Serialize, ToSchema, Clone)]
 pub struct Meta {
+    /// 1-based page number
+    #[schema(example = 1)]
     pub page: Option<i64>,
+    #[schema(example = 10)]
     pub per_page: Option<i64>,
+    /// Items across all pages
+    #[schema(example = 42)]
     pub total: Option<i64>,
+    #[schema(example = 5)]
+    pub total_pages: Option<i64>,
+    #[schema(example = true)]
+    pub has_next: Option<bool>,
+    #[schema(example = false)]
+    pub has_prev: Option<bool>,
+    /// Opaque cursor for the next page, on listings that support keyset pagination
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// Correlation id of the request, set on error responses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl Meta {
     pub fn new(page: i64, per_page: i64, total: i64) -> Self {
+        // Unpaginated lists pass `per_page = total`, which is 0 for an empty list.
+        let total_pages = if per_page > 0 {
+            (total + per_page - 1) / per_page
+        } else {
+            i64::from(total > 0)
+        };
         Self {
             page: Some(page),
             per_page: Some(per_page),
             total: Some(total),
+            total_pages: Some(total_pages),
+            has_next: Some(page < total_pages),
+            has_prev: Some(page > 1),
+            next_cursor: None,
+            request_id: None,
         }
     }
 
+    /// For a page read with `per_page + 1` rows: drops the extra row from `items`, its presence
+    /// being what sets `has_next`. `total` is `None` when the count was skipped
+    /// (`with_total=false`), and so are `total` and `total_pages` then.
+    pub fn for_page<T>(page: i64, per_page: i64, total: Option<i64>, items: &mut Vec<T>) -> Self {
+        let has_next = items.len() as i64 > per_page;
+        items.truncate(per_page as usize);
+        let mut meta = match total {
+            Some(total) => Meta::new(page, per_page, total),
+            None => Self {
+                page: Some(page),
+                per_page: Some(per_page),
+                has_prev: Some(page > 1),
+                ..Self::empty()
+            },
+        };
+        meta.has_next = Some(has_next);
+        meta
+    }
+
     pub fn empty() -> Self {
         Self {
             page: None,
             per_page: None,
             total: None,
+            total_pages: None,
+            has_next: None,
+            has_prev: None,
+            next_cursor: None,
+            request_id: None,
         }
     }
 }
 
+/// Largest `per_page`; more is refused like any other out-of-range value.
+pub const MAX_PER_PAGE: i64 = 100;
+
+/// `(page, limit, offset)` for the `page` and `per_page` query parameters, defaulting to the
+/// first page of 10. Either one out of range is a 400 naming it.
+pub fn resolve_page(page: Option<i64>, per_page: Option<i64>) -> AppResult<(i64, i64, i64)> {
+    let page = page.unwrap_or(1);
+    let per_page = per_page.unwrap_or(10);
+    let mut errors = FieldErrors::default();
+    if page < 1 {
+        errors.add("page", "out_of_range", "page must be at least 1");
+    }
+    if per_page < 1 {
+        errors.add("per_page", "out_of_range", "per_page must be at least 1");
+    } else if per_page > MAX_PER_PAGE {
+        errors.add(
+            "per_page",
+            "out_of_range",
+            format!("per_page must be at most {}", MAX_PER_PAGE),
+        );
+    }
+    errors.finish_query()?;
+    let offset = (page - 1).checked_mul(per_page).ok_or_else(|| {
+        AppError::InvalidQuery(vec![FieldError::new(
+            "page",
+            "out_of_range",
+            "page is too large",
+        )])
+    })?;
+    Ok((page, per_page, offset))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageParams {
+    /// Page number from 1, default 1
+    pub page: Option<i64>,
+    /// Items per page from 1 to 100, default 10
+    pub per_page: Option<i64>,
+    /// false skips counting every match: `total` and `total_pages` are left out, `has_next`
+    /// is still set. Default true
+    pub with_total: Option<bool>,
+}
+
+impl PageParams {
+    /// Returns `(page, limit, offset)`, see [`resolve_page`].
+    pub fn resolve(&self) -> AppResult<(i64, i64, i64)> {
+        resolve_page(self.page, self.per_page)
+    }
+
+    /// Whether the listing should run its count query.
+    pub fn with_total(&self) -> bool {
+        self.with_total.unwrap_or(true)
+    }
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct ApiResponse<T> {
     pub message: String,
@@ -41,4 +158,28 @@ impl<T: Serialize> ApiResponse<T> {
             meta,
         }
     }
+
+    /// Envelope for failed requests; `data` is normally an `ErrorData`.
+    pub fn error(message: impl Into<String>, data: T) -> Self {
+        Self {
+            message: message.into(),
+            data: Some(data),
+            meta: Some(Meta {
+                request_id: current_request_id(),
+                ..Meta::empty()
+            }),
+        }
+    }
+}
+
+/// Response for endpoints that create a resource: status, `Location` header and the usual body.
+pub type Located<T> = (StatusCode, [(HeaderName, String); 1], Json<ApiResponse<T>>);
+
+/// `201 Created` pointing at `location`, e.g. `/api/v1/products/{id}`.
+pub fn created<T>(location: String, body: ApiResponse<T>) -> Located<T> {
+    (
+        StatusCode::CREATED,
+        [(header::LOCATION, location)],
+        Json(body),
+    )
 }
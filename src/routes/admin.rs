@@ -1,41 +1,576 @@
+use std::sync::Arc;
+
 use axum::{
-    Json, Router,
-    extract::{Path, State},
-    routing::get,
+    Json,
+    body::{Body, Bytes},
+    extract::{
+        Path, State,
+        ws::{
+            Message, WebSocket, WebSocketUpgrade, close_code, rejection::WebSocketUpgradeRejection,
+        },
+    },
+    http::header,
+    response::{IntoResponse, Response},
 };
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
+use utoipa::{IntoParams, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
 use uuid::Uuid;
 
 use crate::{
-    db::DbPool,
-    error::{AppError, AppResult},
-    middleware::auth::AuthUser,
-    models::{Order, OrderItem},
-    response::{ApiResponse, Meta},
-    routes::orders::{OrderList, OrderWithItems},
+    admin_events::{self, AUTH_TIMEOUT, AdminEvent, AdminEvents},
+    audit::{AuditAction, AuditEvent, AuditLog, PRUNE_BATCH_SIZE, prune_older_than},
+    cache::{CacheStats, ProductCache, UserCache},
+    db::{DbPool, escape_like},
+    error::{AppError, AppResult, ErrorCode, ErrorData, FieldError, FieldErrors},
+    extract::{AppJson, AppQuery},
+    jobs::{JobRegistry, JobStatus},
+    middleware::{
+        auth::{AuthUser, JwtKeys},
+        cart_session::CartOwner,
+        request_context::RequestContext,
+    },
+    models::{
+        AuditLogEntry, Order, OrderStatus, Product, ProductPriceChange, StockAlert, StockMovement,
+        StockMovementReason, User, UserProfile,
+    },
+    money::{Money, serialize_display},
+    notifier::{self, Notifier, OrderEvent},
+    response::{ApiResponse, Meta, PageParams},
+    routes::{
+        cart::{CartList, fetch_cart},
+        orders::{
+            OrderList, OrderListQuery, OrderSummary, OrderWithItems, PAID_ORDER_STATUSES,
+            commit_reservations, fetch_order_with_items, list_order_summaries, lock_pending_order,
+            release_reservations, restock_order,
+        },
+        products::{
+            ProductList, ProductQuery, load_product_details, push_product_filters,
+            record_stock_movements, validate_price_range,
+        },
+    },
+    state::AppState,
+    stock_alerts::LowStockThreshold,
 };
 
-#[derive(Debug, Clone)]
-pub struct AdminGuard;
+#[derive(Serialize, ToSchema)]
+pub struct PriceHistoryList {
+    pub items: Vec<ProductPriceChange>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct StockMovementList {
+    pub items: Vec<StockMovement>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct InventoryAdjustRequest {
+    /// Units added, or taken out when negative; never zero
+    #[schema(example = 24)]
+    pub delta: i32,
+    /// `restock` and `return` add units, `damage` takes them out, `correction` goes either
+    /// way; `sale` is only recorded by paying an order
+    pub reason: StockMovementReason,
+    /// What the change relates to, e.g. a delivery or a returned order
+    pub reference_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ForceStatusRequest {
+    /// `completed` or `cancelled`
+    pub status: OrderStatus,
+    /// Why the normal transitions are skipped, e.g. a lost shipment; kept in the audit log
+    #[schema(example = "Parcel lost by the carrier")]
+    pub reason: String,
+    /// When cancelling a paid or completed order, put its units back in stock. Default false;
+    /// a pending order always gives its reserved units back
+    #[serde(default)]
+    pub restock: bool,
+}
+
+/// Which products `/admin/products` lists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DeletedFilter {
+    /// Live products only
+    #[default]
+    Exclude,
+    /// Live and deleted products
+    Include,
+    /// Deleted products only
+    Only,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AdminProductQuery {
+    /// `exclude` (default), `include` or `only` deleted products
+    pub deleted: Option<DeletedFilter>,
+    /// Page number from 1, default 1
+    pub page: Option<i64>,
+    /// Items per page from 1 to 100, default 10
+    pub per_page: Option<i64>,
+    /// false skips counting every match: `total` and `total_pages` are left out, `has_next`
+    /// is still set. Default true
+    pub with_total: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct RestoreProductRequest {
+    /// Publish the product again; it stays unpublished otherwise. Default false
+    #[serde(default)]
+    pub publish: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct StockAlertList {
+    pub items: Vec<StockAlert>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StockAlertQuery {
+    /// Only acknowledged (true) or unacknowledged (false) alerts
+    pub acknowledged: Option<bool>,
+    /// Only alerts of restocked products (true) or still low ones (false)
+    pub resolved: Option<bool>,
+    /// Page number from 1, default 1
+    pub page: Option<i64>,
+    /// Items per page from 1 to 100, default 10
+    pub per_page: Option<i64>,
+    /// false skips counting every match: `total` and `total_pages` are left out, `has_next`
+    /// is still set. Default true
+    pub with_total: Option<bool>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct JobList {
+    pub items: Vec<JobStatus>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AuditLogList {
+    pub items: Vec<AuditLogEntry>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditLogQuery {
+    /// Page number from 1, default 1
+    pub page: Option<i64>,
+    /// Items per page from 1 to 100, default 10
+    pub per_page: Option<i64>,
+    /// Only entries by this user
+    pub user_id: Option<Uuid>,
+    /// Only entries for this action
+    pub action: Option<AuditAction>,
+    /// Type of entity acted on, e.g. `product`
+    pub resource: Option<String>,
+    /// Only entries caused by the request with this id
+    pub request_id: Option<String>,
+    /// Only entries at or after this time (RFC 3339)
+    pub from: Option<DateTime<Utc>>,
+    /// Only entries before this time (RFC 3339)
+    pub to: Option<DateTime<Utc>>,
+    /// false skips counting every match: `total` and `total_pages` are left out, `has_next`
+    /// is still set. Default true
+    pub with_total: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct LowStockProduct {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub product: Product,
+    /// `stock - reserved`: units that can still be sold
+    pub available: i32,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LowStockList {
+    pub items: Vec<LowStockProduct>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LowStockQuery {
+    /// Products with fewer units available than this, default `LOW_STOCK_THRESHOLD` (5)
+    pub threshold: Option<i32>,
+    /// Page number from 1, default 1
+    pub page: Option<i64>,
+    /// Items per page from 1 to 100, default 10
+    pub per_page: Option<i64>,
+    /// false skips counting every match: `total` and `total_pages` are left out, `has_next`
+    /// is still set. Default true
+    pub with_total: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LowStockExportQuery {
+    /// Products with fewer units available than this, default `LOW_STOCK_THRESHOLD` (5)
+    pub threshold: Option<i32>,
+}
+
+/// One line of the low-stock reorder sheet.
+#[derive(sqlx::FromRow)]
+struct LowStockExportRow {
+    id: Uuid,
+    sku: Option<String>,
+    name: String,
+    stock: i32,
+    reserved: i32,
+    available: i32,
+    units_sold: i64,
+}
+
+/// An order as listed for admins, with who placed it.
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct AdminOrderSummary {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub summary: OrderSummary,
+    #[schema(example = "jane@example.com")]
+    pub customer_email: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AdminOrderList {
+    pub items: Vec<AdminOrderSummary>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OrderSearchQuery {
+    /// Part of the customer's email, matched case-insensitively, e.g. `jane@`
+    pub email: String,
+    /// Only orders in this status
+    pub status: Option<OrderStatus>,
+    /// Page number from 1, default 1
+    pub page: Option<i64>,
+    /// Items per page from 1 to 100, default 10
+    pub per_page: Option<i64>,
+    /// false skips counting every match: `total` and `total_pages` are left out, `has_next`
+    /// is still set. Default true
+    pub with_total: Option<bool>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UserList {
+    pub items: Vec<UserProfile>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditPruneQuery {
+    /// Delete entries created before this time (RFC 3339)
+    pub before: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AuditPruneResult {
+    #[schema(example = 1200)]
+    pub deleted: u64,
+}
+
+/// Orders placed in one period of the dashboard.
+#[derive(Serialize, ToSchema)]
+pub struct PeriodStats {
+    /// Orders placed, cancelled ones excluded
+    #[schema(example = 12)]
+    pub orders: i64,
+    /// Sum of the paid and completed orders placed, in cents
+    #[schema(value_type = i64, example = 48000)]
+    pub revenue: Money,
+    /// `revenue` formatted in the shop's currency
+    #[serde(serialize_with = "serialize_display")]
+    #[schema(value_type = String, example = "$480.00")]
+    pub revenue_display: Money,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct OrderStatusCounts {
+    #[schema(example = 3)]
+    pub pending: i64,
+    /// Paid or completed
+    #[schema(example = 40)]
+    pub paid: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DashboardStats {
+    /// Since midnight UTC
+    pub today: PeriodStats,
+    /// Since Monday midnight UTC
+    pub week: PeriodStats,
+    /// Since the first of the month, midnight UTC
+    pub month: PeriodStats,
+    /// All orders, by status
+    pub orders_by_status: OrderStatusCounts,
+    #[schema(example = 250)]
+    pub users: i64,
+    #[schema(example = 80)]
+    pub products: i64,
+    /// Products with fewer than `low_stock_threshold` units available
+    #[schema(example = 4)]
+    pub low_stock_products: i64,
+    #[schema(example = 5)]
+    pub low_stock_threshold: i32,
+    /// The 5 newest orders
+    pub recent_orders: Vec<Order>,
+}
+
+/// Order counts and revenue per dashboard period, plus the status breakdown.
+#[derive(sqlx::FromRow)]
+struct DashboardOrderRow {
+    orders_today: i64,
+    revenue_today: Money,
+    orders_week: i64,
+    revenue_week: Money,
+    orders_month: i64,
+    revenue_month: Money,
+    pending: i64,
+    paid: i64,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TopProductsQuery {
+    /// Only orders placed at or after this time (RFC 3339)
+    pub from: Option<DateTime<Utc>>,
+    /// Only orders placed before this time (RFC 3339)
+    pub to: Option<DateTime<Utc>>,
+    /// Number of products, default 20, max 100
+    pub limit: Option<i64>,
+    /// Also list products without sales in the window, at the bottom. Default false
+    pub include_zero: Option<bool>,
+}
+
+/// Sales of one product in paid or completed orders within the report window.
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct TopProduct {
+    pub product_id: Uuid,
+    #[schema(example = "Ceramic Mug")]
+    pub name: String,
+    pub sku: Option<String>,
+    #[schema(example = 42)]
+    pub units_sold: i64,
+    /// Sum of price × quantity over the order lines, in cents
+    #[schema(value_type = i64, example = 52500)]
+    pub revenue: Money,
+    /// `revenue` formatted in the shop's currency
+    #[sqlx(rename = "revenue")]
+    #[serde(serialize_with = "serialize_display")]
+    #[schema(value_type = String, example = "$525.00")]
+    pub revenue_display: Money,
+    /// Distinct users who bought it
+    #[schema(example = 17)]
+    pub buyers: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TopProductList {
+    pub items: Vec<TopProduct>,
+}
+
+/// Orders listed under `recent_orders` on the dashboard.
+const DASHBOARD_RECENT_ORDERS: i64 = 5;
+
+/// Days of sales in the `last_30d_units_sold` column of the low-stock export.
+const LOW_STOCK_SALES_DAYS: i32 = 30;
+
+/// Longest `reason` accepted when forcing an order status.
+const MAX_FORCE_REASON_LEN: usize = 500;
+
+/// Rows fetched per round trip while streaming the product export.
+const EXPORT_CHUNK_SIZE: i64 = 500;
 
-fn ensure_admin(user: &AuthUser) -> Result<(), AppError> {
+pub(crate) fn ensure_admin(user: &AuthUser) -> Result<(), AppError> {
     if user.role != "admin" {
         return Err(AppError::Forbidden);
     }
     Ok(())
 }
 
-pub fn router() -> Router<DbPool> {
-    Router::new()
-        .route("/orders", get(list_all_orders))
-        .route("/orders/{id}", get(get_order_admin))
+pub fn router() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(list_users))
+        .routes(routes!(list_user_orders))
+        .routes(routes!(get_user_cart))
+        .routes(routes!(list_all_orders))
+        .routes(routes!(search_orders))
+        .routes(routes!(get_order_admin))
+        .routes(routes!(pay_order))
+        .routes(routes!(force_order_status))
+        .routes(routes!(admin_socket))
+        .routes(routes!(list_low_stock))
+        .routes(routes!(export_low_stock))
+        .routes(routes!(dashboard))
+        .routes(routes!(top_products_report))
+        .routes(routes!(list_admin_products))
+        .routes(routes!(restore_product))
+        .routes(routes!(export_products))
+        .routes(routes!(product_price_history))
+        .routes(routes!(adjust_inventory))
+        .routes(routes!(list_stock_movements))
+        .routes(routes!(list_stock_alerts))
+        .routes(routes!(acknowledge_stock_alert))
+        .routes(routes!(cache_stats))
+        .routes(routes!(list_jobs))
+        .routes(routes!(list_audit_logs, prune_audit_logs))
+        .routes(routes!(get_audit_log))
 }
 
 #[utoipa::path(
     get,
-    path = "/admin/orders",
+    path = "/users",
+    operation_id = "admin_users_list",
+    params(PageParams),
+    responses(
+        (status = 200, description = "Users with their profiles, newest first (admin only)", body = ApiResponse<UserList>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn list_users(
+    State(pool): State<DbPool>,
+    user: AuthUser,
+    AppQuery(query): AppQuery<PageParams>,
+) -> AppResult<Json<ApiResponse<UserList>>> {
+    ensure_admin(&user)?;
+    let (page, limit, offset) = query.resolve()?;
+    let mut users = sqlx::query_as::<_, User>(
+        "SELECT * FROM users ORDER BY created_at DESC, id DESC LIMIT $1 OFFSET $2",
+    )
+    .bind(limit + 1)
+    .bind(offset)
+    .fetch_all(&pool)
+    .await?;
+    let total = if query.with_total() {
+        Some(
+            sqlx::query_scalar("SELECT count(*) FROM users")
+                .fetch_one(&pool)
+                .await?,
+        )
+    } else {
+        None
+    };
+    let meta = Meta::for_page(page, limit, total, &mut users);
+
+    let items = users.into_iter().map(UserProfile::from).collect();
+    Ok(Json(ApiResponse::success(
+        "Users",
+        UserList { items },
+        Some(meta),
+    )))
+}
+
+/// 404 unless user `id` exists, so an unknown user is not mistaken for one with nothing on file.
+async fn ensure_user_exists(pool: &DbPool, id: Uuid) -> AppResult<()> {
+    let exists: Option<(Uuid,)> = sqlx::query_as("SELECT id FROM users WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    exists.map(|_| ()).ok_or(AppError::NotFound)
+}
+
+/// Records that `admin` looked at `view` (`orders` or `cart`) of user `id`.
+fn record_inspection(
+    audit: &AuditLog,
+    admin: &AuthUser,
+    id: Uuid,
+    view: &str,
+    context: &RequestContext,
+) {
+    audit.record(
+        AuditEvent::new(AuditAction::UserInspect, "user")
+            .actor(admin.user_id)
+            .entity(id)
+            .details(serde_json::json!({ "view": view }))
+            .context(context),
+    );
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{id}/orders",
+    operation_id = "admin_users_orders",
+    params(
+        ("id" = Uuid, Path, description = "User ID"),
+        OrderListQuery
+    ),
+    responses(
+        (status = 200, description = "Orders of one user, newest first (admin only)", body = ApiResponse<OrderList>),
+        (status = 400, description = "Unknown status, or missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+        (status = 404, description = "User not found"),
+    ),
+    tag = "Admin"
+)]
+pub async fn list_user_orders(
+    State(pool): State<DbPool>,
+    State(audit): State<AuditLog>,
+    user: AuthUser,
+    context: RequestContext,
+    Path(id): Path<Uuid>,
+    AppQuery(query): AppQuery<OrderListQuery>,
+) -> AppResult<Json<ApiResponse<OrderList>>> {
+    ensure_admin(&user)?;
+    ensure_user_exists(&pool, id).await?;
+    let (orders, meta) = list_order_summaries(&pool, Some(id), &query).await?;
+    record_inspection(&audit, &user, id, "orders", &context);
+
+    Ok(Json(ApiResponse::success(
+        "Orders",
+        OrderList { items: orders },
+        Some(meta),
+    )))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{id}/cart",
+    operation_id = "admin_users_cart",
+    params(
+        ("id" = Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Current cart of one user, saved-for-later lines excluded (admin only)", body = ApiResponse<CartList>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+        (status = 404, description = "User not found"),
+    ),
+    tag = "Admin"
+)]
+pub async fn get_user_cart(
+    State(pool): State<DbPool>,
+    State(audit): State<AuditLog>,
+    user: AuthUser,
+    context: RequestContext,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<CartList>>> {
+    ensure_admin(&user)?;
+    ensure_user_exists(&pool, id).await?;
+    let data = fetch_cart(&pool, CartOwner::User(id), false).await?;
+    record_inspection(&audit, &user, id, "cart", &context);
+    let total = data.items.len() as i64;
+    let meta = Meta::new(1, total, total);
+
+    Ok(Json(ApiResponse::success("Cart", data, Some(meta))))
+}
+
+#[utoipa::path(
+    get,
+    path = "/orders",
+    operation_id = "admin_orders_list",
+    params(OrderListQuery),
     responses(
     (status = 200, description = "Get all orders (admin only)", body = ApiResponse<OrderList>),
-    (status = 403, description = "Forbidden"),
+        (status = 400, description = "Invalid filters, or missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
     (status = 500, description = "Internal Server Error"),
     ),
     tag = "Admin"
@@ -43,15 +578,10 @@ pub fn router() -> Router<DbPool> {
 pub async fn list_all_orders(
     State(pool): State<DbPool>,
     user: AuthUser,
+    AppQuery(query): AppQuery<OrderListQuery>,
 ) -> AppResult<Json<ApiResponse<OrderList>>> {
     ensure_admin(&user)?;
-    let orders = sqlx::query_as::<_, Order>("SELECT * FROM orders")
-        .fetch_all(&pool)
-        .await?;
-    let total: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM orders")
-        .fetch_one(&pool)
-        .await?;
-    let meta = Meta::new(1, total.0, total.0);
+    let (orders, meta) = list_order_summaries(&pool, None, &query).await?;
 
     let order_list = OrderList { items: orders };
 
@@ -60,15 +590,100 @@ pub async fn list_all_orders(
 
 #[utoipa::path(
     get,
-    path = "/admin/orders/{id}",
+    path = "/orders/search",
+    operation_id = "admin_orders_search",
+    params(OrderSearchQuery),
+    responses(
+        (status = 200, description = "Orders of customers whose email contains the fragment, newest first, with the email (admin only)", body = ApiResponse<AdminOrderList>),
+        (status = 400, description = "Unknown status, or missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+        (status = 422, description = "Missing or empty email", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn search_orders(
+    State(pool): State<DbPool>,
+    user: AuthUser,
+    AppQuery(query): AppQuery<OrderSearchQuery>,
+) -> AppResult<Json<ApiResponse<AdminOrderList>>> {
+    ensure_admin(&user)?;
+    let fragment = query.email.trim();
+    if fragment.is_empty() {
+        let mut errors = FieldErrors::default();
+        errors.add("email", "required", "email must not be empty");
+        errors.finish()?;
+    }
+    // the fragment is matched literally, so `_` in `jane_doe@` is not a wildcard
+    let pattern = format!("%{}%", escape_like(fragment));
+    let params = PageParams {
+        page: query.page,
+        per_page: query.per_page,
+        with_total: query.with_total,
+    };
+    let (page, limit, offset) = params.resolve()?;
+
+    let mut items = sqlx::query_as::<_, AdminOrderSummary>(
+        r#"
+        SELECT o.*, lines.item_count, lines.first_item_name, u.email AS customer_email
+        FROM orders o
+        JOIN users u ON u.id = o.user_id
+        CROSS JOIN LATERAL (
+            SELECT count(*) AS item_count, (array_agg(p.name ORDER BY oi.id))[1] AS first_item_name
+            FROM order_items oi
+            JOIN products p ON p.id = oi.product_id
+            WHERE oi.order_id = o.id
+        ) lines
+        WHERE u.email ILIKE $1 AND ($2::text IS NULL OR o.status = $2)
+        ORDER BY o.created_at DESC, o.id DESC
+        LIMIT $3 OFFSET $4
+        "#,
+    )
+    .bind(&pattern)
+    .bind(query.status)
+    .bind(limit + 1)
+    .bind(offset)
+    .fetch_all(&pool)
+    .await?;
+    let total = if params.with_total() {
+        Some(
+            sqlx::query_scalar(
+                r#"
+                SELECT count(*)
+                FROM orders o
+                JOIN users u ON u.id = o.user_id
+                WHERE u.email ILIKE $1 AND ($2::text IS NULL OR o.status = $2)
+                "#,
+            )
+            .bind(&pattern)
+            .bind(query.status)
+            .fetch_one(&pool)
+            .await?,
+        )
+    } else {
+        None
+    };
+    let meta = Meta::for_page(page, limit, total, &mut items);
+
+    Ok(Json(ApiResponse::success(
+        "Orders",
+        AdminOrderList { items },
+        Some(meta),
+    )))
+}
+
+#[utoipa::path(
+    get,
+    path = "/orders/{id}",
+    operation_id = "admin_orders_get",
     params(
     (
         "id" = Uuid, Path, description = "Order ID")
     ),
     responses(
     (status = 200, description = "Get any order with items (admin only)", body = ApiResponse<OrderWithItems>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
     (status = 404, description = "Not Found", ),
-    (status = 403, description = "Forbidden", ),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
     ),
     tag = "Admin"
 
@@ -79,24 +694,1449 @@ pub async fn get_order_admin(
     Path(id): Path<Uuid>,
 ) -> AppResult<Json<ApiResponse<OrderWithItems>>> {
     ensure_admin(&user)?;
-    let order = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1")
+    let data = fetch_order_with_items(&pool, id, None).await?;
+    Ok(Json(ApiResponse::success(
+        "Order found",
+        data,
+        Some(Meta::empty()),
+    )))
+}
+
+#[utoipa::path(
+    post,
+    path = "/orders/{id}/pay",
+    operation_id = "admin_orders_pay",
+    params(
+        ("id" = Uuid, Path, description = "Order ID")
+    ),
+    responses(
+        (status = 200, description = "Order marked paid; its reserved units leave stock (admin only)", body = ApiResponse<Order>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Order not found"),
+        (status = 409, description = "Order is already paid or cancelled", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn pay_order(
+    State(pool): State<DbPool>,
+    State(cache): State<ProductCache>,
+    State(audit): State<AuditLog>,
+    State(notifier): State<Arc<dyn Notifier>>,
+    user: AuthUser,
+    context: RequestContext,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<Order>>> {
+    ensure_admin(&user)?;
+    let mut tx = pool.begin().await?;
+    let order = lock_pending_order(&mut tx, id, None).await?;
+    let products = commit_reservations(&mut tx, order.id, user.user_id).await?;
+    let order = sqlx::query_as::<_, Order>(
+        "UPDATE orders SET status = $2, paid_at = now() WHERE id = $1 RETURNING *",
+    )
+    .bind(order.id)
+    .bind(OrderStatus::Paid)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    notifier::spawn(pool, notifier, OrderEvent::Paid, order.id);
+
+    for product_id in products {
+        cache.invalidate(product_id).await;
+    }
+    audit.record(
+        AuditEvent::new(AuditAction::OrderPay, "order")
+            .actor(user.user_id)
+            .entity(order.id)
+            .details(serde_json::json!({ "total_amount": order.total_amount }))
+            .context(&context),
+    );
+    Ok(Json(ApiResponse::success("Order paid", order, None)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/orders/{id}/force-status",
+    operation_id = "admin_orders_force_status",
+    params(
+        ("id" = Uuid, Path, description = "Order ID")
+    ),
+    request_body = ForceStatusRequest,
+    responses(
+        (status = 200, description = "Order moved to the status regardless of the normal transitions. Reservations of a pending order are settled, a paid order that is cancelled needs a refund (admin only)", body = ApiResponse<Order>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Order not found"),
+        (status = 409, description = "Order already has the status, or is cancelled", body = ApiResponse<ErrorData>),
+        (status = 422, description = "Status other than completed or cancelled, or missing reason", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn force_order_status(
+    State(state): State<AppState>,
+    user: AuthUser,
+    context: RequestContext,
+    Path(id): Path<Uuid>,
+    AppJson(payload): AppJson<ForceStatusRequest>,
+) -> AppResult<Json<ApiResponse<Order>>> {
+    ensure_admin(&user)?;
+    let to = payload.status;
+    let reason = payload.reason.trim().to_string();
+    let mut errors = FieldErrors::default();
+    if !matches!(to, OrderStatus::Completed | OrderStatus::Cancelled) {
+        errors.add(
+            "status",
+            "unsupported",
+            "only completed and cancelled can be forced",
+        );
+    }
+    if reason.is_empty() {
+        errors.add("reason", "required", "reason must not be empty");
+    } else if reason.chars().count() > MAX_FORCE_REASON_LEN {
+        errors.add(
+            "reason",
+            "too_long",
+            format!("reason must be at most {} characters", MAX_FORCE_REASON_LEN),
+        );
+    }
+    errors.finish()?;
+
+    let mut tx = state.pool.begin().await?;
+    let order = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let from = order.status;
+    if from == to || from == OrderStatus::Cancelled {
+        tx.rollback().await?;
+        let message = if from == to {
+            "Order already has this status"
+        } else {
+            "Cancelled orders cannot be reopened"
+        };
+        return Err(AppError::Conflict(message.into()).with_code(ErrorCode::InvalidOrderStatus));
+    }
+    let refund_needed = from.is_paid() && to == OrderStatus::Cancelled;
+    let products = match from {
+        OrderStatus::Pending if to == OrderStatus::Completed => {
+            commit_reservations(&mut tx, order.id, user.user_id).await?
+        }
+        OrderStatus::Pending => release_reservations(&mut tx, order.id).await?,
+        _ if refund_needed && payload.restock => {
+            restock_order(&mut tx, order.id, user.user_id).await?
+        }
+        _ => Vec::new(),
+    };
+    let order = sqlx::query_as::<_, Order>(
+        r#"
+        UPDATE orders
+        SET status = $2,
+            paid_at = CASE WHEN $2 = 'completed' THEN coalesce(paid_at, now()) ELSE paid_at END,
+            completed_at = CASE WHEN $2 = 'completed' THEN now() ELSE completed_at END,
+            refund_needed = refund_needed OR $3
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(order.id)
+    .bind(to)
+    .bind(refund_needed)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    if to == OrderStatus::Completed {
+        notifier::spawn(
+            state.pool.clone(),
+            state.notifier.clone(),
+            OrderEvent::Shipped,
+            order.id,
+        );
+    }
+
+    for &product_id in &products {
+        state.product_cache.invalidate(product_id).await;
+    }
+    let restocked = refund_needed && !products.is_empty();
+    tracing::warn!(
+        order_id = %order.id,
+        admin_id = %user.user_id,
+        ?from,
+        ?to,
+        refund_needed,
+        restocked,
+        reason = %reason,
+        "order status forced"
+    );
+    state.audit.record(
+        AuditEvent::new(AuditAction::OrderForceStatus, "order")
+            .actor(user.user_id)
+            .entity(order.id)
+            .details(serde_json::json!({
+                "reason": reason,
+                "skipped_transition": { "from": from, "to": to },
+                "refund_needed": refund_needed,
+                "restocked": restocked,
+                "total_amount": order.total_amount,
+            }))
+            .context(&context),
+    );
+    Ok(Json(ApiResponse::success(
+        "Order status forced",
+        order,
+        None,
+    )))
+}
+
+#[utoipa::path(
+    get,
+    path = "/ws",
+    operation_id = "admin_ws",
+    responses(
+        (status = 101, description = "Switches to a WebSocket of JSON admin events. The first text message must be an admin access token, since browsers cannot set headers on a WebSocket; `ready` follows once it is accepted, and a bad token closes the socket with code 1008 (admin only)", body = AdminEvent),
+        (status = 400, description = "Not a WebSocket upgrade", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn admin_socket(
+    State(pool): State<DbPool>,
+    State(users): State<UserCache>,
+    State(jwt): State<Arc<JwtKeys>>,
+    State(events): State<AdminEvents>,
+    upgrade: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+) -> AppResult<Response> {
+    let upgrade = upgrade.map_err(|e| AppError::BadRequest(e.body_text()))?;
+    Ok(upgrade.on_upgrade(move |socket| authenticate_socket(socket, pool, users, jwt, events)))
+}
+
+/// Takes the first text message of `socket` as an admin token, then forwards `events`.
+async fn authenticate_socket(
+    mut socket: WebSocket,
+    pool: DbPool,
+    users: UserCache,
+    jwt: Arc<JwtKeys>,
+    events: AdminEvents,
+) {
+    let admin = match tokio::time::timeout(AUTH_TIMEOUT, socket.recv()).await {
+        Ok(Some(Ok(Message::Text(token)))) => AuthUser::authenticate(&jwt, &users, &pool, &token)
+            .await
+            .and_then(|user| ensure_admin(&user)),
+        _ => Err(AppError::BadRequest(
+            "Expected an access token as the first message".into(),
+        )),
+    };
+    match admin {
+        Ok(()) => admin_events::forward(socket, events.subscribe()).await,
+        Err(e) => admin_events::close(socket, close_code::POLICY, &e.to_string()).await,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/dashboard",
+    operation_id = "admin_dashboard",
+    responses(
+        (status = 200, description = "Order, revenue, user and inventory figures for the admin landing page (admin only)", body = ApiResponse<DashboardStats>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn dashboard(
+    State(pool): State<DbPool>,
+    State(LowStockThreshold(low_stock_threshold)): State<LowStockThreshold>,
+    user: AuthUser,
+) -> AppResult<Json<ApiResponse<DashboardStats>>> {
+    ensure_admin(&user)?;
+    let today = Utc::now().date_naive();
+    let week = today - Duration::days(today.weekday().num_days_from_monday().into());
+    let month = today.with_day(1).unwrap_or(today);
+    let start = |day: chrono::NaiveDate| day.and_time(NaiveTime::MIN).and_utc();
+
+    let orders = sqlx::query_as::<_, DashboardOrderRow>(
+        r#"
+        SELECT
+            count(*) FILTER (WHERE created_at >= $1 AND status <> 'cancelled') AS orders_today,
+            coalesce(sum(total_amount) FILTER (WHERE created_at >= $1 AND status IN ('paid', 'completed')), 0)::bigint AS revenue_today,
+            count(*) FILTER (WHERE created_at >= $2 AND status <> 'cancelled') AS orders_week,
+            coalesce(sum(total_amount) FILTER (WHERE created_at >= $2 AND status IN ('paid', 'completed')), 0)::bigint AS revenue_week,
+            count(*) FILTER (WHERE created_at >= $3 AND status <> 'cancelled') AS orders_month,
+            coalesce(sum(total_amount) FILTER (WHERE created_at >= $3 AND status IN ('paid', 'completed')), 0)::bigint AS revenue_month,
+            count(*) FILTER (WHERE status = 'pending') AS pending,
+            count(*) FILTER (WHERE status IN ('paid', 'completed')) AS paid
+        FROM orders
+        "#,
+    )
+    .bind(start(today))
+    .bind(start(week))
+    .bind(start(month))
+    .fetch_one(&pool);
+    let users = sqlx::query_scalar::<_, i64>("SELECT count(*) FROM users").fetch_one(&pool);
+    let products = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT count(*), count(*) FILTER (WHERE stock - reserved < $1) FROM products WHERE deleted_at IS NULL",
+    )
+    .bind(low_stock_threshold)
+    .fetch_one(&pool);
+    let recent_orders = sqlx::query_as::<_, Order>(
+        "SELECT * FROM orders ORDER BY created_at DESC, id DESC LIMIT $1",
+    )
+    .bind(DASHBOARD_RECENT_ORDERS)
+    .fetch_all(&pool);
+    let (orders, users, products, recent_orders) =
+        tokio::join!(orders, users, products, recent_orders);
+    let (orders, (products, low_stock_products)) = (orders?, products?);
+
+    let period = |orders: i64, revenue: Money| PeriodStats {
+        orders,
+        revenue,
+        revenue_display: revenue,
+    };
+    let data = DashboardStats {
+        today: period(orders.orders_today, orders.revenue_today),
+        week: period(orders.orders_week, orders.revenue_week),
+        month: period(orders.orders_month, orders.revenue_month),
+        orders_by_status: OrderStatusCounts {
+            pending: orders.pending,
+            paid: orders.paid,
+        },
+        users: users?,
+        products,
+        low_stock_products,
+        low_stock_threshold,
+        recent_orders: recent_orders?,
+    };
+    Ok(Json(ApiResponse::success(
+        "Dashboard",
+        data,
+        Some(Meta::empty()),
+    )))
+}
+
+#[utoipa::path(
+    get,
+    path = "/reports/top-products",
+    operation_id = "admin_reports_top_products",
+    params(TopProductsQuery),
+    responses(
+        (status = 200, description = "Products by revenue from paid and completed orders in the window (admin only)", body = ApiResponse<TopProductList>),
+        (status = 400, description = "from is later than to, or missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn top_products_report(
+    State(pool): State<DbPool>,
+    user: AuthUser,
+    AppQuery(query): AppQuery<TopProductsQuery>,
+) -> AppResult<Json<ApiResponse<TopProductList>>> {
+    ensure_admin(&user)?;
+    if let (Some(from), Some(to)) = (query.from, query.to)
+        && from > to
+    {
+        return Err(AppError::BadRequest(
+            "from must not be later than to".to_string(),
+        ));
+    }
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+
+    // products left join sales, so unsold products survive until the HAVING drops them
+    let items = sqlx::query_as::<_, TopProduct>(
+        r#"
+        SELECT p.id AS product_id, p.name, p.sku,
+            coalesce(sum(s.quantity), 0)::bigint AS units_sold,
+            coalesce(sum(s.price * s.quantity), 0)::bigint AS revenue,
+            count(DISTINCT s.user_id) AS buyers
+        FROM products p
+        LEFT JOIN (
+            SELECT oi.product_id, oi.quantity, oi.price, o.user_id
+            FROM order_items oi
+            JOIN orders o ON o.id = oi.order_id
+            WHERE o.status = ANY($1)
+                AND ($2::timestamptz IS NULL OR o.created_at >= $2)
+                AND ($3::timestamptz IS NULL OR o.created_at < $3)
+        ) s ON s.product_id = p.id
+        GROUP BY p.id
+        HAVING ($4 AND p.deleted_at IS NULL) OR count(s.product_id) > 0
+        ORDER BY revenue DESC, units_sold DESC, p.name, p.id
+        LIMIT $5
+        "#,
+    )
+    .bind(PAID_ORDER_STATUSES)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(query.include_zero.unwrap_or(false))
+    .bind(limit)
+    .fetch_all(&pool)
+    .await?;
+
+    let total = items.len() as i64;
+    Ok(Json(ApiResponse::success(
+        "Top products",
+        TopProductList { items },
+        Some(Meta::new(1, total, total)),
+    )))
+}
+
+#[utoipa::path(
+    get,
+    path = "/inventory/low-stock",
+    operation_id = "admin_inventory_low_stock",
+    params(LowStockQuery),
+    responses(
+        (status = 200, description = "Products running low, fewest available first; reserved units do not count as available (admin only)", body = ApiResponse<LowStockList>),
+        (status = 400, description = "Negative threshold, or missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn list_low_stock(
+    State(pool): State<DbPool>,
+    State(LowStockThreshold(default_threshold)): State<LowStockThreshold>,
+    user: AuthUser,
+    AppQuery(query): AppQuery<LowStockQuery>,
+) -> AppResult<Json<ApiResponse<LowStockList>>> {
+    ensure_admin(&user)?;
+    let threshold = resolve_threshold(query.threshold, default_threshold)?;
+    let params = PageParams {
+        page: query.page,
+        per_page: query.per_page,
+        with_total: query.with_total,
+    };
+    let (page, limit, offset) = params.resolve()?;
+
+    let mut items = sqlx::query_as::<_, LowStockProduct>(
+        r#"
+        SELECT p.*, p.stock - p.reserved AS available
+        FROM products p
+        WHERE p.stock - p.reserved < $1 AND p.deleted_at IS NULL
+        ORDER BY available, p.name, p.id
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(threshold)
+    .bind(limit + 1)
+    .bind(offset)
+    .fetch_all(&pool)
+    .await?;
+    let total = if params.with_total() {
+        Some(
+            sqlx::query_scalar(
+                "SELECT count(*) FROM products WHERE stock - reserved < $1 AND deleted_at IS NULL",
+            )
+            .bind(threshold)
+            .fetch_one(&pool)
+            .await?,
+        )
+    } else {
+        None
+    };
+    let meta = Meta::for_page(page, limit, total, &mut items);
+
+    Ok(Json(ApiResponse::success(
+        "Low stock products",
+        LowStockList { items },
+        Some(meta),
+    )))
+}
+
+/// The low-stock `threshold` asked for, or the configured one; never negative.
+fn resolve_threshold(threshold: Option<i32>, default_threshold: i32) -> AppResult<i32> {
+    let threshold = threshold.unwrap_or(default_threshold);
+    if threshold < 0 {
+        return Err(AppError::BadRequest(
+            "threshold must not be negative".to_string(),
+        ));
+    }
+    Ok(threshold)
+}
+
+#[utoipa::path(
+    get,
+    path = "/inventory/low-stock/export",
+    operation_id = "admin_inventory_low_stock_export",
+    params(LowStockExportQuery),
+    responses(
+        (status = 200, description = "The low-stock listing as a CSV reorder sheet, in the same order, with units sold in paid orders over the last 30 days (admin only)", content_type = "text/csv", body = String),
+        (status = 400, description = "Negative threshold, or missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn export_low_stock(
+    State(pool): State<DbPool>,
+    State(audit): State<AuditLog>,
+    State(LowStockThreshold(default_threshold)): State<LowStockThreshold>,
+    user: AuthUser,
+    context: RequestContext,
+    AppQuery(query): AppQuery<LowStockExportQuery>,
+) -> AppResult<Response> {
+    ensure_admin(&user)?;
+    let threshold = resolve_threshold(query.threshold, default_threshold)?;
+    audit.record(
+        AuditEvent::new(AuditAction::LowStockExport, "product")
+            .actor(user.user_id)
+            .details(serde_json::json!({ "threshold": threshold }))
+            .context(&context),
+    );
+
+    // keyset on the listing's sort key, (available, name, id)
+    let stream = futures::stream::try_unfold(
+        (pool, None::<(i32, String, Uuid)>, true),
+        move |(pool, after, first)| async move {
+            if after.is_none() && !first {
+                return Ok(None);
+            }
+
+            let (after_available, after_name, after_id) = match after {
+                Some((available, name, id)) => (Some(available), Some(name), Some(id)),
+                None => (None, None, None),
+            };
+            let rows = sqlx::query_as::<_, LowStockExportRow>(
+                r#"
+                SELECT p.id, p.sku, p.name, p.stock, p.reserved, p.stock - p.reserved AS available,
+                    coalesce(sold.units, 0)::bigint AS units_sold
+                FROM products p
+                LEFT JOIN (
+                    SELECT oi.product_id, sum(oi.quantity) AS units
+                    FROM order_items oi
+                    JOIN orders o ON o.id = oi.order_id
+                    WHERE o.status = ANY($2) AND o.created_at >= now() - make_interval(days => $3)
+                    GROUP BY oi.product_id
+                ) sold ON sold.product_id = p.id
+                WHERE p.stock - p.reserved < $1 AND p.deleted_at IS NULL
+                    AND ($4::int IS NULL OR (p.stock - p.reserved, p.name, p.id) > ($4, $5, $6))
+                ORDER BY available, p.name, p.id
+                LIMIT $7
+                "#,
+            )
+            .bind(threshold)
+            .bind(PAID_ORDER_STATUSES)
+            .bind(LOW_STOCK_SALES_DAYS)
+            .bind(after_available)
+            .bind(after_name)
+            .bind(after_id)
+            .bind(EXPORT_CHUNK_SIZE)
+            .fetch_all(&pool)
+            .await?;
+
+            let chunk = low_stock_csv(&rows, threshold, first)?;
+            let next = match rows.last() {
+                Some(last) if rows.len() as i64 == EXPORT_CHUNK_SIZE => {
+                    Some((last.available, last.name.clone(), last.id))
+                }
+                _ => None,
+            };
+            if next.is_none() && chunk.is_empty() {
+                return Ok(None);
+            }
+            Ok::<_, AppError>(Some((Bytes::from(chunk), (pool, next, false))))
+        },
+    );
+
+    let filename = format!("low-stock-{}.csv", Utc::now().format("%Y%m%d%H%M%S"));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        Body::from_stream(stream),
+    )
+        .into_response())
+}
+
+fn low_stock_csv(
+    rows: &[LowStockExportRow],
+    threshold: i32,
+    with_header: bool,
+) -> AppResult<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    if with_header {
+        writer
+            .write_record([
+                "sku",
+                "name",
+                "stock",
+                "reserved",
+                "threshold",
+                "last_30d_units_sold",
+            ])
+            .map_err(anyhow::Error::from)?;
+    }
+    for row in rows {
+        writer
+            .write_record([
+                row.sku.clone().unwrap_or_default(),
+                row.name.clone(),
+                row.stock.to_string(),
+                row.reserved.to_string(),
+                threshold.to_string(),
+                row.units_sold.to_string(),
+            ])
+            .map_err(anyhow::Error::from)?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| AppError::Internal(anyhow::anyhow!(e.to_string())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/products",
+    operation_id = "admin_products_list",
+    params(AdminProductQuery),
+    responses(
+        (status = 200, description = "Products, published or not, most recently deleted first, then newest (admin only)", body = ApiResponse<ProductList>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn list_admin_products(
+    State(pool): State<DbPool>,
+    State(audit): State<AuditLog>,
+    user: AuthUser,
+    context: RequestContext,
+    AppQuery(query): AppQuery<AdminProductQuery>,
+) -> AppResult<Json<ApiResponse<ProductList>>> {
+    ensure_admin(&user)?;
+    let deleted = query.deleted.unwrap_or_default();
+    let params = PageParams {
+        page: query.page,
+        per_page: query.per_page,
+        with_total: query.with_total,
+    };
+    let (page, limit, offset) = params.resolve()?;
+    let condition = match deleted {
+        DeletedFilter::Exclude => "deleted_at IS NULL",
+        DeletedFilter::Include => "true",
+        DeletedFilter::Only => "deleted_at IS NOT NULL",
+    };
+
+    let mut items = sqlx::query_as::<_, Product>(&format!(
+        "SELECT * FROM products WHERE {} ORDER BY deleted_at DESC NULLS LAST, created_at DESC, id DESC LIMIT $1 OFFSET $2",
+        condition
+    ))
+    .bind(limit + 1)
+    .bind(offset)
+    .fetch_all(&pool)
+    .await?;
+    let total = if params.with_total() {
+        Some(
+            sqlx::query_scalar(&format!(
+                "SELECT count(*) FROM products WHERE {}",
+                condition
+            ))
+            .fetch_one(&pool)
+            .await?,
+        )
+    } else {
+        None
+    };
+    let meta = Meta::for_page(page, limit, total, &mut items);
+    load_product_details(&pool, &mut items).await?;
+    audit.record(
+        AuditEvent::new(AuditAction::ProductAdminList, "product")
+            .actor(user.user_id)
+            .details(serde_json::json!({ "deleted": deleted }))
+            .context(&context),
+    );
+
+    Ok(Json(ApiResponse::success(
+        "Products",
+        ProductList { items },
+        Some(meta),
+    )))
+}
+
+#[utoipa::path(
+    post,
+    path = "/products/{id}/restore",
+    operation_id = "admin_products_restore",
+    params(
+        ("id" = Uuid, Path, description = "Product ID")
+    ),
+    request_body = RestoreProductRequest,
+    responses(
+        (status = 200, description = "Deleted product restored, published only when asked (admin only)", body = ApiResponse<Product>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Product not found"),
+        (status = 409, description = "Product is not deleted, or a live product now has its slug or SKU", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn restore_product(
+    State(pool): State<DbPool>,
+    State(cache): State<ProductCache>,
+    State(audit): State<AuditLog>,
+    user: AuthUser,
+    context: RequestContext,
+    Path(id): Path<Uuid>,
+    AppJson(payload): AppJson<RestoreProductRequest>,
+) -> AppResult<Json<ApiResponse<Product>>> {
+    ensure_admin(&user)?;
+    let mut tx = pool.begin().await?;
+    let product = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    if product.deleted_at.is_none() {
+        return Err(AppError::Conflict("Product is not deleted".into()));
+    }
+    let taken: Option<(bool,)> = sqlx::query_as(
+        "SELECT slug = $2 FROM products WHERE id <> $1 AND deleted_at IS NULL AND (slug = $2 OR sku = $3) LIMIT 1",
+    )
+    .bind(id)
+    .bind(&product.slug)
+    .bind(&product.sku)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some((slug_taken,)) = taken {
+        let message = match (slug_taken, &product.sku) {
+            (false, Some(sku)) => format!("sku {} is now used by another product", sku),
+            _ => format!("slug {} is now used by another product", product.slug),
+        };
+        return Err(AppError::Conflict(message));
+    }
+    // a product created between the check and here still trips the unique indexes
+    let mut product = sqlx::query_as::<_, Product>(
+        "UPDATE products SET deleted_at = NULL, is_published = $2 WHERE id = $1 RETURNING *",
+    )
+    .bind(id)
+    .bind(payload.publish)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e.as_database_error() {
+        Some(db) if db.is_unique_violation() => {
+            AppError::Conflict("slug or sku is now used by another product".to_string())
+        }
+        _ => e.into(),
+    })?;
+    tx.commit().await?;
+
+    cache.invalidate(id).await;
+    audit.record(
+        AuditEvent::new(AuditAction::ProductRestore, "product")
+            .actor(user.user_id)
+            .entity(id)
+            .details(serde_json::json!({ "publish": payload.publish }))
+            .context(&context),
+    );
+    load_product_details(&pool, std::slice::from_mut(&mut product)).await?;
+    Ok(Json(ApiResponse::success(
+        "Product restored",
+        product,
+        Some(Meta::empty()),
+    )))
+}
+
+#[utoipa::path(
+    get,
+    path = "/products/export",
+    operation_id = "admin_products_export",
+    params(ProductQuery),
+    responses(
+        (status = 200, description = "All products matching the filters as CSV (admin only)", content_type = "text/csv", body = String),
+        (status = 400, description = "Invalid price range, or missing or invalid bearer token"),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn export_products(
+    State(pool): State<DbPool>,
+    State(audit): State<AuditLog>,
+    user: AuthUser,
+    context: RequestContext,
+    AppQuery(mut query): AppQuery<ProductQuery>,
+) -> AppResult<Response> {
+    ensure_admin(&user)?;
+    validate_price_range(&query)?;
+    query.include_unpublished = Some(true);
+    audit.record(
+        AuditEvent::new(AuditAction::ProductExport, "product")
+            .actor(user.user_id)
+            .details(serde_json::json!({
+                "q": query.q,
+                "min_price": query.min_price,
+                "max_price": query.max_price,
+                "category": query.category,
+                "sku": query.sku,
+                "in_stock": query.in_stock,
+            }))
+            .context(&context),
+    );
+
+    // keyset on id so memory stays flat no matter how large the catalog is
+    let stream = futures::stream::try_unfold(
+        (pool, query, None::<Uuid>, true),
+        |(pool, query, after, first)| async move {
+            if after.is_none() && !first {
+                return Ok(None);
+            }
+
+            let mut builder = QueryBuilder::<Postgres>::new("SELECT * FROM products");
+            let has_where = push_product_filters(&mut builder, &query);
+            if let Some(after) = after {
+                builder
+                    .push(if has_where { " AND " } else { " WHERE " })
+                    .push("id > ")
+                    .push_bind(after);
+            }
+            builder
+                .push(" ORDER BY id LIMIT ")
+                .push_bind(EXPORT_CHUNK_SIZE);
+            let rows = builder.build_query_as::<Product>().fetch_all(&pool).await?;
+
+            let chunk = products_csv(&rows, first)?;
+            let next = match rows.last() {
+                Some(last) if rows.len() as i64 == EXPORT_CHUNK_SIZE => Some(last.id),
+                _ => None,
+            };
+            if next.is_none() && chunk.is_empty() {
+                return Ok(None);
+            }
+            Ok::<_, AppError>(Some((Bytes::from(chunk), (pool, query, next, false))))
+        },
+    );
+
+    let filename = format!("products-{}.csv", Utc::now().format("%Y%m%d%H%M%S"));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        Body::from_stream(stream),
+    )
+        .into_response())
+}
+
+fn products_csv(rows: &[Product], with_header: bool) -> AppResult<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    if with_header {
+        writer
+            .write_record([
+                "id",
+                "name",
+                "slug",
+                "sku",
+                "description",
+                "price",
+                "stock",
+                "category_id",
+                "created_at",
+            ])
+            .map_err(anyhow::Error::from)?;
+    }
+    for p in rows {
+        writer
+            .write_record([
+                p.id.to_string(),
+                p.name.clone(),
+                p.slug.clone(),
+                p.sku.clone().unwrap_or_default(),
+                p.description.clone().unwrap_or_default(),
+                p.price.minor().to_string(),
+                p.stock.to_string(),
+                p.category_id.map(|id| id.to_string()).unwrap_or_default(),
+                p.created_at.to_rfc3339(),
+            ])
+            .map_err(anyhow::Error::from)?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| AppError::Internal(anyhow::anyhow!(e.to_string())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/products/{id}/price-history",
+    operation_id = "admin_products_price_history",
+    params(
+        ("id" = Uuid, Path, description = "Product ID"),
+        PageParams
+    ),
+    responses(
+        (status = 200, description = "Price changes of a product, oldest first (admin only)", body = ApiResponse<PriceHistoryList>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Product not found"),
+    ),
+    tag = "Admin"
+)]
+pub async fn product_price_history(
+    State(pool): State<DbPool>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    AppQuery(params): AppQuery<PageParams>,
+) -> AppResult<Json<ApiResponse<PriceHistoryList>>> {
+    ensure_admin(&user)?;
+
+    let exists: Option<(Uuid,)> = sqlx::query_as("SELECT id FROM products WHERE id = $1")
         .bind(id)
         .fetch_optional(&pool)
         .await?;
-    let order = match order {
-        Some(o) => o,
-        None => return Err(AppError::NotFound),
+    if exists.is_none() {
+        return Err(AppError::NotFound);
+    }
+
+    let (page, limit, offset) = params.resolve()?;
+    let mut items = sqlx::query_as::<_, ProductPriceChange>(
+        r#"
+        SELECT * FROM product_price_history
+        WHERE product_id = $1
+        ORDER BY created_at, id
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(id)
+    .bind(limit + 1)
+    .bind(offset)
+    .fetch_all(&pool)
+    .await?;
+
+    let total = if params.with_total() {
+        Some(
+            sqlx::query_scalar("SELECT count(*) FROM product_price_history WHERE product_id = $1")
+                .bind(id)
+                .fetch_one(&pool)
+                .await?,
+        )
+    } else {
+        None
     };
+    let meta = Meta::for_page(page, limit, total, &mut items);
+
+    Ok(Json(ApiResponse::success(
+        "Price history",
+        PriceHistoryList { items },
+        Some(meta),
+    )))
+}
 
-    let items = sqlx::query_as::<_, OrderItem>("SELECT * FROM order_items WHERE order_id = $1")
-        .bind(order.id)
+#[utoipa::path(
+    post,
+    path = "/products/{id}/inventory",
+    operation_id = "admin_products_adjust_inventory",
+    params(
+        ("id" = Uuid, Path, description = "Product ID")
+    ),
+    request_body = InventoryAdjustRequest,
+    responses(
+        (status = 200, description = "Stock changed and the change recorded in the product's stock ledger (admin only)", body = ApiResponse<Product>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Product not found"),
+        (status = 422, description = "Missing or unknown reason, zero delta, a delta that does not fit the reason, or stock dropping below the reserved units", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn adjust_inventory(
+    State(pool): State<DbPool>,
+    State(cache): State<ProductCache>,
+    State(audit): State<AuditLog>,
+    user: AuthUser,
+    context: RequestContext,
+    Path(id): Path<Uuid>,
+    AppJson(payload): AppJson<InventoryAdjustRequest>,
+) -> AppResult<Json<ApiResponse<Product>>> {
+    ensure_admin(&user)?;
+    let InventoryAdjustRequest {
+        delta,
+        reason,
+        reference_id,
+    } = payload;
+    let mut errors = FieldErrors::default();
+    match reason {
+        _ if delta == 0 => errors.add("delta", "zero", "delta must not be zero"),
+        StockMovementReason::Sale => {
+            errors.add("reason", "sale", "sales are recorded when an order is paid")
+        }
+        StockMovementReason::Restock | StockMovementReason::Return if delta < 0 => {
+            errors.add("delta", "negative", "a restock or return must add units")
+        }
+        StockMovementReason::Damage if delta > 0 => {
+            errors.add("delta", "positive", "damage must take units out")
+        }
+        _ => {}
+    }
+    errors.finish()?;
+
+    let mut tx = pool.begin().await?;
+    // the WHERE clause is the stock check, so no row lock is taken before it passes
+    let product = sqlx::query_as::<_, Product>(
+        r#"
+        UPDATE products
+        SET stock = stock + $2
+        WHERE id = $1 AND deleted_at IS NULL
+            AND stock::bigint + $2 BETWEEN reserved AND 2147483647
+        RETURNING *
+        "#,
+    )
+    .bind(id)
+    .bind(delta)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(mut product) = product else {
+        tx.rollback().await?;
+        return Err(rejected_adjustment(&pool, id, delta).await?);
+    };
+    if let Err(e) = record_stock_movements(
+        &mut tx,
+        &[(id, delta)],
+        reason,
+        reference_id,
+        Some(user.user_id),
+    )
+    .await
+    {
+        tx.rollback().await?;
+        return Err(e);
+    }
+    tx.commit().await?;
+
+    cache.invalidate(id).await;
+    audit.record(
+        AuditEvent::new(AuditAction::InventoryAdjust, "product")
+            .actor(user.user_id)
+            .entity(id)
+            .details(
+                serde_json::json!({ "delta": delta, "reason": reason, "stock": product.stock }),
+            )
+            .context(&context),
+    );
+    load_product_details(&pool, std::slice::from_mut(&mut product)).await?;
+    Ok(Json(ApiResponse::success(
+        "Inventory adjusted",
+        product,
+        Some(Meta::empty()),
+    )))
+}
+
+/// Why the conditional update in [`adjust_inventory`] matched no row: the product is gone,
+/// or `delta` would take its stock below the reserved units or past `i32::MAX`.
+async fn rejected_adjustment(pool: &DbPool, id: Uuid, delta: i32) -> AppResult<AppError> {
+    let product: Option<(i32, i32)> =
+        sqlx::query_as("SELECT stock, reserved FROM products WHERE id = $1 AND deleted_at IS NULL")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+    let Some((stock, reserved)) = product else {
+        return Ok(AppError::NotFound);
+    };
+    let error = if stock.checked_add(delta).is_none() {
+        FieldError::new("delta", "too_large", "stock would overflow")
+    } else {
+        FieldError::new(
+            "delta",
+            "below_reserved",
+            format!(
+                "stock must not drop below the {} units reserved by pending orders",
+                reserved
+            ),
+        )
+    };
+    Ok(AppError::Validation(vec![error]))
+}
+
+#[utoipa::path(
+    get,
+    path = "/products/{id}/stock-movements",
+    operation_id = "admin_products_stock_movements",
+    params(
+        ("id" = Uuid, Path, description = "Product ID"),
+        PageParams
+    ),
+    responses(
+        (status = 200, description = "Stock ledger of a product, oldest first; the deltas sum to its stock (admin only)", body = ApiResponse<StockMovementList>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Product not found"),
+    ),
+    tag = "Admin"
+)]
+pub async fn list_stock_movements(
+    State(pool): State<DbPool>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    AppQuery(params): AppQuery<PageParams>,
+) -> AppResult<Json<ApiResponse<StockMovementList>>> {
+    ensure_admin(&user)?;
+
+    let exists: Option<(Uuid,)> = sqlx::query_as("SELECT id FROM products WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&pool)
+        .await?;
+    if exists.is_none() {
+        return Err(AppError::NotFound);
+    }
+
+    let (page, limit, offset) = params.resolve()?;
+    let mut items = sqlx::query_as::<_, StockMovement>(
+        r#"
+        SELECT * FROM stock_movements
+        WHERE product_id = $1
+        ORDER BY created_at, id
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(id)
+    .bind(limit + 1)
+    .bind(offset)
+    .fetch_all(&pool)
+    .await?;
+
+    let total = if params.with_total() {
+        Some(
+            sqlx::query_scalar("SELECT count(*) FROM stock_movements WHERE product_id = $1")
+                .bind(id)
+                .fetch_one(&pool)
+                .await?,
+        )
+    } else {
+        None
+    };
+    let meta = Meta::for_page(page, limit, total, &mut items);
+
+    Ok(Json(ApiResponse::success(
+        "Stock movements",
+        StockMovementList { items },
+        Some(meta),
+    )))
+}
+
+#[utoipa::path(
+    get,
+    path = "/stock-alerts",
+    operation_id = "admin_stock_alerts_list",
+    params(StockAlertQuery),
+    responses(
+        (status = 200, description = "Low-stock alerts raised by the periodic scan, newest first (admin only)", body = ApiResponse<StockAlertList>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn list_stock_alerts(
+    State(pool): State<DbPool>,
+    user: AuthUser,
+    AppQuery(query): AppQuery<StockAlertQuery>,
+) -> AppResult<Json<ApiResponse<StockAlertList>>> {
+    ensure_admin(&user)?;
+    let params = PageParams {
+        page: query.page,
+        per_page: query.per_page,
+        with_total: query.with_total,
+    };
+    let (page, limit, offset) = params.resolve()?;
+    let filter = r#"
+        ($1::bool IS NULL OR (a.acknowledged_at IS NOT NULL) = $1)
+        AND ($2::bool IS NULL OR (a.resolved_at IS NOT NULL) = $2)
+    "#;
+
+    let mut items = sqlx::query_as::<_, StockAlert>(&format!(
+        r#"
+        SELECT a.*, p.name AS product_name
+        FROM stock_alerts a
+        JOIN products p ON p.id = a.product_id
+        WHERE {}
+        ORDER BY a.created_at DESC, a.id DESC
+        LIMIT $3 OFFSET $4
+        "#,
+        filter
+    ))
+    .bind(query.acknowledged)
+    .bind(query.resolved)
+    .bind(limit + 1)
+    .bind(offset)
+    .fetch_all(&pool)
+    .await?;
+    let total = if params.with_total() {
+        Some(
+            sqlx::query_scalar(&format!(
+                "SELECT count(*) FROM stock_alerts a WHERE {}",
+                filter
+            ))
+            .bind(query.acknowledged)
+            .bind(query.resolved)
+            .fetch_one(&pool)
+            .await?,
+        )
+    } else {
+        None
+    };
+    let meta = Meta::for_page(page, limit, total, &mut items);
+
+    Ok(Json(ApiResponse::success(
+        "Stock alerts",
+        StockAlertList { items },
+        Some(meta),
+    )))
+}
+
+#[utoipa::path(
+    post,
+    path = "/stock-alerts/{id}/ack",
+    operation_id = "admin_stock_alerts_acknowledge",
+    params(
+        ("id" = Uuid, Path, description = "Stock alert ID")
+    ),
+    responses(
+        (status = 200, description = "Alert acknowledged; the product alerts again only after it is restocked and runs low once more (admin only)", body = ApiResponse<StockAlert>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Alert not found"),
+        (status = 409, description = "Alert already acknowledged", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn acknowledge_stock_alert(
+    State(pool): State<DbPool>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<StockAlert>>> {
+    ensure_admin(&user)?;
+    let alert = sqlx::query_as::<_, StockAlert>(
+        r#"
+        WITH acked AS (
+            UPDATE stock_alerts
+            SET acknowledged_at = NOW(), acknowledged_by = $2
+            WHERE id = $1 AND acknowledged_at IS NULL
+            RETURNING *
+        )
+        SELECT acked.*, p.name AS product_name
+        FROM acked
+        JOIN products p ON p.id = acked.product_id
+        "#,
+    )
+    .bind(id)
+    .bind(user.user_id)
+    .fetch_optional(&pool)
+    .await?;
+    match alert {
+        Some(alert) => Ok(Json(ApiResponse::success(
+            "Alert acknowledged",
+            alert,
+            Some(Meta::empty()),
+        ))),
+        None => {
+            let exists: Option<(Uuid,)> =
+                sqlx::query_as("SELECT id FROM stock_alerts WHERE id = $1")
+                    .bind(id)
+                    .fetch_optional(&pool)
+                    .await?;
+            match exists {
+                Some(_) => Err(AppError::Conflict("Alert is already acknowledged".into())),
+                None => Err(AppError::NotFound),
+            }
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/cache/stats",
+    operation_id = "admin_cache_stats",
+    responses(
+        (status = 200, description = "Product cache hit/miss counters (admin only)", body = ApiResponse<CacheStats>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn cache_stats(
+    State(cache): State<ProductCache>,
+    user: AuthUser,
+) -> AppResult<Json<ApiResponse<CacheStats>>> {
+    ensure_admin(&user)?;
+    Ok(Json(ApiResponse::success(
+        "Cache stats",
+        cache.stats(),
+        None,
+    )))
+}
+
+#[utoipa::path(
+    get,
+    path = "/jobs",
+    operation_id = "admin_jobs_list",
+    responses(
+        (status = 200, description = "Background jobs with their last run and last error (admin only)", body = ApiResponse<JobList>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn list_jobs(
+    State(jobs): State<JobRegistry>,
+    user: AuthUser,
+) -> AppResult<Json<ApiResponse<JobList>>> {
+    ensure_admin(&user)?;
+    Ok(Json(ApiResponse::success(
+        "Jobs",
+        JobList {
+            items: jobs.statuses(),
+        },
+        None,
+    )))
+}
+
+/// Appends the filters of `query`, for both the list and the count query.
+fn push_audit_filters(builder: &mut QueryBuilder<'_, Postgres>, query: &AuditLogQuery) {
+    builder.push(" WHERE TRUE");
+    if let Some(user_id) = query.user_id {
+        builder.push(" AND actor_id = ").push_bind(user_id);
+    }
+    if let Some(action) = query.action {
+        builder.push(" AND action = ").push_bind(action.as_str());
+    }
+    if let Some(resource) = &query.resource {
+        builder
+            .push(" AND entity_type = ")
+            .push_bind(resource.clone());
+    }
+    if let Some(request_id) = &query.request_id {
+        builder
+            .push(" AND request_id = ")
+            .push_bind(request_id.clone());
+    }
+    if let Some(from) = query.from {
+        builder.push(" AND created_at >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        builder.push(" AND created_at < ").push_bind(to);
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/audit-logs",
+    operation_id = "admin_audit_logs_list",
+    params(AuditLogQuery),
+    responses(
+        (status = 200, description = "Audit log entries, newest first (admin only)", body = ApiResponse<AuditLogList>),
+        (status = 400, description = "Invalid filters, or missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn list_audit_logs(
+    State(pool): State<DbPool>,
+    user: AuthUser,
+    AppQuery(query): AppQuery<AuditLogQuery>,
+) -> AppResult<Json<ApiResponse<AuditLogList>>> {
+    ensure_admin(&user)?;
+    if let (Some(from), Some(to)) = (query.from, query.to)
+        && from > to
+    {
+        return Err(AppError::BadRequest(
+            "from must not be later than to".to_string(),
+        ));
+    }
+    let params = PageParams {
+        page: query.page,
+        per_page: query.per_page,
+        with_total: query.with_total,
+    };
+    let (page, limit, offset) = params.resolve()?;
+
+    let mut builder = QueryBuilder::<Postgres>::new("SELECT * FROM audit_log");
+    push_audit_filters(&mut builder, &query);
+    builder
+        .push(" ORDER BY created_at DESC, id DESC LIMIT ")
+        .push_bind(limit + 1)
+        .push(" OFFSET ")
+        .push_bind(offset);
+    let mut items = builder
+        .build_query_as::<AuditLogEntry>()
         .fetch_all(&pool)
         .await?;
 
-    let data = OrderWithItems { order, items };
+    let total = if params.with_total() {
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT count(*) FROM audit_log");
+        push_audit_filters(&mut builder, &query);
+        Some(builder.build_query_scalar().fetch_one(&pool).await?)
+    } else {
+        None
+    };
+    let meta = Meta::for_page(page, limit, total, &mut items);
+
     Ok(Json(ApiResponse::success(
-        "Order found",
-        data,
+        "Audit logs",
+        AuditLogList { items },
+        Some(meta),
+    )))
+}
+
+#[utoipa::path(
+    get,
+    path = "/audit-logs/{id}",
+    operation_id = "admin_audit_logs_get",
+    params(
+        ("id" = Uuid, Path, description = "Audit log entry ID")
+    ),
+    responses(
+        (status = 200, description = "One audit log entry with its details (admin only)", body = ApiResponse<AuditLogEntry>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+        (status = 404, description = "No such entry"),
+    ),
+    tag = "Admin"
+)]
+pub async fn get_audit_log(
+    State(pool): State<DbPool>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<AuditLogEntry>>> {
+    ensure_admin(&user)?;
+    let entry = sqlx::query_as::<_, AuditLogEntry>("SELECT * FROM audit_log WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    Ok(Json(ApiResponse::success(
+        "Audit log entry",
+        entry,
         Some(Meta::empty()),
     )))
 }
+
+#[utoipa::path(
+    delete,
+    path = "/audit-logs",
+    operation_id = "admin_audit_logs_prune",
+    params(AuditPruneQuery),
+    responses(
+        (status = 200, description = "Entries older than `before` deleted; the pruning itself is audited (admin only)", body = ApiResponse<AuditPruneResult>),
+        (status = 400, description = "Missing or invalid `before`, or missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn prune_audit_logs(
+    State(pool): State<DbPool>,
+    State(audit): State<AuditLog>,
+    user: AuthUser,
+    context: RequestContext,
+    AppQuery(query): AppQuery<AuditPruneQuery>,
+) -> AppResult<Json<ApiResponse<AuditPruneResult>>> {
+    ensure_admin(&user)?;
+    let deleted = prune_older_than(&pool, query.before, PRUNE_BATCH_SIZE).await?;
+    audit.record(
+        AuditEvent::new(AuditAction::AuditLogPrune, "audit_log")
+            .actor(user.user_id)
+            .details(serde_json::json!({ "before": query.before, "deleted": deleted }))
+            .context(&context),
+    );
+    Ok(Json(ApiResponse::success(
+        "Audit logs pruned",
+        AuditPruneResult { deleted },
+        None,
+    )))
+}
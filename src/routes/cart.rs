@@ -1,145 +1,353 @@
 use axum::{
-    Json, Router,
+    Json,
     extract::{Path, State},
-    routing::{delete, get},
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
 use uuid::Uuid;
 
 use crate::{
     db::DbPool,
-    error::{AppError, AppResult},
-    middleware::auth::AuthUser,
-    models::CartItem,
+    error::{AppError, AppResult, ErrorData, FieldErrors},
+    extract::AppJson,
+    ids::new_id,
+    middleware::cart_session::{CartOwner, GUEST_CART_TTL_DAYS},
+    models::{CartItem, CartSession},
+    money::{Money, serialize_display},
     response::{ApiResponse, Meta},
+    state::AppState,
 };
 
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct AddToCartRequest {
     pub product_id: Uuid,
+    /// Units to put in the cart; replaces the quantity of an existing line
+    #[schema(example = 2, minimum = 1)]
     pub quantity: i32,
 }
 
+pub const MAX_BULK_CART_ITEMS: usize = 100;
+
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct CartLine {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub product_name: String,
+    pub quantity: i32,
+    /// Current product price.
+    #[schema(value_type = i64)]
+    pub price: Money,
+    /// Product price when the line was added to the cart.
+    #[schema(value_type = i64)]
+    pub price_at_add: Money,
+    pub price_changed: bool,
+    #[schema(value_type = i64)]
+    pub line_total: Money,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CartSummary {
+    pub item_count: i64,
+    pub total_quantity: i64,
+    #[schema(value_type = i64)]
+    pub subtotal: Money,
+    /// `subtotal` formatted in the shop's currency.
+    #[serde(serialize_with = "serialize_display")]
+    #[schema(value_type = String, example = "$12.50")]
+    pub subtotal_display: Money,
+}
+
+impl CartSummary {
+    /// Fails with 400 when the subtotal does not fit in an `i64`.
+    pub fn from_lines(lines: &[CartLine]) -> AppResult<Self> {
+        let subtotal = Money::checked_sum(lines.iter().map(|l| l.line_total))
+            .ok_or_else(|| AppError::BadRequest("Cart subtotal is too large".into()))?;
+        Ok(Self {
+            item_count: lines.len() as i64,
+            total_quantity: lines.iter().map(|l| l.quantity as i64).sum(),
+            subtotal,
+            subtotal_display: subtotal,
+        })
+    }
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct CartList {
-    pub items: Vec<CartItem>,
+    pub items: Vec<CartLine>,
+    pub summary: CartSummary,
 }
 
-pub fn router() -> Router<DbPool> {
-    Router::new()
-        .route("/", get(cart_list).post(add_to_cart))
-        .route("/{product_id}", delete(remove_from_cart))
+pub fn router() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(cart_list, add_to_cart))
+        .routes(routes!(bulk_add_to_cart))
+        .routes(routes!(create_cart_session))
+        .routes(routes!(remove_from_cart))
+        .routes(routes!(remove_cart_item))
+        .routes(routes!(saved_list))
+        .routes(routes!(save_for_later))
+        .routes(routes!(unsave))
 }
 
 #[utoipa::path(
     get,
-    path = "/api/cart",
+    path = "/",
+    operation_id = "cart_list",
+    params(
+        ("X-Cart-Token" = Option<Uuid>, Header, description = "Guest cart token, used when no Authorization header is sent")
+    ),
     responses(
-        (status = 200, description = "List cart items for current user", body = ApiResponse<CartList>)
+        (status = 200, description = "List cart items for current user or guest", body = ApiResponse<CartList>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
     ),
     tag = "cart"
 )]
 pub async fn cart_list(
     State(pool): State<DbPool>,
-    user: AuthUser,
+    owner: CartOwner,
 ) -> AppResult<Json<ApiResponse<CartList>>> {
-    let items = sqlx::query_as::<_, CartItem>("SELECT * FROM cart_items where user_id = $1")
-        .bind(user.user_id)
-        .fetch_all(&pool)
-        .await?;
-
-    let total: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM cart_items WHERE user_id = $1")
-        .bind(user.user_id)
-        .fetch_one(&pool)
-        .await?;
+    let data = fetch_cart(&pool, owner, false).await?;
+    let total = data.items.len() as i64;
+    let meta = Meta::new(1, total, total);
 
-    let meta = Meta::new(1, total.0, total.0);
+    Ok(Json(ApiResponse::success("OK", data, Some(meta))))
+}
 
-    let data = CartList { items };
+#[utoipa::path(
+    get,
+    path = "/saved",
+    operation_id = "cart_saved_list",
+    params(
+        ("X-Cart-Token" = Option<Uuid>, Header, description = "Guest cart token, used when no Authorization header is sent")
+    ),
+    responses(
+        (status = 200, description = "List items saved for later", body = ApiResponse<CartList>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+    ),
+    tag = "cart"
+)]
+pub async fn saved_list(
+    State(pool): State<DbPool>,
+    owner: CartOwner,
+) -> AppResult<Json<ApiResponse<CartList>>> {
+    let data = fetch_cart(&pool, owner, true).await?;
+    let total = data.items.len() as i64;
+    let meta = Meta::new(1, total, total);
 
     Ok(Json(ApiResponse::success("OK", data, Some(meta))))
 }
 
+/// Loads either the active cart or the saved-for-later lines of `owner`.
+pub(crate) async fn fetch_cart(
+    pool: &DbPool,
+    owner: CartOwner,
+    saved: bool,
+) -> AppResult<CartList> {
+    let sql = format!(
+        r#"
+        SELECT ci.id, ci.product_id, p.name AS product_name, ci.quantity, p.price,
+               ci.price_at_add, p.price <> ci.price_at_add AS price_changed,
+               p.price * ci.quantity AS line_total, ci.created_at
+        FROM cart_items ci
+        JOIN products p ON p.id = ci.product_id
+        WHERE ci.{} = $1 AND ci.saved = $2
+        ORDER BY ci.created_at
+        "#,
+        owner.column()
+    );
+    let items = sqlx::query_as::<_, CartLine>(&sql)
+        .bind(owner.id())
+        .bind(saved)
+        .fetch_all(pool)
+        .await?;
+
+    let summary = CartSummary::from_lines(&items)?;
+    Ok(CartList { items, summary })
+}
+
 #[utoipa::path(
     post,
-    path = "/api/cart",
+    path = "/",
+    operation_id = "cart_add",
+    params(
+        ("X-Cart-Token" = Option<Uuid>, Header, description = "Guest cart token, used when no Authorization header is sent")
+    ),
     request_body = AddToCartRequest,
     responses(
         (status = 200, description = "Add or update cart item", body = ApiResponse<CartItem>),
-        (status = 400, description = "Bad request"),
+        (status = 400, description = "Bad request, or missing or invalid bearer token"),
+        (status = 422, description = "Invalid quantity", body = ApiResponse<ErrorData>),
     ),
     tag = "cart"
 )]
 pub async fn add_to_cart(
     State(pool): State<DbPool>,
-    user: AuthUser,
-    Json(payload): Json<AddToCartRequest>,
+    owner: CartOwner,
+    AppJson(payload): AppJson<AddToCartRequest>,
 ) -> AppResult<Json<ApiResponse<CartItem>>> {
     if payload.quantity <= 0 {
-        return Err(AppError::BadRequest(
-            "quantity must be greater than 0".to_string(),
-        ));
+        let mut errors = FieldErrors::default();
+        errors.add(
+            "quantity",
+            "not_positive",
+            "quantity must be greater than 0",
+        );
+        errors.finish()?;
     }
-    let product_exist: Option<(Uuid,)> = sqlx::query_as("SELECT id FROM products WHERE id = $1 ")
-        .bind(payload.product_id)
-        .fetch_optional(&pool)
-        .await?;
-    if product_exist.is_none() {
-        return Err(AppError::BadRequest("product not found".to_string()));
-    }
-    let exist: Option<CartItem> =
-        sqlx::query_as("SELECT * FROM cart_items WHERE user_id = $1 AND product_id = $2")
+    let product: Option<(i64,)> =
+        sqlx::query_as("SELECT price FROM products WHERE id = $1 AND is_published")
             .bind(payload.product_id)
             .fetch_optional(&pool)
             .await?;
-
-    let cart_item = if let Some(item) = exist {
-        sqlx::query_as::<_, CartItem>(
-            r#"
-            UPDATE cart_items
-            SET quantity = $3
-            WHERE id = $1
-            RETURNING *
-            "#,
-        )
-        .bind(item.id)
-        .bind(user.user_id)
+    let Some((price,)) = product else {
+        return Err(AppError::BadRequest("product not found".to_string()));
+    };
+    // upsert so concurrent adds of the same product can't trip the unique constraint;
+    // an existing line keeps its price_at_add
+    let sql = format!(
+        r#"
+        INSERT INTO cart_items (id, {col}, product_id, quantity, price_at_add)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT ({col}, product_id) DO UPDATE SET quantity = EXCLUDED.quantity, saved = false
+        RETURNING *
+        "#,
+        col = owner.column()
+    );
+    let cart_item: CartItem = sqlx::query_as(&sql)
+        .bind(new_id())
+        .bind(owner.id())
+        .bind(payload.product_id)
         .bind(payload.quantity)
+        .bind(price)
         .fetch_one(&pool)
-        .await?
-    } else {
-        sqlx::query_as("INSERT INTO cart_items (user_id, product_id, quantity) VALUES ($1, $2, $3) RETURNING *")
-            .bind(user.user_id)
-            .bind(payload.product_id)
-            .bind(payload.quantity)
-            .fetch_one(&pool)
-            .await?
-    };
+        .await?;
     Ok(Json(ApiResponse::success("OK", cart_item, None)))
 }
 
 #[utoipa::path(
-    delete,
-    path = "/api/cart/{product_id}",
+    post,
+    path = "/bulk",
+    operation_id = "cart_bulk_add",
     params(
+        ("X-Cart-Token" = Option<Uuid>, Header, description = "Guest cart token, used when no Authorization header is sent")
+    ),
+    request_body = Vec<AddToCartRequest>,
+    responses(
+        (status = 200, description = "Add or update several cart items at once", body = ApiResponse<CartList>),
+        (status = 400, description = "Unknown product ids or too many entries, or missing or invalid bearer token"),
+        (status = 422, description = "Invalid quantity", body = ApiResponse<ErrorData>),
+    ),
+    tag = "cart"
+)]
+pub async fn bulk_add_to_cart(
+    State(pool): State<DbPool>,
+    owner: CartOwner,
+    AppJson(payload): AppJson<Vec<AddToCartRequest>>,
+) -> AppResult<Json<ApiResponse<CartList>>> {
+    if payload.is_empty() {
+        return Err(AppError::BadRequest("items must not be empty".to_string()));
+    }
+    if payload.len() > MAX_BULK_CART_ITEMS {
+        return Err(AppError::BadRequest(format!(
+            "at most {} items can be added at once",
+            MAX_BULK_CART_ITEMS
+        )));
+    }
+    let mut errors = FieldErrors::default();
+    for (i, item) in payload.iter().enumerate() {
+        if item.quantity <= 0 {
+            errors.add(
+                &format!("[{}].quantity", i),
+                "not_positive",
+                "quantity must be greater than 0",
+            );
+        }
+    }
+    errors.finish()?;
 
-        ("product_id" = Uuid, Path, description = "Product ID")
+    let mut product_ids: Vec<Uuid> = payload.iter().map(|item| item.product_id).collect();
+    product_ids.sort();
+    product_ids.dedup();
+    if product_ids.len() != payload.len() {
+        return Err(AppError::BadRequest(
+            "each product may only appear once".to_string(),
+        ));
+    }
+
+    let found: Vec<(Uuid,)> =
+        sqlx::query_as("SELECT id FROM products WHERE id = ANY($1) AND is_published")
+            .bind(&product_ids)
+            .fetch_all(&pool)
+            .await?;
+    let missing: Vec<String> = product_ids
+        .iter()
+        .filter(|id| !found.iter().any(|(f,)| f == *id))
+        .map(|id| id.to_string())
+        .collect();
+    if !missing.is_empty() {
+        return Err(AppError::BadRequest(format!(
+            "products not found: {}",
+            missing.join(", ")
+        )));
+    }
+
+    let ids: Vec<Uuid> = payload.iter().map(|_| new_id()).collect();
+    let product_ids: Vec<Uuid> = payload.iter().map(|item| item.product_id).collect();
+    let quantities: Vec<i32> = payload.iter().map(|item| item.quantity).collect();
+
+    // a single statement keeps the whole batch atomic
+    let sql = format!(
+        r#"
+        INSERT INTO cart_items (id, {col}, product_id, quantity, price_at_add)
+        SELECT t.id, $2, t.product_id, t.quantity, p.price
+        FROM UNNEST($1::uuid[], $3::uuid[], $4::int[]) AS t(id, product_id, quantity)
+        JOIN products p ON p.id = t.product_id
+        ON CONFLICT ({col}, product_id) DO UPDATE SET quantity = EXCLUDED.quantity, saved = false
+        "#,
+        col = owner.column()
+    );
+    sqlx::query(&sql)
+        .bind(&ids)
+        .bind(owner.id())
+        .bind(&product_ids)
+        .bind(&quantities)
+        .execute(&pool)
+        .await?;
+
+    let data = fetch_cart(&pool, owner, false).await?;
+    Ok(Json(ApiResponse::success("OK", data, None)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{product_id}",
+    operation_id = "cart_remove_product",
+    params(
+        ("product_id" = Uuid, Path, description = "Product ID"),
+        ("X-Cart-Token" = Option<Uuid>, Header, description = "Guest cart token, used when no Authorization header is sent")
     ),
     responses(
         (status = 200, description = "OK", body = ApiResponse<serde_json::Value>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
         (status = 404, description = "Cart item not found"),
     ),
-    tag = "Cart"
+    tag = "cart"
 )]
 pub async fn remove_from_cart(
     State(pool): State<DbPool>,
-    auht: AuthUser,
+    owner: CartOwner,
     Path(product_id): Path<Uuid>,
 ) -> AppResult<Json<ApiResponse<serde_json::Value>>> {
-    let result = sqlx::query("DELETE from cart_items where product_id = $1 and user_id = $2")
+    let sql = format!(
+        "DELETE from cart_items where product_id = $1 and {} = $2",
+        owner.column()
+    );
+    let result = sqlx::query(&sql)
         .bind(product_id)
-        .bind(auht.user_id)
+        .bind(owner.id())
         .execute(&pool)
         .await?;
 
@@ -153,3 +361,203 @@ pub async fn remove_from_cart(
         Some(Meta::empty()),
     )))
 }
+
+#[utoipa::path(
+    delete,
+    path = "/items/{id}",
+    operation_id = "cart_remove_item",
+    params(
+        ("id" = Uuid, Path, description = "Cart item ID"),
+        ("X-Cart-Token" = Option<Uuid>, Header, description = "Guest cart token, used when no Authorization header is sent")
+    ),
+    responses(
+        (status = 200, description = "OK", body = ApiResponse<serde_json::Value>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Cart item not found"),
+    ),
+    tag = "cart"
+)]
+pub async fn remove_cart_item(
+    State(pool): State<DbPool>,
+    owner: CartOwner,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<serde_json::Value>>> {
+    let sql = format!(
+        "DELETE FROM cart_items WHERE id = $1 AND {} = $2",
+        owner.column()
+    );
+    let result = sqlx::query(&sql)
+        .bind(id)
+        .bind(owner.id())
+        .execute(&pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(Json(ApiResponse::success(
+        "Removed from cart",
+        serde_json::json!({}),
+        Some(Meta::empty()),
+    )))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/save",
+    operation_id = "cart_save_for_later",
+    params(
+        ("id" = Uuid, Path, description = "Cart item ID"),
+        ("X-Cart-Token" = Option<Uuid>, Header, description = "Guest cart token, used when no Authorization header is sent")
+    ),
+    responses(
+        (status = 200, description = "Move a cart line to the saved-for-later list", body = ApiResponse<CartItem>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Cart item not found"),
+    ),
+    tag = "cart"
+)]
+pub async fn save_for_later(
+    State(pool): State<DbPool>,
+    owner: CartOwner,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<CartItem>>> {
+    let item = set_saved(&pool, owner, id, true).await?;
+    Ok(Json(ApiResponse::success("Saved for later", item, None)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/unsave",
+    operation_id = "cart_unsave",
+    params(
+        ("id" = Uuid, Path, description = "Cart item ID"),
+        ("X-Cart-Token" = Option<Uuid>, Header, description = "Guest cart token, used when no Authorization header is sent")
+    ),
+    responses(
+        (status = 200, description = "Move a saved line back into the active cart", body = ApiResponse<CartItem>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Cart item not found"),
+    ),
+    tag = "cart"
+)]
+pub async fn unsave(
+    State(pool): State<DbPool>,
+    owner: CartOwner,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<CartItem>>> {
+    let item = set_saved(&pool, owner, id, false).await?;
+    Ok(Json(ApiResponse::success("Moved to cart", item, None)))
+}
+
+async fn set_saved(pool: &DbPool, owner: CartOwner, id: Uuid, saved: bool) -> AppResult<CartItem> {
+    let sql = format!(
+        "UPDATE cart_items SET saved = $3 WHERE id = $1 AND {} = $2 RETURNING *",
+        owner.column()
+    );
+    let item = sqlx::query_as::<_, CartItem>(&sql)
+        .bind(id)
+        .bind(owner.id())
+        .bind(saved)
+        .fetch_optional(pool)
+        .await?;
+    item.ok_or(AppError::NotFound)
+}
+
+#[utoipa::path(
+    post,
+    path = "/session",
+    operation_id = "cart_create_session",
+    responses(
+        (status = 200, description = "Issue a guest cart token to send as X-Cart-Token", body = ApiResponse<CartSession>),
+    ),
+    tag = "cart"
+)]
+pub async fn create_cart_session(
+    State(pool): State<DbPool>,
+) -> AppResult<Json<ApiResponse<CartSession>>> {
+    let session = sqlx::query_as::<_, CartSession>(
+        "INSERT INTO cart_sessions (token) VALUES ($1) RETURNING *",
+    )
+    .bind(Uuid::new_v4())
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(ApiResponse::success(
+        "Cart session created",
+        session,
+        Some(Meta::empty()),
+    )))
+}
+
+/// Deletes guest carts older than [`GUEST_CART_TTL_DAYS`]; their lines cascade. Run hourly
+/// by the `prune_guest_carts` job.
+pub async fn prune_guest_carts(pool: &DbPool) -> AppResult<u64> {
+    let result = sqlx::query(
+        "DELETE FROM cart_sessions WHERE created_at <= NOW() - make_interval(days => $1)",
+    )
+    .bind(GUEST_CART_TTL_DAYS)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Moves a guest cart into the user's cart, summing quantities for products in both. A
+/// token older than [`GUEST_CART_TTL_DAYS`] is ignored, as it is by [`CartOwner`].
+pub async fn merge_guest_cart(pool: &DbPool, token: Uuid, user_id: Uuid) -> AppResult<()> {
+    let mut tx = pool.begin().await?;
+
+    let session: Option<(Uuid,)> = sqlx::query_as(
+        "SELECT token FROM cart_sessions WHERE token = $1 AND created_at > NOW() - make_interval(days => $2) FOR UPDATE",
+    )
+    .bind(token)
+    .bind(GUEST_CART_TTL_DAYS)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if session.is_none() {
+        // kedaluwarsa atau tidak dikenal: biarkan untuk job prune_guest_carts
+        return Ok(());
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE cart_items u
+        SET quantity = u.quantity + g.quantity
+        FROM cart_items g
+        WHERE g.session_token = $1 AND u.user_id = $2 AND u.product_id = g.product_id
+        "#,
+    )
+    .bind(token)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        DELETE FROM cart_items g
+        USING cart_items u
+        WHERE g.session_token = $1 AND u.user_id = $2 AND u.product_id = g.product_id
+        "#,
+    )
+    .bind(token)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "UPDATE cart_items SET user_id = $2, session_token = NULL WHERE session_token = $1",
+    )
+    .bind(token)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("DELETE FROM cart_sessions WHERE token = $1")
+        .bind(token)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
@@ -0,0 +1,289 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
+use uuid::Uuid;
+
+use crate::{
+    cache::ProductCache,
+    db::DbPool,
+    error::{AppError, AppResult, ErrorData},
+    extract::{AppJson, AppQuery},
+    ids::new_id,
+    middleware::auth::AuthUser,
+    models::Category,
+    response::{ApiResponse, Meta},
+    routes::admin::ensure_admin,
+    slug::slugify,
+    state::AppState,
+};
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateCategoryRequest {
+    pub name: String,
+    /// Defaults to a slug generated from the name
+    pub slug: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateCategoryRequest {
+    pub name: Option<String>,
+    pub slug: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CategoryList {
+    pub items: Vec<Category>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteCategoryQuery {
+    /// Detach products from the category instead of refusing the delete
+    pub force: Option<bool>,
+}
+
+pub fn router() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(list_categories, create_category))
+        .routes(routes!(update_category, delete_category))
+}
+
+fn normalize_slug(name: &str, slug: Option<&str>) -> AppResult<String> {
+    let slug = slugify(slug.unwrap_or(name));
+    if slug.is_empty() {
+        return Err(AppError::BadRequest(
+            "slug must contain at least one letter or digit".to_string(),
+        ));
+    }
+    Ok(slug)
+}
+
+async fn ensure_slug_available(pool: &DbPool, slug: &str, except: Option<Uuid>) -> AppResult<()> {
+    let taken: Option<(Uuid,)> =
+        sqlx::query_as("SELECT id FROM categories WHERE slug = $1 AND id IS DISTINCT FROM $2")
+            .bind(slug)
+            .bind(except)
+            .fetch_optional(pool)
+            .await?;
+    if taken.is_some() {
+        return Err(AppError::Conflict(format!(
+            "category slug {} is already taken",
+            slug
+        )));
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    operation_id = "admin_categories_list",
+    responses(
+        (status = 200, description = "List categories (admin only)", body = ApiResponse<CategoryList>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+    ),
+    tag = "Admin"
+)]
+pub async fn list_categories(
+    State(pool): State<DbPool>,
+    user: AuthUser,
+) -> AppResult<Json<ApiResponse<CategoryList>>> {
+    ensure_admin(&user)?;
+    let items = sqlx::query_as::<_, Category>("SELECT * FROM categories ORDER BY name")
+        .fetch_all(&pool)
+        .await?;
+    let total = items.len() as i64;
+    let meta = Meta::new(1, total, total);
+
+    Ok(Json(ApiResponse::success(
+        "Categories",
+        CategoryList { items },
+        Some(meta),
+    )))
+}
+
+#[utoipa::path(
+    post,
+    path = "/",
+    operation_id = "admin_categories_create",
+    request_body = CreateCategoryRequest,
+    responses(
+        (status = 200, description = "Create category (admin only)", body = ApiResponse<Category>),
+        (status = 400, description = "Invalid slug, or missing or invalid bearer token"),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+        (status = 409, description = "Slug already taken"),
+    ),
+    tag = "Admin"
+)]
+pub async fn create_category(
+    State(pool): State<DbPool>,
+    user: AuthUser,
+    AppJson(payload): AppJson<CreateCategoryRequest>,
+) -> AppResult<Json<ApiResponse<Category>>> {
+    ensure_admin(&user)?;
+    let name = payload.name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::BadRequest("name must not be empty".to_string()));
+    }
+    let slug = normalize_slug(&name, payload.slug.as_deref())?;
+    ensure_slug_available(&pool, &slug, None).await?;
+
+    let category = sqlx::query_as::<_, Category>(
+        "INSERT INTO categories (id, name, slug) VALUES ($1, $2, $3) RETURNING *",
+    )
+    .bind(new_id())
+    .bind(name)
+    .bind(slug)
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(ApiResponse::success(
+        "Category created",
+        category,
+        Some(Meta::empty()),
+    )))
+}
+
+#[utoipa::path(
+    put,
+    path = "/{id}",
+    operation_id = "admin_categories_update",
+    params(
+        ("id" = Uuid, Path, description = "Category ID")
+    ),
+    request_body = UpdateCategoryRequest,
+    responses(
+        (status = 200, description = "Update category (admin only)", body = ApiResponse<Category>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Category not found"),
+        (status = 409, description = "Slug already taken"),
+    ),
+    tag = "Admin"
+)]
+pub async fn update_category(
+    State(pool): State<DbPool>,
+    State(cache): State<ProductCache>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    AppJson(payload): AppJson<UpdateCategoryRequest>,
+) -> AppResult<Json<ApiResponse<Category>>> {
+    ensure_admin(&user)?;
+    let existing = sqlx::query_as::<_, Category>("SELECT * FROM categories WHERE id = $1")
+        .bind(id)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let name = match payload.name {
+        Some(name) if name.trim().is_empty() => {
+            return Err(AppError::BadRequest("name must not be empty".to_string()));
+        }
+        Some(name) => name.trim().to_string(),
+        None => existing.name,
+    };
+    let slug = match payload.slug {
+        Some(slug) => normalize_slug(&name, Some(&slug))?,
+        None => existing.slug,
+    };
+    ensure_slug_available(&pool, &slug, Some(id)).await?;
+
+    let category = sqlx::query_as::<_, Category>(
+        "UPDATE categories SET name = $2, slug = $3 WHERE id = $1 RETURNING *",
+    )
+    .bind(id)
+    .bind(name)
+    .bind(slug)
+    .fetch_one(&pool)
+    .await?;
+
+    // Cached products carry the category name, so drop the ones filed under it.
+    let product_ids: Vec<Uuid> =
+        sqlx::query_scalar("SELECT id FROM products WHERE category_id = $1")
+            .bind(id)
+            .fetch_all(&pool)
+            .await?;
+    for product_id in product_ids {
+        cache.invalidate(product_id).await;
+    }
+
+    Ok(Json(ApiResponse::success(
+        "Updated",
+        category,
+        Some(Meta::empty()),
+    )))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    operation_id = "admin_categories_delete",
+    params(
+        ("id" = Uuid, Path, description = "Category ID"),
+        DeleteCategoryQuery
+    ),
+    responses(
+        (status = 200, description = "Delete category (admin only)", body = ApiResponse<serde_json::Value>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Category not found"),
+        (status = 409, description = "Category still has products and force is not set"),
+    ),
+    tag = "Admin"
+)]
+pub async fn delete_category(
+    State(pool): State<DbPool>,
+    State(cache): State<ProductCache>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    AppQuery(query): AppQuery<DeleteCategoryQuery>,
+) -> AppResult<Json<ApiResponse<serde_json::Value>>> {
+    ensure_admin(&user)?;
+    let mut tx = pool.begin().await?;
+
+    let products: (i64,) = sqlx::query_as("SELECT count(*) FROM products WHERE category_id = $1")
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    let detached: Vec<Uuid> = if products.0 > 0 {
+        if !query.force.unwrap_or(false) {
+            return Err(AppError::Conflict(format!(
+                "category has {} products; pass force=true to detach them",
+                products.0
+            )));
+        }
+        sqlx::query_scalar(
+            "UPDATE products SET category_id = NULL WHERE category_id = $1 RETURNING id",
+        )
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?
+    } else {
+        Vec::new()
+    };
+
+    let result = sqlx::query("DELETE FROM categories WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    tx.commit().await?;
+    for product_id in detached {
+        cache.invalidate(product_id).await;
+    }
+
+    Ok(Json(ApiResponse::success(
+        "Deleted",
+        serde_json::json!({}),
+        Some(Meta::empty()),
+    )))
+}
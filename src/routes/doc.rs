@@ -1,50 +1,104 @@
-use utoipa::{OpenApi, openapi::OpenApi as OpenApiSpec};
+use axum::{Json, Router, routing::get};
+use utoipa::{
+    Modify, OpenApi,
+    openapi::{
+        OpenApi as OpenApiSpec,
+        extensions::ExtensionsBuilder,
+        security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+        server::Server,
+    },
+};
 use utoipa_scalar::{Scalar, Servable};
+use utoipa_swagger_ui::{Config, SwaggerUi};
 
 use crate::{
-    models::{CartItem, Favorite, Order, OrderItem, Product, User},
+    audit::AuditAction,
+    build_info,
+    config::DocsUi,
+    error::{ErrorCode, ErrorData, FieldError},
+    models::{
+        AuditLogEntry, CartItem, CartSession, Category, Favorite, Order, OrderItem, Product,
+        ProductImage, ProductPriceChange, Review, StockAlert, StockMovement, StockMovementReason,
+        User, UserProfile,
+    },
     response::{ApiResponse, Meta},
-    routes::{admin, auth, cart, favorites, health, orders, products},
+    routes::{admin, auth, cart, health, orders, products, reviews, v1_router},
 };
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(
-        health::health_check,
-        auth::login,
-        auth::register,
-        cart::cart_list,
-        cart::add_to_cart,
-        cart::remove_from_cart,
-        products::list_products,
-        products::create_product,
-        products::get_product,
-        products::update_product,
-        products::delete_product,
-        orders::list_order,
-        orders::checkout,
-        orders::get_order,
-        admin::list_all_orders,
-        admin::get_order_admin,
-        favorites::add_favorite,
-        favorites::remove_favorite,
-        favorites::list_favorites
-    ),
+    modifiers(&SecurityAddon, &BuildInfoAddon),
+    // the token is optional on public routes, hence the empty requirement
+    security((), ("bearer_auth" = [])),
     components(
         schemas(
             User,
+            UserProfile,
             Product,
+            Category,
+            ProductImage,
+            ProductPriceChange,
+            StockMovement,
+            StockMovementReason,
+            StockAlert,
+            Review,
+            reviews::ReviewList,
+            admin::PriceHistoryList,
+            admin::StockMovementList,
+            admin::StockAlertList,
+            admin::InventoryAdjustRequest,
+            admin::ForceStatusRequest,
+            admin::RestoreProductRequest,
+            admin::DeletedFilter,
+            admin::JobList,
+            admin::AuditLogList,
+            admin::AuditPruneResult,
+            admin::UserList,
+            admin::LowStockList,
+            admin::DashboardStats,
+            admin::PeriodStats,
+            admin::OrderStatusCounts,
+            admin::AdminOrderList,
+            admin::TopProductList,
+            AuditLogEntry,
+            AuditAction,
+            FieldError,
+            ErrorCode,
+            ErrorData,
             Favorite,
             CartItem,
+            CartSession,
             Order,
             OrderItem,
             Meta,
             ApiResponse<Product>,
-            ApiResponse<products::ProductList>
+            ApiResponse<products::ProductList>,
+            cart::CartList,
+            cart::CartLine,
+            cart::CartSummary,
+            cart::AddToCartRequest,
+            orders::CheckoutRequest,
+            orders::OrderSummary,
+            orders::OrderInclude,
+            products::CreateProductRequest,
+            products::ProductSortBy,
+            products::SortOrder,
+            auth::RegisterRequest,
+            auth::LoginRequest,
+            auth::LoginResponse,
+            auth::UpdateProfileRequest,
+            health::HealthData,
+            health::DependencyStatus,
+            health::DependencyState,
+            health::PoolStats,
+            health::LiveData,
+            health::VersionData,
+            health::ReadyData,
+            health::MigrationStatus
         )
     ),
     tags(
-        (name = "Health", description = "Health check endpoint"),
+        (name = "Health", description = "Health, liveness and readiness probes"),
         (name = "Products", description = "Product endpoints"),
         (name = "Cart", description = "Cart endpoints"),
         (name = "Orders", description = "Order endpoints"),
@@ -54,29 +108,108 @@ use crate::{
 )]
 pub struct ApiDoc;
 
-pub fn scalar_docs() -> Scalar<OpenApiSpec> {
-    Scalar::with_url("/docs", ApiDoc::openapi())
-    //.custom_html(SCALAR_HTML)
+/// Declares the JWT bearer scheme, which gives the docs UIs their "Authorize" button.
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut OpenApiSpec) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
 }
 
-const SCALAR_HTML: &str = r#"<!doctype html>
-<html>
-<head>
-    <title>API Reference</title>
-    <meta charset="utf-8"/>
-    <meta name="viewport" content="width=device-width, initial-scale=1"/>
-</head>
-<body>
+/// Puts the commit and build time next to the version in `info`.
+struct BuildInfoAddon;
+
+impl Modify for BuildInfoAddon {
+    fn modify(&self, openapi: &mut OpenApiSpec) {
+        openapi.info.version = build_info::VERSION.to_string();
+        openapi.info.extensions = Some(
+            ExtensionsBuilder::new()
+                .add(
+                    "x-build",
+                    serde_json::json!({
+                        "git_commit": build_info::GIT_COMMIT,
+                        "timestamp": build_info::BUILD_TIMESTAMP,
+                    }),
+                )
+                .build(),
+        );
+    }
+}
+
+/// The v1 spec, built without state or a database.
+pub fn openapi_spec(server_url: &str) -> OpenApiSpec {
+    with_server(v1_router().into_openapi(), server_url)
+}
+
+/// Lists `server_url` as the only server, so "try it" in the docs targets this deployment.
+pub fn with_server(mut spec: OpenApiSpec, server_url: &str) -> OpenApiSpec {
+    spec.servers = Some(vec![Server::new(server_url)]);
+    spec
+}
+
+/// Per `(version, spec)`: the raw spec at `/api-docs/{version}/openapi.json` and, if `ui` has
+/// it, Scalar at `/docs/{version}`; `/docs` and `/api-docs/openapi.json` serve the last, current
+/// one. Swagger UI at `/swagger` lists every version. `DocsUi::None` mounts nothing.
+pub fn docs_router<S>(versions: Vec<(&str, OpenApiSpec)>, ui: DocsUi) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    let mut router = Router::new();
+    if ui == DocsUi::None {
+        return router;
+    }
+
+    if let Some((_, current)) = versions.last() {
+        router = router.merge(spec_routes(
+            "/docs",
+            "/api-docs/openapi.json",
+            current.clone(),
+            ui,
+        ));
+    }
+    let mut spec_urls = Vec::new();
+    for (version, spec) in versions {
+        let json_path = format!("/api-docs/{}/openapi.json", version);
+        router = router.merge(spec_routes(
+            &format!("/docs/{}", version),
+            &json_path,
+            spec,
+            ui,
+        ));
+        spec_urls.push(json_path);
+    }
+    if ui.swagger() {
+        // Current version first, as Swagger UI opens the first; it reads the spec routes above.
+        spec_urls.reverse();
+        router = router.merge(SwaggerUi::new("/swagger").config(Config::new(spec_urls)));
+    }
+    router
+}
 
-<script id="api-reference" type="application/json">
+fn spec_routes<S>(docs_path: &str, json_path: &str, spec: OpenApiSpec, ui: DocsUi) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
 {
-  "themeId": "elysiajs",
-  "colorMode": "dark",
-  "layout": "modern",
-  "spec": $spec
+    let router = Router::new().route(
+        json_path,
+        get({
+            let spec = spec.clone();
+            move || async move { Json(spec) }
+        }),
+    );
+    if ui.scalar() {
+        router.merge(Scalar::with_url(docs_path.to_string(), spec))
+    } else {
+        router
+    }
 }
-</script>
-<script src="https://cdn.jsdelivr.net/npm/@scalar/api-reference"></script>
-</body>
-</html>
-"#;
@@ -1,18 +1,24 @@
 use axum::{
-    Json, Router,
+    Json,
     extract::{Path, State},
-    routing::{delete, get, post},
+    http::{StatusCode, header},
 };
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
 use uuid::Uuid;
 
 use crate::{
+    cache::ProductCache,
     db::DbPool,
-    error::{AppError, AppResult},
+    error::{AppError, AppResult, ErrorCode, ErrorData},
+    extract::AppJson,
+    ids::new_id,
     middleware::auth::AuthUser,
-    models::{Favorite, Product},
-    response::{ApiResponse, Meta},
+    models::{CartItem, Favorite, Product},
+    response::{ApiResponse, Located, Meta},
+    routes::products::load_product_details,
+    state::AppState,
 };
 
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
@@ -24,27 +30,59 @@ pub struct FavoriteProductList {
     pub items: Vec<Product>,
 }
 
-pub fn router() -> Router<DbPool> {
-    Router::new()
-        .route("/", get(list_favorites).post(add_favorite))
-        .route("/{product_id}", delete(remove_favorite))
+/// Largest list accepted by `PUT /api/favorites`.
+pub const MAX_SYNC_FAVORITES: usize = 500;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SyncFavoritesRequest {
+    /// The complete set of favorites the client wants to end up with
+    pub product_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SyncFavoritesResult {
+    pub items: Vec<Product>,
+    /// Requested ids that don't match a published product
+    pub skipped: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FavoriteStatus {
+    pub favorited: bool,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct MoveToCartRequest {
+    /// Also remove the product from favorites once it is in the cart.
+    #[serde(default)]
+    pub remove_favorite: bool,
+}
+
+pub fn router() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(list_favorites, add_favorite, sync_favorites))
+        .routes(routes!(favorite_status, remove_favorite))
+        .routes(routes!(move_to_cart))
+        .routes(routes!(toggle_favorite))
 }
 
 #[utoipa::path(
-    post,
-    path = "/favorites",
+    delete,
+    path = "/{product_id}",
+    operation_id = "favorites_remove",
     tag = "favorites",
-    operation_id = "add_favorite",
-    request_body = AddFavoriteRequest,
+    params(
+        ("product_id" = Uuid, Path, description = "Product ID")
+    ),
     responses(
-        (status = 200, description = "OK", body = ApiResponse<Favorite>),
-        (status = 400, description = "Bad Request", body = ApiResponse<serde_json::Value>),
-        (status = 401, description = "Unauthorized", body = ApiResponse<serde_json::Value>),
-        (status = 404, description = "Not Found", body = ApiResponse<serde_json::Value>),
+        (status = 200, description = "Removed from favorites", body = ApiResponse<serde_json::Value>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Not Found", body = ApiResponse<ErrorData>),
     )
 )]
 pub async fn remove_favorite(
     State(pool): State<DbPool>,
+    State(cache): State<ProductCache>,
     user: AuthUser,
     Path(product_id): Path<Uuid>,
 ) -> AppResult<Json<ApiResponse<serde_json::Value>>> {
@@ -57,6 +95,7 @@ pub async fn remove_favorite(
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound);
     }
+    cache.invalidate(product_id).await;
 
     Ok(Json(ApiResponse::success(
         "Removed from favorites",
@@ -67,101 +106,380 @@ pub async fn remove_favorite(
 
 #[utoipa::path(
     get,
-    path = "/favorites",
+    path = "/",
+    operation_id = "favorites_list",
     tag = "favorites",
-    operation_id = "list_favorites",
     responses(
         (status = 200, description = "OK", body = ApiResponse<FavoriteProductList>),
-        (status = 401, description = "Unauthorized", body = ApiResponse<serde_json::Value>),
-        (status = 404, description = "Not Found", body = ApiResponse<serde_json::Value>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Not Found", body = ApiResponse<ErrorData>),
     )
 )]
 pub async fn list_favorites(
     State(db): State<DbPool>,
     user: AuthUser,
 ) -> AppResult<Json<ApiResponse<FavoriteProductList>>> {
-    let products = sqlx::query_as::<_, Product>(
+    let products = fetch_favorite_products(&db, user.user_id).await?;
+    // hanya produk yang masih tampil yang dihitung, sama dengan isi `items`
+    let total = products.len() as i64;
+    let meta = Meta::new(1, total, total);
+
+    let data = FavoriteProductList { items: products };
+
+    Ok(Json(ApiResponse::success("OK", data, Some(meta))))
+}
+
+async fn fetch_favorite_products(pool: &DbPool, user_id: Uuid) -> AppResult<Vec<Product>> {
+    let mut products = sqlx::query_as::<_, Product>(
         r#"
         SELECT p.*
         FROM favorites f
         JOIN products p ON p.id = f.product_id
-        WHERE f.user_id = $1
+        WHERE f.user_id = $1 AND p.is_published
         ORDER BY f.created_at DESC
         "#,
     )
-    .bind(user.user_id)
-    .fetch_all(&db)
+    .bind(user_id)
+    .fetch_all(pool)
     .await?;
-
-    let total: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM favorites WHERE user_id = $1")
-        .bind(user.user_id)
-        .fetch_one(&db)
-        .await?;
-
-    let meta = Meta::new(1, total.0, total.0);
-
-    let data = FavoriteProductList { items: products };
-
-    Ok(Json(ApiResponse::success("OK", data, Some(meta))))
+    load_product_details(pool, &mut products).await?;
+    for product in products.iter_mut() {
+        product.is_favorited = true;
+    }
+    Ok(products)
 }
 
 #[utoipa::path(
     post,
-    path = "/favorites/{product_id}",
+    path = "/",
+    operation_id = "favorites_add",
     tag = "favorites",
-    operation_id = "add_favorite",
     request_body = AddFavoriteRequest,
     responses(
-        (status = 200, description = "OK", body = ApiResponse<Favorite>),
-        (status = 400, description = "Bad Request", body = ApiResponse<serde_json::Value>),
-        (status = 401, description = "Unauthorized", body = ApiResponse<serde_json::Value>),
-        (status = 404, description = "Not Found", body = ApiResponse<serde_json::Value>),
+        (status = 201, description = "Favorite added", body = ApiResponse<Favorite>,
+            headers(("Location" = String, description = "URL of the favorite status"))),
+        (status = 200, description = "Product was already a favorite", body = ApiResponse<Favorite>),
+        (status = 400, description = "Bad Request, or missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Not Found", body = ApiResponse<ErrorData>),
     )
 )]
 pub async fn add_favorite(
     State(pool): State<DbPool>,
+    State(cache): State<ProductCache>,
     user: AuthUser,
-    Json(payload): Json<AddFavoriteRequest>,
-) -> AppResult<Json<ApiResponse<Favorite>>> {
+    AppJson(payload): AppJson<AddFavoriteRequest>,
+) -> AppResult<Located<Favorite>> {
     // cek apakah product ada
-    let product_exists: Option<(Uuid,)> = sqlx::query_as("SELECT id FROM products WHERE id = $1")
-        .bind(payload.product_id)
-        .fetch_optional(&pool)
-        .await?;
+    let product_exists: Option<(Uuid,)> =
+        sqlx::query_as("SELECT id FROM products WHERE id = $1 AND is_published")
+            .bind(payload.product_id)
+            .fetch_optional(&pool)
+            .await?;
 
     if product_exists.is_none() {
         return Err(AppError::BadRequest("Product not found".into()));
     }
 
-    // cek apakah favorite sudah ada
-    let existing: Option<Favorite> =
-        sqlx::query_as("SELECT * FROM favorites WHERE user_id = $1 AND product_id = $2")
+    // insert langsung; kalau sudah ada (termasuk request paralel) ambil row yang ada
+    let inserted = sqlx::query_as::<_, Favorite>(
+        r#"
+        INSERT INTO favorites (id, user_id, product_id)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, product_id) DO NOTHING
+        RETURNING *
+        "#,
+    )
+    .bind(new_id())
+    .bind(user.user_id)
+    .bind(payload.product_id)
+    .fetch_optional(&pool)
+    .await?;
+
+    let (status, favorite) = match inserted {
+        Some(favorite) => {
+            cache.invalidate(payload.product_id).await;
+            (StatusCode::CREATED, favorite)
+        }
+        None => {
+            let existing =
+                sqlx::query_as("SELECT * FROM favorites WHERE user_id = $1 AND product_id = $2")
+                    .bind(user.user_id)
+                    .bind(payload.product_id)
+                    .fetch_one(&pool)
+                    .await?;
+            (StatusCode::OK, existing)
+        }
+    };
+
+    Ok((
+        status,
+        [(
+            header::LOCATION,
+            format!("/api/v1/favorites/{}", payload.product_id),
+        )],
+        Json(ApiResponse::success(
+            "Added to favorites",
+            favorite,
+            Some(Meta::empty()),
+        )),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{product_id}/move-to-cart",
+    operation_id = "favorites_move_to_cart",
+    tag = "favorites",
+    params(
+        ("product_id" = Uuid, Path, description = "Product ID")
+    ),
+    request_body(content = Option<MoveToCartRequest>),
+    responses(
+        (status = 200, description = "OK", body = ApiResponse<CartItem>),
+        (status = 400, description = "Product is out of stock, or missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Favorite not found", body = ApiResponse<ErrorData>),
+    )
+)]
+pub async fn move_to_cart(
+    State(pool): State<DbPool>,
+    State(cache): State<ProductCache>,
+    user: AuthUser,
+    Path(product_id): Path<Uuid>,
+    payload: Option<AppJson<MoveToCartRequest>>,
+) -> AppResult<Json<ApiResponse<CartItem>>> {
+    let AppJson(payload) = payload.unwrap_or_default();
+
+    let mut tx = pool.begin().await?;
+
+    let product: Option<(i32, i64)> = sqlx::query_as(
+        r#"
+        SELECT p.stock - p.reserved, p.price
+        FROM favorites f
+        JOIN products p ON p.id = f.product_id
+        WHERE f.user_id = $1 AND f.product_id = $2 AND p.is_published
+        "#,
+    )
+    .bind(user.user_id)
+    .bind(product_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some((available, price)) = product else {
+        return Err(AppError::NotFound);
+    };
+    if available <= 0 {
+        return Err(AppError::BadRequest("Product is out of stock".into())
+            .with_code(ErrorCode::InsufficientStock));
+    }
+
+    let cart_item = sqlx::query_as::<_, CartItem>(
+        r#"
+        INSERT INTO cart_items (id, user_id, product_id, quantity, price_at_add)
+        VALUES ($1, $2, $3, 1, $4)
+        ON CONFLICT (user_id, product_id) DO UPDATE SET quantity = cart_items.quantity + 1, saved = false
+        RETURNING *
+        "#,
+    )
+    .bind(new_id())
+    .bind(user.user_id)
+    .bind(product_id)
+    .bind(price)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if payload.remove_favorite {
+        sqlx::query("DELETE FROM favorites WHERE user_id = $1 AND product_id = $2")
             .bind(user.user_id)
-            .bind(payload.product_id)
+            .bind(product_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    if payload.remove_favorite {
+        cache.invalidate(product_id).await;
+    }
+
+    Ok(Json(ApiResponse::success(
+        "Moved to cart",
+        cart_item,
+        Some(Meta::empty()),
+    )))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{product_id}",
+    operation_id = "favorites_status",
+    tag = "favorites",
+    params(
+        ("product_id" = Uuid, Path, description = "Product ID")
+    ),
+    responses(
+        (status = 200, description = "Whether the current user has favorited the product", body = ApiResponse<FavoriteStatus>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+    )
+)]
+pub async fn favorite_status(
+    State(pool): State<DbPool>,
+    user: AuthUser,
+    Path(product_id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<FavoriteStatus>>> {
+    let (favorited,): (bool,) = sqlx::query_as(
+        "SELECT EXISTS (SELECT 1 FROM favorites WHERE user_id = $1 AND product_id = $2)",
+    )
+    .bind(user.user_id)
+    .bind(product_id)
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(ApiResponse::success(
+        "OK",
+        FavoriteStatus { favorited },
+        None,
+    )))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{product_id}/toggle",
+    operation_id = "favorites_toggle",
+    tag = "favorites",
+    params(
+        ("product_id" = Uuid, Path, description = "Product ID")
+    ),
+    responses(
+        (status = 200, description = "Favorite added or removed; returns the new state", body = ApiResponse<FavoriteStatus>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Product not found", body = ApiResponse<ErrorData>),
+    )
+)]
+pub async fn toggle_favorite(
+    State(pool): State<DbPool>,
+    State(cache): State<ProductCache>,
+    user: AuthUser,
+    Path(product_id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<FavoriteStatus>>> {
+    let product_exists: Option<(Uuid,)> =
+        sqlx::query_as("SELECT id FROM products WHERE id = $1 AND is_published")
+            .bind(product_id)
             .fetch_optional(&pool)
             .await?;
+    if product_exists.is_none() {
+        return Err(AppError::NotFound);
+    }
 
-    let favorite = if let Some(fav) = existing {
-        fav
-    } else {
-        let id = Uuid::new_v4();
-        sqlx::query_as::<_, Favorite>(
-            r#"
+    // satu statement: hapus kalau ada, insert kalau tidak. Kalau toggle lain menang
+    // duluan saat insert, ON CONFLICT membuat favorite tetap ada, jadi hasilnya true.
+    let (favorited,): (bool,) = sqlx::query_as(
+        r#"
+        WITH deleted AS (
+            DELETE FROM favorites
+            WHERE user_id = $1 AND product_id = $2
+            RETURNING id
+        ),
+        inserted AS (
             INSERT INTO favorites (id, user_id, product_id)
-            VALUES ($1, $2, $3)
-            RETURNING *
-            "#,
+            SELECT $3, $1, $2
+            WHERE NOT EXISTS (SELECT 1 FROM deleted)
+            ON CONFLICT (user_id, product_id) DO NOTHING
+            RETURNING id
         )
-        .bind(id)
-        .bind(user.user_id)
-        .bind(payload.product_id)
-        .fetch_one(&pool)
-        .await?
-    };
+        SELECT NOT EXISTS (SELECT 1 FROM deleted)
+        "#,
+    )
+    .bind(user.user_id)
+    .bind(product_id)
+    .bind(new_id())
+    .fetch_one(&pool)
+    .await?;
+    cache.invalidate(product_id).await;
 
     Ok(Json(ApiResponse::success(
-        "Added to favorites",
-        favorite,
-        Some(Meta::empty()),
+        if favorited {
+            "Added to favorites"
+        } else {
+            "Removed from favorites"
+        },
+        FavoriteStatus { favorited },
+        None,
+    )))
+}
+
+#[utoipa::path(
+    put,
+    path = "/",
+    operation_id = "favorites_sync",
+    tag = "favorites",
+    request_body = SyncFavoritesRequest,
+    responses(
+        (status = 200, description = "Favorites replaced by the given set; unknown ids are skipped", body = ApiResponse<SyncFavoritesResult>),
+        (status = 400, description = "Too many product ids, or missing or invalid bearer token", body = ApiResponse<ErrorData>),
+    )
+)]
+pub async fn sync_favorites(
+    State(pool): State<DbPool>,
+    State(cache): State<ProductCache>,
+    user: AuthUser,
+    AppJson(payload): AppJson<SyncFavoritesRequest>,
+) -> AppResult<Json<ApiResponse<SyncFavoritesResult>>> {
+    if payload.product_ids.len() > MAX_SYNC_FAVORITES {
+        return Err(AppError::BadRequest(format!(
+            "at most {} product ids can be synced at once",
+            MAX_SYNC_FAVORITES
+        )));
+    }
+    let mut requested = payload.product_ids;
+    requested.sort();
+    requested.dedup();
+
+    let mut tx = pool.begin().await?;
+
+    let known: Vec<(Uuid,)> =
+        sqlx::query_as("SELECT id FROM products WHERE id = ANY($1) AND is_published")
+            .bind(&requested)
+            .fetch_all(&mut *tx)
+            .await?;
+    let known: Vec<Uuid> = known.into_iter().map(|(id,)| id).collect();
+    let skipped: Vec<Uuid> = requested
+        .into_iter()
+        .filter(|id| !known.contains(id))
+        .collect();
+
+    let removed: Vec<(Uuid,)> = sqlx::query_as(
+        "DELETE FROM favorites WHERE user_id = $1 AND NOT (product_id = ANY($2)) RETURNING product_id",
+    )
+    .bind(user.user_id)
+    .bind(&known)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let ids: Vec<Uuid> = known.iter().map(|_| new_id()).collect();
+    let added: Vec<(Uuid,)> = sqlx::query_as(
+        r#"
+        INSERT INTO favorites (id, user_id, product_id)
+        SELECT t.id, $2, t.product_id
+        FROM UNNEST($1::uuid[], $3::uuid[]) AS t(id, product_id)
+        ON CONFLICT (user_id, product_id) DO NOTHING
+        RETURNING product_id
+        "#,
+    )
+    .bind(&ids)
+    .bind(user.user_id)
+    .bind(&known)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    for (product_id,) in removed.iter().chain(&added) {
+        cache.invalidate(*product_id).await;
+    }
+
+    let items = fetch_favorite_products(&pool, user.user_id).await?;
+    Ok(Json(ApiResponse::success(
+        "Favorites synced",
+        SyncFavoritesResult { items, skipped },
+        None,
     )))
 }
@@ -1,30 +1,290 @@
-use axum::Json;
+use std::time::{Duration, Instant};
+
+use axum::{Json, extract::State, http::StatusCode};
 use serde::Serialize;
 use utoipa::ToSchema;
 
-use crate::response::{ApiResponse, Meta};
+use crate::{
+    build_info,
+    db::{DbPool, MIGRATOR, MigrationState, migration_status},
+    response::{ApiResponse, Meta},
+};
+
+/// How long the database gets to answer before it counts as down.
+const DB_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyState {
+    Up,
+    Down,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DependencyStatus {
+    pub status: DependencyState,
+    /// Time the check took
+    #[schema(example = 3)]
+    pub latency_ms: u64,
+    /// Why the check failed, when it did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Connections in the database pool.
+#[derive(Serialize, ToSchema)]
+pub struct PoolStats {
+    /// Open connections, idle or in use
+    #[schema(example = 5)]
+    pub size: u32,
+    #[schema(example = 4)]
+    pub idle: usize,
+    /// Upper bound on `size`, from `DB_MAX_CONNECTIONS`
+    #[schema(example = 10)]
+    pub max: u32,
+    /// How long this check waited for a connection; absent when it got none
+    #[schema(example = 0)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acquire_ms: Option<u64>,
+}
 
 #[derive(Serialize, ToSchema)]
 pub struct HealthData {
+    /// `ok` when every dependency is up, `degraded` otherwise
+    #[schema(example = "ok")]
+    status: String,
+    database: DependencyStatus,
+    pool: PoolStats,
+    /// Time the whole check took
+    #[schema(example = 3)]
+    latency_ms: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MigrationStatus {
+    #[schema(example = 14)]
+    pub applied: usize,
+    /// Migrations this build ships with
+    #[schema(example = 14)]
+    pub expected: usize,
+    /// Versions not yet applied (successfully)
+    #[schema(example = json!([]))]
+    pub pending: Vec<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ReadyData {
+    /// `ready`, or `not_ready` while the database is down or migrations are pending
+    #[schema(example = "ready")]
+    status: String,
+    database: DependencyStatus,
+    migrations: MigrationStatus,
+    #[schema(example = 3600)]
+    uptime_secs: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VersionData {
+    #[schema(example = "0.1.0")]
+    version: String,
+    /// Short hash of the commit the binary was built from
+    #[schema(example = "3debe33a1c2f")]
+    git_commit: String,
+    #[schema(example = "2026-10-17T09:30:00Z")]
+    build_timestamp: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LiveData {
+    #[schema(example = "ok")]
     status: String,
 }
 
 #[utoipa::path(
     get,
     path = "/health",
+    operation_id = "health_check",
     responses(
-        (status = 200, description = "OK", body = ApiResponse<HealthData>),
+        (status = 200, description = "The instance and its database are up", body = ApiResponse<HealthData>),
+        (status = 503, description = "The database is unreachable", body = ApiResponse<HealthData>),
     ),
         tag = "Health"
 )]
-pub async fn health_check() -> Json<ApiResponse<HealthData>> {
+pub async fn health_check(
+    State(pool): State<DbPool>,
+) -> (StatusCode, Json<ApiResponse<HealthData>>) {
+    let started = Instant::now();
+    let acquire_ms = tokio::time::timeout(DB_CHECK_TIMEOUT, pool.acquire())
+        .await
+        .is_ok_and(|conn| conn.is_ok())
+        .then(|| elapsed_ms(started));
+    let database = check_database(&pool).await;
+    let healthy = database.status == DependencyState::Up;
     let data = HealthData {
-        status: "ok".to_string(),
+        status: if healthy { "ok" } else { "degraded" }.to_string(),
+        database,
+        pool: PoolStats {
+            size: pool.size(),
+            idle: pool.num_idle(),
+            max: pool.options().get_max_connections(),
+            acquire_ms,
+        },
+        latency_ms: elapsed_ms(started),
     };
 
+    if healthy {
+        (
+            StatusCode::OK,
+            Json(ApiResponse::success(
+                "Health check",
+                data,
+                Some(Meta::empty()),
+            )),
+        )
+    } else {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::error("Service Unavailable", data)),
+        )
+    }
+}
+
+/// Liveness probe: answers as long as the process serves requests, without touching the
+/// database, so a database outage does not get the instance restarted.
+#[utoipa::path(
+    get,
+    path = "/live",
+    operation_id = "health_live",
+    responses(
+        (status = 200, description = "The process is serving requests", body = ApiResponse<LiveData>),
+    ),
+    tag = "Health"
+)]
+pub async fn live() -> Json<ApiResponse<LiveData>> {
+    Json(ApiResponse::success(
+        "Alive",
+        LiveData {
+            status: "ok".to_string(),
+        },
+        Some(Meta::empty()),
+    ))
+}
+
+/// Which build is running.
+#[utoipa::path(
+    get,
+    path = "/health/version",
+    operation_id = "health_version",
+    responses(
+        (status = 200, description = "Version and build of the running binary", body = ApiResponse<VersionData>),
+    ),
+    tag = "Health"
+)]
+pub async fn version() -> Json<ApiResponse<VersionData>> {
     Json(ApiResponse::success(
-        "Health check",
-        data,
+        "Version",
+        VersionData {
+            version: build_info::VERSION.to_string(),
+            git_commit: build_info::GIT_COMMIT.to_string(),
+            build_timestamp: build_info::BUILD_TIMESTAMP.to_string(),
+        },
         Some(Meta::empty()),
     ))
 }
+
+/// Readiness probe: the database answers and every migration this build ships with has been
+/// applied.
+#[utoipa::path(
+    get,
+    path = "/ready",
+    operation_id = "health_ready",
+    responses(
+        (status = 200, description = "Ready to take traffic", body = ApiResponse<ReadyData>),
+        (status = 503, description = "Database unreachable or migrations pending", body = ApiResponse<ReadyData>),
+    ),
+    tag = "Health"
+)]
+pub async fn ready(
+    State(pool): State<DbPool>,
+    State(started_at): State<Instant>,
+) -> (StatusCode, Json<ApiResponse<ReadyData>>) {
+    let database = check_database(&pool).await;
+    let migrations = check_migrations(&pool).await;
+    let ready = database.status == DependencyState::Up && migrations.pending.is_empty();
+    let data = ReadyData {
+        status: if ready { "ready" } else { "not_ready" }.to_string(),
+        database,
+        migrations,
+        uptime_secs: started_at.elapsed().as_secs(),
+    };
+
+    if ready {
+        (
+            StatusCode::OK,
+            Json(ApiResponse::success("Ready", data, Some(Meta::empty()))),
+        )
+    } else {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::error("Service Unavailable", data)),
+        )
+    }
+}
+
+/// Compares the embedded migrations with `_sqlx_migrations`; if that cannot be read, nothing
+/// counts as applied.
+async fn check_migrations(pool: &DbPool) -> MigrationStatus {
+    let states = match tokio::time::timeout(DB_CHECK_TIMEOUT, migration_status(pool)).await {
+        Ok(Ok(states)) => states,
+        _ => MIGRATOR
+            .iter()
+            .filter(|m| !m.migration_type.is_down_migration())
+            .map(|m| MigrationState {
+                version: m.version,
+                description: m.description.to_string(),
+                applied: false,
+                changed: false,
+            })
+            .collect(),
+    };
+    let pending: Vec<i64> = states
+        .iter()
+        .filter(|m| !m.applied)
+        .map(|m| m.version)
+        .collect();
+    MigrationStatus {
+        applied: states.len() - pending.len(),
+        expected: states.len(),
+        pending,
+    }
+}
+
+async fn check_database(pool: &DbPool) -> DependencyStatus {
+    let started = Instant::now();
+    let result =
+        tokio::time::timeout(DB_CHECK_TIMEOUT, sqlx::query("SELECT 1").execute(pool)).await;
+    let error = match result {
+        Ok(Ok(_)) => None,
+        Ok(Err(e)) => {
+            tracing::warn!(error = %e, "database health check failed");
+            Some("query failed".to_string())
+        }
+        Err(_) => {
+            tracing::warn!(timeout = ?DB_CHECK_TIMEOUT, "database health check timed out");
+            Some("timed out".to_string())
+        }
+    };
+    DependencyStatus {
+        status: if error.is_none() {
+            DependencyState::Up
+        } else {
+            DependencyState::Down
+        },
+        latency_ms: elapsed_ms(started),
+        error,
+    }
+}
+
+fn elapsed_ms(started: Instant) -> u64 {
+    started.elapsed().as_millis() as u64
+}
@@ -1,23 +1,40 @@
-use axum::Router;
+use utoipa::OpenApi;
+use utoipa_axum::{router::OpenApiRouter, routes};
 
-use crate::db::DbPool;
+use crate::{routes::doc::ApiDoc, state::AppState};
 
 pub mod admin;
 pub mod auth;
 pub mod cart;
+pub mod categories;
 pub mod doc;
 pub mod favorites;
 pub mod health;
 pub mod orders;
+pub mod product_images;
 pub mod products;
+pub mod reviews;
 
 // Build the API router without binding state; it will be provided at the top level.
-pub fn create_api_router() -> Router<DbPool> {
-    Router::new()
+pub fn create_api_router() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
         .nest("/products", products::router())
         .nest("/auth", auth::router())
         .nest("/cart", cart::router())
         .nest("/orders", orders::route())
         .nest("/admin", admin::router())
+        .nest("/admin/categories", categories::router())
         .nest("/favorites", favorites::router())
 }
+
+/// Version 1 of the API under `/api/v1`, plus the health probes at the root, on top of `ApiDoc`. Each
+/// handler's path is declared once, in its `#[utoipa::path]`, and the spec picks up the
+/// prefixes it is mounted under.
+pub fn v1_router() -> OpenApiRouter<AppState> {
+    OpenApiRouter::with_openapi(ApiDoc::openapi())
+        .routes(routes!(health::health_check))
+        .routes(routes!(health::live))
+        .routes(routes!(health::ready))
+        .routes(routes!(health::version))
+        .nest("/api/v1", create_api_router())
+}
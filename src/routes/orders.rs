@@ -1,23 +1,87 @@
+use std::collections::HashMap;
+
 use axum::{
-    Json, Router,
+    Json,
     extract::{Path, State},
-    routing::{get, post},
 };
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use sqlx::{Postgres, Transaction};
+use utoipa::{IntoParams, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
 use uuid::Uuid;
 
 use crate::{
+    admin_events::{AdminEvent, NewOrderEvent},
+    audit::{AuditAction, AuditEvent, AuditLog},
+    cache::ProductCache,
     db::DbPool,
-    error::{AppError, AppResult},
-    middleware::auth::AuthUser,
-    models::{Order, OrderItem},
-    response::{ApiResponse, Meta},
+    error::{AppError, AppResult, ErrorCode, ErrorData},
+    extract::{AppJson, AppQuery},
+    ids::new_id,
+    middleware::{auth::AuthUser, request_context::RequestContext},
+    models::{Order, OrderItem, OrderStatus, StockMovementReason},
+    money::Money,
+    notifier::{self, OrderEvent},
+    response::{ApiResponse, Located, Meta, PageParams, created},
+    routes::products::record_stock_movements,
+    state::AppState,
 };
 
+/// Order statuses that count as a completed purchase, see [`OrderStatus::is_paid`].
+pub const PAID_ORDER_STATUSES: &[OrderStatus] = &[OrderStatus::Paid, OrderStatus::Completed];
+
+/// Caps checkout enforces before creating an order, from `MAX_ORDER_TOTAL` and
+/// `MAX_ITEMS_PER_ORDER`.
+#[derive(Debug, Clone, Copy)]
+pub struct OrderLimits {
+    pub max_total: Money,
+    /// Units summed over the lines
+    pub max_items: i64,
+}
+
 #[derive(Debug, ToSchema, Serialize, Deserialize)]
 pub struct OrderList {
-    pub items: Vec<Order>,
+    pub items: Vec<OrderSummary>,
+}
+
+/// An order as listed: enough about its lines to show "3 items" without fetching each one.
+#[derive(Debug, ToSchema, Serialize, Deserialize, sqlx::FromRow)]
+pub struct OrderSummary {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub order: Order,
+    /// Number of order lines
+    pub item_count: i64,
+    /// Name of the product on the first line
+    #[schema(example = "Ceramic Mug")]
+    pub first_item_name: Option<String>,
+    /// The lines themselves, with `include=items`
+    #[sqlx(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub items: Option<Vec<OrderItem>>,
+}
+
+/// Relations `include` can embed in an order listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderInclude {
+    Items,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OrderListQuery {
+    /// Only orders in this status
+    pub status: Option<OrderStatus>,
+    /// `items` embeds each order's lines
+    pub include: Option<OrderInclude>,
+    /// Page number from 1, default 1
+    pub page: Option<i64>,
+    /// Items per page from 1 to 100, default 10
+    pub per_page: Option<i64>,
+    /// false skips counting every match: `total` and `total_pages` are left out, `has_next`
+    /// is still set. Default true
+    pub with_total: Option<bool>,
 }
 
 #[derive(Debug, ToSchema, Serialize, Deserialize)]
@@ -26,39 +90,313 @@ pub struct OrderWithItems {
     pub items: Vec<OrderItem>,
 }
 
-pub fn route() -> Router<DbPool> {
-    Router::new()
-        .route("/", get(list_order))
-        .route("/checkout", post(checkout))
-        .route("/{id}", get(get_order))
+pub fn route() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(list_order))
+        .routes(routes!(checkout))
+        .routes(routes!(get_order))
+        .routes(routes!(cancel_order))
+}
+
+/// Orders matching `query`, newest first, with their item counts from one grouped query and
+/// their lines, if included, from one more. `user_id` limits them to one buyer.
+pub(crate) async fn list_order_summaries(
+    pool: &DbPool,
+    user_id: Option<Uuid>,
+    query: &OrderListQuery,
+) -> AppResult<(Vec<OrderSummary>, Meta)> {
+    let params = PageParams {
+        page: query.page,
+        per_page: query.per_page,
+        with_total: query.with_total,
+    };
+    let (page, limit, offset) = params.resolve()?;
+    let mut orders = sqlx::query_as::<_, OrderSummary>(
+        r#"
+        SELECT o.*, lines.item_count, lines.first_item_name
+        FROM orders o
+        CROSS JOIN LATERAL (
+            SELECT count(*) AS item_count, (array_agg(p.name ORDER BY oi.id))[1] AS first_item_name
+            FROM order_items oi
+            JOIN products p ON p.id = oi.product_id
+            WHERE oi.order_id = o.id
+        ) lines
+        WHERE ($1::uuid IS NULL OR o.user_id = $1) AND ($2::text IS NULL OR o.status = $2)
+        ORDER BY o.created_at DESC, o.id DESC
+        LIMIT $3 OFFSET $4
+        "#,
+    )
+    .bind(user_id)
+    .bind(query.status)
+    .bind(limit + 1)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+    let total = if params.with_total() {
+        Some(
+            sqlx::query_scalar(
+                r#"
+                SELECT count(*) FROM orders
+                WHERE ($1::uuid IS NULL OR user_id = $1) AND ($2::text IS NULL OR status = $2)
+                "#,
+            )
+            .bind(user_id)
+            .bind(query.status)
+            .fetch_one(pool)
+            .await?,
+        )
+    } else {
+        None
+    };
+    let meta = Meta::for_page(page, limit, total, &mut orders);
+
+    if query.include == Some(OrderInclude::Items) {
+        let order_ids: Vec<Uuid> = orders.iter().map(|o| o.order.id).collect();
+        let items = sqlx::query_as::<_, OrderItem>(
+            "SELECT * FROM order_items WHERE order_id = ANY($1) ORDER BY id",
+        )
+        .bind(&order_ids)
+        .fetch_all(pool)
+        .await?;
+        let mut by_order: HashMap<Uuid, Vec<OrderItem>> = HashMap::new();
+        for item in items {
+            by_order.entry(item.order_id).or_default().push(item);
+        }
+        for order in &mut orders {
+            order.items = Some(by_order.remove(&order.order.id).unwrap_or_default());
+        }
+    }
+    Ok((orders, meta))
+}
+
+/// An order row joined with one of its lines; the `item_*` columns are null for orders
+/// without lines.
+#[derive(sqlx::FromRow)]
+struct OrderItemRow {
+    #[sqlx(flatten)]
+    order: Order,
+    item_id: Option<Uuid>,
+    item_product_id: Option<Uuid>,
+    item_quantity: Option<i32>,
+    item_price: Option<Money>,
+}
+
+/// Loads order `id` with its lines in one round-trip. With `owner`, other users' orders
+/// are not found.
+pub(crate) async fn fetch_order_with_items(
+    pool: &DbPool,
+    id: Uuid,
+    owner: Option<Uuid>,
+) -> AppResult<OrderWithItems> {
+    let rows = sqlx::query_as::<_, OrderItemRow>(
+        r#"
+        SELECT o.*, oi.id AS item_id, oi.product_id AS item_product_id,
+               oi.quantity AS item_quantity, oi.price AS item_price
+        FROM orders o
+        LEFT JOIN order_items oi ON oi.order_id = o.id
+        WHERE o.id = $1 AND ($2::uuid IS NULL OR o.user_id = $2)
+        ORDER BY oi.id
+        "#,
+    )
+    .bind(id)
+    .bind(owner)
+    .fetch_all(pool)
+    .await?;
+
+    let mut order = None;
+    let mut items = Vec::with_capacity(rows.len());
+    for row in rows {
+        if let (Some(id), Some(product_id), Some(quantity), Some(price)) = (
+            row.item_id,
+            row.item_product_id,
+            row.item_quantity,
+            row.item_price,
+        ) {
+            items.push(OrderItem {
+                id,
+                order_id: row.order.id,
+                product_id,
+                quantity,
+                price,
+                price_display: price,
+            });
+        }
+        order.get_or_insert(row.order);
+    }
+    let order = order.ok_or(AppError::NotFound)?;
+    Ok(OrderWithItems { order, items })
+}
+
+fn insufficient_stock(product_id: Uuid) -> AppError {
+    AppError::BadRequest(format!("Insufficient stock for product {}", product_id))
+        .with_code(ErrorCode::InsufficientStock)
+}
+
+fn checkout_in_progress() -> AppError {
+    AppError::Conflict("Another checkout of this cart is in progress".into())
+        .with_code(ErrorCode::CheckoutInProgress)
+}
+
+/// Locks order `id` for a status change, answering 409 unless it is still pending. With
+/// `owner`, other users' orders are not found.
+pub(crate) async fn lock_pending_order(
+    tx: &mut Transaction<'_, Postgres>,
+    id: Uuid,
+    owner: Option<Uuid>,
+) -> AppResult<Order> {
+    let order = sqlx::query_as::<_, Order>(
+        "SELECT * FROM orders WHERE id = $1 AND ($2::uuid IS NULL OR user_id = $2) FOR UPDATE",
+    )
+    .bind(id)
+    .bind(owner)
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or(AppError::NotFound)?;
+    match order.status {
+        OrderStatus::Pending => Ok(order),
+        status if status.is_paid() => Err(AppError::Conflict("Order is already paid".into())
+            .with_code(ErrorCode::OrderAlreadyPaid)),
+        _ => Err(AppError::Conflict("Order is no longer pending".into())
+            .with_code(ErrorCode::InvalidOrderStatus)),
+    }
+}
+
+/// Takes the units a pending order reserved out of stock, once it is paid, and records them as
+/// sales in the stock ledger. Returns the products touched.
+pub(crate) async fn commit_reservations(
+    tx: &mut Transaction<'_, Postgres>,
+    order_id: Uuid,
+    paid_by: Uuid,
+) -> AppResult<Vec<Uuid>> {
+    let taken = settle_reservations(tx, order_id, true).await?;
+    let sales: Vec<(Uuid, i32)> = taken
+        .iter()
+        .map(|&(product_id, quantity)| (product_id, -quantity))
+        .collect();
+    record_stock_movements(
+        tx,
+        &sales,
+        StockMovementReason::Sale,
+        Some(order_id),
+        Some(paid_by),
+    )
+    .await?;
+    Ok(taken
+        .into_iter()
+        .map(|(product_id, _)| product_id)
+        .collect())
+}
+
+/// Gives the units a pending order reserved back, when it is cancelled. Returns the products
+/// touched.
+pub(crate) async fn release_reservations(
+    tx: &mut Transaction<'_, Postgres>,
+    order_id: Uuid,
+) -> AppResult<Vec<Uuid>> {
+    let released = settle_reservations(tx, order_id, false).await?;
+    Ok(released
+        .into_iter()
+        .map(|(product_id, _)| product_id)
+        .collect())
+}
+
+/// Puts the units of a paid order back in stock, when an admin cancels it, and records them as
+/// returns in the stock ledger. Returns the products touched.
+pub(crate) async fn restock_order(
+    tx: &mut Transaction<'_, Postgres>,
+    order_id: Uuid,
+    restocked_by: Uuid,
+) -> AppResult<Vec<Uuid>> {
+    let returned: Vec<(Uuid, i32)> = sqlx::query_as(
+        r#"
+        WITH sold AS (
+            SELECT product_id, SUM(quantity)::int AS quantity
+            FROM order_items
+            WHERE order_id = $1
+            GROUP BY product_id
+        )
+        UPDATE products p
+        SET stock = p.stock + sold.quantity
+        FROM sold
+        WHERE p.id = sold.product_id
+        RETURNING p.id, sold.quantity
+        "#,
+    )
+    .bind(order_id)
+    .fetch_all(&mut **tx)
+    .await?;
+    record_stock_movements(
+        tx,
+        &returned,
+        StockMovementReason::Return,
+        Some(order_id),
+        Some(restocked_by),
+    )
+    .await?;
+    Ok(returned
+        .into_iter()
+        .map(|(product_id, _)| product_id)
+        .collect())
+}
+
+async fn settle_reservations(
+    tx: &mut Transaction<'_, Postgres>,
+    order_id: Uuid,
+    take_from_stock: bool,
+) -> AppResult<Vec<(Uuid, i32)>> {
+    let lines: i64 = sqlx::query_scalar(
+        "SELECT count(DISTINCT product_id) FROM order_items WHERE order_id = $1",
+    )
+    .bind(order_id)
+    .fetch_one(&mut **tx)
+    .await?;
+    // the reserved >= guard keeps a missing reservation from going negative
+    let products: Vec<(Uuid, i32)> = sqlx::query_as(
+        r#"
+        WITH held AS (
+            SELECT product_id, SUM(quantity)::int AS quantity
+            FROM order_items
+            WHERE order_id = $1
+            GROUP BY product_id
+        )
+        UPDATE products p
+        SET reserved = p.reserved - held.quantity,
+            stock = p.stock - CASE WHEN $2 THEN held.quantity ELSE 0 END
+        FROM held
+        WHERE p.id = held.product_id AND p.reserved >= held.quantity
+        RETURNING p.id, held.quantity
+        "#,
+    )
+    .bind(order_id)
+    .bind(take_from_stock)
+    .fetch_all(&mut **tx)
+    .await?;
+    if products.len() as i64 != lines {
+        return Err(AppError::Internal(anyhow::anyhow!(
+            "order {} holds fewer units than it reserved",
+            order_id
+        )));
+    }
+    Ok(products)
 }
 
 #[utoipa::path(
     get,
-    path = "/api/orders",
+    path = "/",
+    operation_id = "orders_list",
+    params(OrderListQuery),
     responses(
-        (status = 200, description = "List orders for current user", body = ApiResponse<OrderList>)
+        (status = 200, description = "List orders for current user", body = ApiResponse<OrderList>),
+        (status = 400, description = "Unknown status, or missing or invalid bearer token", body = ApiResponse<ErrorData>),
     ),
     tag = "orders"
 )]
 pub async fn list_order(
     State(db): State<DbPool>,
     user: AuthUser,
+    AppQuery(query): AppQuery<OrderListQuery>,
 ) -> AppResult<Json<ApiResponse<OrderList>>> {
-    let orders = sqlx::query_as::<_, Order>(
-        "SELECT * FROM orders where user_id = $1 order by created_at desc",
-    )
-    .bind(user.user_id)
-    .fetch_all(&db)
-    .await?;
-
-    let total: (i64,) =
-        sqlx::query_as("SELECT count(*), sum(total) FROM orders where user_id = $1")
-            .bind(user.user_id)
-            .fetch_one(&db)
-            .await?;
-
-    let meta = Meta::new(1, total.0, total.0);
+    let (orders, meta) = list_order_summaries(&db, Some(user.user_id), &query).await?;
     let data = OrderList { items: orders };
     Ok(Json(ApiResponse::success("Ok", data, Some(meta))))
 }
@@ -67,138 +405,289 @@ pub async fn list_order(
 pub struct CartProductRow {
     product_id: Uuid,
     quantity: i32,
-    price: i64,
+    price: Money,
+    price_at_add: Money,
     stock: i32,
+    reserved: i32,
 }
-#[utoipa::path(
-    post,
-    path = "/api/orders/checkout", 
-    responses(
-        (status = 200, description = "Checkout current cart into an order", body = ApiResponse<OrderWithItems>),
-        (status = 400, description = "Cart empty or validation error"),
+
+fn default_accept_price_changes() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CheckoutRequest {
+    /// When false, checkout is refused with 409 if any cart line was repriced since it was added.
+    #[serde(default = "default_accept_price_changes")]
+    #[schema(default = true, example = false)]
+    pub accept_price_changes: bool,
+}
+
+/// Turns the cart of `user_id` into a pending order inside `tx`, reserving the stock and
+/// emptying the cart; the caller commits.
+async fn place_order(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    limits: OrderLimits,
+    accept_price_changes: bool,
+) -> AppResult<OrderWithItems> {
+    // satu checkout per user sekaligus; yang kalah langsung ditolak, tidak menunggu
+    let locked: bool = sqlx::query_scalar(
+        "SELECT pg_try_advisory_xact_lock(hashtextextended('checkout:' || $1::text, 0))",
     )
-    , tag = "Orders"
-)]
-pub async fn checkout(
-    State(pool): State<DbPool>,
-    user: AuthUser,
-) -> AppResult<Json<ApiResponse<OrderWithItems>>> {
-    let mut tx = pool.begin().await?;
+    .bind(user_id)
+    .fetch_one(&mut **tx)
+    .await?;
+    if !locked {
+        return Err(checkout_in_progress());
+    }
 
     // ambil cart + info produk untuk user ini
     let rows = sqlx::query_as::<_, CartProductRow>(
         r#"
-        SELECT ci.product_id, ci.quantity, p.price, p.stock
+        SELECT ci.product_id, ci.quantity, p.price, ci.price_at_add, p.stock, p.reserved
         FROM cart_items ci
         JOIN products p ON p.id = ci.product_id
-        WHERE ci.user_id = $1
+        WHERE ci.user_id = $1 AND NOT ci.saved
+        ORDER BY ci.created_at, ci.id
         FOR UPDATE
         "#,
     )
-    .bind(user.user_id)
-    .fetch_all(&mut *tx)
+    .bind(user_id)
+    .fetch_all(&mut **tx)
     .await?;
 
     if rows.is_empty() {
-        return Err(AppError::BadRequest("Cart is empty".into()));
+        return Err(AppError::BadRequest("Cart is empty".into()).with_code(ErrorCode::CartEmpty));
+    }
+
+    if !accept_price_changes {
+        let changed: Vec<String> = rows
+            .iter()
+            .filter(|row| row.price != row.price_at_add)
+            .map(|row| row.product_id.to_string())
+            .collect();
+        if !changed.is_empty() {
+            return Err(AppError::Conflict(format!(
+                "Prices changed for products {}",
+                changed.join(", ")
+            ))
+            .with_code(ErrorCode::PriceChanged));
+        }
     }
 
     // cek stok & hitung total
-    let mut total_amount: i64 = 0;
+    let mut total_amount = Money::ZERO;
+    let mut total_items: i64 = 0;
     for row in &rows {
         if row.quantity <= 0 {
             return Err(AppError::BadRequest("Cart has invalid quantity".into()));
         }
-        if row.stock < row.quantity {
-            return Err(AppError::BadRequest(format!(
-                "Insufficient stock for product {}",
-                row.product_id
-            )));
+        if row.stock - row.reserved < row.quantity {
+            return Err(insufficient_stock(row.product_id));
         }
-        total_amount += row.price * (row.quantity as i64);
+        total_items += i64::from(row.quantity);
+        total_amount = row
+            .price
+            .checked_mul(i64::from(row.quantity))
+            .and_then(|line| total_amount.checked_add(line))
+            .ok_or_else(|| AppError::BadRequest("order total exceeds limits".into()))?;
+    }
+    if total_items > limits.max_items {
+        return Err(AppError::BadRequest(format!(
+            "order has {} items; at most {} are allowed",
+            total_items, limits.max_items
+        )));
+    }
+    if total_amount > limits.max_total {
+        return Err(AppError::BadRequest("order total exceeds limits".into()));
     }
 
-    let order_id = Uuid::new_v4();
+    let order_id = new_id();
 
-    // insert order
+    // insert order, with the recipient copied from the profile as it is now
     let order = sqlx::query_as::<_, Order>(
         r#"
-        INSERT INTO orders (id, user_id, total_amount, status)
-        VALUES ($1, $2, $3, 'pending')
+        INSERT INTO orders (id, user_id, total_amount, status, recipient_name, recipient_phone)
+        SELECT $1, u.id, $3, $4, u.full_name, u.phone FROM users u WHERE u.id = $2
         RETURNING *
         "#,
     )
     .bind(order_id)
-    .bind(user.user_id)
+    .bind(user_id)
     .bind(total_amount)
-    .fetch_one(&mut *tx)
+    .bind(OrderStatus::Pending)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    // pesan stok semua produk sekaligus; stok baru berkurang saat order dibayar
+    let product_ids: Vec<Uuid> = rows.iter().map(|row| row.product_id).collect();
+    let quantities: Vec<i32> = rows.iter().map(|row| row.quantity).collect();
+    let reserved: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        UPDATE products p
+        SET reserved = p.reserved + t.quantity
+        FROM UNNEST($1::uuid[], $2::int[]) AS t(id, quantity)
+        WHERE p.id = t.id AND p.stock - p.reserved >= t.quantity
+        RETURNING p.id
+        "#,
+    )
+    .bind(&product_ids)
+    .bind(&quantities)
+    .fetch_all(&mut **tx)
     .await?;
+    if let Some(short) = product_ids.iter().find(|id| !reserved.contains(id)) {
+        return Err(insufficient_stock(*short));
+    }
 
-    // insert order items & update stok
-    let mut order_items: Vec<OrderItem> = Vec::new();
+    // insert semua order item dalam satu statement, urutannya ikut cart
+    let item_ids: Vec<Uuid> = rows.iter().map(|_| new_id()).collect();
+    let prices: Vec<Money> = rows.iter().map(|row| row.price).collect();
+    let mut order_items = sqlx::query_as::<_, OrderItem>(
+        r#"
+        INSERT INTO order_items (id, order_id, product_id, quantity, price)
+        SELECT t.id, $2, t.product_id, t.quantity, t.price
+        FROM UNNEST($1::uuid[], $3::uuid[], $4::int[], $5::bigint[])
+            AS t(id, product_id, quantity, price)
+        RETURNING *
+        "#,
+    )
+    .bind(&item_ids)
+    .bind(order.id)
+    .bind(&product_ids)
+    .bind(&quantities)
+    .bind(&prices)
+    .fetch_all(&mut **tx)
+    .await?;
+    let position: HashMap<Uuid, usize> = item_ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (*id, i))
+        .collect();
+    order_items.sort_by_key(|item| position[&item.id]);
 
-    for row in &rows {
-        let item_id = Uuid::new_v4();
-
-        let item = sqlx::query_as::<_, OrderItem>(
-            r#"
-            INSERT INTO order_items (id, order_id, product_id, quantity, price)
-            VALUES ($1, $2, $3, $4, $5)
-            RETURNING *
-            "#,
-        )
-        .bind(item_id)
-        .bind(order.id)
-        .bind(row.product_id)
-        .bind(row.quantity)
-        .bind(row.price)
-        .fetch_one(&mut *tx)
-        .await?;
+    // kosongkan baris cart yang dipesan, item yang disimpan tetap ada
+    let consumed = sqlx::query(
+        "DELETE FROM cart_items WHERE user_id = $1 AND NOT saved AND product_id = ANY($2)",
+    )
+    .bind(user_id)
+    .bind(&product_ids)
+    .execute(&mut **tx)
+    .await?
+    .rows_affected();
+    if consumed != rows.len() as u64 {
+        return Err(checkout_in_progress());
+    }
 
-        order_items.push(item);
+    Ok(OrderWithItems {
+        order,
+        items: order_items,
+    })
+}
 
-        // kurangi stok produk
-        sqlx::query(
-            r#"
-            UPDATE products
-            SET stock = stock - $2
-            WHERE id = $1
-            "#,
-        )
-        .bind(row.product_id)
-        .bind(row.quantity)
-        .execute(&mut *tx)
-        .await?;
+/// Tells the admins watching `/admin/ws` about a new order; looks up the customer's email
+/// only when someone is listening.
+async fn publish_new_order(state: &AppState, data: &OrderWithItems) {
+    if !state.admin_events.has_subscribers() {
+        return;
     }
+    let email = sqlx::query_scalar::<_, String>("SELECT email FROM users WHERE id = $1")
+        .bind(data.order.user_id)
+        .fetch_one(&state.pool)
+        .await;
+    match email {
+        Ok(customer_email) => state
+            .admin_events
+            .publish(AdminEvent::NewOrder(NewOrderEvent {
+                order_id: data.order.id,
+                total_amount: data.order.total_amount,
+                total_amount_display: data.order.total_amount,
+                customer_email,
+                items: data.items.len(),
+                created_at: data.order.created_at,
+            })),
+        Err(e) => tracing::warn!(order_id = %data.order.id, error = %e, "new order event dropped"),
+    }
+}
 
-    // kosongkan cart user
-    sqlx::query("DELETE FROM cart_items WHERE user_id = $1")
-        .bind(user.user_id)
-        .execute(&mut *tx)
-        .await?;
+#[utoipa::path(
+    post,
+    path = "/checkout",
+    operation_id = "orders_checkout",
+    request_body(content = Option<CheckoutRequest>),
+    responses(
+        (status = 201, description = "Checkout current cart into an order", body = ApiResponse<OrderWithItems>,
+            headers(("Location" = String, description = "URL of the new order"))),
+        (status = 400, description = "Cart empty, validation error, order over the configured limits, or missing or invalid bearer token"),
+        (status = 409, description = "Cart prices changed and accept_price_changes is false, or another checkout of this cart is in progress"),
+    )
+    , tag = "Orders"
+)]
+pub async fn checkout(
+    State(state): State<AppState>,
+    user: AuthUser,
+    context: RequestContext,
+    payload: Option<AppJson<CheckoutRequest>>,
+) -> AppResult<Located<OrderWithItems>> {
+    let accept_price_changes = payload
+        .map(|AppJson(p)| p.accept_price_changes)
+        .unwrap_or(true);
 
-    tx.commit().await?;
+    let mut tx = state.pool.begin().await?;
 
-    let data = OrderWithItems {
-        order,
-        items: order_items,
+    let data = match place_order(
+        &mut tx,
+        user.user_id,
+        state.order_limits,
+        accept_price_changes,
+    )
+    .await
+    {
+        Ok(data) => data,
+        Err(err) => {
+            // rollback sekarang supaya lock checkout lepas sebelum response dikirim
+            tx.rollback().await?;
+            return Err(err);
+        }
     };
+    tx.commit().await?;
+    notifier::spawn(
+        state.pool.clone(),
+        state.notifier.clone(),
+        OrderEvent::Created,
+        data.order.id,
+    );
+    publish_new_order(&state, &data).await;
+
+    // reserved berubah, buang cache produk yang dibeli
+    for item in &data.items {
+        state.product_cache.invalidate(item.product_id).await;
+    }
+
+    state.audit.record(
+        AuditEvent::new(AuditAction::OrderCreate, "order")
+            .actor(user.user_id)
+            .entity(data.order.id)
+            .details(serde_json::json!({ "total_amount": data.order.total_amount, "items": data.items.len() }))
+            .context(&context),
+    );
 
-    Ok(Json(ApiResponse::success(
-        "Checkout success",
-        data,
-        Some(Meta::empty()),
-    )))
+    Ok(created(
+        format!("/api/v1/orders/{}", data.order.id),
+        ApiResponse::success("Checkout success", data, Some(Meta::empty())),
+    ))
 }
 
 #[utoipa::path(
     get,
-    path = "/api/orders/{id}",
+    path = "/{id}",
+    operation_id = "orders_get",
     params(
         ("id" = Uuid, Path, description = "Order ID")
     ),
     responses(
         (status = 200, description = "Get order with items", body = ApiResponse<OrderWithItems>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
         (status = 404, description = "Order not found"),
     ),
     tag = "orders"
@@ -208,22 +697,53 @@ pub async fn get_order(
     user: AuthUser,
     Path(id): Path<Uuid>,
 ) -> AppResult<Json<ApiResponse<OrderWithItems>>> {
-    let order = sqlx::query_as::<_, Order>("SELECT * FROM orders where user_id = $1 and id = $2")
-        .bind(user.user_id)
-        .bind(id)
-        .fetch_optional(&db)
-        .await?;
-    let order = match order {
-        Some(o) => o,
-        None => return Err(AppError::NotFound),
-    };
+    let data = fetch_order_with_items(&db, id, Some(user.user_id)).await?;
 
-    let items = sqlx::query_as::<_, OrderItem>("SELECT * FROM order_items WHERE order_id = $1")
-        .bind(order.id)
-        .fetch_all(&db)
-        .await?;
+    Ok(Json(ApiResponse::success("OK", data, Some(Meta::empty()))))
+}
 
-    let data = OrderWithItems { order, items };
+#[utoipa::path(
+    post,
+    path = "/{id}/cancel",
+    operation_id = "orders_cancel",
+    params(
+        ("id" = Uuid, Path, description = "Order ID")
+    ),
+    responses(
+        (status = 200, description = "Order cancelled and its reserved stock released", body = ApiResponse<Order>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Order not found"),
+        (status = 409, description = "Order is paid or already cancelled", body = ApiResponse<ErrorData>),
+    ),
+    tag = "orders"
+)]
+pub async fn cancel_order(
+    State(pool): State<DbPool>,
+    State(cache): State<ProductCache>,
+    State(audit): State<AuditLog>,
+    user: AuthUser,
+    context: RequestContext,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<Order>>> {
+    let mut tx = pool.begin().await?;
+    let order = lock_pending_order(&mut tx, id, Some(user.user_id)).await?;
+    let products = release_reservations(&mut tx, order.id).await?;
+    let order =
+        sqlx::query_as::<_, Order>("UPDATE orders SET status = $2 WHERE id = $1 RETURNING *")
+            .bind(order.id)
+            .bind(OrderStatus::Cancelled)
+            .fetch_one(&mut *tx)
+            .await?;
+    tx.commit().await?;
 
-    Ok(Json(ApiResponse::success("OK", data, Some(Meta::empty()))))
+    for product_id in products {
+        cache.invalidate(product_id).await;
+    }
+    audit.record(
+        AuditEvent::new(AuditAction::OrderCancel, "order")
+            .actor(user.user_id)
+            .entity(order.id)
+            .context(&context),
+    );
+    Ok(Json(ApiResponse::success("Order cancelled", order, None)))
 }
@@ -0,0 +1,286 @@
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{DefaultBodyLimit, Multipart, Path, State},
+};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouterExt},
+    routes,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, AppResult, ErrorData},
+    extract::AppJson,
+    ids::new_id,
+    middleware::auth::AuthUser,
+    models::ProductImage,
+    response::{ApiResponse, Meta},
+    routes::admin::ensure_admin,
+    state::AppState,
+};
+
+/// Largest accepted image file.
+pub const MAX_IMAGE_BYTES: usize = 2 * 1024 * 1024;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateProductImageRequest {
+    pub position: Option<i32>,
+    pub alt: Option<String>,
+}
+
+/// Multipart body of an image upload.
+#[derive(ToSchema)]
+pub struct UploadProductImageForm {
+    /// JPEG, PNG, WebP or GIF, at most 2 MiB
+    #[schema(value_type = String, format = Binary)]
+    pub file: Bytes,
+    pub alt: Option<String>,
+}
+
+impl UploadProductImageForm {
+    /// Reads the form, along with the file extension for the uploaded file's content type.
+    async fn read(mut multipart: Multipart) -> AppResult<(&'static str, Self)> {
+        let mut file = None;
+        let mut alt = None;
+        while let Some(field) = multipart
+            .next_field()
+            .await
+            .map_err(|e| AppError::BadRequest(e.body_text()))?
+        {
+            match field.name() {
+                Some("file") => {
+                    let extension =
+                        field
+                            .content_type()
+                            .and_then(image_extension)
+                            .ok_or_else(|| {
+                                AppError::BadRequest(
+                                    "file must be a jpeg, png, webp or gif image".to_string(),
+                                )
+                            })?;
+                    let bytes = field
+                        .bytes()
+                        .await
+                        .map_err(|e| AppError::BadRequest(e.body_text()))?;
+                    file = Some((extension, bytes));
+                }
+                Some("alt") => {
+                    let text = field
+                        .text()
+                        .await
+                        .map_err(|e| AppError::BadRequest(e.body_text()))?;
+                    alt = Some(text).filter(|t| !t.trim().is_empty());
+                }
+                _ => {}
+            }
+        }
+
+        let (extension, file) =
+            file.ok_or_else(|| AppError::BadRequest("file is required".to_string()))?;
+        Ok((extension, Self { file, alt }))
+    }
+}
+
+pub fn router() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(upload_image).layer(DefaultBodyLimit::max(MAX_IMAGE_BYTES + 64 * 1024)))
+        .routes(routes!(update_image, delete_image))
+}
+
+/// Best-effort removal of image files whose rows are already gone.
+pub async fn remove_stored_images(state: &AppState, images: &[ProductImage]) {
+    for image in images {
+        if let Err(e) = state.storage.delete(&image.storage_key).await {
+            tracing::warn!("failed to remove image file {}: {}", image.storage_key, e);
+        }
+    }
+}
+
+fn image_extension(content_type: &str) -> Option<&'static str> {
+    match content_type {
+        "image/jpeg" => Some("jpg"),
+        "image/png" => Some("png"),
+        "image/webp" => Some("webp"),
+        "image/gif" => Some("gif"),
+        _ => None,
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/images",
+    operation_id = "products_images_upload",
+    params(
+        ("id" = Uuid, Path, description = "Product ID")
+    ),
+    request_body(content = UploadProductImageForm, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Upload a product image (admin only)", body = ApiResponse<ProductImage>),
+        (status = 400, description = "Missing file, unsupported content type or file too large, or missing or invalid bearer token"),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Product not found"),
+    ),
+    tag = "products"
+)]
+pub async fn upload_image(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    multipart: Multipart,
+) -> AppResult<Json<ApiResponse<ProductImage>>> {
+    ensure_admin(&user)?;
+
+    let product: Option<(Uuid,)> =
+        sqlx::query_as("SELECT id FROM products WHERE id = $1 AND deleted_at IS NULL")
+            .bind(id)
+            .fetch_optional(&state.pool)
+            .await?;
+    if product.is_none() {
+        return Err(AppError::NotFound);
+    }
+
+    let (extension, form) = UploadProductImageForm::read(multipart).await?;
+    let bytes = form.file;
+    if bytes.is_empty() {
+        return Err(AppError::BadRequest("file must not be empty".to_string()));
+    }
+    if bytes.len() > MAX_IMAGE_BYTES {
+        return Err(AppError::BadRequest(format!(
+            "file must be at most {} bytes",
+            MAX_IMAGE_BYTES
+        )));
+    }
+
+    let image_id = new_id();
+    let key = format!("products/{}/{}.{}", id, image_id, extension);
+    let url = state.storage.put(&key, bytes).await?;
+
+    let image = sqlx::query_as::<_, ProductImage>(
+        r#"
+        INSERT INTO product_images (id, product_id, url, storage_key, alt, position)
+        VALUES ($1, $2, $3, $4, $5,
+            (SELECT COALESCE(MAX(position) + 1, 0) FROM product_images WHERE product_id = $2))
+        RETURNING *
+        "#,
+    )
+    .bind(image_id)
+    .bind(id)
+    .bind(url)
+    .bind(&key)
+    .bind(form.alt)
+    .fetch_one(&state.pool)
+    .await;
+
+    let image = match image {
+        Ok(image) => {
+            state.product_cache.invalidate(id).await;
+            image
+        }
+        Err(e) => {
+            if let Err(cleanup) = state.storage.delete(&key).await {
+                tracing::warn!("failed to remove orphaned upload {}: {}", key, cleanup);
+            }
+            return Err(e.into());
+        }
+    };
+
+    Ok(Json(ApiResponse::success(
+        "Image uploaded",
+        image,
+        Some(Meta::empty()),
+    )))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/{id}/images/{image_id}",
+    operation_id = "products_images_update",
+    params(
+        ("id" = Uuid, Path, description = "Product ID"),
+        ("image_id" = Uuid, Path, description = "Image ID")
+    ),
+    request_body = UpdateProductImageRequest,
+    responses(
+        (status = 200, description = "Update image position or alt text (admin only)", body = ApiResponse<ProductImage>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Image not found"),
+    ),
+    tag = "products"
+)]
+pub async fn update_image(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path((id, image_id)): Path<(Uuid, Uuid)>,
+    AppJson(payload): AppJson<UpdateProductImageRequest>,
+) -> AppResult<Json<ApiResponse<ProductImage>>> {
+    ensure_admin(&user)?;
+
+    let image = sqlx::query_as::<_, ProductImage>(
+        r#"
+        UPDATE product_images
+        SET position = COALESCE($3, position), alt = COALESCE($4, alt)
+        WHERE id = $1 AND product_id = $2
+        RETURNING *
+        "#,
+    )
+    .bind(image_id)
+    .bind(id)
+    .bind(payload.position)
+    .bind(payload.alt)
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+    state.product_cache.invalidate(id).await;
+
+    Ok(Json(ApiResponse::success(
+        "Updated",
+        image,
+        Some(Meta::empty()),
+    )))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}/images/{image_id}",
+    operation_id = "products_images_delete",
+    params(
+        ("id" = Uuid, Path, description = "Product ID"),
+        ("image_id" = Uuid, Path, description = "Image ID")
+    ),
+    responses(
+        (status = 200, description = "Delete a product image (admin only)", body = ApiResponse<serde_json::Value>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Image not found"),
+    ),
+    tag = "products"
+)]
+pub async fn delete_image(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path((id, image_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<Json<ApiResponse<serde_json::Value>>> {
+    ensure_admin(&user)?;
+
+    let image = sqlx::query_as::<_, ProductImage>(
+        "DELETE FROM product_images WHERE id = $1 AND product_id = $2 RETURNING *",
+    )
+    .bind(image_id)
+    .bind(id)
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+    state.product_cache.invalidate(id).await;
+
+    remove_stored_images(&state, std::slice::from_ref(&image)).await;
+
+    Ok(Json(ApiResponse::success(
+        "Deleted",
+        serde_json::json!({}),
+        Some(Meta::empty()),
+    )))
+}
@@ -1,32 +1,130 @@
 use axum::{
-    Json, Router,
+    Json,
     extract::{Path, State},
 };
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use sqlx::{PgExecutor, Postgres, QueryBuilder, Transaction};
+use utoipa::{IntoParams, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
 use uuid::Uuid;
 
 use crate::{
-    db::DbPool,
-    error::{AppError, AppResult},
-    models::Product,
-    response::{ApiResponse, Meta},
+    audit::{AuditAction, AuditEvent, AuditLog},
+    cache::ProductCache,
+    db::{DbPool, escape_like},
+    error::{AppError, AppResult, ErrorData, FieldErrors},
+    extract::{AppJson, AppQuery},
+    ids::new_id,
+    middleware::{auth::AuthUser, request_context::RequestContext},
+    models::{Category, Product, ProductImage, StockMovementReason},
+    money::Money,
+    response::{ApiResponse, Located, Meta, created, resolve_page},
+    routes::{admin::ensure_admin, orders::PAID_ORDER_STATUSES, product_images, reviews},
+    slug::slugify,
+    state::AppState,
 };
 
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateProductRequest {
+    #[schema(example = "Ceramic Mug")]
     pub name: String,
+    #[schema(example = "350 ml, dishwasher safe")]
     pub description: String,
-    pub price: i64,
+    /// Price in the smallest currency unit (cents), not a decimal
+    #[schema(value_type = i64, example = 1250, minimum = 0)]
+    pub price: Money,
+    /// Units available for sale
+    #[schema(example = 10, minimum = 0)]
     pub stock: i32,
+    pub category_id: Option<Uuid>,
+    /// Stock keeping unit, unique across products
+    #[schema(example = "MUG-350-WHT")]
+    pub sku: Option<String>,
+    /// Defaults to true; unpublished products are only visible to admins
+    pub is_published: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateProductRequest {
     pub name: Option<String>,
-    pub description: Option<String>,
-    pub price: Option<i64>,
+    /// Omit to keep the current description, send `null` to clear it
+    #[serde(default, deserialize_with = "deserialize_patch")]
+    #[schema(value_type = Option<String>, nullable)]
+    pub description: Option<Option<String>>,
+    #[schema(value_type = Option<i64>)]
+    pub price: Option<Money>,
     pub stock: Option<i32>,
+    pub category_id: Option<Uuid>,
+    /// Replaces the slug; renaming alone keeps the current one
+    pub slug: Option<String>,
+    /// Omit to keep the current SKU, send `null` or an empty string to clear it
+    #[serde(default, deserialize_with = "deserialize_patch")]
+    #[schema(value_type = Option<String>, nullable)]
+    pub sku: Option<Option<String>>,
+    pub is_published: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PopularQuery {
+    /// Look-back window in days, default 30, max 365
+    pub days: Option<i32>,
+    /// Number of products, default 10, max 50
+    pub limit: Option<i64>,
+}
+
+/// Suggestions are capped at this many, however many are asked for.
+pub const MAX_SUGGESTIONS: i64 = 10;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SuggestQuery {
+    /// What has been typed so far; shorter than 2 characters suggests nothing
+    #[param(example = "mu")]
+    pub q: Option<String>,
+    /// Number of suggestions, default 8, max 10
+    pub limit: Option<i64>,
+}
+
+/// Just enough of a product for the search box to show and link it.
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct ProductSuggestion {
+    pub id: Uuid,
+    #[schema(example = "Ceramic Mug")]
+    pub name: String,
+    #[schema(example = "ceramic-mug")]
+    pub slug: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ProductSuggestionList {
+    pub items: Vec<ProductSuggestion>,
+}
+
+#[derive(Debug, Serialize, ToSchema, sqlx::FromRow)]
+pub struct PopularProduct {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub product: Product,
+    /// Units sold in paid orders within the window
+    pub units_sold: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PopularProductList {
+    pub items: Vec<PopularProduct>,
+}
+
+/// Keeps an explicit `null` apart from a missing field: absent stays `None` (via
+/// `#[serde(default)]`), `null` becomes `Some(None)`.
+fn deserialize_patch<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
 }
 
 #[derive(Serialize, ToSchema)]
@@ -34,57 +132,698 @@ pub struct ProductList {
     pub items: Vec<Product>,
 }
 
-pub fn router() -> Router<DbPool> {
-    Router::new()
-        .route("/", axum::routing::post(create_product))
-        .route("/", axum::routing::get(list_products))
-        .route("/{id}", axum::routing::get(get_product))
-        .route("/{id}", axum::routing::put(update_product))
-        .route("/{id}", axum::routing::delete(delete_product))
+/// Column products are sorted by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductSortBy {
+    #[default]
+    CreatedAt,
+    Name,
+    Price,
+    Stock,
+}
+
+impl ProductSortBy {
+    fn column(self) -> &'static str {
+        match self {
+            ProductSortBy::CreatedAt => "created_at",
+            ProductSortBy::Name => "name",
+            ProductSortBy::Price => "price",
+            ProductSortBy::Stock => "stock",
+        }
+    }
+
+    fn parse(field: &str) -> Option<Self> {
+        match field {
+            "created_at" => Some(ProductSortBy::CreatedAt),
+            "name" => Some(ProductSortBy::Name),
+            "price" => Some(ProductSortBy::Price),
+            "stock" => Some(ProductSortBy::Stock),
+            _ => None,
+        }
+    }
+}
+
+/// Sort direction.
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    fn keyword(self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+
+    /// Comparison that selects rows after the cursor in this direction.
+    fn after(self) -> &'static str {
+        match self {
+            SortOrder::Asc => ">",
+            SortOrder::Desc => "<",
+        }
+    }
+}
+
+/// Most keys accepted in `sort`.
+const MAX_SORT_KEYS: usize = 4;
+
+/// Resolves the ordering: `sort_by`/`order` win when present, then `sort`, then
+/// `created_at` ascending.
+fn sort_keys(query: &ProductQuery) -> AppResult<Vec<(ProductSortBy, SortOrder)>> {
+    if query.sort_by.is_some() || query.order.is_some() {
+        return Ok(vec![(
+            query.sort_by.unwrap_or_default(),
+            query.order.unwrap_or_default(),
+        )]);
+    }
+    let Some(sort) = query.sort.as_deref().filter(|s| !s.trim().is_empty()) else {
+        return Ok(vec![(ProductSortBy::default(), SortOrder::default())]);
+    };
+
+    let mut keys: Vec<(ProductSortBy, SortOrder)> = Vec::new();
+    for part in sort.split(',') {
+        let (field, direction) = part.trim().split_once(':').unwrap_or((part.trim(), "asc"));
+        let field = ProductSortBy::parse(field.trim())
+            .ok_or_else(|| AppError::BadRequest(format!("unknown sort field: {}", field.trim())))?;
+        let direction = match direction.trim() {
+            "asc" => SortOrder::Asc,
+            "desc" => SortOrder::Desc,
+            other => {
+                return Err(AppError::BadRequest(format!(
+                    "unknown sort direction: {}",
+                    other
+                )));
+            }
+        };
+        if keys.iter().any(|(f, _)| *f == field) {
+            return Err(AppError::BadRequest(format!(
+                "sort field {} given more than once",
+                field.column()
+            )));
+        }
+        keys.push((field, direction));
+    }
+    if keys.len() > MAX_SORT_KEYS {
+        return Err(AppError::BadRequest(format!(
+            "at most {} sort fields are allowed",
+            MAX_SORT_KEYS
+        )));
+    }
+    Ok(keys)
+}
+
+/// Sort key value of the row a cursor points after.
+#[derive(Clone)]
+enum SortKey {
+    CreatedAt(DateTime<Utc>),
+    Name(String),
+    Price(i64),
+    Stock(i32),
+}
+
+impl SortKey {
+    fn push_bind(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        match self.clone() {
+            SortKey::CreatedAt(v) => builder.push_bind(v),
+            SortKey::Name(v) => builder.push_bind(v),
+            SortKey::Price(v) => builder.push_bind(v),
+            SortKey::Stock(v) => builder.push_bind(v),
+        };
+    }
+}
+
+/// Wire form of a cursor, encoded as URL-safe base64 JSON.
+#[derive(Serialize, Deserialize)]
+struct ProductCursor {
+    keys: Vec<serde_json::Value>,
+    id: Uuid,
+}
+
+fn encode_cursor(product: &Product, sort: &[(ProductSortBy, SortOrder)]) -> String {
+    let keys = sort
+        .iter()
+        .map(|(field, _)| match field {
+            ProductSortBy::CreatedAt => serde_json::json!(product.created_at),
+            ProductSortBy::Name => serde_json::json!(product.name),
+            ProductSortBy::Price => serde_json::json!(product.price),
+            ProductSortBy::Stock => serde_json::json!(product.stock),
+        })
+        .collect();
+    let cursor = ProductCursor {
+        keys,
+        id: product.id,
+    };
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(&cursor).unwrap_or_default())
+}
+
+fn decode_cursor(
+    cursor: &str,
+    sort: &[(ProductSortBy, SortOrder)],
+) -> AppResult<(Vec<SortKey>, Uuid)> {
+    let invalid = || AppError::BadRequest("invalid cursor".to_string());
+    let bytes = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+    let ProductCursor { keys, id } = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+    if keys.len() != sort.len() {
+        return Err(invalid());
+    }
+    let keys = sort
+        .iter()
+        .zip(keys)
+        .map(|((field, _), key)| match field {
+            ProductSortBy::CreatedAt => serde_json::from_value(key).map(SortKey::CreatedAt),
+            ProductSortBy::Name => serde_json::from_value(key).map(SortKey::Name),
+            ProductSortBy::Price => serde_json::from_value(key).map(SortKey::Price),
+            ProductSortBy::Stock => serde_json::from_value(key).map(SortKey::Stock),
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| invalid())?;
+    Ok((keys, id))
+}
+
+/// Appends `((a > $a) OR (a = $a AND b < $b) OR (a = $a AND b = $b AND id > $id))`,
+/// which selects the rows after the cursor for any mix of directions. `id` follows the
+/// first key's direction, matching the ORDER BY.
+fn push_keyset_predicate(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    sort: &[(ProductSortBy, SortOrder)],
+    keys: &[SortKey],
+    id: Uuid,
+) {
+    builder.push("(");
+    for level in 0..=sort.len() {
+        if level > 0 {
+            builder.push(" OR ");
+        }
+        builder.push("(");
+        for ((field, _), key) in sort.iter().zip(keys).take(level) {
+            builder.push(format!("{} = ", field.column()));
+            key.push_bind(builder);
+            builder.push(" AND ");
+        }
+        match sort.get(level) {
+            Some((field, order)) => {
+                builder.push(format!("{} {} ", field.column(), order.after()));
+                keys[level].push_bind(builder);
+            }
+            None => {
+                builder
+                    .push(format!("id {} ", sort[0].1.after()))
+                    .push_bind(id);
+            }
+        }
+        builder.push(")");
+    }
+    builder.push(")");
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProductQuery {
+    /// Page number from 1, default 1
+    pub page: Option<i64>,
+    /// Items per page from 1 to 100, default 10
+    pub per_page: Option<i64>,
+    /// Case-insensitive search on name and description
+    pub q: Option<String>,
+    /// Minimum price (inclusive)
+    pub min_price: Option<i64>,
+    /// Maximum price (inclusive)
+    pub max_price: Option<i64>,
+    /// Category id or slug
+    pub category: Option<String>,
+    /// Exact SKU
+    pub sku: Option<String>,
+    /// true: only products with unreserved stock left, false: only sold-out products
+    pub in_stock: Option<bool>,
+    /// Sort column, default created_at
+    #[param(inline)]
+    pub sort_by: Option<ProductSortBy>,
+    /// Sort direction, default asc
+    #[param(inline)]
+    pub order: Option<SortOrder>,
+    /// Comma-separated `field:direction` pairs, e.g. `price:asc,name:asc`; ignored when
+    /// `sort_by` or `order` is given
+    pub sort: Option<String>,
+    /// Admins only: also list unpublished products
+    pub include_unpublished: Option<bool>,
+    /// `next_cursor` from the previous page; replaces `page` with keyset pagination
+    pub cursor: Option<String>,
+    /// false skips counting every match: `total` and `total_pages` are left out, `has_next`
+    /// is still set. Default true
+    pub with_total: Option<bool>,
+}
+
+/// Emits ` WHERE ` before the first predicate and ` AND ` before every later one.
+fn push_predicate(builder: &mut QueryBuilder<'_, Postgres>, has_where: &mut bool) {
+    builder.push(if *has_where { " AND " } else { " WHERE " });
+    *has_where = true;
+}
+
+/// Longest product name accepted, matching the column check.
+const MAX_NAME_LEN: usize = 255;
+
+/// Field checks shared by create and update; `None` means the field is left unchanged.
+fn validate_product_fields(
+    name: Option<&str>,
+    price: Option<Money>,
+    stock: Option<i32>,
+) -> AppResult<()> {
+    let mut errors = FieldErrors::default();
+    if let Some(name) = name {
+        if name.trim().is_empty() {
+            errors.add("name", "required", "name must not be empty");
+        } else if name.chars().count() > MAX_NAME_LEN {
+            errors.add(
+                "name",
+                "too_long",
+                format!("name must be at most {} characters", MAX_NAME_LEN),
+            );
+        }
+    }
+    if price.is_some_and(Money::is_negative) {
+        errors.add("price", "negative", "price must not be negative");
+    }
+    if stock.is_some_and(|stock| stock < 0) {
+        errors.add("stock", "negative", "stock must not be negative");
+    }
+    errors.finish()
+}
+
+pub(crate) fn validate_price_range(query: &ProductQuery) -> AppResult<()> {
+    if let (Some(min), Some(max)) = (query.min_price, query.max_price)
+        && min > max
+    {
+        return Err(AppError::BadRequest(
+            "min_price must not be greater than max_price".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Appends the filters of `query`; used for both the list and the count query so they
+/// always agree. Returns whether a `WHERE` was emitted.
+pub(crate) fn push_product_filters(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    query: &ProductQuery,
+) -> bool {
+    // deleted products only show up under /admin/products
+    builder.push(" WHERE deleted_at IS NULL");
+    let mut has_where = true;
+
+    if query.include_unpublished != Some(true) {
+        push_predicate(builder, &mut has_where);
+        builder.push("is_published");
+    }
+    if let Some(q) = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        let pattern = format!("%{}%", escape_like(q));
+        push_predicate(builder, &mut has_where);
+        builder
+            .push("(name ILIKE ")
+            .push_bind(pattern.clone())
+            .push(" OR description ILIKE ")
+            .push_bind(pattern)
+            .push(")");
+    }
+    if let Some(min_price) = query.min_price {
+        push_predicate(builder, &mut has_where);
+        builder.push("price >= ").push_bind(min_price);
+    }
+    if let Some(max_price) = query.max_price {
+        push_predicate(builder, &mut has_where);
+        builder.push("price <= ").push_bind(max_price);
+    }
+    if let Some(category) = query
+        .category
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+    {
+        push_predicate(builder, &mut has_where);
+        match Uuid::parse_str(category) {
+            Ok(id) => builder.push("category_id = ").push_bind(id),
+            Err(_) => builder
+                .push("category_id = (SELECT id FROM categories WHERE slug = ")
+                .push_bind(category.to_string())
+                .push(")"),
+        };
+    }
+    if let Some(sku) = query
+        .sku
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        push_predicate(builder, &mut has_where);
+        builder.push("sku = ").push_bind(sku.to_string());
+    }
+    if let Some(in_stock) = query.in_stock {
+        push_predicate(builder, &mut has_where);
+        builder.push(if in_stock {
+            "stock - reserved > 0"
+        } else {
+            "stock - reserved <= 0"
+        });
+    }
+    has_where
+}
+
+/// Fills in `category`, `images`, the rating aggregates and `favorite_count` with one
+/// lookup per relation.
+pub async fn load_product_details(pool: &DbPool, products: &mut [Product]) -> AppResult<()> {
+    if products.is_empty() {
+        return Ok(());
+    }
+
+    let category_ids: Vec<Uuid> = products.iter().filter_map(|p| p.category_id).collect();
+    let categories = sqlx::query_as::<_, Category>("SELECT * FROM categories WHERE id = ANY($1)")
+        .bind(&category_ids)
+        .fetch_all(pool)
+        .await?;
+
+    let product_ids: Vec<Uuid> = products.iter().map(|p| p.id).collect();
+    let images = sqlx::query_as::<_, ProductImage>(
+        "SELECT * FROM product_images WHERE product_id = ANY($1) ORDER BY position, created_at",
+    )
+    .bind(&product_ids)
+    .fetch_all(pool)
+    .await?;
+
+    let ratings: Vec<(Uuid, f64, i64)> = sqlx::query_as(
+        r#"
+        SELECT product_id, AVG(rating)::float8, count(*)
+        FROM reviews
+        WHERE product_id = ANY($1)
+        GROUP BY product_id
+        "#,
+    )
+    .bind(&product_ids)
+    .fetch_all(pool)
+    .await?;
+
+    let favorite_counts: Vec<(Uuid, i64)> = sqlx::query_as(
+        r#"
+        SELECT product_id, count(*)
+        FROM favorites
+        WHERE product_id = ANY($1)
+        GROUP BY product_id
+        "#,
+    )
+    .bind(&product_ids)
+    .fetch_all(pool)
+    .await?;
+
+    for product in products.iter_mut() {
+        product.favorite_count = favorite_counts
+            .iter()
+            .find(|(id, _)| *id == product.id)
+            .map_or(0, |&(_, count)| count);
+        (product.average_rating, product.review_count) = ratings
+            .iter()
+            .find(|(id, _, _)| *id == product.id)
+            .map_or((None, 0), |&(_, average, count)| (Some(average), count));
+        product.category = product
+            .category_id
+            .and_then(|id| categories.iter().find(|c| c.id == id).cloned());
+        product.images = images
+            .iter()
+            .filter(|image| image.product_id == product.id)
+            .cloned()
+            .collect();
+    }
+    Ok(())
+}
+
+/// Slug for a new product: the slugified name, or `name-2`, `name-3`, ... when taken.
+async fn unique_product_slug(pool: &DbPool, name: &str) -> AppResult<String> {
+    let mut base = slugify(name);
+    if base.is_empty() {
+        base = "product".to_string();
+    }
+
+    let taken: Vec<(String,)> =
+        sqlx::query_as("SELECT slug FROM products WHERE (slug = $1 OR slug LIKE $1 || '-%') AND deleted_at IS NULL")
+            .bind(&base)
+            .fetch_all(pool)
+            .await?;
+    if !taken.iter().any(|(slug,)| *slug == base) {
+        return Ok(base);
+    }
+
+    let mut suffix = 2;
+    loop {
+        let candidate = format!("{}-{}", base, suffix);
+        if !taken.iter().any(|(slug,)| *slug == candidate) {
+            return Ok(candidate);
+        }
+        suffix += 1;
+    }
+}
+
+async fn ensure_slug_available(db: impl PgExecutor<'_>, slug: &str, except: Uuid) -> AppResult<()> {
+    let taken: Option<(Uuid,)> = sqlx::query_as(
+        "SELECT id FROM products WHERE slug = $1 AND id <> $2 AND deleted_at IS NULL",
+    )
+    .bind(slug)
+    .bind(except)
+    .fetch_optional(db)
+    .await?;
+    if taken.is_some() {
+        return Err(AppError::Conflict(format!(
+            "product slug {} is already taken",
+            slug
+        )));
+    }
+    Ok(())
+}
+
+/// Trims a new product's SKU; a blank one is a 422 on `sku`.
+fn normalize_sku(sku: Option<String>) -> AppResult<Option<String>> {
+    let sku = sku.map(|s| s.trim().to_string());
+    let mut errors = FieldErrors::default();
+    if sku.as_deref().is_some_and(str::is_empty) {
+        errors.add("sku", "empty", "sku must not be empty");
+    }
+    errors.finish()?;
+    Ok(sku)
+}
+
+/// Turns a violation of the unique SKU constraint into a 409 naming the SKU.
+fn sku_conflict(err: sqlx::Error, sku: Option<&str>) -> AppError {
+    let duplicate = err
+        .as_database_error()
+        .is_some_and(|e| e.is_unique_violation() && e.constraint() == Some("products_sku_key"));
+    match (duplicate, sku) {
+        (true, Some(sku)) => AppError::Conflict(format!("sku {} is already in use", sku)),
+        _ => err.into(),
+    }
+}
+
+async fn record_price_change(
+    tx: &mut Transaction<'_, Postgres>,
+    product: &Product,
+    old_price: Option<Money>,
+    user: &AuthUser,
+) -> AppResult<()> {
+    sqlx::query(
+        "INSERT INTO product_price_history (id, product_id, old_price, new_price, changed_by) VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(new_id())
+    .bind(product.id)
+    .bind(old_price)
+    .bind(product.price)
+    .bind(user.user_id)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Writes one stock ledger entry per `(product_id, delta)`, in the transaction that changes
+/// the stock.
+pub(crate) async fn record_stock_movements(
+    tx: &mut Transaction<'_, Postgres>,
+    movements: &[(Uuid, i32)],
+    reason: StockMovementReason,
+    reference_id: Option<Uuid>,
+    created_by: Option<Uuid>,
+) -> AppResult<()> {
+    if movements.is_empty() {
+        return Ok(());
+    }
+    let ids: Vec<Uuid> = movements.iter().map(|_| new_id()).collect();
+    let (product_ids, deltas): (Vec<Uuid>, Vec<i32>) = movements.iter().copied().unzip();
+    sqlx::query(
+        r#"
+        INSERT INTO stock_movements (id, product_id, delta, reason, reference_id, created_by)
+        SELECT t.id, t.product_id, t.delta, $4, $5, $6
+        FROM UNNEST($1::uuid[], $2::uuid[], $3::int[]) AS t(id, product_id, delta)
+        "#,
+    )
+    .bind(&ids)
+    .bind(&product_ids)
+    .bind(&deltas)
+    .bind(reason)
+    .bind(reference_id)
+    .bind(created_by)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Sets `is_favorited` for `user` with a single lookup; a no-op for anonymous requests.
+pub async fn mark_favorites(
+    pool: &DbPool,
+    user: Option<&AuthUser>,
+    products: &mut [Product],
+) -> AppResult<()> {
+    let Some(user) = user else {
+        return Ok(());
+    };
+    if products.is_empty() {
+        return Ok(());
+    }
+
+    let ids: Vec<Uuid> = products.iter().map(|p| p.id).collect();
+    let favorited: Vec<(Uuid,)> = sqlx::query_as(
+        "SELECT product_id FROM favorites WHERE user_id = $1 AND product_id = ANY($2)",
+    )
+    .bind(user.user_id)
+    .bind(&ids)
+    .fetch_all(pool)
+    .await?;
+
+    for product in products.iter_mut() {
+        product.is_favorited = favorited.iter().any(|(id,)| *id == product.id);
+    }
+    Ok(())
+}
+
+fn is_admin(user: Option<&AuthUser>) -> bool {
+    user.is_some_and(|u| u.role == "admin")
+}
+
+/// Hides unpublished products from everyone but admins.
+fn ensure_visible(product: &Product, user: Option<&AuthUser>) -> AppResult<()> {
+    if product.deleted_at.is_some() || (!product.is_published && !is_admin(user)) {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+async fn ensure_category_exists(db: impl PgExecutor<'_>, id: Uuid) -> AppResult<()> {
+    let exists: Option<(Uuid,)> = sqlx::query_as("SELECT id FROM categories WHERE id = $1")
+        .bind(id)
+        .fetch_optional(db)
+        .await?;
+    if exists.is_none() {
+        return Err(AppError::BadRequest("category not found".to_string()));
+    }
+    Ok(())
+}
+
+pub fn router() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(list_products, create_product))
+        .routes(routes!(popular_products))
+        .routes(routes!(suggest_products))
+        .routes(routes!(get_product, update_product, delete_product))
+        .routes(routes!(get_product_by_slug))
+        .routes(routes!(get_product_by_sku))
+        .merge(product_images::router())
+        .merge(reviews::router())
 }
 
 #[utoipa::path(
     get,
-    path = "/api/products",
-    params(
-        ("page" = Option<i64>, Query, description = "Page number, default 1"),
-        ("per_page" = Option<i64>, Query, description = "Items per page, default 10"),
-    ),
+    path = "/",
+    operation_id = "products_list",
+    params(ProductQuery),
     responses(
-        (status = 200, description = "List products", body = ApiResponse<ProductList>)
+        (status = 200, description = "List products", body = ApiResponse<ProductList>),
+        (status = 400, description = "Invalid price range, or invalid bearer token"),
     ),
     tag = "products"
 )]
 pub async fn list_products(
     State(pool): State<DbPool>,
+    user: Option<AuthUser>,
+    AppQuery(mut query): AppQuery<ProductQuery>,
 ) -> AppResult<Json<ApiResponse<ProductList>>> {
-    let page = 1_i64;
-    let limit = 10_i64;
-    let offset = (page - 1) * limit;
-    let items = sqlx::query_as::<_, Product>(
-        "SELECT * FROM products order by created_at LIMIT $1 OFFSET $2",
-    )
-    .bind(limit)
-    .bind(offset)
-    .fetch_all(&pool)
-    .await?;
+    validate_price_range(&query)?;
+    if !is_admin(user.as_ref()) {
+        query.include_unpublished = None;
+    }
+
+    let (page, limit, offset) = resolve_page(query.page, query.per_page)?;
+    let sort = sort_keys(&query)?;
+    let after = query
+        .cursor
+        .as_deref()
+        .map(|cursor| decode_cursor(cursor, &sort))
+        .transpose()?;
 
-    let total: (i64,) = sqlx::query_as("SELECT count(*) FROM products")
-        .fetch_one(&pool)
+    let mut list_builder = QueryBuilder::<Postgres>::new("SELECT * FROM products");
+    let mut has_where = push_product_filters(&mut list_builder, &query);
+    if let Some((keys, id)) = after {
+        push_predicate(&mut list_builder, &mut has_where);
+        push_keyset_predicate(&mut list_builder, &sort, &keys, id);
+    }
+    // id breaks ties so pages stay stable and the keyset predicate is exact
+    let order_by: Vec<String> = sort
+        .iter()
+        .map(|(field, order)| format!("{} {}", field.column(), order.keyword()))
+        .chain(std::iter::once(format!("id {}", sort[0].1.keyword())))
+        .collect();
+    list_builder
+        .push(" ORDER BY ")
+        .push(order_by.join(", "))
+        .push(" LIMIT ")
+        .push_bind(limit + 1);
+    if query.cursor.is_none() {
+        list_builder.push(" OFFSET ").push_bind(offset);
+    }
+    let mut items = list_builder
+        .build_query_as::<Product>()
+        .fetch_all(&pool)
         .await?;
 
-    let meta = Meta::new(page, limit, total.0);
+    let total = if query.with_total.unwrap_or(true) {
+        let mut count_builder = QueryBuilder::<Postgres>::new("SELECT count(*) FROM products");
+        push_product_filters(&mut count_builder, &query);
+        Some(count_builder.build_query_scalar().fetch_one(&pool).await?)
+    } else {
+        None
+    };
+    let mut meta = Meta::for_page(page, limit, total, &mut items);
+    meta.next_cursor = items
+        .last()
+        .filter(|_| meta.has_next == Some(true))
+        .map(|last| encode_cursor(last, &sort));
+    if query.cursor.is_some() {
+        // Page numbers mean nothing in keyset mode; the cursor is the only way forward.
+        meta.page = None;
+        meta.has_prev = None;
+    }
+    load_product_details(&pool, &mut items).await?;
+    mark_favorites(&pool, user.as_ref(), &mut items).await?;
     let data = ProductList { items };
     Ok(Json(ApiResponse::success("Products", data, Some(meta))))
 }
 #[utoipa::path(
     get,
-    path = "/api/products/{id}",
+    path = "/{id}",
+    operation_id = "products_get",
     params(
         ("id" = Uuid, Path, description = "Product ID")
     ),
     responses(
         (status = 200, description = "Get product", body = ApiResponse<Product>),
+        (status = 400, description = "Invalid bearer token", body = ApiResponse<ErrorData>),
         (status = 404, description = "Product not found"),
     ),
     tag = "products"
@@ -93,85 +832,369 @@ pub async fn list_products(
 pub async fn get_product(
     Path(id): Path<Uuid>,
     State(pool): State<DbPool>,
+    State(cache): State<ProductCache>,
+    user: Option<AuthUser>,
 ) -> AppResult<Json<ApiResponse<Product>>> {
+    if let Some(mut product) = cache.get(id).await {
+        ensure_visible(&product, user.as_ref())?;
+        mark_favorites(&pool, user.as_ref(), std::slice::from_mut(&mut product)).await?;
+        return Ok(Json(ApiResponse::success("Product", product, None)));
+    }
+
     let result = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = $1")
         .bind(id)
         .fetch_optional(&pool)
         .await?;
-    let result = match result {
+    let mut result = match result {
         Some(p) => p,
         None => return Err(AppError::NotFound),
     };
+    load_product_details(&pool, std::slice::from_mut(&mut result)).await?;
+    cache.insert(result.clone()).await;
+    ensure_visible(&result, user.as_ref())?;
+    mark_favorites(&pool, user.as_ref(), std::slice::from_mut(&mut result)).await?;
     Ok(Json(ApiResponse::success("Product", result, None)))
 }
+#[utoipa::path(
+    get,
+    path = "/suggest",
+    operation_id = "products_suggest",
+    params(SuggestQuery),
+    responses(
+        (status = 200, description = "Published products whose name contains `q`, case-insensitively: names starting with it first, then alphabetical. Meant to be called on every keystroke, so there is no count, paging or product detail", body = ApiResponse<ProductSuggestionList>),
+    ),
+    tag = "products"
+)]
+pub async fn suggest_products(
+    State(pool): State<DbPool>,
+    AppQuery(query): AppQuery<SuggestQuery>,
+) -> AppResult<Json<ApiResponse<ProductSuggestionList>>> {
+    let q = query.q.as_deref().map(str::trim).unwrap_or_default();
+    let limit = query.limit.unwrap_or(8).clamp(1, MAX_SUGGESTIONS);
+    // satu huruf cocok dengan hampir semua produk, jadi tidak perlu ke database
+    if q.chars().count() < 2 {
+        return Ok(Json(ApiResponse::success(
+            "Product suggestions",
+            ProductSuggestionList { items: Vec::new() },
+            None,
+        )));
+    }
+
+    let escaped = escape_like(q);
+    let items = sqlx::query_as::<_, ProductSuggestion>(
+        r#"
+        SELECT id, name, slug
+        FROM products
+        WHERE deleted_at IS NULL AND is_published AND name ILIKE $1
+        ORDER BY name ILIKE $2 DESC, lower(name), id
+        LIMIT $3
+        "#,
+    )
+    .bind(format!("%{}%", escaped))
+    .bind(format!("{}%", escaped))
+    .bind(limit)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(ApiResponse::success(
+        "Product suggestions",
+        ProductSuggestionList { items },
+        None,
+    )))
+}
+
+#[utoipa::path(
+    get,
+    path = "/popular",
+    operation_id = "products_popular",
+    params(PopularQuery),
+    responses(
+        (status = 200, description = "Best sellers by units sold in paid orders", body = ApiResponse<PopularProductList>),
+    ),
+    tag = "products"
+)]
+pub async fn popular_products(
+    State(pool): State<DbPool>,
+    AppQuery(query): AppQuery<PopularQuery>,
+) -> AppResult<Json<ApiResponse<PopularProductList>>> {
+    let days = query.days.unwrap_or(30).clamp(1, 365);
+    let limit = query.limit.unwrap_or(10).clamp(1, 50);
+
+    let rows = sqlx::query_as::<_, PopularProduct>(
+        r#"
+        SELECT p.*, sold.units_sold
+        FROM (
+            SELECT oi.product_id, SUM(oi.quantity)::BIGINT AS units_sold
+            FROM order_items oi
+            JOIN orders o ON o.id = oi.order_id
+            WHERE o.status = ANY($1) AND o.created_at >= NOW() - make_interval(days => $2)
+            GROUP BY oi.product_id
+        ) sold
+        JOIN products p ON p.id = sold.product_id
+        WHERE p.is_published
+        ORDER BY sold.units_sold DESC, p.name
+        LIMIT $3
+        "#,
+    )
+    .bind(PAID_ORDER_STATUSES)
+    .bind(days)
+    .bind(limit)
+    .fetch_all(&pool)
+    .await?;
+
+    let (mut products, units_sold): (Vec<Product>, Vec<i64>) = rows
+        .into_iter()
+        .map(|row| (row.product, row.units_sold))
+        .unzip();
+    load_product_details(&pool, &mut products).await?;
+    let items = products
+        .into_iter()
+        .zip(units_sold)
+        .map(|(product, units_sold)| PopularProduct {
+            product,
+            units_sold,
+        })
+        .collect();
+
+    Ok(Json(ApiResponse::success(
+        "Popular products",
+        PopularProductList { items },
+        None,
+    )))
+}
+
+#[utoipa::path(
+    get,
+    path = "/slug/{slug}",
+    operation_id = "products_get_by_slug",
+    params(
+        ("slug" = String, Path, description = "Product slug")
+    ),
+    responses(
+        (status = 200, description = "Get product by slug", body = ApiResponse<Product>),
+        (status = 400, description = "Invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Product not found"),
+    ),
+    tag = "products"
+)]
+pub async fn get_product_by_slug(
+    Path(slug): Path<String>,
+    State(pool): State<DbPool>,
+    user: Option<AuthUser>,
+) -> AppResult<Json<ApiResponse<Product>>> {
+    let mut product = sqlx::query_as::<_, Product>(
+        "SELECT * FROM products WHERE slug = $1 AND deleted_at IS NULL",
+    )
+    .bind(slug)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+    ensure_visible(&product, user.as_ref())?;
+    load_product_details(&pool, std::slice::from_mut(&mut product)).await?;
+    mark_favorites(&pool, user.as_ref(), std::slice::from_mut(&mut product)).await?;
+    Ok(Json(ApiResponse::success("Product", product, None)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sku/{sku}",
+    operation_id = "products_get_by_sku",
+    params(
+        ("sku" = String, Path, description = "Product SKU")
+    ),
+    responses(
+        (status = 200, description = "Get product by SKU", body = ApiResponse<Product>),
+        (status = 400, description = "Invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Product not found"),
+    ),
+    tag = "products"
+)]
+pub async fn get_product_by_sku(
+    Path(sku): Path<String>,
+    State(pool): State<DbPool>,
+    user: Option<AuthUser>,
+) -> AppResult<Json<ApiResponse<Product>>> {
+    let mut product = sqlx::query_as::<_, Product>(
+        "SELECT * FROM products WHERE sku = $1 AND deleted_at IS NULL",
+    )
+    .bind(sku)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+    ensure_visible(&product, user.as_ref())?;
+    load_product_details(&pool, std::slice::from_mut(&mut product)).await?;
+    mark_favorites(&pool, user.as_ref(), std::slice::from_mut(&mut product)).await?;
+    Ok(Json(ApiResponse::success("Product", product, None)))
+}
+
 #[utoipa::path(
     post,
-    path = "/api/products",
+    path = "/",
+    operation_id = "products_create",
     request_body = CreateProductRequest,
     responses(
-        (status = 201, description = "Create product", body = ApiResponse<Product>)
+        (status = 201, description = "Create product", body = ApiResponse<Product>,
+            headers(("Location" = String, description = "URL of the new product"))),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 422, description = "Invalid name, price, stock or sku", body = ApiResponse<ErrorData>),
+        (status = 409, description = "SKU already in use"),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
     ),
     tag = "products"
 )]
 
 pub async fn create_product(
     State(pool): State<DbPool>,
-    Json(payload): Json<CreateProductRequest>,
-) -> AppResult<Json<ApiResponse<Product>>> {
-    let id = Uuid::new_v4();
-    let product = sqlx::query_as::<_, Product>(
-        "INSERT INTO products (id, name, description, price, stock) VALUES ($1, $2, $3, $4, $5) RETURNING *",
+    State(audit): State<AuditLog>,
+    user: AuthUser,
+    context: RequestContext,
+    AppJson(payload): AppJson<CreateProductRequest>,
+) -> AppResult<Located<Product>> {
+    ensure_admin(&user)?;
+    validate_product_fields(
+        Some(&payload.name),
+        Some(payload.price),
+        Some(payload.stock),
+    )?;
+    if let Some(category_id) = payload.category_id {
+        ensure_category_exists(&pool, category_id).await?;
+    }
+
+    let sku = normalize_sku(payload.sku)?;
+
+    let id = new_id();
+    let slug = unique_product_slug(&pool, &payload.name).await?;
+    let mut tx = pool.begin().await?;
+    let mut product = sqlx::query_as::<_, Product>(
+        "INSERT INTO products (id, name, slug, description, price, stock, category_id, sku, is_published) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING *",
     )
     .bind(id)
     .bind(payload.name)
+    .bind(slug)
     .bind(payload.description)
     .bind(payload.price)
     .bind(payload.stock)
-    .fetch_one(&pool)
-    .await?;
+    .bind(payload.category_id)
+    .bind(&sku)
+    .bind(payload.is_published.unwrap_or(true))
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| sku_conflict(e, sku.as_deref()))?;
+    record_price_change(&mut tx, &product, None, &user).await?;
+    if product.stock != 0 {
+        record_stock_movements(
+            &mut tx,
+            &[(product.id, product.stock)],
+            StockMovementReason::Restock,
+            None,
+            Some(user.user_id),
+        )
+        .await?;
+    }
+    tx.commit().await?;
+    audit.record(
+        AuditEvent::new(AuditAction::ProductCreate, "product")
+            .actor(user.user_id)
+            .entity(product.id)
+            .details(serde_json::json!({ "name": product.name, "price": product.price, "stock": product.stock }))
+            .context(&context),
+    );
+    load_product_details(&pool, std::slice::from_mut(&mut product)).await?;
 
-    Ok(Json(ApiResponse::success(
-        "Product created",
-        product,
-        Some(Meta::empty()),
-    )))
+    Ok(created(
+        format!("/api/v1/products/{}", product.id),
+        ApiResponse::success("Product created", product, Some(Meta::empty())),
+    ))
 }
 #[utoipa::path(
     put,
-    path = "/api/products/{id}",
+    path = "/{id}",
+    operation_id = "products_update",
     params(
         ("id" = Uuid, Path, description = "Product ID")
     ),
     request_body = UpdateProductRequest,
     responses(
-        (status = 200, description = "Updated product", body = ApiResponse<Product>)
+        (status = 200, description = "Updated product", body = ApiResponse<Product>),
+        (status = 400, description = "Invalid slug, or missing or invalid bearer token"),
+        (status = 422, description = "Invalid name, price or stock", body = ApiResponse<ErrorData>),
+        (status = 409, description = "Slug or SKU already taken"),
+        (status = 404, description = "Product not found"),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
     ),
     tag = "products"
 )]
 
 pub async fn update_product(
     State(pool): State<DbPool>,
+    State(cache): State<ProductCache>,
+    State(audit): State<AuditLog>,
+    user: AuthUser,
+    context: RequestContext,
     Path(id): Path<Uuid>,
-    Json(payload): Json<UpdateProductRequest>,
+    AppJson(payload): AppJson<UpdateProductRequest>,
 ) -> AppResult<Json<ApiResponse<Product>>> {
-    let existing = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = $1")
-        .bind(id)
-        .fetch_optional(&pool)
-        .await?;
+    ensure_admin(&user)?;
+    validate_product_fields(payload.name.as_deref(), payload.price, payload.stock)?;
+
+    let mut tx = pool.begin().await?;
+    let existing = sqlx::query_as::<_, Product>(
+        "SELECT * FROM products WHERE id = $1 AND deleted_at IS NULL FOR UPDATE",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await?;
     let existing = match existing {
         Some(p) => p,
         None => return Err(AppError::NotFound),
     };
 
     let name = payload.name.unwrap_or(existing.name);
-    let description = payload.description.or(existing.description);
+    let description = payload.description.unwrap_or(existing.description);
+    let old_price = existing.price;
+    let old_stock = existing.stock;
     let price = payload.price.unwrap_or(existing.price);
     let stock = payload.stock.unwrap_or(existing.stock);
+    if stock < existing.reserved {
+        let mut errors = FieldErrors::default();
+        errors.add(
+            "stock",
+            "below_reserved",
+            format!(
+                "stock must not be below the {} units reserved by pending orders",
+                existing.reserved
+            ),
+        );
+        errors.finish()?;
+    }
+    if let Some(category_id) = payload.category_id {
+        ensure_category_exists(&mut *tx, category_id).await?;
+    }
+    let category_id = payload.category_id.or(existing.category_id);
+    let slug = match payload.slug {
+        Some(slug) => {
+            let slug = slugify(&slug);
+            if slug.is_empty() {
+                return Err(AppError::BadRequest(
+                    "slug must contain at least one letter or digit".to_string(),
+                ));
+            }
+            ensure_slug_available(&mut *tx, &slug, id).await?;
+            slug
+        }
+        None => existing.slug,
+    };
+    let sku = match payload.sku {
+        Some(sku) => sku.map(|s| s.trim().to_string()).filter(|s| !s.is_empty()),
+        None => existing.sku,
+    };
+    let was_published = existing.is_published;
+    let is_published = payload.is_published.unwrap_or(existing.is_published);
 
-    let product = sqlx::query_as::<_, Product>(
+    let mut product = sqlx::query_as::<_, Product>(
         r#"
         UPDATE products
-        SET name = $2, description = $3, price = $4, stock = $5
+        SET name = $2, description = $3, price = $4, stock = $5, category_id = $6, slug = $7, sku = $8, is_published = $9
         WHERE id = $1
         RETURNING *
         "#,
@@ -181,8 +1204,49 @@ pub async fn update_product(
     .bind(description)
     .bind(price)
     .bind(stock)
-    .fetch_one(&pool)
-    .await?;
+    .bind(category_id)
+    .bind(slug)
+    .bind(&sku)
+    .bind(is_published)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| sku_conflict(e, sku.as_deref()))?;
+    if product.price != old_price {
+        record_price_change(&mut tx, &product, Some(old_price), &user).await?;
+    }
+    if product.stock != old_stock {
+        record_stock_movements(
+            &mut tx,
+            &[(id, product.stock - old_stock)],
+            StockMovementReason::Correction,
+            None,
+            Some(user.user_id),
+        )
+        .await?;
+    }
+    tx.commit().await?;
+    cache.invalidate(id).await;
+    audit.record(
+        AuditEvent::new(AuditAction::ProductUpdate, "product")
+            .actor(user.user_id)
+            .entity(id)
+            .details(serde_json::json!({
+                "old_price": old_price,
+                "price": product.price,
+                "stock": product.stock,
+                "is_published": is_published,
+            }))
+            .context(&context),
+    );
+    if is_published != was_published {
+        tracing::info!(
+            product_id = %id,
+            user_id = %user.user_id,
+            is_published,
+            "product publication changed"
+        );
+    }
+    load_product_details(&pool, std::slice::from_mut(&mut product)).await?;
 
     Ok(Json(ApiResponse::success(
         "Updated",
@@ -192,28 +1256,51 @@ pub async fn update_product(
 }
 #[utoipa::path(
     delete,
-    path = "/api/products/{id}",
+    path = "/{id}",
+    operation_id = "products_delete",
     params(
         ("id" = Uuid, Path, description = "Product ID")
     ),
     responses(
-        (status = 204, description = "Deleted product")
+        (status = 200, description = "Product deleted and unpublished; admins can still list and restore it", body = ApiResponse<serde_json::Value>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 404, description = "Product not found or already deleted"),
+        (status = 403, description = "Admin role required", body = ApiResponse<ErrorData>),
     ),
     tag = "products"
 )]
 
 pub async fn delete_product(
-    State(pool): State<DbPool>,
+    State(state): State<AppState>,
+    user: AuthUser,
+    context: RequestContext,
     Path(id): Path<Uuid>,
 ) -> AppResult<Json<ApiResponse<serde_json::Value>>> {
-    let result = sqlx::query("DELETE FROM products WHERE id = $1")
-        .bind(id)
-        .execute(&pool)
-        .await?;
-
+    ensure_admin(&user)?;
+    let mut tx = state.pool.begin().await?;
+    // unpublished too, so favorites and listings treat it as gone; images stay for a restore
+    let result = sqlx::query(
+        "UPDATE products SET deleted_at = now(), is_published = false WHERE id = $1 AND deleted_at IS NULL",
+    )
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound);
     }
+    // checkout takes whatever is in the cart, so it cannot stay there
+    sqlx::query("DELETE FROM cart_items WHERE product_id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    state.product_cache.invalidate(id).await;
+    state.audit.record(
+        AuditEvent::new(AuditAction::ProductDelete, "product")
+            .actor(user.user_id)
+            .entity(id)
+            .context(&context),
+    );
 
     Ok(Json(ApiResponse::success(
         "Deleted",
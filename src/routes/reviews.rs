@@ -0,0 +1,232 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+use uuid::Uuid;
+
+use crate::{
+    cache::ProductCache,
+    db::DbPool,
+    error::{AppError, AppResult, ErrorData},
+    extract::{AppJson, AppQuery},
+    ids::new_id,
+    middleware::auth::AuthUser,
+    models::Review,
+    response::{ApiResponse, Meta, PageParams},
+    routes::orders::PAID_ORDER_STATUSES,
+    state::AppState,
+};
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateReviewRequest {
+    /// 1 to 5
+    pub rating: i32,
+    pub comment: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ReviewList {
+    pub items: Vec<Review>,
+}
+
+pub fn router() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(list_reviews, create_review))
+        .routes(routes!(delete_review))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/reviews",
+    operation_id = "products_reviews_list",
+    params(
+        ("id" = Uuid, Path, description = "Product ID"),
+        PageParams
+    ),
+    responses(
+        (status = 200, description = "Reviews of a product, newest first", body = ApiResponse<ReviewList>),
+        (status = 404, description = "Product not found"),
+    ),
+    tag = "products"
+)]
+pub async fn list_reviews(
+    State(pool): State<DbPool>,
+    Path(id): Path<Uuid>,
+    AppQuery(params): AppQuery<PageParams>,
+) -> AppResult<Json<ApiResponse<ReviewList>>> {
+    ensure_product_exists(&pool, id).await?;
+
+    let (page, limit, offset) = params.resolve()?;
+    let mut items = sqlx::query_as::<_, Review>(
+        r#"
+        SELECT * FROM reviews
+        WHERE product_id = $1
+        ORDER BY created_at DESC, id
+        LIMIT $2 OFFSET $3
+        "#,
+    )
+    .bind(id)
+    .bind(limit + 1)
+    .bind(offset)
+    .fetch_all(&pool)
+    .await?;
+
+    let total = if params.with_total() {
+        Some(
+            sqlx::query_scalar("SELECT count(*) FROM reviews WHERE product_id = $1")
+                .bind(id)
+                .fetch_one(&pool)
+                .await?,
+        )
+    } else {
+        None
+    };
+    let meta = Meta::for_page(page, limit, total, &mut items);
+
+    Ok(Json(ApiResponse::success(
+        "Reviews",
+        ReviewList { items },
+        Some(meta),
+    )))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/reviews",
+    operation_id = "products_reviews_create",
+    params(
+        ("id" = Uuid, Path, description = "Product ID")
+    ),
+    request_body = CreateReviewRequest,
+    responses(
+        (status = 200, description = "Review a purchased product", body = ApiResponse<Review>),
+        (status = 400, description = "Rating out of range, or missing or invalid bearer token"),
+        (status = 403, description = "No paid order contains this product"),
+        (status = 404, description = "Product not found"),
+        (status = 409, description = "Product already reviewed by this user"),
+    ),
+    tag = "products"
+)]
+pub async fn create_review(
+    State(pool): State<DbPool>,
+    State(cache): State<ProductCache>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+    AppJson(payload): AppJson<CreateReviewRequest>,
+) -> AppResult<Json<ApiResponse<Review>>> {
+    if !(1..=5).contains(&payload.rating) {
+        return Err(AppError::BadRequest(
+            "rating must be between 1 and 5".to_string(),
+        ));
+    }
+    ensure_product_exists(&pool, id).await?;
+
+    let purchased: (bool,) = sqlx::query_as(
+        r#"
+        SELECT EXISTS (
+            SELECT 1
+            FROM order_items oi
+            JOIN orders o ON o.id = oi.order_id
+            WHERE o.user_id = $1 AND oi.product_id = $2 AND o.status = ANY($3)
+        )
+        "#,
+    )
+    .bind(user.user_id)
+    .bind(id)
+    .bind(PAID_ORDER_STATUSES)
+    .fetch_one(&pool)
+    .await?;
+    if !purchased.0 {
+        return Err(AppError::Forbidden);
+    }
+
+    let comment = payload
+        .comment
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty());
+    let review = sqlx::query_as::<_, Review>(
+        r#"
+        INSERT INTO reviews (id, user_id, product_id, rating, comment)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (user_id, product_id) DO NOTHING
+        RETURNING *
+        "#,
+    )
+    .bind(new_id())
+    .bind(user.user_id)
+    .bind(id)
+    .bind(payload.rating)
+    .bind(comment)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| AppError::Conflict("you have already reviewed this product".to_string()))?;
+    cache.invalidate(id).await;
+
+    Ok(Json(ApiResponse::success(
+        "Review created",
+        review,
+        Some(Meta::empty()),
+    )))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}/reviews/{review_id}",
+    operation_id = "products_reviews_delete",
+    params(
+        ("id" = Uuid, Path, description = "Product ID"),
+        ("review_id" = Uuid, Path, description = "Review ID")
+    ),
+    responses(
+        (status = 200, description = "Delete a review (author or admin)", body = ApiResponse<serde_json::Value>),
+        (status = 400, description = "Missing or invalid bearer token", body = ApiResponse<ErrorData>),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Review not found"),
+    ),
+    tag = "products"
+)]
+pub async fn delete_review(
+    State(pool): State<DbPool>,
+    State(cache): State<ProductCache>,
+    user: AuthUser,
+    Path((id, review_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<Json<ApiResponse<serde_json::Value>>> {
+    let review =
+        sqlx::query_as::<_, Review>("SELECT * FROM reviews WHERE id = $1 AND product_id = $2")
+            .bind(review_id)
+            .bind(id)
+            .fetch_optional(&pool)
+            .await?
+            .ok_or(AppError::NotFound)?;
+
+    if review.user_id != user.user_id && user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    sqlx::query("DELETE FROM reviews WHERE id = $1")
+        .bind(review.id)
+        .execute(&pool)
+        .await?;
+    cache.invalidate(id).await;
+
+    Ok(Json(ApiResponse::success(
+        "Deleted",
+        serde_json::json!({}),
+        Some(Meta::empty()),
+    )))
+}
+
+async fn ensure_product_exists(pool: &DbPool, id: Uuid) -> AppResult<()> {
+    let exists: Option<(Uuid,)> =
+        sqlx::query_as("SELECT id FROM products WHERE id = $1 AND deleted_at IS NULL")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+    if exists.is_none() {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
@@ -0,0 +1,485 @@
+//! Demo and load-test data for the `seed` binary.
+//!
+//! Seeded rows are recognisable: users have an `@seed.example.com` address and products a
+//! `SEED-` SKU. Users and products are derived from their number, so seeding twice inserts
+//! nothing new, and orders are topped up to the requested count rather than added again.
+
+use std::collections::HashMap;
+
+use argon2::{Argon2, PasswordHasher};
+use chrono::{Duration, Utc};
+use fake::{
+    Fake,
+    faker::{
+        lorem::en::Paragraph,
+        name::en::{FirstName, LastName},
+    },
+};
+use password_hash::{SaltString, rand_core::OsRng};
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+use uuid::Uuid;
+
+use crate::{db::DbPool, ids::new_id, models::OrderStatus, slug::slugify};
+
+/// Password of every seeded and bootstrapped account.
+pub const SEED_PASSWORD: &str = "password123";
+
+const EMAIL_DOMAIN: &str = "seed.example.com";
+const SKU_PREFIX: &str = "SEED-";
+
+/// Rows per insert statement.
+const BATCH: usize = 5_000;
+
+const CATEGORIES: [&str; 6] = ["Kitchen", "Office", "Outdoors", "Home", "Toys", "Garden"];
+const ADJECTIVES: [&str; 12] = [
+    "Rustic",
+    "Classic",
+    "Compact",
+    "Deluxe",
+    "Handmade",
+    "Modern",
+    "Vintage",
+    "Sturdy",
+    "Minimal",
+    "Travel",
+    "Ergonomic",
+    "Everyday",
+];
+const MATERIALS: [&str; 10] = [
+    "Oak", "Ceramic", "Steel", "Linen", "Bamboo", "Leather", "Glass", "Cotton", "Copper", "Walnut",
+];
+const NOUNS: [&str; 14] = [
+    "Mug", "Lamp", "Notebook", "Backpack", "Teapot", "Chair", "Planter", "Blanket", "Desk",
+    "Bottle", "Bowl", "Clock", "Tray", "Candle",
+];
+
+/// How many of each to have once seeding is done.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Volumes {
+    pub products: usize,
+    pub users: usize,
+    pub orders: usize,
+}
+
+/// Rows a [`seed`] run inserted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Seeded {
+    pub products: u64,
+    pub users: u64,
+    pub orders: u64,
+    pub cart_items: u64,
+}
+
+/// The accounts and products a fresh development database starts with: `admin@example.com`
+/// and `user@example.com`, both with [`SEED_PASSWORD`], and four products.
+pub async fn bootstrap(pool: &DbPool) -> anyhow::Result<()> {
+    let password_hash = hash_password()?;
+    for (email, role) in [("admin@example.com", "admin"), ("user@example.com", "user")] {
+        sqlx::query(
+            "INSERT INTO users (id, email, password_hash, role) VALUES ($1, $2, $3, $4)
+             ON CONFLICT (email) DO NOTHING",
+        )
+        .bind(new_id())
+        .bind(email)
+        .bind(&password_hash)
+        .bind(role)
+        .execute(pool)
+        .await?;
+    }
+
+    let products = [
+        ("Ceramic Mug", 1_250, 40),
+        ("Linen Notebook", 890, 120),
+        ("Steel Water Bottle", 2_400, 35),
+        ("Oak Desk Lamp", 5_900, 12),
+    ];
+    for (name, price, stock) in products {
+        sqlx::query(
+            "INSERT INTO products (id, name, slug, description, price, stock)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(new_id())
+        .bind(name)
+        .bind(slugify(name))
+        .bind(format!("A {} for everyday use.", name.to_lowercase()))
+        .bind(price)
+        .bind(stock)
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Deletes every seeded user with their orders and carts, then the seeded products.
+pub async fn clean(pool: &DbPool) -> anyhow::Result<()> {
+    let mut tx = pool.begin().await?;
+    // deleting a user keeps its orders, so they go first
+    sqlx::query("DELETE FROM orders WHERE user_id IN (SELECT id FROM users WHERE email LIKE $1)")
+        .bind(format!("%@{}", EMAIL_DOMAIN))
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM users WHERE email LIKE $1")
+        .bind(format!("%@{}", EMAIL_DOMAIN))
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM products WHERE sku LIKE $1")
+        .bind(format!("{}%", SKU_PREFIX))
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Seeds up to `volumes`: products in a few categories, users with carts, and pending or
+/// paid orders whose items come out of product stock.
+pub async fn seed(pool: &DbPool, volumes: Volumes) -> anyhow::Result<Seeded> {
+    let categories = seed_categories(pool).await?;
+    let products = seed_products(pool, volumes.products, &categories).await?;
+    let users = seed_users(pool, volumes.users).await?;
+    let cart_items = seed_carts(pool, &users).await?;
+    let orders = seed_orders(pool, volumes.orders).await?;
+    Ok(Seeded {
+        products,
+        users: users.len() as u64,
+        orders,
+        cart_items,
+    })
+}
+
+async fn seed_categories(pool: &DbPool) -> anyhow::Result<Vec<Uuid>> {
+    let names: Vec<String> = CATEGORIES.iter().map(|c| c.to_string()).collect();
+    let slugs: Vec<String> = CATEGORIES.iter().map(|c| slugify(c)).collect();
+    let ids: Vec<Uuid> = CATEGORIES.iter().map(|_| new_id()).collect();
+    sqlx::query(
+        r#"
+        INSERT INTO categories (id, name, slug)
+        SELECT * FROM UNNEST($1::uuid[], $2::text[], $3::text[])
+        ON CONFLICT (slug) DO NOTHING
+        "#,
+    )
+    .bind(&ids)
+    .bind(&names)
+    .bind(&slugs)
+    .execute(pool)
+    .await?;
+
+    let rows: Vec<(Uuid,)> = sqlx::query_as("SELECT id FROM categories WHERE slug = ANY($1)")
+        .bind(&slugs)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().map(|(id,)| id).collect())
+}
+
+async fn seed_products(pool: &DbPool, count: usize, categories: &[Uuid]) -> anyhow::Result<u64> {
+    let mut inserted = 0;
+    for start in (1..=count).step_by(BATCH) {
+        let end = (start + BATCH - 1).min(count);
+        let mut ids = Vec::new();
+        let mut names = Vec::new();
+        let mut slugs = Vec::new();
+        let mut skus = Vec::new();
+        let mut descriptions = Vec::new();
+        let mut prices = Vec::new();
+        let mut stocks = Vec::new();
+        let mut category_ids = Vec::new();
+        let mut published = Vec::new();
+        for n in start..=end {
+            let mut rng = rng_for("product", n);
+            let name = format!(
+                "{} {} {}",
+                ADJECTIVES.choose(&mut rng).unwrap(),
+                MATERIALS.choose(&mut rng).unwrap(),
+                NOUNS.choose(&mut rng).unwrap()
+            );
+            ids.push(new_id());
+            slugs.push(format!("{}-{}", slugify(&name), n));
+            names.push(name);
+            skus.push(format!("{}{:06}", SKU_PREFIX, n));
+            descriptions.push(Paragraph(1..3).fake_with_rng::<String, _>(&mut rng));
+            // Mostly cheap, a few expensive: whole units from 2 to about 1000, ending in .99.
+            let units = 10f64.powf(rng.gen_range(0.3..3.0)) as i64;
+            prices.push(units * 100 + 99);
+            // One in ten sold out; the rest mostly small quantities.
+            stocks.push(if rng.gen_bool(0.1) {
+                0
+            } else {
+                (rng.gen_range(1.0f64..15.0).powi(2)) as i32
+            });
+            category_ids.push(categories.choose(&mut rng).copied());
+            published.push(rng.gen_bool(0.95));
+        }
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO products
+                (id, name, slug, sku, description, price, stock, category_id, is_published)
+            SELECT * FROM UNNEST(
+                $1::uuid[], $2::text[], $3::text[], $4::text[], $5::text[],
+                $6::bigint[], $7::int[], $8::uuid[], $9::bool[]
+            )
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(&ids)
+        .bind(&names)
+        .bind(&slugs)
+        .bind(&skus)
+        .bind(&descriptions)
+        .bind(&prices)
+        .bind(&stocks)
+        .bind(&category_ids)
+        .bind(&published)
+        .execute(pool)
+        .await?;
+        inserted += result.rows_affected();
+    }
+    Ok(inserted)
+}
+
+/// Returns the ids of the users this run added.
+async fn seed_users(pool: &DbPool, count: usize) -> anyhow::Result<Vec<Uuid>> {
+    // Hashing is slow on purpose; one hash serves every seeded account.
+    let password_hash = hash_password()?;
+    let mut inserted = Vec::new();
+    for start in (1..=count).step_by(BATCH) {
+        let end = (start + BATCH - 1).min(count);
+        let ids: Vec<Uuid> = (start..=end).map(|_| new_id()).collect();
+        let emails: Vec<String> = (start..=end)
+            .map(|n| {
+                let mut rng = rng_for("user", n);
+                let first: String = FirstName().fake_with_rng(&mut rng);
+                let last: String = LastName().fake_with_rng(&mut rng);
+                format!(
+                    "{}.{}{}@{}",
+                    first.to_lowercase(),
+                    last.to_lowercase().replace(['\'', ' '], ""),
+                    n,
+                    EMAIL_DOMAIN
+                )
+            })
+            .collect();
+        let rows: Vec<(Uuid,)> = sqlx::query_as(
+            r#"
+            INSERT INTO users (id, email, password_hash)
+            SELECT id, email, $3 FROM UNNEST($1::uuid[], $2::text[]) AS t(id, email)
+            ON CONFLICT (email) DO NOTHING
+            RETURNING id
+            "#,
+        )
+        .bind(&ids)
+        .bind(&emails)
+        .bind(&password_hash)
+        .fetch_all(pool)
+        .await?;
+        inserted.extend(rows.into_iter().map(|(id,)| id));
+    }
+    Ok(inserted)
+}
+
+/// Gives about half of `users` a few lines in their cart.
+async fn seed_carts(pool: &DbPool, users: &[Uuid]) -> anyhow::Result<u64> {
+    let products = seeded_products(pool).await?;
+    if products.is_empty() {
+        return Ok(0);
+    }
+
+    let mut rng = rand::thread_rng();
+    let (mut ids, mut user_ids, mut product_ids, mut quantities, mut prices) =
+        (Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for user in users {
+        if !rng.gen_bool(0.5) {
+            continue;
+        }
+        let lines = rng.gen_range(1..=3).min(products.len());
+        for product in products.choose_multiple(&mut rng, lines) {
+            ids.push(new_id());
+            user_ids.push(*user);
+            product_ids.push(product.id);
+            quantities.push(rng.gen_range(1..=3));
+            prices.push(product.price);
+        }
+    }
+
+    let mut inserted = 0;
+    for start in (0..ids.len()).step_by(BATCH) {
+        let end = (start + BATCH).min(ids.len());
+        let result = sqlx::query(
+            r#"
+            INSERT INTO cart_items (id, user_id, product_id, quantity, price_at_add)
+            SELECT * FROM UNNEST($1::uuid[], $2::uuid[], $3::uuid[], $4::int[], $5::bigint[])
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(&ids[start..end])
+        .bind(&user_ids[start..end])
+        .bind(&product_ids[start..end])
+        .bind(&quantities[start..end])
+        .bind(&prices[start..end])
+        .execute(pool)
+        .await?;
+        inserted += result.rows_affected();
+    }
+    Ok(inserted)
+}
+
+/// Adds orders until seeded users have `count` between them, or stock runs out.
+async fn seed_orders(pool: &DbPool, count: usize) -> anyhow::Result<u64> {
+    let existing: (i64,) = sqlx::query_as(
+        "SELECT count(*) FROM orders o JOIN users u ON u.id = o.user_id WHERE u.email LIKE $1",
+    )
+    .bind(format!("%@{}", EMAIL_DOMAIN))
+    .fetch_one(pool)
+    .await?;
+    let wanted = count.saturating_sub(existing.0 as usize);
+    if wanted == 0 {
+        return Ok(0);
+    }
+    let users = seeded_user_ids(pool).await?;
+    anyhow::ensure!(!users.is_empty(), "orders need seeded users; pass --users");
+    let mut products = seeded_products(pool).await?;
+
+    let mut rng = rand::thread_rng();
+    let (mut order_ids, mut order_users, mut totals, mut statuses, mut created) =
+        (Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    let (mut item_ids, mut item_orders, mut item_products, mut item_quantities, mut item_prices) =
+        (Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    // pending orders only reserve their units, paid ones take them out of stock
+    let mut sold: HashMap<Uuid, (i32, i32)> = HashMap::new();
+    for _ in 0..wanted {
+        products.retain(|p| p.stock > 0);
+        if products.is_empty() {
+            tracing::warn!("seeded products are sold out; stopping early");
+            break;
+        }
+        let order_id = new_id();
+        let status = if rng.gen_bool(0.6) {
+            OrderStatus::Paid
+        } else {
+            OrderStatus::Pending
+        };
+        let lines = rng.gen_range(1..=3).min(products.len());
+        let mut total = 0;
+        for index in rand::seq::index::sample(&mut rng, products.len(), lines) {
+            let product = &mut products[index];
+            let quantity = rng.gen_range(1..=3).min(product.stock);
+            product.stock -= quantity;
+            let (taken, reserved) = sold.entry(product.id).or_default();
+            if status.is_paid() {
+                *taken += quantity;
+            } else {
+                *reserved += quantity;
+            }
+            total += product.price * i64::from(quantity);
+            item_ids.push(new_id());
+            item_orders.push(order_id);
+            item_products.push(product.id);
+            item_quantities.push(quantity);
+            item_prices.push(product.price);
+        }
+        order_ids.push(order_id);
+        order_users.push(*users.choose(&mut rng).unwrap());
+        totals.push(total);
+        statuses.push(status);
+        created.push(Utc::now() - Duration::minutes(rng.gen_range(0..90 * 24 * 60)));
+    }
+    let (sold_ids, (sold_quantities, reserved_quantities)): (Vec<Uuid>, (Vec<i32>, Vec<i32>)) =
+        sold.into_iter().unzip();
+
+    let mut tx = pool.begin().await?;
+    for start in (0..order_ids.len()).step_by(BATCH) {
+        let end = (start + BATCH).min(order_ids.len());
+        sqlx::query(
+            r#"
+            INSERT INTO orders (id, user_id, total_amount, status, created_at, paid_at, completed_at)
+            SELECT t.*,
+                CASE WHEN t.status IN ('paid', 'completed') THEN t.created_at END,
+                CASE WHEN t.status = 'completed' THEN t.created_at END
+            FROM UNNEST($1::uuid[], $2::uuid[], $3::bigint[], $4::text[], $5::timestamptz[])
+                AS t(id, user_id, total_amount, status, created_at)
+            "#,
+        )
+        .bind(&order_ids[start..end])
+        .bind(&order_users[start..end])
+        .bind(&totals[start..end])
+        .bind(&statuses[start..end])
+        .bind(&created[start..end])
+        .execute(&mut *tx)
+        .await?;
+    }
+    for start in (0..item_ids.len()).step_by(BATCH) {
+        let end = (start + BATCH).min(item_ids.len());
+        sqlx::query(
+            r#"
+            INSERT INTO order_items (id, order_id, product_id, quantity, price)
+            SELECT * FROM UNNEST($1::uuid[], $2::uuid[], $3::uuid[], $4::int[], $5::bigint[])
+            "#,
+        )
+        .bind(&item_ids[start..end])
+        .bind(&item_orders[start..end])
+        .bind(&item_products[start..end])
+        .bind(&item_quantities[start..end])
+        .bind(&item_prices[start..end])
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query(
+        r#"
+        UPDATE products p SET stock = p.stock - t.quantity, reserved = p.reserved + t.reserved
+        FROM UNNEST($1::uuid[], $2::int[], $3::int[]) AS t(id, quantity, reserved)
+        WHERE p.id = t.id
+        "#,
+    )
+    .bind(&sold_ids)
+    .bind(&sold_quantities)
+    .bind(&reserved_quantities)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(order_ids.len() as u64)
+}
+
+#[derive(sqlx::FromRow)]
+struct SeededProduct {
+    id: Uuid,
+    price: i64,
+    /// Units still available, reserved ones excluded
+    stock: i32,
+}
+
+async fn seeded_products(pool: &DbPool) -> anyhow::Result<Vec<SeededProduct>> {
+    Ok(sqlx::query_as(
+        r#"
+        SELECT id, price, stock - reserved AS stock
+        FROM products
+        WHERE sku LIKE $1 AND is_published AND stock - reserved > 0
+        "#,
+    )
+    .bind(format!("{}%", SKU_PREFIX))
+    .fetch_all(pool)
+    .await?)
+}
+
+async fn seeded_user_ids(pool: &DbPool) -> anyhow::Result<Vec<Uuid>> {
+    let rows: Vec<(Uuid,)> = sqlx::query_as("SELECT id FROM users WHERE email LIKE $1")
+        .bind(format!("%@{}", EMAIL_DOMAIN))
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().map(|(id,)| id).collect())
+}
+
+/// The same generator for the same row every run, which keeps reseeding idempotent.
+fn rng_for(kind: &str, n: usize) -> StdRng {
+    let kind = kind
+        .bytes()
+        .fold(0u64, |h, b| h.wrapping_mul(31) + u64::from(b));
+    StdRng::seed_from_u64(kind ^ n as u64)
+}
+
+fn hash_password() -> anyhow::Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(SEED_PASSWORD.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| anyhow::anyhow!(e.to_string()))
+}
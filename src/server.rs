@@ -0,0 +1,170 @@
+use std::{net::SocketAddr, path::Path, sync::Arc, time::Duration};
+
+use anyhow::Context;
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use rustls::{
+    ServerConfig,
+    crypto::ring,
+    pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+};
+
+/// How long in-flight requests get to finish once shutdown starts.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+/// Serves `app` on `listener` until `shutdown` resolves and in-flight requests are done, over
+/// TLS when `tls` is given. Either way handlers see the peer address through `ConnectInfo`,
+/// which the rate limiter keys on.
+pub async fn serve(
+    listener: std::net::TcpListener,
+    app: Router,
+    tls: Option<RustlsConfig>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> anyhow::Result<()> {
+    listener.set_nonblocking(true)?;
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+    match tls {
+        Some(tls) => {
+            tracing::info!("serving HTTPS on {}", listener.local_addr()?);
+            let handle = axum_server::Handle::new();
+            tokio::spawn({
+                let handle = handle.clone();
+                async move {
+                    shutdown.await;
+                    handle.graceful_shutdown(Some(SHUTDOWN_GRACE));
+                }
+            });
+            axum_server::from_tcp_rustls(listener, tls)
+                .handle(handle)
+                .serve(app)
+                .await?;
+        }
+        None => {
+            tracing::info!("serving plain HTTP on {}", listener.local_addr()?);
+            axum::serve(tokio::net::TcpListener::from_std(listener)?, app)
+                .with_graceful_shutdown(shutdown)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Serves `app` on a unix socket at `path` until `shutdown` resolves, then removes the socket.
+/// A stale socket left by a crashed run is replaced; any other file there is an error.
+#[cfg(unix)]
+pub async fn serve_unix(
+    path: &Path,
+    mode: u32,
+    app: Router,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> anyhow::Result<()> {
+    use std::{fs, os::unix::fs::FileTypeExt, os::unix::fs::PermissionsExt};
+
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_socket() => fs::remove_file(path)
+            .with_context(|| format!("cannot remove stale socket {}", path.display()))?,
+        Ok(_) => anyhow::bail!("{} exists and is not a socket", path.display()),
+        Err(_) => {}
+    }
+    let listener = tokio::net::UnixListener::bind(path)
+        .with_context(|| format!("cannot bind unix socket {}", path.display()))?;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .with_context(|| format!("cannot set permissions on {}", path.display()))?;
+
+    tracing::info!("serving plain HTTP on unix socket {}", path.display());
+    let served = axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown)
+        .await;
+    fs::remove_file(path).ok();
+    served?;
+    Ok(())
+}
+
+/// Resolves on Ctrl-C or, on unix, SIGTERM.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.ok();
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    tracing::info!("shutting down");
+}
+
+/// Reads a PEM certificate chain and its private key (PKCS#8, PKCS#1 or SEC1) into a rustls
+/// config, with errors that name the file at fault.
+pub fn tls_server_config(cert_path: &Path, key_path: &Path) -> anyhow::Result<ServerConfig> {
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("cannot read TLS certificate {}", cert_path.display()))?;
+    anyhow::ensure!(
+        !certs.is_empty(),
+        "TLS certificate {} has no certificates in it",
+        cert_path.display()
+    );
+    let key = PrivateKeyDer::from_pem_file(key_path)
+        .with_context(|| format!("cannot read TLS private key {}", key_path.display()))?;
+
+    let mut config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .with_context(|| {
+            format!(
+                "TLS private key {} does not fit certificate {}",
+                key_path.display(),
+                cert_path.display()
+            )
+        })?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+/// Loads the certificate for `serve`.
+pub fn load_tls(cert_path: &Path, key_path: &Path) -> anyhow::Result<RustlsConfig> {
+    let config = tls_server_config(cert_path, key_path)?;
+    Ok(RustlsConfig::from_config(Arc::new(config)))
+}
+
+/// Re-reads the certificate and key whenever the process gets SIGHUP, e.g. after renewal.
+/// A broken pair is logged and the previous one stays in use.
+#[cfg(unix)]
+pub fn reload_tls_on_sighup(tls: RustlsConfig, cert_path: String, key_path: String) {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    tokio::spawn(async move {
+        let mut hangups = match signal(SignalKind::hangup()) {
+            Ok(hangups) => hangups,
+            Err(e) => {
+                tracing::warn!(error = %e, "cannot listen for SIGHUP; TLS reload disabled");
+                return;
+            }
+        };
+        while hangups.recv().await.is_some() {
+            match tls_server_config(Path::new(&cert_path), Path::new(&key_path)) {
+                Ok(config) => {
+                    tls.reload_from_config(Arc::new(config));
+                    tracing::info!("reloaded TLS certificate");
+                }
+                Err(e) => tracing::error!(error = %format!("{:#}", e), "TLS reload failed"),
+            }
+        }
+    });
+}
+
+#[cfg(not(unix))]
+pub fn reload_tls_on_sighup(_tls: RustlsConfig, _cert_path: String, _key_path: String) {}
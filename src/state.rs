@@ -0,0 +1,85 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::extract::FromRef;
+
+use crate::{
+    admin_events::AdminEvents,
+    audit::AuditLog,
+    cache::{ProductCache, UserCache},
+    config::AppConfig,
+    db::DbPool,
+    jobs::JobRegistry,
+    middleware::auth::JwtKeys,
+    money::{self, Money},
+    notifier::{LogNotifier, Notifier, SmtpNotifier},
+    routes::orders::OrderLimits,
+    stock_alerts::LowStockThreshold,
+    storage::{LocalStorage, Storage},
+};
+
+/// How many token holders [`UserCache`] remembers, and for how long. A deleted account's
+/// tokens keep working on other instances for at most the TTL.
+const USER_CACHE_CAPACITY: u64 = 10_000;
+const USER_CACHE_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Clone, FromRef)]
+pub struct AppState {
+    pub pool: DbPool,
+    pub storage: Arc<dyn Storage>,
+    pub product_cache: ProductCache,
+    /// Which token holders still have an account, see [`AuthUser::authenticate`].
+    ///
+    /// [`AuthUser::authenticate`]: crate::middleware::auth::AuthUser::authenticate
+    pub user_cache: UserCache,
+    /// When the process started serving, for the uptime in `/ready`.
+    pub started_at: Instant,
+    /// Background job status, filled in once the scheduler starts.
+    pub jobs: JobRegistry,
+    pub audit: AuditLog,
+    pub jwt: Arc<JwtKeys>,
+    pub low_stock_threshold: LowStockThreshold,
+    pub order_limits: OrderLimits,
+    pub notifier: Arc<dyn Notifier>,
+    /// Live events for admins on `/admin/ws`.
+    pub admin_events: AdminEvents,
+}
+
+impl AppState {
+    /// The state `main` serves with: local file storage, the product cache and order emails as
+    /// configured. Starts the audit writer, so it must be called inside the runtime.
+    pub fn new(pool: DbPool, config: &AppConfig) -> Self {
+        money::set_currency(config.currency);
+        Self {
+            audit: AuditLog::start(pool.clone()),
+            pool,
+            storage: Arc::new(LocalStorage::new(
+                &config.upload_dir,
+                &config.upload_base_url,
+            )),
+            product_cache: ProductCache::new(
+                config.product_cache_capacity,
+                Duration::from_secs(config.product_cache_ttl_secs),
+            ),
+            user_cache: UserCache::new(USER_CACHE_CAPACITY, USER_CACHE_TTL),
+            started_at: Instant::now(),
+            jobs: JobRegistry::default(),
+            jwt: Arc::new(JwtKeys::new(&config.jwt_secret)),
+            low_stock_threshold: LowStockThreshold(config.low_stock_threshold),
+            order_limits: OrderLimits {
+                max_total: Money::from_minor(config.max_order_total),
+                max_items: config.max_items_per_order,
+            },
+            notifier: match &config.smtp_url {
+                Some(url) => Arc::new(
+                    SmtpNotifier::new(url, &config.mail_from)
+                        .expect("SMTP settings are checked by AppConfig"),
+                ),
+                None => Arc::new(LogNotifier),
+            },
+            admin_events: AdminEvents::default(),
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! Low-stock alerts, in the `stock_alerts` table.
+//!
+//! The `low_stock_alerts` job calls [`scan`] periodically. A product gets an alert when its
+//! available units (`stock - reserved`) drop below the threshold, and keeps that one alert,
+//! acknowledged or not, until it is back at the threshold; only a later drop raises another.
+
+use std::time::Duration;
+
+use anyhow::Context;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{db::DbPool, ids::new_id, models::StockAlert};
+
+/// How long the webhook gets to accept a batch of alerts.
+pub const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Products with fewer units available are low on stock, from `AppConfig::low_stock_threshold`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LowStockThreshold(pub i32);
+
+/// Resolves the alerts of restocked or deleted products, then raises one for every low live
+/// product without an open alert. Returns the new alerts.
+pub async fn scan(pool: &DbPool, threshold: i32) -> sqlx::Result<Vec<StockAlert>> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"
+        UPDATE stock_alerts a
+        SET resolved_at = NOW()
+        FROM products p
+        WHERE p.id = a.product_id AND a.resolved_at IS NULL
+          AND (p.stock - p.reserved >= $1 OR p.deleted_at IS NOT NULL)
+        "#,
+    )
+    .bind(threshold)
+    .execute(&mut *tx)
+    .await?;
+
+    let low: Vec<(Uuid, i32)> = sqlx::query_as(
+        r#"
+        SELECT p.id, p.stock - p.reserved
+        FROM products p
+        WHERE p.stock - p.reserved < $1 AND p.deleted_at IS NULL
+          AND NOT EXISTS (
+              SELECT 1 FROM stock_alerts a WHERE a.product_id = p.id AND a.resolved_at IS NULL
+          )
+        ORDER BY p.id
+        "#,
+    )
+    .bind(threshold)
+    .fetch_all(&mut *tx)
+    .await?;
+    let ids: Vec<Uuid> = low.iter().map(|_| new_id()).collect();
+    let (product_ids, available): (Vec<Uuid>, Vec<i32>) = low.into_iter().unzip();
+    // another instance scanning at the same time may have raised some of them already
+    let alerts = sqlx::query_as::<_, StockAlert>(
+        r#"
+        WITH raised AS (
+            INSERT INTO stock_alerts (id, product_id, available, threshold)
+            SELECT t.id, t.product_id, t.available, $4
+            FROM UNNEST($1::uuid[], $2::uuid[], $3::int[]) AS t(id, product_id, available)
+            ON CONFLICT (product_id) WHERE resolved_at IS NULL DO NOTHING
+            RETURNING *
+        )
+        SELECT raised.*, p.name AS product_name
+        FROM raised
+        JOIN products p ON p.id = raised.product_id
+        ORDER BY p.name, raised.id
+        "#,
+    )
+    .bind(&ids)
+    .bind(&product_ids)
+    .bind(&available)
+    .bind(threshold)
+    .fetch_all(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(alerts)
+}
+
+/// POSTs `alerts` to `url` as `{"alerts": [...]}`, failing unless it answers with a 2xx.
+pub async fn notify(
+    client: &reqwest::Client,
+    url: &str,
+    alerts: &[StockAlert],
+) -> anyhow::Result<()> {
+    client
+        .post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(&json!({ "alerts": alerts }))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .context("low-stock webhook failed")?;
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use axum::body::Bytes;
+
+/// Where uploaded files live. Only local disk is implemented; S3 and friends can be added
+/// behind the same trait.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Stores `bytes` under `key` and returns the public URL of the file.
+    async fn put(&self, key: &str, bytes: Bytes) -> anyhow::Result<String>;
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()>;
+}
+
+pub struct LocalStorage {
+    root: PathBuf,
+    base_url: String,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>, base_url: impl Into<String>) -> Self {
+        Self {
+            root: root.into(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, bytes: Bytes) -> anyhow::Result<String> {
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, &bytes).await?;
+        Ok(format!("{}/{}", self.base_url, key))
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        match tokio::fs::remove_file(self.root.join(key)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
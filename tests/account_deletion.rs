@@ -0,0 +1,293 @@
+//! `DELETE /auth/me` removes the account but keeps its orders, anonymized.
+
+mod common;
+
+use std::time::Duration;
+
+use axum::http::{Method, StatusCode};
+use axum_ecommerce_api::{app as build_app, cache::UserCache};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use common::{PASSWORD, TestApp};
+
+const ME: &str = "/api/v1/auth/me";
+
+async fn delete_account(app: &TestApp, token: &str, password: &str) -> common::TestResponse {
+    let body = json!({ "password": password });
+    app.request(Method::DELETE, ME, Some(token), Some(body))
+        .await
+}
+
+async fn count(app: &TestApp, sql: &str, id: Uuid) -> i64 {
+    sqlx::query_scalar(sql)
+        .bind(id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap()
+}
+
+/// `(stock, reserved)` of `product_id`, straight from the table.
+async fn stock(app: &TestApp, product_id: Uuid) -> (i32, i32) {
+    sqlx::query_as("SELECT stock, reserved FROM products WHERE id = $1")
+        .bind(product_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn a_deleted_account_leaves_only_anonymous_orders_behind() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let teapot = app.create_product(&admin, "Teapot", 4_000, 10).await;
+    let buyer_id: Uuid = app.get(ME, Some(&buyer)).await.body["data"]["id"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+
+    let profile = json!({ "full_name": "Budi Santoso", "phone": "+62 812-3456-7890" });
+    app.request(Method::PUT, ME, Some(&buyer), Some(profile))
+        .await;
+    let add = json!({ "product_id": mug, "quantity": 2 });
+    app.post("/api/v1/cart", Some(&buyer), add).await;
+    let response = app
+        .post("/api/v1/orders/checkout", Some(&buyer), json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let order_id: Uuid = response.body["data"]["order"]["id"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    let pay = format!("/api/v1/admin/orders/{}/pay", order_id);
+    let response = app.post(&pay, Some(&admin), json!({})).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let review = json!({ "rating": 5, "comment": "lovely" });
+    let uri = format!("/api/v1/products/{}/reviews", mug);
+    let response = app.post(&uri, Some(&buyer), review).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let add = json!({ "product_id": teapot, "quantity": 1 });
+    app.post("/api/v1/cart", Some(&buyer), add).await;
+    let favorite = json!({ "product_id": teapot });
+    let response = app.post("/api/v1/favorites", Some(&buyer), favorite).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+
+    // The password has to be entered again.
+    let response = delete_account(&app, &buyer, "not my password").await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+    assert_eq!(response.body["data"]["error_code"], "INVALID_CREDENTIALS");
+    assert_eq!(app.get(ME, Some(&buyer)).await.status, StatusCode::OK);
+
+    let response = delete_account(&app, &buyer, PASSWORD).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let (user_id, total, status, name, phone): (
+        Option<Uuid>,
+        i64,
+        String,
+        Option<String>,
+        Option<String>,
+    ) = sqlx::query_as(
+        "SELECT user_id, total_amount, status, recipient_name, recipient_phone FROM orders WHERE id = $1",
+    )
+    .bind(order_id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(user_id, None);
+    assert_eq!((total, status.as_str()), (2_500, "paid"));
+    assert_eq!((name, phone), (None, None));
+    let lines = count(
+        &app,
+        "SELECT count(*) FROM order_items WHERE order_id = $1",
+        order_id,
+    )
+    .await;
+    assert_eq!(lines, 1);
+    for sql in [
+        "SELECT count(*) FROM users WHERE id = $1",
+        "SELECT count(*) FROM cart_items WHERE user_id = $1",
+        "SELECT count(*) FROM favorites WHERE user_id = $1",
+        "SELECT count(*) FROM reviews WHERE user_id = $1",
+    ] {
+        assert_eq!(count(&app, sql, buyer_id).await, 0, "{}", sql);
+    }
+    let users: i64 = sqlx::query_scalar("SELECT count(*) FROM users WHERE email = $1")
+        .bind("buyer@example.com")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(users, 0);
+
+    // The admin still sees the order, and the product no longer shows the review.
+    let response = app
+        .get(&format!("/api/v1/admin/orders/{}", order_id), Some(&admin))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["order"]["user_id"], Value::Null);
+    let response = app.get(&uri, None).await;
+    assert_eq!(
+        response.body["data"]["items"],
+        json!([]),
+        "{}",
+        response.body
+    );
+
+    // The audit trail keeps a hash of the email instead of the email.
+    app.state.audit.flush().await;
+    let (actor, details): (Option<Uuid>, Value) = sqlx::query_as(
+        "SELECT actor_id, details FROM audit_log WHERE action = 'user.delete' AND entity_id = $1",
+    )
+    .bind(buyer_id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(actor, None);
+    let hash = format!("{:x}", Sha256::digest(b"buyer@example.com"));
+    assert_eq!(details["email_sha256"], hash);
+    assert_eq!(details["orders_anonymized"], 1);
+    let mentions = count(
+        &app,
+        "SELECT count(*) FROM audit_log WHERE actor_id = $1 OR details::text LIKE '%buyer@example.com%'",
+        buyer_id,
+    )
+    .await;
+    assert_eq!(mentions, 0);
+}
+
+#[tokio::test]
+async fn after_deletion_the_account_cannot_be_used_or_told_apart_from_a_stranger() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let token = app.register("gone@example.com").await;
+    let response = delete_account(&app, &token, PASSWORD).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    // The old token no longer works, just like any bad token.
+    for uri in [ME, "/api/v1/cart", "/api/v1/favorites"] {
+        let response = app.get(uri, Some(&token)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", uri);
+        assert!(
+            response.body["message"]
+                .as_str()
+                .unwrap()
+                .contains("Invalid or expired token"),
+            "{}",
+            response.body
+        );
+    }
+    let response = delete_account(&app, &token, PASSWORD).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    // Logging in fails exactly as for an address that never had an account.
+    let login = |email: &str| json!({ "email": email, "password": PASSWORD });
+    let gone = app
+        .post("/api/v1/auth/login", None, login("gone@example.com"))
+        .await;
+    let stranger = app
+        .post("/api/v1/auth/login", None, login("stranger@example.com"))
+        .await;
+    assert_eq!(gone.status, StatusCode::BAD_REQUEST);
+    assert_eq!(gone.body["data"]["error_code"], "INVALID_CREDENTIALS");
+    assert_eq!(gone.body["message"], stranger.body["message"]);
+    assert_eq!(
+        gone.body["data"]["error_code"],
+        stranger.body["data"]["error_code"]
+    );
+
+    // The address is free again.
+    app.register("gone@example.com").await;
+}
+
+#[tokio::test]
+async fn deleting_an_account_cancels_its_unpaid_orders_and_frees_their_stock() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let add = json!({ "product_id": mug, "quantity": 3 });
+    app.post("/api/v1/cart", Some(&buyer), add).await;
+    let response = app
+        .post("/api/v1/orders/checkout", Some(&buyer), json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let order_id: Uuid = response.body["data"]["order"]["id"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert_eq!(stock(&app, mug).await, (10, 3));
+
+    let response = delete_account(&app, &buyer, PASSWORD).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    assert_eq!(stock(&app, mug).await, (10, 0));
+    let (user_id, status): (Option<Uuid>, String) =
+        sqlx::query_as("SELECT user_id, status FROM orders WHERE id = $1")
+            .bind(order_id)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!((user_id, status.as_str()), (None, "cancelled"));
+    let product = app.get(&format!("/api/v1/products/{}", mug), None).await;
+    assert_eq!(product.body["data"]["reserved"], 0, "{}", product.body);
+
+    app.state.audit.flush().await;
+    let details: Value = sqlx::query_scalar(
+        "SELECT details FROM audit_log WHERE action = 'user.delete' AND entity_id IS NOT NULL",
+    )
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(details["orders_cancelled"], 1);
+}
+
+#[tokio::test]
+async fn tokens_are_checked_against_the_accounts_only_once_in_a_while() {
+    let Some(mut app) = TestApp::spawn().await else {
+        return;
+    };
+    app.state.user_cache = UserCache::new(10, Duration::from_millis(200));
+    app.router = build_app(&app.config, app.state.clone());
+    let token = app.register("gone@example.com").await;
+    assert_eq!(
+        app.get("/api/v1/cart", Some(&token)).await.status,
+        StatusCode::OK
+    );
+
+    // Removed behind the API's back, the account is still taken to exist for a while...
+    sqlx::query("DELETE FROM users WHERE email = $1")
+        .bind("gone@example.com")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        app.get("/api/v1/cart", Some(&token)).await.status,
+        StatusCode::OK
+    );
+
+    // ...until the entry expires.
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let response = app.get("/api/v1/cart", Some(&token)).await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+}
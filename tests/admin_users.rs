@@ -0,0 +1,95 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use common::TestApp;
+
+async fn user_id(app: &TestApp, email: &str) -> Uuid {
+    let (id,): (Uuid,) = sqlx::query_as("SELECT id FROM users WHERE email = $1")
+        .bind(email)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    id
+}
+
+#[tokio::test]
+async fn admins_can_see_a_users_orders_and_cart() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let other = app.register("other@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let teapot = app.create_product(&admin, "Teapot", 4_000, 10).await;
+
+    let add = json!({ "product_id": mug, "quantity": 2 });
+    app.post("/api/v1/cart", Some(&buyer), add.clone()).await;
+    let response = app
+        .post("/api/v1/orders/checkout", Some(&buyer), json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    app.post("/api/v1/cart", Some(&other), add).await;
+    app.post("/api/v1/orders/checkout", Some(&other), json!({}))
+        .await;
+    let add = json!({ "product_id": teapot, "quantity": 1 });
+    app.post("/api/v1/cart", Some(&buyer), add).await;
+
+    let id = user_id(&app, "buyer@example.com").await;
+    let response = app
+        .get(&format!("/api/v1/admin/users/{}/orders", id), Some(&admin))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let orders = response.body["data"]["items"].as_array().unwrap();
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0]["user_id"], id.to_string());
+    let response = app
+        .get(
+            &format!("/api/v1/admin/users/{}/orders?status=cancelled", id),
+            Some(&admin),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["items"], json!([]));
+
+    let response = app
+        .get(&format!("/api/v1/admin/users/{}/cart", id), Some(&admin))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let items = response.body["data"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["product_id"], teapot.to_string());
+
+    app.state.audit.flush().await;
+    let logged: Vec<(Option<Uuid>, Value)> = sqlx::query_as(
+        "SELECT entity_id, details FROM audit_log WHERE action = 'user.inspect' ORDER BY created_at",
+    )
+    .fetch_all(&app.pool)
+    .await
+    .unwrap();
+    let views: Vec<_> = logged.iter().map(|(_, d)| d["view"].clone()).collect();
+    assert_eq!(views, [json!("orders"), json!("orders"), json!("cart")]);
+    assert!(logged.iter().all(|(entity, _)| *entity == Some(id)));
+}
+
+#[tokio::test]
+async fn unknown_users_are_not_found_and_buyers_are_forbidden() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let id = user_id(&app, "buyer@example.com").await;
+
+    for view in ["orders", "cart"] {
+        let unknown = format!("/api/v1/admin/users/{}/{}", Uuid::new_v4(), view);
+        let response = app.get(&unknown, Some(&admin)).await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND, "{}", view);
+        let own = format!("/api/v1/admin/users/{}/{}", id, view);
+        let response = app.get(&own, Some(&buyer)).await;
+        assert_eq!(response.status, StatusCode::FORBIDDEN, "{}", view);
+    }
+}
@@ -0,0 +1,140 @@
+//! `GET /admin/ws` pushes new orders to every connected admin, over a real server.
+
+mod common;
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use axum_ecommerce_api::server::serve;
+use futures::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use tokio::{net::TcpStream, task::JoinHandle};
+use tokio_tungstenite::{
+    MaybeTlsStream, WebSocketStream, connect_async,
+    tungstenite::{Message, protocol::frame::coding::CloseCode},
+};
+
+use common::TestApp;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Serves the test app's router and state on a free port; returns the `ws://` base URL.
+fn spawn_server(app: &TestApp) -> (String, JoinHandle<anyhow::Result<()>>) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = axum_ecommerce_api::app(&app.config, app.state.clone());
+    let server = tokio::spawn(serve(listener, router, None, std::future::pending()));
+    (format!("ws://{}/api/v1/admin/ws", addr), server)
+}
+
+/// The next JSON message, skipping pings.
+async fn next_event(socket: &mut Socket) -> Value {
+    loop {
+        let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .expect("no message within 5s")
+            .expect("socket closed")
+            .unwrap();
+        match message {
+            Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+            Message::Ping(_) | Message::Pong(_) => continue,
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+}
+
+/// Opens a socket and authenticates it with `token` as the first message.
+async fn connect(url: &str, token: &str) -> Socket {
+    let (mut socket, _) = connect_async(url).await.unwrap();
+    socket.send(Message::text(token)).await.unwrap();
+    assert_eq!(next_event(&mut socket).await, json!({ "type": "ready" }));
+    socket
+}
+
+/// The close frame `socket` is sent after `token` as its first message.
+async fn rejected(url: &str, token: &str) -> CloseCode {
+    let (mut socket, _) = connect_async(url).await.unwrap();
+    socket.send(Message::text(token)).await.unwrap();
+    let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let Message::Close(Some(frame)) = message else {
+        panic!("expected a close frame, got {:?}", message);
+    };
+    frame.code
+}
+
+#[tokio::test]
+async fn every_connected_admin_hears_about_a_new_order() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let (url, server) = spawn_server(&app);
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+
+    // The token may come with or without its `Bearer ` prefix.
+    let mut first = connect(&url, &admin).await;
+    let mut second = connect(&url, admin.trim_start_matches("Bearer ")).await;
+
+    let add = json!({ "product_id": mug, "quantity": 3 });
+    app.post("/api/v1/cart", Some(&buyer), add).await;
+    let response = app
+        .post("/api/v1/orders/checkout", Some(&buyer), json!({}))
+        .await;
+    let order = &response.body["data"]["order"];
+
+    for socket in [&mut first, &mut second] {
+        let event = next_event(socket).await;
+        assert_eq!(event["type"], "new_order");
+        assert_eq!(event["order_id"], order["id"]);
+        assert_eq!(event["total_amount"], 3_750);
+        assert_eq!(event["total_amount_display"], "$37.50");
+        assert_eq!(event["customer_email"], "buyer@example.com");
+        assert_eq!(event["items"], 1);
+        assert_eq!(event["created_at"], order["created_at"]);
+    }
+
+    // The socket answers pings and says goodbye cleanly.
+    first.send(Message::Ping("hi".into())).await.unwrap();
+    let pong = tokio::time::timeout(Duration::from_secs(5), first.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(pong, Message::Pong("hi".into()));
+    first.close(None).await.unwrap();
+    second.close(None).await.unwrap();
+    server.abort();
+}
+
+#[tokio::test]
+async fn only_admins_may_listen() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let (url, server) = spawn_server(&app);
+    let buyer = app.register("buyer@example.com").await;
+
+    assert_eq!(rejected(&url, &buyer).await, CloseCode::Policy);
+    assert_eq!(rejected(&url, "garbage").await, CloseCode::Policy);
+
+    // The token is only taken from the first message, never from the URL.
+    let admin = app.register_admin("admin@example.com").await;
+    let bare = admin.trim_start_matches("Bearer ");
+    let with_query = format!("{}?token={}", url, bare);
+    assert_eq!(rejected(&with_query, "garbage").await, CloseCode::Policy);
+
+    // A plain request is not an upgrade.
+    let response = app.get("/api/v1/admin/ws", Some(&admin)).await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+    server.abort();
+}
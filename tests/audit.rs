@@ -0,0 +1,507 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode, header},
+};
+use axum_ecommerce_api::{
+    audit::{AuditAction, AuditEvent, AuditLog, prune_older_than},
+    db::DbPool,
+    jobs::{Job, PruneAuditLog},
+    with_middleware,
+};
+use common::TestApp;
+use serde_json::json;
+use uuid::Uuid;
+
+async fn audit_count(pool: &DbPool) -> i64 {
+    let (n,): (i64,) = sqlx::query_as("SELECT count(*) FROM audit_log")
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    n
+}
+
+#[tokio::test]
+async fn mutating_requests_are_audited() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let buyer = app.register("buyer@example.com").await;
+    let wrong = json!({ "email": "buyer@example.com", "password": "not the password" });
+    let response = app.post("/api/v1/auth/login", None, wrong).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    app.post(
+        "/api/v1/cart",
+        Some(&buyer),
+        json!({ "product_id": mug, "quantity": 2 }),
+    )
+    .await;
+    let response = app
+        .post("/api/v1/orders/checkout", Some(&buyer), json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let order_id: Uuid = response.body["data"]["order"]["id"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+
+    app.state.audit.flush().await;
+
+    let rows: Vec<(String, Option<Uuid>)> = sqlx::query_as(
+        "SELECT action, entity_id FROM audit_log WHERE action <> 'http.mutation' ORDER BY created_at, action",
+    )
+    .fetch_all(&app.pool)
+    .await
+    .unwrap();
+    let actions: Vec<&str> = rows.iter().map(|(action, _)| action.as_str()).collect();
+    assert_eq!(
+        actions,
+        [
+            // register_admin logs in again once promoted
+            "user.register",
+            "user.login",
+            "user.login",
+            "product.create",
+            "user.register",
+            "user.login",
+            "user.login_failed",
+            "order.create",
+        ]
+    );
+    assert_eq!(rows[3].1, Some(mug));
+    assert_eq!(rows[7].1, Some(order_id));
+
+    let (details,): (serde_json::Value,) =
+        sqlx::query_as("SELECT details FROM audit_log WHERE action = 'order.create'")
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(details, json!({ "total_amount": 2_500, "items": 1 }));
+}
+
+#[tokio::test]
+async fn entries_carry_the_request_they_came_from() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let mut config = app.config.clone();
+    config.trust_proxy = true;
+    let router = with_middleware(axum_ecommerce_api::app(&config, app.state.clone()), &config);
+    let body = json!({ "email": "user@example.com", "password": "password123" });
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/auth/register")
+        .header("x-request-id", "audit-test-request")
+        .header("x-forwarded-for", "198.51.100.9, 203.0.113.7")
+        .header(header::USER_AGENT, "audit-test/1.0")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = common::send(&router, request).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    // Events from outside a request, and rows from before tracking, have no context.
+    app.state
+        .audit
+        .record(AuditEvent::new(AuditAction::ProductUpdate, "test"));
+    app.state.audit.flush().await;
+
+    let row: (String, Option<String>, Option<String>, Option<String>) = sqlx::query_as(
+        "SELECT action, request_id, ip_address, user_agent FROM audit_log WHERE action = 'user.register'",
+    )
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        row,
+        (
+            "user.register".to_string(),
+            Some("audit-test-request".to_string()),
+            Some("203.0.113.7".to_string()),
+            Some("audit-test/1.0".to_string()),
+        )
+    );
+    let (untracked,): (i64,) = sqlx::query_as(
+        "SELECT count(*) FROM audit_log WHERE entity_type = 'test' AND request_id IS NULL AND ip_address IS NULL AND user_agent IS NULL",
+    )
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(untracked, 1);
+
+    let admin = app.register_admin("admin@example.com").await;
+    let response = app
+        .get(
+            "/api/v1/admin/audit-logs?request_id=audit-test-request",
+            Some(&admin),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    // The handler's entry and the middleware's, newest first
+    assert_eq!(response.body["meta"]["total"], 2);
+    let items = &response.body["data"]["items"];
+    assert_eq!(items[0]["action"], "http.mutation");
+    assert_eq!(items[1]["action"], "user.register");
+    assert_eq!(items[1]["user_agent"], "audit-test/1.0");
+}
+
+#[tokio::test]
+async fn mutating_requests_get_one_middleware_entry_each() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let admin_id: Uuid = sqlx::query_scalar("SELECT id FROM users WHERE email = $1")
+        .bind("admin@example.com")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    app.state.audit.flush().await;
+    let before = audit_count(&app.pool).await;
+
+    let product = format!("/api/v1/products/{}", mug);
+    let response = app
+        .request(
+            Method::PUT,
+            &product,
+            Some(&admin),
+            Some(json!({ "price": 1_500 })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    // Reads are not audited.
+    app.get(&product, Some(&admin)).await;
+    app.state.audit.flush().await;
+
+    let rows: Vec<(String, Option<Uuid>, serde_json::Value)> = sqlx::query_as(
+        "SELECT action, actor_id, details FROM audit_log ORDER BY created_at OFFSET $1",
+    )
+    .bind(before)
+    .fetch_all(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(rows.len(), 2, "{:?}", rows);
+    assert_eq!(rows[0].0, "product.update");
+    assert_eq!(rows[1].0, "http.mutation");
+    assert_eq!(rows[1].1, Some(admin_id));
+    assert_eq!(
+        rows[1].2,
+        json!({ "method": "PUT", "path": "/api/v1/products/{id}", "status": 200 })
+    );
+
+    // Nothing is sampled at a rate of 0, but handlers still audit.
+    let count_action = async |action: &str| -> i64 {
+        sqlx::query_scalar("SELECT count(*) FROM audit_log WHERE action = $1")
+            .bind(action)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap()
+    };
+    let mutations = count_action("http.mutation").await;
+    let mut config = app.config.clone();
+    config.audit_http_sample_rate = 0.0;
+    let router = axum_ecommerce_api::app(&config, app.state.clone());
+    let request = Request::builder()
+        .method(Method::DELETE)
+        .uri(&product)
+        .header(header::AUTHORIZATION, format!("Bearer {}", admin))
+        .body(Body::empty())
+        .unwrap();
+    let response = common::send(&router, request).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    app.state.audit.flush().await;
+    assert_eq!(count_action("http.mutation").await, mutations);
+    assert_eq!(count_action("product.delete").await, 1);
+}
+
+#[tokio::test]
+async fn events_queued_before_shutdown_are_written() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let audit = AuditLog::start(app.pool.clone());
+    // More than one batch, so both size- and shutdown-triggered writes happen.
+    for n in 0..1_200 {
+        audit
+            .record(AuditEvent::new(AuditAction::ProductUpdate, "test").details(json!({ "n": n })));
+    }
+    drop(audit);
+
+    for _ in 0..100 {
+        if audit_count(&app.pool).await == 1_200 {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("only {} events written", audit_count(&app.pool).await);
+}
+
+#[tokio::test]
+async fn a_failed_write_does_not_stop_the_writer() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let audit = AuditLog::start(app.pool.clone());
+    // With the table gone this batch cannot be written.
+    sqlx::query("ALTER TABLE audit_log RENAME TO audit_log_away")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    audit.record(AuditEvent::new(AuditAction::ProductUpdate, "test"));
+    audit.flush().await;
+    sqlx::query("ALTER TABLE audit_log_away RENAME TO audit_log")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(audit_count(&app.pool).await, 0);
+
+    audit.record(AuditEvent::new(AuditAction::ProductUpdate, "test"));
+    audit.flush().await;
+    assert_eq!(audit_count(&app.pool).await, 1);
+}
+
+#[tokio::test]
+async fn an_actor_deleted_while_queued_is_written_as_unset() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let audit = AuditLog::start(app.pool.clone());
+    // No such user any more; the rest of the batch must not be lost over it.
+    audit.record(AuditEvent::new(AuditAction::ProductUpdate, "test").actor(Uuid::new_v4()));
+    audit.record(AuditEvent::new(AuditAction::ProductCreate, "test"));
+    audit.flush().await;
+    let actors: Vec<Option<Uuid>> = sqlx::query_scalar("SELECT actor_id FROM audit_log")
+        .fetch_all(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(actors, [None, None]);
+}
+
+#[tokio::test]
+async fn admins_filter_the_audit_log() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let user = app.register("user@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let admin_id: Uuid = sqlx::query_scalar("SELECT id FROM users WHERE email = $1")
+        .bind("admin@example.com")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    let mut old = AuditEvent::new(AuditAction::ProductUpdate, "product").entity(mug);
+    old.created_at = "2020-01-15T12:00:00Z".parse().unwrap();
+    app.state.audit.record(old);
+    app.state.audit.flush().await;
+
+    let list = |query: &str| format!("/api/v1/admin/audit-logs{}", query);
+    let response = app.get(&list("?per_page=20"), Some(&admin)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    // 2 registrations, 3 logins (the admin again once promoted), the create and the old
+    // update, plus an `http.mutation` for each of the 6 requests
+    assert_eq!(response.body["meta"]["total"], 13);
+    let items = response.body["data"]["items"].as_array().unwrap();
+    assert_eq!(items[0]["action"], "http.mutation");
+    assert_eq!(items[1]["action"], "product.create");
+    assert_eq!(items[12]["action"], "product.update", "oldest last");
+
+    let response = app.get(&list("?resource=product"), Some(&admin)).await;
+    assert_eq!(response.body["meta"]["total"], 2);
+    let response = app.get(&list("?action=user.login"), Some(&admin)).await;
+    assert_eq!(response.body["meta"]["total"], 3);
+    let by_admin = format!("?user_id={}", admin_id);
+    let response = app.get(&list(&by_admin), Some(&admin)).await;
+    // Only the create request carried the admin's token
+    assert_eq!(response.body["meta"]["total"], 5);
+    let response = app
+        .get(
+            &list("?from=2020-01-01T00:00:00Z&to=2020-02-01T00:00:00Z"),
+            Some(&admin),
+        )
+        .await;
+    assert_eq!(response.body["meta"]["total"], 1);
+    let response = app
+        .get(
+            &list("?resource=product&from=2021-01-01T00:00:00Z"),
+            Some(&admin),
+        )
+        .await;
+    assert_eq!(response.body["meta"]["total"], 1);
+    let created = &response.body["data"]["items"][0];
+    assert_eq!(created["action"], "product.create");
+    let response = app.get(&list("?per_page=2&page=2"), Some(&admin)).await;
+    assert_eq!(response.body["data"]["items"].as_array().unwrap().len(), 2);
+    assert_eq!(response.body["meta"]["total_pages"], 7);
+
+    let response = app
+        .get(
+            &list("?from=2021-01-01T00:00:00Z&to=2020-01-01T00:00:00Z"),
+            Some(&admin),
+        )
+        .await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+    let response = app.get(&list("?action=user.logn"), Some(&admin)).await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+    let response = app.get(&list(""), Some(&user)).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN, "{}", response.body);
+
+    let detail = format!(
+        "/api/v1/admin/audit-logs/{}",
+        created["id"].as_str().unwrap()
+    );
+    let response = app.get(&detail, Some(&admin)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["entity_id"], mug.to_string());
+    assert_eq!(
+        response.body["data"]["details"],
+        json!({ "name": "Ceramic Mug", "price": 1_250, "stock": 10 })
+    );
+    let missing = format!("/api/v1/admin/audit-logs/{}", Uuid::new_v4());
+    let response = app.get(&missing, Some(&admin)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND, "{}", response.body);
+}
+
+#[tokio::test]
+async fn every_action_round_trips_through_the_database() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    for action in AuditAction::ALL {
+        app.state.audit.record(AuditEvent::new(action, "test"));
+    }
+    app.state.audit.flush().await;
+
+    let stored: Vec<(String,)> = sqlx::query_as("SELECT action FROM audit_log")
+        .fetch_all(&app.pool)
+        .await
+        .unwrap();
+    let mut stored: Vec<AuditAction> = stored
+        .iter()
+        .map(|(action,)| action.parse().unwrap())
+        .collect();
+    stored.sort_by_key(|a| AuditAction::ALL.iter().position(|b| a == b));
+    assert_eq!(stored, AuditAction::ALL);
+    for action in AuditAction::ALL {
+        // The stored, displayed and serialized forms agree.
+        assert_eq!(action.to_string(), action.as_str());
+        assert_eq!(json!(action), json!(action.as_str()));
+        let parsed: AuditAction = serde_json::from_value(json!(action.as_str())).unwrap();
+        assert_eq!(parsed, action);
+    }
+    assert!("test.event".parse::<AuditAction>().is_err());
+}
+
+/// Inserts `count` entries created `days_ago` days ago.
+async fn seed_entries(pool: &DbPool, count: i32, days_ago: i32) {
+    sqlx::query(
+        r#"
+        INSERT INTO audit_log (id, action, entity_type, created_at)
+        SELECT gen_random_uuid(), 'test.event', 'test', NOW() - make_interval(days => $2)
+        FROM generate_series(1, $1)
+        "#,
+    )
+    .bind(count)
+    .bind(days_ago)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn pruning_deletes_old_entries_batch_by_batch() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    seed_entries(&app.pool, 10, 40).await;
+    seed_entries(&app.pool, 5, 1).await;
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(30);
+
+    // 10 rows in batches of 3 takes four deletes, the last one short.
+    let deleted = prune_older_than(&app.pool, cutoff, 3).await.unwrap();
+    assert_eq!(deleted, 10);
+    assert_eq!(audit_count(&app.pool).await, 5);
+    // A multiple of the batch size needs one extra, empty delete to stop.
+    seed_entries(&app.pool, 6, 40).await;
+    assert_eq!(prune_older_than(&app.pool, cutoff, 3).await.unwrap(), 6);
+    assert_eq!(prune_older_than(&app.pool, cutoff, 3).await.unwrap(), 0);
+    assert_eq!(audit_count(&app.pool).await, 5);
+
+    seed_entries(&app.pool, 4, 400).await;
+    let job = PruneAuditLog {
+        retention: chrono::Duration::days(365),
+    };
+    job.run(&app.state).await.unwrap();
+    assert_eq!(audit_count(&app.pool).await, 5);
+}
+
+#[tokio::test]
+async fn admins_prune_by_date_and_the_pruning_is_audited() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let user = app.register("user@example.com").await;
+    app.state.audit.flush().await;
+    let recent = audit_count(&app.pool).await;
+    seed_entries(&app.pool, 7, 100).await;
+
+    let prune = "/api/v1/admin/audit-logs?before=2000-01-01T00:00:00Z";
+    let response = app.request(Method::DELETE, prune, Some(&admin), None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["deleted"], 0);
+
+    let before = (chrono::Utc::now() - chrono::Duration::days(30))
+        .to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let prune = format!("/api/v1/admin/audit-logs?before={}", before);
+    let response = app
+        .request(Method::DELETE, &prune, Some(&admin), None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["deleted"], 7);
+
+    app.state.audit.flush().await;
+    // Each prune, and each DELETE request by the middleware
+    assert_eq!(audit_count(&app.pool).await, recent + 4);
+    let (details,): (serde_json::Value,) = sqlx::query_as(
+        "SELECT details FROM audit_log WHERE action = 'audit_log.prune' ORDER BY created_at DESC LIMIT 1",
+    )
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(details["deleted"], 7);
+    assert_eq!(details["before"], before);
+
+    let response = app.request(Method::DELETE, &prune, Some(&user), None).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN, "{}", response.body);
+    let response = app
+        .request(
+            Method::DELETE,
+            "/api/v1/admin/audit-logs",
+            Some(&admin),
+            None,
+        )
+        .await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+}
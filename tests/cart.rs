@@ -0,0 +1,493 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode, header},
+};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use axum_ecommerce_api::seed;
+use common::{TestApp, TestResponse};
+
+const CART: &str = "/api/cart";
+
+/// Sets the quantity of `product_id` in the cart of `token`.
+async fn add(app: &TestApp, token: &str, product_id: Uuid, quantity: i32) -> Value {
+    let body = json!({ "product_id": product_id, "quantity": quantity });
+    let response = app.post(CART, Some(token), body).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    response.body["data"].clone()
+}
+
+#[tokio::test]
+async fn cart_lists_line_totals_and_a_summary() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let teapot = app.create_product(&admin, "Teapot", 4_000, 10).await;
+
+    let response = app.get(CART, Some(&buyer)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["items"], json!([]));
+    assert_eq!(response.body["data"]["summary"]["subtotal"], 0);
+
+    add(&app, &buyer, mug, 3).await;
+    add(&app, &buyer, teapot, 1).await;
+    let response = app.get(CART, Some(&buyer)).await;
+    let cart = &response.body["data"];
+    let items = cart["items"].as_array().unwrap();
+    assert_eq!(items.len(), 2, "{}", cart);
+    for item in items {
+        let price = item["price"].as_i64().unwrap();
+        let quantity = item["quantity"].as_i64().unwrap();
+        assert_eq!(item["line_total"].as_i64().unwrap(), price * quantity);
+    }
+    assert_eq!(items[0]["product_name"], "Ceramic Mug");
+    assert_eq!(items[0]["line_total"], 3_750);
+    let summary = &cart["summary"];
+    assert_eq!(summary["item_count"], 2);
+    assert_eq!(summary["total_quantity"], 4);
+    assert_eq!(summary["subtotal"], 7_750);
+
+    // A quantity is replaced, not added to.
+    add(&app, &buyer, mug, 1).await;
+    let response = app.get(CART, Some(&buyer)).await;
+    assert_eq!(response.body["data"]["summary"]["subtotal"], 5_250);
+    assert_eq!(response.body["data"]["summary"]["total_quantity"], 2);
+}
+
+/// Quantities in the active cart, by product.
+async fn quantities(app: &TestApp, token: &str) -> Vec<(Uuid, i64)> {
+    let response = app.get(CART, Some(token)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let mut lines: Vec<(Uuid, i64)> = response.body["data"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| {
+            (
+                item["product_id"].as_str().unwrap().parse().unwrap(),
+                item["quantity"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    lines.sort();
+    lines
+}
+
+#[tokio::test]
+async fn bulk_add_sets_new_and_existing_lines_or_none_at_all() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let teapot = app.create_product(&admin, "Teapot", 4_000, 10).await;
+    let kettle = app.create_product(&admin, "Kettle", 9_000, 10).await;
+    add(&app, &buyer, mug, 1).await;
+
+    let bulk = json!([
+        { "product_id": mug, "quantity": 4 },
+        { "product_id": teapot, "quantity": 2 },
+    ]);
+    let response = app.post("/api/cart/bulk", Some(&buyer), bulk).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["items"].as_array().unwrap().len(), 2);
+    assert_eq!(response.body["data"]["summary"]["subtotal"], 13_000);
+    let mut expected = vec![(mug, 4), (teapot, 2)];
+    expected.sort();
+    assert_eq!(quantities(&app, &buyer).await, expected);
+
+    // One unknown product refuses the whole batch and names it.
+    let unknown = Uuid::new_v4();
+    let bulk = json!([
+        { "product_id": kettle, "quantity": 1 },
+        { "product_id": unknown, "quantity": 1 },
+    ]);
+    let response = app.post("/api/cart/bulk", Some(&buyer), bulk).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert!(
+        response.body["message"]
+            .as_str()
+            .unwrap()
+            .contains(&unknown.to_string()),
+        "{}",
+        response.body
+    );
+    assert_eq!(quantities(&app, &buyer).await, expected);
+
+    // So does a bad quantity, before anything is looked up.
+    let bulk = json!([
+        { "product_id": kettle, "quantity": 1 },
+        { "product_id": mug, "quantity": 0 },
+    ]);
+    let response = app.post("/api/cart/bulk", Some(&buyer), bulk).await;
+    assert_eq!(
+        response.status,
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "{}",
+        response.body
+    );
+    assert_eq!(response.body["data"]["errors"][0]["field"], "[1].quantity");
+    assert_eq!(quantities(&app, &buyer).await, expected);
+
+    // Empty, duplicated and oversized batches are refused.
+    let too_many: Vec<Value> = (0..101)
+        .map(|_| json!({ "product_id": Uuid::new_v4(), "quantity": 1 }))
+        .collect();
+    for bulk in [
+        json!([]),
+        json!([
+            { "product_id": kettle, "quantity": 1 },
+            { "product_id": kettle, "quantity": 2 },
+        ]),
+        Value::Array(too_many),
+    ] {
+        let response = app.post("/api/cart/bulk", Some(&buyer), bulk).await;
+        assert_eq!(
+            response.status,
+            StatusCode::BAD_REQUEST,
+            "{}",
+            response.body
+        );
+    }
+    assert_eq!(quantities(&app, &buyer).await, expected);
+}
+
+#[tokio::test]
+async fn repriced_lines_are_flagged_and_can_refuse_checkout() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let teapot = app.create_product(&admin, "Teapot", 4_000, 10).await;
+    add(&app, &buyer, mug, 2).await;
+    add(&app, &buyer, teapot, 1).await;
+
+    let response = app
+        .request(
+            Method::PUT,
+            &format!("/api/products/{}", mug),
+            Some(&admin),
+            Some(json!({ "price": 1_500 })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app.get(CART, Some(&buyer)).await;
+    let items = response.body["data"]["items"].as_array().unwrap();
+    assert_eq!(items[0]["product_id"], mug.to_string());
+    assert_eq!(items[0]["price_changed"], true);
+    assert_eq!(items[0]["price_at_add"], 1_250);
+    assert_eq!(items[0]["price"], 1_500);
+    assert_eq!(items[0]["line_total"], 3_000);
+    assert_eq!(items[1]["price_changed"], false);
+
+    // Setting the quantity again keeps the price the line was added at.
+    add(&app, &buyer, mug, 3).await;
+    let response = app.get(CART, Some(&buyer)).await;
+    assert_eq!(response.body["data"]["items"][0]["price_at_add"], 1_250);
+
+    let checkout = "/api/orders/checkout";
+    let refuse = json!({ "accept_price_changes": false });
+    let response = app.post(checkout, Some(&buyer), refuse).await;
+    assert_eq!(response.status, StatusCode::CONFLICT, "{}", response.body);
+    assert_eq!(response.body["data"]["error_code"], "PRICE_CHANGED");
+    let message = response.body["message"].as_str().unwrap();
+    assert!(message.contains(&mug.to_string()), "{}", message);
+    assert!(!message.contains(&teapot.to_string()), "{}", message);
+
+    // Without the flag the new price is charged.
+    let response = app
+        .request(Method::POST, checkout, Some(&buyer), None)
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    assert_eq!(response.body["data"]["order"]["total_amount"], 8_500);
+}
+
+#[tokio::test]
+async fn lines_are_removed_by_item_id_only_by_their_owner() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let other = app.register("other@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let teapot = app.create_product(&admin, "Teapot", 4_000, 10).await;
+    let line = add(&app, &buyer, mug, 2).await;
+    let line_id = line["id"].as_str().unwrap().to_string();
+    add(&app, &buyer, teapot, 1).await;
+    add(&app, &other, mug, 1).await;
+
+    // Someone else guessing the id gets a 404 and the line stays.
+    let by_id = format!("{}/items/{}", CART, line_id);
+    let response = app
+        .request(Method::DELETE, &by_id, Some(&other), None)
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND, "{}", response.body);
+    assert_eq!(
+        quantities(&app, &buyer).await.len(),
+        2,
+        "the line was deleted"
+    );
+
+    let response = app
+        .request(Method::DELETE, &by_id, Some(&buyer), None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(quantities(&app, &buyer).await, [(teapot, 1)]);
+    let response = app
+        .request(Method::DELETE, &by_id, Some(&buyer), None)
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    // Removing by product still works, and only touches the caller's cart.
+    let by_product = format!("{}/{}", CART, teapot);
+    let response = app
+        .request(Method::DELETE, &by_product, Some(&buyer), None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert!(quantities(&app, &buyer).await.is_empty());
+    let response = app
+        .request(Method::DELETE, &by_product, Some(&other), None)
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(quantities(&app, &other).await, [(mug, 1)]);
+}
+
+/// Sends a request for the guest cart of `cart_token`, with a JSON body if given.
+async fn as_guest(
+    app: &TestApp,
+    method: Method,
+    uri: &str,
+    cart_token: &str,
+    body: Option<Value>,
+) -> TestResponse {
+    let builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("X-Cart-Token", cart_token);
+    let request = match body {
+        Some(body) => builder
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string())),
+        None => builder.body(Body::empty()),
+    }
+    .unwrap();
+    app.send(request).await
+}
+
+#[tokio::test]
+async fn a_guest_cart_is_merged_into_the_users_cart_on_login() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let teapot = app.create_product(&admin, "Teapot", 4_000, 10).await;
+    add(&app, &buyer, mug, 2).await;
+
+    // Cart routes need a token of one kind or the other.
+    let response = app.get(CART, None).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    let unknown = Uuid::new_v4().to_string();
+    let response = as_guest(&app, Method::GET, CART, &unknown, None).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app.post("/api/cart/session", None, json!({})).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let guest = response.body["data"]["token"].as_str().unwrap().to_string();
+    for (product_id, quantity) in [(mug, 1), (teapot, 3)] {
+        let add = json!({ "product_id": product_id, "quantity": quantity });
+        let response = as_guest(&app, Method::POST, CART, &guest, Some(add)).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    }
+    let response = as_guest(&app, Method::GET, CART, &guest, None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["summary"]["total_quantity"], 4);
+    // The user's cart and the guest's are apart until then.
+    let mut expected = vec![(mug, 2)];
+    assert_eq!(quantities(&app, &buyer).await, expected);
+
+    let login = json!({ "email": "buyer@example.com", "password": common::PASSWORD });
+    let response = as_guest(&app, Method::POST, "/api/auth/login", &guest, Some(login)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let token = response.body["data"]["token"].as_str().unwrap();
+
+    expected = vec![(mug, 3), (teapot, 3)];
+    expected.sort();
+    assert_eq!(quantities(&app, token).await, expected);
+    // The guest cart is gone with its token.
+    let response = as_guest(&app, Method::GET, CART, &guest, None).await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+
+    // A guest cart past its 30 days is refused too, and not merged at login.
+    let response = app.post("/api/cart/session", None, json!({})).await;
+    let stale = response.body["data"]["token"].as_str().unwrap().to_string();
+    let add = json!({ "product_id": teapot, "quantity": 5 });
+    let response = as_guest(&app, Method::POST, CART, &stale, Some(add)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    sqlx::query("UPDATE cart_sessions SET created_at = NOW() - interval '31 days'")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let response = as_guest(&app, Method::GET, CART, &stale, None).await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+    let login = json!({ "email": "buyer@example.com", "password": common::PASSWORD });
+    let response = as_guest(&app, Method::POST, "/api/auth/login", &stale, Some(login)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(quantities(&app, token).await, expected);
+}
+
+#[tokio::test]
+async fn saved_lines_stay_out_of_the_cart_and_the_checkout() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let other = app.register("other@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    // More than is in stock, which a saved line must not trip over.
+    let teapot = app.create_product(&admin, "Teapot", 4_000, 1).await;
+    add(&app, &buyer, mug, 2).await;
+    let line = add(&app, &buyer, teapot, 5).await;
+    let save = format!("{}/{}/save", CART, line["id"].as_str().unwrap());
+    let unsave = format!("{}/{}/unsave", CART, line["id"].as_str().unwrap());
+
+    let response = app.request(Method::POST, &save, Some(&other), None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    let response = app.request(Method::POST, &save, Some(&buyer), None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["saved"], true);
+
+    assert_eq!(quantities(&app, &buyer).await, [(mug, 2)]);
+    let response = app.get(&format!("{}/saved", CART), Some(&buyer)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let saved = response.body["data"]["items"].as_array().unwrap();
+    assert_eq!(saved.len(), 1);
+    assert_eq!(saved[0]["product_id"], teapot.to_string());
+    assert_eq!(response.body["data"]["summary"]["subtotal"], 20_000);
+
+    // Checkout orders the active lines only, and leaves the saved one.
+    let checkout = "/api/orders/checkout";
+    let response = app
+        .request(Method::POST, checkout, Some(&buyer), None)
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    assert_eq!(response.body["data"]["order"]["total_amount"], 2_500);
+    assert_eq!(response.body["data"]["items"].as_array().unwrap().len(), 1);
+    let response = app.get(&format!("{}/saved", CART), Some(&buyer)).await;
+    assert_eq!(response.body["data"]["items"].as_array().unwrap().len(), 1);
+
+    // Unsaved, it is back in the cart and in the next order.
+    let response = app.request(Method::POST, &unsave, Some(&buyer), None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    add(&app, &buyer, teapot, 1).await;
+    assert_eq!(quantities(&app, &buyer).await, [(teapot, 1)]);
+    let response = app
+        .request(Method::POST, checkout, Some(&buyer), None)
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    assert_eq!(response.body["data"]["order"]["total_amount"], 4_000);
+    let response = app.get(&format!("{}/saved", CART), Some(&buyer)).await;
+    assert_eq!(response.body["data"]["items"], json!([]));
+}
+
+#[tokio::test]
+async fn seeded_cart_lines_match_their_rows_and_products() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let volumes = seed::Volumes {
+        products: 30,
+        // About half get a cart, so enough that some surely do
+        users: 20,
+        orders: 0,
+    };
+    seed::seed(&app.pool, volumes).await.unwrap();
+    // Some lines repriced since they were added.
+    sqlx::query(
+        "UPDATE products SET price = price + 100 WHERE sku LIKE 'SEED-%' AND stock % 2 = 0",
+    )
+    .execute(&app.pool)
+    .await
+    .unwrap();
+    let (user_id, email): (Uuid, String) = sqlx::query_as(
+        r#"
+        SELECT u.id, u.email FROM users u
+        WHERE EXISTS (SELECT 1 FROM cart_items ci WHERE ci.user_id = u.id AND NOT ci.saved)
+        LIMIT 1
+        "#,
+    )
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    let login = json!({ "email": email, "password": seed::SEED_PASSWORD });
+    let response = app.post("/api/v1/auth/login", None, login).await;
+    let token = response.body["data"]["token"]
+        .as_str()
+        .unwrap()
+        .trim_start_matches("Bearer ")
+        .to_string();
+
+    // Built from each table on its own, without the join the endpoint uses.
+    let rows: Vec<(Uuid, Uuid, i32, i64)> = sqlx::query_as(
+        "SELECT id, product_id, quantity, price_at_add FROM cart_items WHERE user_id = $1 AND NOT saved ORDER BY created_at",
+    )
+    .bind(user_id)
+    .fetch_all(&app.pool)
+    .await
+    .unwrap();
+    let mut expected = Vec::new();
+    for (id, product_id, quantity, price_at_add) in rows {
+        let (name, price): (String, i64) =
+            sqlx::query_as("SELECT name, price FROM products WHERE id = $1")
+                .bind(product_id)
+                .fetch_one(&app.pool)
+                .await
+                .unwrap();
+        expected.push(json!({
+            "id": id,
+            "product_id": product_id,
+            "product_name": name,
+            "quantity": quantity,
+            "price": price,
+            "price_at_add": price_at_add,
+            "price_changed": price != price_at_add,
+            "line_total": price * i64::from(quantity),
+        }));
+    }
+
+    let response = app.get("/api/v1/cart", Some(&token)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let mut served = response.body["data"]["items"].as_array().unwrap().clone();
+    for line in served.iter_mut() {
+        line.as_object_mut().unwrap().remove("created_at");
+    }
+    // Seeded lines share a creation time, so their order is not fixed.
+    let by_id = |line: &Value| line["id"].as_str().unwrap().to_string();
+    served.sort_by_key(by_id);
+    expected.sort_by_key(by_id);
+    assert!(!expected.is_empty());
+    assert_eq!(served, expected);
+}
@@ -0,0 +1,266 @@
+//! Shared setup for the integration tests.
+//!
+//! Every [`TestApp`] creates its own database next to the one `DATABASE_URL` points at (or
+//! `TEST_DATABASE_URL`, when set), applies the migrations to it and drops it again when the
+//! `TestApp` goes out of scope, so test binaries and the tests inside them can run in parallel.
+//!
+//! Without a database URL the tests fail; set `SKIP_DB_TESTS=1` to skip them instead.
+//! `JWT_SECRET` must be set as well; both are also read from `.env`.
+
+#![allow(dead_code)]
+
+use std::{env, path::PathBuf, str::FromStr};
+
+use chrono::{DateTime, Utc};
+
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{HeaderMap, Method, Request, StatusCode, header},
+};
+use axum_ecommerce_api::{
+    app,
+    config::AppConfig,
+    db::{DbPool, MIGRATOR},
+    state::AppState,
+};
+use serde_json::{Value, json};
+use sqlx::{
+    Connection, Executor, PgConnection,
+    postgres::{PgConnectOptions, PgPoolOptions},
+};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+pub const PASSWORD: &str = "correct horse battery";
+
+pub struct TestApp {
+    pub router: Router,
+    pub state: AppState,
+    pub pool: DbPool,
+    pub config: AppConfig,
+    /// Name of the database this test owns
+    pub database: String,
+    admin: PgConnectOptions,
+    upload_dir: PathBuf,
+}
+
+/// Response of [`TestApp::request`], with the body parsed as JSON (`Null` when empty).
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Value,
+}
+
+/// `None` (after saying so) when `SKIP_DB_TESTS` is set; otherwise the server URL to test
+/// against, failing loudly when there is none.
+fn database_url() -> Option<String> {
+    dotenvy::dotenv().ok();
+    if env::var("SKIP_DB_TESTS").is_ok_and(|v| v == "1" || v == "true") {
+        eprintln!("SKIP_DB_TESTS is set; skipping database test");
+        return None;
+    }
+    let url = env::var("TEST_DATABASE_URL")
+        .or_else(|_| env::var("DATABASE_URL"))
+        .expect("integration tests need TEST_DATABASE_URL or DATABASE_URL (or SKIP_DB_TESTS=1)");
+    assert!(
+        env::var("JWT_SECRET").is_ok(),
+        "integration tests need JWT_SECRET"
+    );
+    Some(url)
+}
+
+impl TestApp {
+    /// A fully migrated app on a fresh database; `None` when database tests are skipped.
+    pub async fn spawn() -> Option<Self> {
+        let url = database_url()?;
+        let admin = PgConnectOptions::from_str(&url).expect("database URL is not valid");
+        let database = format!("test_{}", Uuid::new_v4().simple());
+
+        let mut conn = PgConnection::connect_with(&admin)
+            .await
+            .expect("cannot connect to the test database server");
+        conn.execute(format!(r#"CREATE DATABASE "{}""#, database).as_str())
+            .await
+            .expect("cannot create the test database");
+        conn.close().await.ok();
+
+        let mut config = AppConfig::from_env().expect("invalid test configuration");
+        config.upload_dir = env::temp_dir().join(&database).display().to_string();
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect_with(admin.clone().database(&database))
+            .await
+            .expect("cannot connect to the test database");
+        MIGRATOR
+            .run(&pool)
+            .await
+            .expect("migrations failed on the test database");
+
+        let state = AppState::new(pool.clone(), &config);
+
+        Some(Self {
+            router: app(&config, state.clone()),
+            state,
+            upload_dir: PathBuf::from(&config.upload_dir),
+            pool,
+            config,
+            database,
+            admin,
+        })
+    }
+
+    /// Options for the database the URL names, which test databases are created from.
+    pub fn server_options(&self) -> PgConnectOptions {
+        self.admin.clone()
+    }
+
+    /// Options for connecting to this test's database outside the pool.
+    pub fn connect_options(&self) -> PgConnectOptions {
+        self.admin.clone().database(&self.database)
+    }
+
+    /// Sends one request through the full router, with a JSON body and bearer token if given.
+    pub async fn request(
+        &self,
+        method: Method,
+        uri: &str,
+        token: Option<&str>,
+        body: Option<Value>,
+    ) -> TestResponse {
+        let mut builder = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let request = match body {
+            Some(body) => builder
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => builder.body(Body::empty()),
+        }
+        .unwrap();
+        self.send(request).await
+    }
+
+    /// Sends a request built by the caller, for bodies other than JSON.
+    pub async fn send(&self, request: Request<Body>) -> TestResponse {
+        send(&self.router, request).await
+    }
+
+    pub async fn get(&self, uri: &str, token: Option<&str>) -> TestResponse {
+        self.request(Method::GET, uri, token, None).await
+    }
+
+    pub async fn post(&self, uri: &str, token: Option<&str>, body: Value) -> TestResponse {
+        self.request(Method::POST, uri, token, Some(body)).await
+    }
+
+    /// Registers `email` with [`PASSWORD`] and returns a token for it.
+    pub async fn register(&self, email: &str) -> String {
+        let body = json!({ "email": email, "password": PASSWORD });
+        let response = self.post("/api/auth/register", None, body.clone()).await;
+        assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+        self.login(email).await
+    }
+
+    pub async fn login(&self, email: &str) -> String {
+        let body = json!({ "email": email, "password": PASSWORD });
+        let response = self.post("/api/auth/login", None, body).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+        response.body["data"]["token"]
+            .as_str()
+            .expect("login response has no token")
+            .to_string()
+    }
+
+    /// Registers `email` as an admin and returns a token carrying the admin role.
+    pub async fn register_admin(&self, email: &str) -> String {
+        self.register(email).await;
+        sqlx::query("UPDATE users SET role = 'admin' WHERE email = $1")
+            .bind(email)
+            .execute(&self.pool)
+            .await
+            .unwrap();
+        self.login(email).await
+    }
+
+    /// Moves the `created_at` of row `id` in `table` back to `created_at`, for tests about
+    /// time windows. The update trigger keeps it as it was, so triggers are off for the write.
+    pub async fn backdate(&self, table: &str, id: Uuid, created_at: DateTime<Utc>) {
+        let mut tx = self.pool.begin().await.unwrap();
+        tx.execute("SET LOCAL session_replication_role = replica")
+            .await
+            .unwrap();
+        sqlx::query(&format!(
+            "UPDATE {} SET created_at = $2 WHERE id = $1",
+            table
+        ))
+        .bind(id)
+        .bind(created_at)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+        tx.commit().await.unwrap();
+    }
+
+    /// Creates a product and returns its id.
+    pub async fn create_product(&self, token: &str, name: &str, price: i64, stock: i32) -> Uuid {
+        let body = json!({
+            "name": name,
+            "description": format!("{} for tests", name),
+            "price": price,
+            "stock": stock,
+        });
+        let response = self.post("/api/products", Some(token), body).await;
+        assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+        response.body["data"]["id"]
+            .as_str()
+            .and_then(|id| id.parse().ok())
+            .expect("product response has no id")
+    }
+}
+
+/// Sends one request through `router`, e.g. an app built from a modified [`TestApp::config`].
+pub async fn send(router: &Router, request: Request<Body>) -> TestResponse {
+    let response = router.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = if bytes.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned()))
+    };
+    TestResponse {
+        status,
+        headers,
+        body,
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        let admin = self.admin.clone();
+        let database = self.database.clone();
+        let upload_dir = self.upload_dir.clone();
+        // Drop runs outside any async context, so clean up on a runtime of our own.
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            runtime.block_on(async {
+                if let Ok(mut conn) = PgConnection::connect_with(&admin).await {
+                    let drop = format!(r#"DROP DATABASE IF EXISTS "{}" WITH (FORCE)"#, database);
+                    if let Err(e) = conn.execute(drop.as_str()).await {
+                        eprintln!("cannot drop test database {}: {}", database, e);
+                    }
+                }
+            });
+            std::fs::remove_dir_all(upload_dir).ok();
+        })
+        .join()
+        .ok();
+    }
+}
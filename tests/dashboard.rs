@@ -0,0 +1,103 @@
+mod common;
+
+use axum::http::StatusCode;
+use chrono::{Duration, Utc};
+use serde_json::json;
+use uuid::Uuid;
+
+use common::TestApp;
+
+const DASHBOARD: &str = "/api/v1/admin/dashboard";
+
+/// Checks out `quantity` of `product_id` for `token`, moves the order to `status` and
+/// backdates it by `days_ago`. Returns the order id.
+async fn order(
+    app: &TestApp,
+    token: &str,
+    product_id: Uuid,
+    quantity: i32,
+    status: &str,
+    days_ago: i32,
+) -> Uuid {
+    let add = json!({ "product_id": product_id, "quantity": quantity });
+    app.post("/api/v1/cart", Some(token), add).await;
+    let response = app
+        .post("/api/v1/orders/checkout", Some(token), json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let id = response.body["data"]["order"]["id"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    sqlx::query("UPDATE orders SET status = $2 WHERE id = $1")
+        .bind(id)
+        .bind(status)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    app.backdate("orders", id, Utc::now() - Duration::days(days_ago.into()))
+        .await;
+    id
+}
+
+#[tokio::test]
+async fn dashboard_sums_up_orders_users_and_inventory() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_000, 100).await;
+    let gadget = app.create_product(&admin, "Gadget", 500, 6).await;
+    app.create_product(&admin, "Rare Vase", 9_000, 3).await;
+
+    // Sixth newest, so left out of `recent_orders`.
+    order(&app, &buyer, mug, 1, "cancelled", 50).await;
+    let old = order(&app, &buyer, mug, 3, "paid", 40).await;
+    order(&app, &buyer, mug, 2, "paid", 0).await;
+    order(&app, &buyer, mug, 1, "completed", 0).await;
+    // Leaves the gadget with 4 of 6 available, under the threshold of 5.
+    order(&app, &buyer, gadget, 2, "pending", 0).await;
+    let newest = order(&app, &buyer, mug, 1, "cancelled", 0).await;
+
+    let response = app.get(DASHBOARD, Some(&admin)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let data = &response.body["data"];
+    for period in ["today", "week", "month"] {
+        assert_eq!(data[period]["orders"], 3, "{}", period);
+        assert_eq!(data[period]["revenue"], 3_000, "{}", period);
+        assert!(data[period]["revenue_display"].is_string());
+    }
+    assert_eq!(data["orders_by_status"], json!({ "pending": 1, "paid": 3 }));
+    assert_eq!(data["users"], 2);
+    assert_eq!(data["products"], 3);
+    assert_eq!(data["low_stock_products"], 2);
+    assert_eq!(data["low_stock_threshold"], 5);
+
+    let recent = data["recent_orders"].as_array().unwrap();
+    assert_eq!(recent.len(), 5);
+    assert_eq!(recent[0]["id"], newest.to_string());
+    assert_eq!(recent[4]["id"], old.to_string());
+
+    let forbidden = app.get(DASHBOARD, Some(&buyer)).await;
+    assert_eq!(forbidden.status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn an_empty_shop_has_a_zeroed_dashboard() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+
+    let response = app.get(DASHBOARD, Some(&admin)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let data = &response.body["data"];
+    assert_eq!(data["today"]["orders"], 0);
+    assert_eq!(data["month"]["revenue"], 0);
+    assert_eq!(data["orders_by_status"], json!({ "pending": 0, "paid": 0 }));
+    assert_eq!(data["users"], 1);
+    assert_eq!(data["products"], 0);
+    assert_eq!(data["recent_orders"], json!([]));
+}
@@ -0,0 +1,184 @@
+//! How failures are turned into responses: status, code and what the client gets to see.
+
+mod common;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::{
+    body::{Body, Bytes, to_bytes},
+    http::{Method, Request, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use axum_ecommerce_api::{app as build_app, error::AppError, storage::Storage};
+use common::{TestApp, TestResponse};
+
+/// Storage that fails every write.
+struct BrokenStorage;
+
+#[async_trait]
+impl Storage for BrokenStorage {
+    async fn put(&self, _key: &str, _bytes: Bytes) -> anyhow::Result<String> {
+        anyhow::bail!("disk full at /srv/secret/uploads")
+    }
+
+    async fn delete(&self, _key: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Uploads an image to a fresh product with `storage` swapped in.
+async fn upload_with(mut app: TestApp, storage: BrokenStorage) -> TestResponse {
+    let admin = app.register_admin("admin@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    app.state.storage = Arc::new(storage);
+    app.router = build_app(&app.config, app.state.clone());
+
+    let body = "--b\r\nContent-Disposition: form-data; name=\"file\"; filename=\"mug.png\"\r\n\
+                Content-Type: image/png\r\n\r\npng\r\n--b--\r\n";
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/api/products/{}/images", mug))
+        .header(header::AUTHORIZATION, format!("Bearer {}", admin))
+        .header(header::CONTENT_TYPE, "multipart/form-data; boundary=b")
+        .body(Body::from(body))
+        .unwrap();
+    app.send(request).await
+}
+
+/// A 500 in the usual envelope that names the request but nothing of the cause.
+fn assert_opaque_500(response: &TestResponse) {
+    assert_eq!(
+        response.status,
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "{}",
+        response.body
+    );
+    assert_eq!(response.body["message"], "Internal Server Error");
+    assert_eq!(response.body["data"]["error_code"], "INTERNAL_ERROR");
+    let request_id = response.headers["x-request-id"].to_str().unwrap();
+    assert!(!request_id.is_empty());
+    assert_eq!(response.body["meta"]["request_id"], request_id);
+    let text = response.body.to_string();
+    assert!(!text.contains("/srv/secret"), "{}", text);
+}
+
+async fn into_parts(response: Response) -> (StatusCode, Value) {
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap())
+}
+
+#[tokio::test]
+async fn database_errors_map_to_client_statuses_without_details() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let category = "INSERT INTO categories (id, name, slug) VALUES ($1, 'Kitchen', 'kitchen')";
+    sqlx::query(category)
+        .bind(Uuid::new_v4())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let duplicate = sqlx::query(category)
+        .bind(Uuid::new_v4())
+        .execute(&app.pool)
+        .await
+        .unwrap_err();
+    let ghost = Uuid::new_v4();
+    let dangling =
+        sqlx::query("INSERT INTO favorites (id, user_id, product_id) VALUES ($1, $1, $1)")
+            .bind(ghost)
+            .execute(&app.pool)
+            .await
+            .unwrap_err();
+    let negative = sqlx::query(
+        "INSERT INTO products (id, name, slug, price, stock) VALUES ($1, 'Mug', 'mug', -1, 0)",
+    )
+    .bind(Uuid::new_v4())
+    .execute(&app.pool)
+    .await
+    .unwrap_err();
+    let malformed = sqlx::query("SELECT 'not-a-uuid'::uuid")
+        .execute(&app.pool)
+        .await
+        .unwrap_err();
+
+    for (error, status, code, message) in [
+        (
+            duplicate,
+            StatusCode::CONFLICT,
+            "CONFLICT",
+            "Conflict duplicate value",
+        ),
+        (
+            dangling,
+            StatusCode::CONFLICT,
+            "CONFLICT",
+            "Conflict resource is still referenced",
+        ),
+        (
+            negative,
+            StatusCode::BAD_REQUEST,
+            "CONSTRAINT_VIOLATION",
+            "Bad Request value out of range",
+        ),
+        (
+            malformed,
+            StatusCode::BAD_REQUEST,
+            "BAD_REQUEST",
+            "Bad Request invalid input syntax",
+        ),
+    ] {
+        // Postgres' own wording, the offending values and the constraint stay in the logs.
+        let (got, body) = into_parts(AppError::from(error).into_response()).await;
+        assert_eq!(got, status, "{}", body);
+        assert_eq!(body["data"]["error_code"], code);
+        assert_eq!(body["message"], message);
+    }
+
+    // Anything else is a plain 500.
+    let missing = sqlx::query("SELECT * FROM no_such_table")
+        .execute(&app.pool)
+        .await
+        .unwrap_err();
+    let (status, body) = into_parts(AppError::from(missing).into_response()).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body["message"], "Internal Server Error");
+    assert_eq!(body["data"]["error_code"], "INTERNAL_ERROR");
+}
+
+#[tokio::test]
+async fn racing_duplicate_slugs_get_a_409_not_a_500() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let create = async |name: &str| {
+        let body = json!({ "name": name, "slug": "kitchen" });
+        app.post("/api/admin/categories", Some(&admin), body)
+            .await
+            .status
+    };
+
+    // The first past the availability check wins; the other trips the unique index.
+    let (first, second) = tokio::join!(create("Kitchen"), create("Kitchenware"));
+    let mut statuses = [first, second];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::OK, StatusCode::CONFLICT]);
+    assert_eq!(create("Kitchen again").await, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn internal_errors_carry_the_request_id_but_not_the_cause() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let response = upload_with(app, BrokenStorage).await;
+    assert_opaque_500(&response);
+    assert!(!response.body.to_string().contains("disk full"));
+}
@@ -0,0 +1,328 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use uuid::Uuid;
+
+use common::TestApp;
+
+const FAVORITES: &str = "/api/favorites";
+
+/// Whether `token`'s user has favorited `product_id`, as the status endpoint says.
+async fn is_favorited(app: &TestApp, token: &str, product_id: Uuid) -> bool {
+    let response = app
+        .get(&format!("{}/{}", FAVORITES, product_id), Some(token))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    response.body["data"]["favorited"].as_bool().unwrap()
+}
+
+async fn favorite(app: &TestApp, token: &str, product_id: Uuid) {
+    let body = json!({ "product_id": product_id });
+    let response = app.post(FAVORITES, Some(token), body).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+}
+
+#[tokio::test]
+async fn favorites_move_to_the_cart_in_one_step() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let sold_out = app.create_product(&admin, "Teapot", 4_000, 0).await;
+    let move_to_cart = |product_id: Uuid| format!("{}/{}/move-to-cart", FAVORITES, product_id);
+
+    // Only a favorite can be moved.
+    let response = app
+        .request(Method::POST, &move_to_cart(mug), Some(&buyer), None)
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND, "{}", response.body);
+
+    favorite(&app, &buyer, mug).await;
+    let response = app
+        .request(Method::POST, &move_to_cart(mug), Some(&buyer), None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["quantity"], 1);
+    assert_eq!(response.body["data"]["price_at_add"], 1_250);
+    assert!(is_favorited(&app, &buyer, mug).await);
+
+    // Again adds one more, and can drop the favorite on the way.
+    let response = app
+        .post(
+            &move_to_cart(mug),
+            Some(&buyer),
+            json!({ "remove_favorite": true }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["quantity"], 2);
+    assert!(!is_favorited(&app, &buyer, mug).await);
+
+    // A sold-out product stays a favorite and stays out of the cart.
+    favorite(&app, &buyer, sold_out).await;
+    let response = app
+        .post(
+            &move_to_cart(sold_out),
+            Some(&buyer),
+            json!({ "remove_favorite": true }),
+        )
+        .await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+    assert_eq!(response.body["data"]["error_code"], "INSUFFICIENT_STOCK");
+    assert!(is_favorited(&app, &buyer, sold_out).await);
+
+    let response = app.get("/api/cart", Some(&buyer)).await;
+    let items = response.body["data"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1, "{}", response.body);
+    assert_eq!(items[0]["product_id"], mug.to_string());
+    assert_eq!(items[0]["quantity"], 2);
+}
+
+#[tokio::test]
+async fn products_say_whether_the_caller_favorited_them() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let fan = app.register("fan@example.com").await;
+    let other = app.register("other@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    app.create_product(&admin, "Teapot", 4_000, 10).await;
+    favorite(&app, &fan, mug).await;
+
+    let favorited = async |uri: &str, token: Option<&str>| {
+        let response = app.get(uri, token).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+        let data = &response.body["data"];
+        match data["items"].as_array() {
+            Some(items) => items
+                .iter()
+                .map(|item| {
+                    (
+                        item["name"].as_str().unwrap().to_string(),
+                        item["is_favorited"].as_bool().unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>(),
+            None => vec![(
+                data["name"].as_str().unwrap().to_string(),
+                data["is_favorited"].as_bool().unwrap(),
+            )],
+        }
+    };
+    let list = "/api/products?sort_by=name";
+    let one = format!("/api/products/{}", mug);
+    let by_slug = "/api/products/slug/ceramic-mug";
+    let mug_only = |flag| vec![("Ceramic Mug".to_string(), flag)];
+
+    assert_eq!(
+        favorited(list, Some(&fan)).await,
+        [
+            ("Ceramic Mug".to_string(), true),
+            ("Teapot".to_string(), false)
+        ]
+    );
+    assert_eq!(favorited(&one, Some(&fan)).await, mug_only(true));
+    assert_eq!(favorited(by_slug, Some(&fan)).await, mug_only(true));
+    // The cached copy carries nobody's flag.
+    for token in [None, Some(other.as_str())] {
+        assert_eq!(
+            favorited(list, token).await,
+            [
+                ("Ceramic Mug".to_string(), false),
+                ("Teapot".to_string(), false)
+            ]
+        );
+        assert_eq!(favorited(&one, token).await, mug_only(false));
+        assert_eq!(favorited(by_slug, token).await, mug_only(false));
+    }
+
+    // A bad token is refused rather than treated as anonymous.
+    let response = app.get(list, Some("not-a-token")).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn favorite_status_and_counts_follow_each_user() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let jane = app.register("jane@example.com").await;
+    let john = app.register("john@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let teapot = app.create_product(&admin, "Teapot", 4_000, 10).await;
+
+    assert!(!is_favorited(&app, &jane, mug).await);
+    favorite(&app, &jane, mug).await;
+    favorite(&app, &john, mug).await;
+    assert!(is_favorited(&app, &jane, mug).await);
+    assert!(!is_favorited(&app, &jane, teapot).await);
+
+    let response = app.get(&format!("/api/products/{}", mug), None).await;
+    assert_eq!(response.body["data"]["favorite_count"], 2);
+    let response = app.get("/api/products?sort_by=name", None).await;
+    let counts: Vec<_> = response.body["data"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["favorite_count"].as_i64().unwrap())
+        .collect();
+    assert_eq!(counts, [2, 0]);
+
+    let response = app.get(&format!("{}/{}", FAVORITES, mug), None).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn toggling_flips_the_favorite_each_time() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let toggle = async |product_id: Uuid| {
+        let uri = format!("{}/{}/toggle", FAVORITES, product_id);
+        let response = app.request(Method::POST, &uri, Some(&buyer), None).await;
+        (response.status, response.body["data"]["favorited"].clone())
+    };
+
+    assert_eq!(toggle(mug).await, (StatusCode::OK, json!(true)));
+    assert!(is_favorited(&app, &buyer, mug).await);
+    assert_eq!(toggle(mug).await, (StatusCode::OK, json!(false)));
+    assert!(!is_favorited(&app, &buyer, mug).await);
+    assert_eq!(toggle(mug).await, (StatusCode::OK, json!(true)));
+    assert_eq!(toggle(Uuid::new_v4()).await.0, StatusCode::NOT_FOUND);
+
+    // Racing toggles never fail on the unique index; each sees a definite state.
+    for _ in 0..5 {
+        let (first, second) = tokio::join!(toggle(mug), toggle(mug));
+        assert_eq!(first.0, StatusCode::OK);
+        assert_eq!(second.0, StatusCode::OK);
+        let now = is_favorited(&app, &buyer, mug).await;
+        assert!(first.1 == json!(now) || second.1 == json!(now));
+    }
+}
+
+#[tokio::test]
+async fn concurrent_adds_keep_a_single_row() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let add = async |uri: &str, body: serde_json::Value| {
+        let response = app.post(uri, Some(&buyer), body).await;
+        response.status
+    };
+
+    let body = json!({ "product_id": mug });
+    let (first, second) = tokio::join!(add(FAVORITES, body.clone()), add(FAVORITES, body.clone()));
+    let mut statuses = [first, second];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::OK, StatusCode::CREATED]);
+    assert_eq!(add(FAVORITES, body).await, StatusCode::OK);
+    let favorites: i64 = sqlx::query_scalar("SELECT count(*) FROM favorites")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(favorites, 1);
+
+    // The cart's upsert sets the quantity on the one line.
+    let body = json!({ "product_id": mug, "quantity": 1 });
+    let (first, second) = tokio::join!(add("/api/cart", body.clone()), add("/api/cart", body));
+    assert!(
+        first.is_success() && second.is_success(),
+        "{} {}",
+        first,
+        second
+    );
+    let lines: Vec<i32> = sqlx::query_scalar("SELECT quantity FROM cart_items")
+        .fetch_all(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(lines, [1]);
+}
+
+#[tokio::test]
+async fn syncing_replaces_the_favorites_and_reports_unknown_ids() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let teapot = app.create_product(&admin, "Teapot", 4_000, 10).await;
+    let bowl = app.create_product(&admin, "Bowl", 900, 10).await;
+    favorite(&app, &buyer, mug).await;
+    favorite(&app, &buyer, teapot).await;
+    let unknown = Uuid::new_v4();
+
+    // Keep the teapot, drop the mug, add the bowl, skip the unknown id.
+    let body = json!({ "product_ids": [teapot, bowl, unknown, bowl] });
+    let response = app
+        .request(Method::PUT, FAVORITES, Some(&buyer), Some(body))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let mut names: Vec<_> = response.body["data"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["name"].as_str().unwrap().to_string())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["Bowl", "Teapot"]);
+    assert_eq!(response.body["data"]["skipped"], json!([unknown]));
+    assert!(!is_favorited(&app, &buyer, mug).await);
+    assert!(is_favorited(&app, &buyer, bowl).await);
+
+    // An empty list clears them all; an oversized one changes nothing.
+    let too_many: Vec<Uuid> = (0..501).map(|_| Uuid::new_v4()).collect();
+    let body = json!({ "product_ids": too_many });
+    let response = app
+        .request(Method::PUT, FAVORITES, Some(&buyer), Some(body))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert!(is_favorited(&app, &buyer, bowl).await);
+    let body = json!({ "product_ids": [] });
+    let response = app
+        .request(Method::PUT, FAVORITES, Some(&buyer), Some(body))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["items"], json!([]));
+}
+
+#[tokio::test]
+async fn the_favorites_total_counts_only_listed_products() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let jane = app.register("jane@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let teapot = app.create_product(&admin, "Teapot", 4_000, 10).await;
+    favorite(&app, &jane, mug).await;
+    favorite(&app, &jane, teapot).await;
+
+    let uri = format!("/api/products/{}", teapot);
+    let response = app.request(Method::DELETE, &uri, Some(&admin), None).await;
+    assert!(response.status.is_success(), "{}", response.body);
+
+    let response = app.get(FAVORITES, Some(&jane)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let items = response.body["data"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["id"], mug.to_string());
+    assert_eq!(response.body["meta"]["total"], 1);
+    assert_eq!(response.body["meta"]["total_pages"], 1);
+}
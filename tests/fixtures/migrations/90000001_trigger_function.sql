@@ -0,0 +1,24 @@
+-- Exercises what a naive split on ';' would break: a dollar-quoted function body, a DO block,
+-- a semicolon inside a string literal and inside comments; /* like this; */
+CREATE TABLE migration_fixture_notes (
+    id SERIAL PRIMARY KEY,
+    body TEXT NOT NULL,
+    touched_by TEXT
+);
+
+CREATE FUNCTION migration_fixture_touch() RETURNS trigger AS $$
+BEGIN
+    NEW.touched_by := 'trigger; with a semicolon';
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER migration_fixture_touch
+    BEFORE INSERT ON migration_fixture_notes
+    FOR EACH ROW EXECUTE FUNCTION migration_fixture_touch();
+
+DO $body$
+BEGIN
+    INSERT INTO migration_fixture_notes (body) VALUES ('seeded; from a DO block');
+END
+$body$;
@@ -0,0 +1,2 @@
+-- Runs after 90000001, so the trigger it created fires here too.
+INSERT INTO migration_fixture_notes (body) VALUES ('it''s; quoted');
@@ -0,0 +1,199 @@
+//! `/health` checks the database, `/ready` also the migrations; `/live` and `/health/version`
+//! never touch either.
+
+mod common;
+
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{body::Body, http::Request, http::StatusCode};
+use axum_ecommerce_api::{db::create_pool, state::AppState};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn health_reports_the_database_as_up() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+
+    let response = app.get("/health", None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let data = &response.body["data"];
+    assert_eq!(data["status"], "ok");
+    assert_eq!(data["database"]["status"], "up");
+    assert!(data["database"].get("error").is_none());
+    assert!(data["latency_ms"].is_u64());
+    assert!(data["pool"]["size"].as_u64().unwrap() >= 1);
+    assert!(data["pool"]["idle"].is_u64());
+    assert_eq!(data["pool"]["max"], app.config.db_max_connections);
+    assert!(data["pool"]["acquire_ms"].is_u64());
+
+    let live = app.get("/live", None).await;
+    assert_eq!(live.status, StatusCode::OK);
+    assert_eq!(live.body["data"]["status"], "ok");
+}
+
+#[tokio::test]
+async fn health_answers_503_when_the_database_is_unreachable() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    // Nothing listens on port 1, so every connection attempt is refused.
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_secs(1))
+        .connect_lazy("postgres://postgres@127.0.0.1:1/unreachable")
+        .unwrap();
+    let state = AppState {
+        pool,
+        ..app.state.clone()
+    };
+    let router = axum_ecommerce_api::app(&app.config, state);
+    let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+    let response = common::send(&router, get("/health")).await;
+    assert_eq!(
+        response.status,
+        StatusCode::SERVICE_UNAVAILABLE,
+        "{}",
+        response.body
+    );
+    let data = &response.body["data"];
+    assert_eq!(data["status"], "degraded");
+    assert_eq!(data["database"]["status"], "down");
+    assert!(data["database"]["error"].is_string());
+    assert!(data["pool"].get("acquire_ms").is_none());
+    assert!(response.body["meta"]["request_id"].is_string());
+
+    let ready = common::send(&router, get("/ready")).await;
+    assert_eq!(ready.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(ready.body["data"]["database"]["status"], "down");
+
+    // Liveness does not care.
+    let live = common::send(&router, get("/live")).await;
+    assert_eq!(live.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn ready_once_every_migration_is_applied() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+
+    let response = app.get("/ready", None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let data = &response.body["data"];
+    assert_eq!(data["status"], "ready");
+    assert_eq!(data["database"]["status"], "up");
+    assert_eq!(data["migrations"]["pending"], json!([]));
+    assert_eq!(
+        data["migrations"]["applied"],
+        data["migrations"]["expected"]
+    );
+    assert!(data["uptime_secs"].is_u64());
+}
+
+#[tokio::test]
+async fn not_ready_while_a_migration_is_pending() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let latest: i64 = sqlx::query_scalar("SELECT max(version) FROM _sqlx_migrations")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM _sqlx_migrations WHERE version = $1")
+        .bind(latest)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let response = app.get("/ready", None).await;
+    assert_eq!(
+        response.status,
+        StatusCode::SERVICE_UNAVAILABLE,
+        "{}",
+        response.body
+    );
+    let data = &response.body["data"];
+    assert_eq!(data["status"], "not_ready");
+    assert_eq!(data["database"]["status"], "up");
+    assert_eq!(data["migrations"]["pending"], json!([latest]));
+}
+
+#[tokio::test]
+async fn version_reports_the_crate_version_and_build() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+
+    let response = app.get("/health/version", None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let data = &response.body["data"];
+    assert_eq!(data["version"], env!("CARGO_PKG_VERSION"));
+    assert!(!data["git_commit"].as_str().unwrap().is_empty());
+    assert!(
+        chrono::DateTime::parse_from_rfc3339(data["build_timestamp"].as_str().unwrap()).is_ok(),
+        "{}",
+        data
+    );
+
+    let spec = app.get("/api-docs/openapi.json", None).await;
+    assert_eq!(spec.body["info"]["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(
+        spec.body["info"]["x-build"]["git_commit"],
+        data["git_commit"]
+    );
+}
+
+/// Collects formatted log lines for a test to inspect.
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn statements_over_the_threshold_are_logged_as_slow() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let mut config = app.config.clone();
+    config.db_log_statements = false;
+    config.db_slow_query_ms = 50;
+    let pool = create_pool(&config).await.unwrap();
+
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::WARN)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    sqlx::query("SELECT 1").execute(&pool).await.unwrap();
+    assert!(logs.0.lock().unwrap().is_empty());
+
+    sqlx::query("SELECT pg_sleep(0.2)")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let logged = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    assert!(logged.contains("WARN"), "{}", logged);
+    assert!(logged.contains("slow statement"), "{}", logged);
+    assert!(logged.contains("SELECT pg_sleep"), "{}", logged);
+}
@@ -0,0 +1,568 @@
+//! End-to-end flows over HTTP through the router `main` serves, layers included.
+
+mod common;
+
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{HeaderName, Method, Request, StatusCode, header},
+    routing::get,
+};
+use axum_ecommerce_api::{
+    config::{AppConfig, DocsUi, RateLimit},
+    routes::auth::Claims,
+    state::AppState,
+    with_middleware,
+};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use common::{TestApp, TestResponse};
+
+fn assert_error(response: &TestResponse, status: StatusCode, code: &str) {
+    assert_eq!(response.status, status, "{}", response.body);
+    assert_eq!(response.body["data"]["error_code"], code);
+    assert_eq!(
+        response.body["data"]["error"], response.body["message"],
+        "error envelope repeats the message"
+    );
+    assert_eq!(
+        response.body["meta"]["request_id"],
+        response.headers["x-request-id"].to_str().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn bad_credentials_and_bad_bodies_are_enveloped() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    app.register("known@example.com").await;
+
+    let wrong = json!({ "email": "known@example.com", "password": "wrong password" });
+    let response = app.post("/api/auth/login", None, wrong).await;
+    assert_error(&response, StatusCode::BAD_REQUEST, "INVALID_CREDENTIALS");
+
+    let again = json!({ "email": "known@example.com", "password": common::PASSWORD });
+    let response = app.post("/api/auth/register", None, again).await;
+    assert_error(&response, StatusCode::BAD_REQUEST, "EMAIL_TAKEN");
+
+    let response = app
+        .post("/api/auth/login", None, json!({ "email": 42 }))
+        .await;
+    assert_error(
+        &response,
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "VALIDATION_FAILED",
+    );
+    assert_eq!(response.body["data"]["errors"][0]["field"], "body");
+}
+
+#[tokio::test]
+async fn unknown_routes_get_the_enveloped_404() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+
+    let response = app.get("/api/no-such-thing", None).await;
+    assert_error(&response, StatusCode::NOT_FOUND, "NOT_FOUND");
+
+    let response = app
+        .request(Method::DELETE, "/definitely/not/here", None, None)
+        .await;
+    assert_error(&response, StatusCode::NOT_FOUND, "NOT_FOUND");
+
+    let response = app
+        .get(&format!("/api/products/{}", Uuid::new_v4()), None)
+        .await;
+    assert_error(&response, StatusCode::NOT_FOUND, "NOT_FOUND");
+}
+
+#[tokio::test]
+async fn every_invalid_field_is_reported_in_one_422() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+
+    let body = json!({ "email": "not-an-email", "password": "short" });
+    let response = app.post("/api/auth/register", None, body).await;
+    assert_error(
+        &response,
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "VALIDATION_FAILED",
+    );
+    assert_eq!(
+        response.body["data"]["errors"],
+        json!([
+            {
+                "field": "email",
+                "code": "invalid",
+                "message": "email must look like name@example.com",
+            },
+            {
+                "field": "password",
+                "code": "too_short",
+                "message": "password must be at least 8 characters",
+            },
+        ])
+    );
+}
+
+#[tokio::test]
+async fn unreadable_bodies_get_an_enveloped_400_everywhere() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let raw = async |method: Method, uri: &str, content_type: Option<&str>, body: &'static str| {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {}", admin));
+        if let Some(content_type) = content_type {
+            request = request.header(header::CONTENT_TYPE, content_type);
+        }
+        app.send(request.body(Body::from(body)).unwrap()).await
+    };
+    let json = Some("application/json");
+
+    for (method, uri) in [
+        (Method::POST, "/api/auth/login".to_string()),
+        (Method::POST, "/api/cart".to_string()),
+        (Method::POST, "/api/products".to_string()),
+        (Method::PUT, format!("/api/products/{}", mug)),
+        (Method::POST, "/api/admin/categories".to_string()),
+    ] {
+        for body in ["{not json", "", "{\"quantity\": "] {
+            let response = raw(method.clone(), &uri, json, body).await;
+            assert_error(&response, StatusCode::BAD_REQUEST, "BAD_REQUEST");
+            assert!(
+                response.body["message"].as_str().unwrap().contains("JSON"),
+                "{} {}: {}",
+                method,
+                uri,
+                response.body
+            );
+        }
+        let response = raw(method.clone(), &uri, None, "{}").await;
+        assert_error(&response, StatusCode::BAD_REQUEST, "BAD_REQUEST");
+        assert!(
+            response.body["message"]
+                .as_str()
+                .unwrap()
+                .contains("Content-Type: application/json"),
+            "{}",
+            response.body
+        );
+    }
+}
+
+#[tokio::test]
+async fn created_resources_answer_201_with_a_location_that_resolves() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let body = json!({ "email": "jane@example.com", "password": common::PASSWORD });
+    let response = app.post("/api/auth/register", None, body).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let jane = app.login("jane@example.com").await;
+
+    let product = json!({ "name": "Teapot", "description": "", "price": 4_000, "stock": 3 });
+    let response = app.post("/api/products", Some(&admin), product).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let id = response.body["data"]["id"].as_str().unwrap().to_string();
+    let location = response.headers[header::LOCATION].to_str().unwrap();
+    assert_eq!(location, format!("/api/v1/products/{}", id));
+    let response = app.get(location, None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["name"], "Teapot");
+
+    // A new favorite is created; adding it again points at the same place with a 200.
+    let favorite = json!({ "product_id": id });
+    let first = app
+        .post("/api/favorites", Some(&jane), favorite.clone())
+        .await;
+    assert_eq!(first.status, StatusCode::CREATED, "{}", first.body);
+    let again = app.post("/api/favorites", Some(&jane), favorite).await;
+    assert_eq!(again.status, StatusCode::OK, "{}", again.body);
+    assert_eq!(again.body["data"], first.body["data"]);
+    let location = first.headers[header::LOCATION].to_str().unwrap();
+    assert_eq!(location, format!("/api/v1/favorites/{}", id));
+    assert_eq!(again.headers[header::LOCATION], location);
+    let response = app.get(location, Some(&jane)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["favorited"], true);
+
+    // Deletes answer 200 with the envelope, as documented.
+    let response = app
+        .request(Method::DELETE, location, Some(&jane), None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let response = app
+        .request(
+            Method::DELETE,
+            &format!("/api/products/{}", id),
+            Some(&admin),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+}
+
+async fn boom() -> &'static str {
+    panic!("secret detail")
+}
+
+#[tokio::test]
+async fn panicking_handlers_answer_with_the_500_envelope() {
+    let config = AppConfig::from_vars(|key| match key {
+        "DATABASE_URL" => Some("postgres://localhost/unused".to_string()),
+        "JWT_SECRET" => Some("unused".to_string()),
+        _ => None,
+    })
+    .unwrap();
+    let router = with_middleware(Router::new().route("/boom", get(boom)), &config);
+
+    let request = Request::builder()
+        .uri("/boom")
+        .header("x-request-id", "trace-me")
+        .body(Body::empty())
+        .unwrap();
+    let response = router.oneshot(request).await.unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    let response = TestResponse {
+        status,
+        headers,
+        body,
+    };
+
+    assert_error(
+        &response,
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "INTERNAL_ERROR",
+    );
+    assert_eq!(response.body["meta"]["request_id"], "trace-me");
+    assert_eq!(response.body["message"], "Internal Server Error");
+    assert!(!response.body.to_string().contains("secret detail"));
+}
+
+#[tokio::test]
+async fn the_raw_spec_is_served_next_to_the_docs() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+
+    let response = app.get("/api-docs/openapi.json", None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert!(response.body["paths"]["/api/v1/products"].is_object());
+    assert_eq!(response.body["servers"][0]["url"], app.config.public_url);
+}
+
+#[tokio::test]
+async fn the_unversioned_alias_serves_v1_and_says_it_is_deprecated() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+
+    for path in ["/products".to_string(), format!("/products/{}", mug)] {
+        let v1 = app.get(&format!("/api/v1{}", path), None).await;
+        let alias = app.get(&format!("/api{}", path), None).await;
+        assert_eq!(v1.status, StatusCode::OK, "{}", v1.body);
+        assert_eq!(alias.status, StatusCode::OK, "{}", alias.body);
+        assert_eq!(alias.body, v1.body);
+        assert_eq!(alias.headers["deprecation"], "true");
+        assert!(!v1.headers.contains_key("deprecation"));
+    }
+
+    let versioned = app.get("/api-docs/v1/openapi.json", None).await;
+    assert_eq!(versioned.status, StatusCode::OK);
+    assert_eq!(
+        app.get("/api-docs/openapi.json", None).await.body,
+        versioned.body
+    );
+    assert_eq!(app.get("/docs/v1", None).await.status, StatusCode::OK);
+
+    // Once the window closes only /api/v1 is left.
+    let mut config = app.config.clone();
+    config.unversioned_api_alias = false;
+    let router = axum_ecommerce_api::app(&config, app.state.clone());
+    let get = |uri: &str| {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        router.clone().oneshot(request)
+    };
+    assert_eq!(
+        get("/api/products").await.unwrap().status(),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        get("/api/v1/products").await.unwrap().status(),
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+async fn docs_uis_follow_the_docs_ui_setting() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+
+    for (ui, scalar, swagger) in [
+        (DocsUi::Scalar, true, false),
+        (DocsUi::Swagger, false, true),
+        (DocsUi::Both, true, true),
+        (DocsUi::None, false, false),
+    ] {
+        let mut config = app.config.clone();
+        config.docs_ui = ui;
+        let router = axum_ecommerce_api::app(&config, app.state.clone());
+        let status = async |uri: &str| {
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            router.clone().oneshot(request).await.unwrap().status()
+        };
+        let served = |on: bool| {
+            if on {
+                StatusCode::OK
+            } else {
+                StatusCode::NOT_FOUND
+            }
+        };
+
+        assert_eq!(status("/docs/v1").await, served(scalar), "{:?}", ui);
+        assert_eq!(status("/swagger/").await, served(swagger), "{:?}", ui);
+        let spec = served(ui != DocsUi::None);
+        assert_eq!(status("/api-docs/v1/openapi.json").await, spec, "{:?}", ui);
+    }
+}
+
+#[tokio::test]
+async fn auth_is_rate_limited_per_client_ip() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let mut config = app.config.clone();
+    config.trust_proxy = true;
+    config.auth_rate_limit = RateLimit {
+        burst: 3,
+        per_second: 0.01,
+    };
+    let router = axum_ecommerce_api::app(&config, app.state.clone());
+    let login = async |uri: &str, ip: &str| {
+        let body = json!({ "email": "nobody@example.com", "password": "guess" });
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .header("x-forwarded-for", ip)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        common::send(&router, request).await
+    };
+
+    for _ in 0..3 {
+        let response = login("/api/v1/auth/login", "203.0.113.7").await;
+        assert_error(&response, StatusCode::BAD_REQUEST, "INVALID_CREDENTIALS");
+    }
+    // The unversioned alias shares the same budget.
+    for uri in ["/api/v1/auth/login", "/api/auth/login"] {
+        let limited = login(uri, "203.0.113.7").await;
+        assert_error(&limited, StatusCode::TOO_MANY_REQUESTS, "RATE_LIMITED");
+        let retry_after: u64 = limited.headers[header::RETRY_AFTER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((1..=100).contains(&retry_after), "{}", retry_after);
+    }
+
+    let other = login("/api/v1/auth/login", "198.51.100.2").await;
+    assert_error(&other, StatusCode::BAD_REQUEST, "INVALID_CREDENTIALS");
+
+    // Everything else has its own, much larger budget.
+    let request = Request::builder()
+        .uri("/api/v1/products")
+        .header("x-forwarded-for", "203.0.113.7")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(common::send(&router, request).await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn bodies_over_the_configured_limit_get_the_413_envelope() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let mut config = app.config.clone();
+    config.max_body_bytes = 256;
+    config.request_id_header = HeaderName::from_static("x-correlation-id");
+    let router = axum_ecommerce_api::app(&config, app.state.clone());
+    let create = |description: String| {
+        let body = json!({ "name": "Mug", "description": description, "price": 100, "stock": 1 });
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/v1/products")
+            .header(header::AUTHORIZATION, format!("Bearer {}", admin))
+            .header(header::CONTENT_TYPE, "application/json")
+            .header("x-correlation-id", "big-body")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = common::send(&router, create("x".repeat(1024))).await;
+    assert_eq!(
+        response.status,
+        StatusCode::PAYLOAD_TOO_LARGE,
+        "{}",
+        response.body
+    );
+    assert_eq!(response.body["data"]["error_code"], "PAYLOAD_TOO_LARGE");
+    assert_eq!(response.body["meta"]["request_id"], "big-body");
+    assert_eq!(response.headers["x-correlation-id"], "big-body");
+
+    let response = common::send(&router, create("small".to_string())).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+}
+
+#[tokio::test]
+async fn the_served_app_boots_with_the_main_state() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    // Same construction as `main`: `AppState::new` and `app`.
+    let state = AppState::new(app.pool.clone(), &app.config);
+    let router = axum_ecommerce_api::app(&app.config, state);
+    let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+    let health = common::send(&router, get("/health")).await;
+    assert_eq!(health.status, StatusCode::OK, "{}", health.body);
+    for uri in ["/api/v1/products", "/api/products"] {
+        let products = common::send(&router, get(uri)).await;
+        assert_eq!(products.status, StatusCode::OK, "{}", products.body);
+        assert_eq!(products.body["data"]["items"], json!([]));
+    }
+}
+
+/// A token for `user_id` signed with `secret` that expired an hour ago.
+fn expired_token(user_id: &str, secret: &str) -> String {
+    let claims = Claims {
+        sub: user_id.to_string(),
+        role: "user".to_string(),
+        exp: (chrono::Utc::now().timestamp() - 3600) as usize,
+    };
+    jsonwebtoken::encode(
+        &jsonwebtoken::Header::default(),
+        &claims,
+        &jsonwebtoken::EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn a_shopper_buys_a_product_over_http() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+
+    let credentials = json!({ "email": "shopper@example.com", "password": common::PASSWORD });
+    let registered = app
+        .post("/api/v1/auth/register", None, credentials.clone())
+        .await;
+    assert_eq!(
+        registered.status,
+        StatusCode::CREATED,
+        "{}",
+        registered.body
+    );
+    let user_id = registered.body["data"]["id"].as_str().unwrap().to_string();
+    let login = app.post("/api/v1/auth/login", None, credentials).await;
+    assert_eq!(login.status, StatusCode::OK, "{}", login.body);
+    let token = login.body["data"]["token"]
+        .as_str()
+        .unwrap()
+        .trim_start_matches("Bearer ")
+        .to_string();
+
+    let added = app
+        .post(
+            "/api/v1/cart",
+            Some(&token),
+            json!({ "product_id": mug, "quantity": 3 }),
+        )
+        .await;
+    assert!(added.status.is_success(), "{}", added.body);
+    let cart = app.get("/api/v1/cart", Some(&token)).await;
+    assert_eq!(cart.status, StatusCode::OK, "{}", cart.body);
+
+    let checkout = app
+        .post("/api/v1/orders/checkout", Some(&token), json!({}))
+        .await;
+    assert_eq!(checkout.status, StatusCode::CREATED, "{}", checkout.body);
+    assert_eq!(checkout.body["data"]["order"]["total_amount"], 3_750);
+    assert_eq!(checkout.body["data"]["order"]["status"], "pending");
+    let location = checkout.headers[header::LOCATION].to_str().unwrap();
+    let order_id = checkout.body["data"]["order"]["id"].as_str().unwrap();
+
+    let order = app.get(location, Some(&token)).await;
+    assert_eq!(order.status, StatusCode::OK, "{}", order.body);
+    assert_eq!(order.body["data"]["items"][0]["quantity"], 3);
+    let orders = app.get("/api/v1/orders", Some(&token)).await;
+    assert_eq!(
+        orders.body["data"]["items"][0]["id"], order_id,
+        "{}",
+        orders.body
+    );
+    // Checkout only reserves the units; paying takes them out of stock.
+    let product = app.get(&format!("/api/v1/products/{}", mug), None).await;
+    assert_eq!(product.body["data"]["stock"], 10);
+    assert_eq!(product.body["data"]["reserved"], 3);
+    let empty = app
+        .post("/api/v1/orders/checkout", Some(&token), json!({}))
+        .await;
+    assert_error(&empty, StatusCode::BAD_REQUEST, "CART_EMPTY");
+
+    let admin_view = format!("/api/v1/admin/orders/{}", order_id);
+    let seen = app.get(&admin_view, Some(&admin)).await;
+    assert_eq!(seen.status, StatusCode::OK, "{}", seen.body);
+    assert_eq!(seen.body["data"]["order"]["user_id"], user_id.as_str());
+    let forbidden = app.get(&admin_view, Some(&token)).await;
+    assert_error(&forbidden, StatusCode::FORBIDDEN, "FORBIDDEN");
+    let paid = app
+        .post(&format!("{}/pay", admin_view), Some(&admin), json!({}))
+        .await;
+    assert_eq!(paid.status, StatusCode::OK, "{}", paid.body);
+    assert_eq!(paid.body["data"]["status"], "paid");
+    let product = app.get(&format!("/api/v1/products/{}", mug), None).await;
+    assert_eq!(product.body["data"]["stock"], 7);
+    assert_eq!(product.body["data"]["reserved"], 0);
+
+    // Auth failures, all enveloped.
+    let missing = app.get("/api/v1/orders", None).await;
+    assert_error(&missing, StatusCode::BAD_REQUEST, "BAD_REQUEST");
+    let expired = app
+        .get(
+            "/api/v1/orders",
+            Some(&expired_token(&user_id, &app.config.jwt_secret)),
+        )
+        .await;
+    assert_error(&expired, StatusCode::BAD_REQUEST, "BAD_REQUEST");
+    let forged = app
+        .get(
+            "/api/v1/orders",
+            Some(&expired_token(&user_id, "not the secret")),
+        )
+        .await;
+    assert_error(&forged, StatusCode::BAD_REQUEST, "BAD_REQUEST");
+
+    let unknown = app.get("/api/v1/no-such-thing", Some(&token)).await;
+    assert_error(&unknown, StatusCode::NOT_FOUND, "NOT_FOUND");
+}
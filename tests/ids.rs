@@ -0,0 +1,37 @@
+mod common;
+
+use axum::http::StatusCode;
+use axum_ecommerce_api::ids::new_id;
+use uuid::Uuid;
+
+use common::TestApp;
+
+#[test]
+fn consecutive_ids_increase() {
+    let ids: Vec<Uuid> = (0..10_000).map(|_| new_id()).collect();
+    assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(ids[0].get_version_num(), 7);
+}
+
+#[tokio::test]
+async fn new_rows_get_v7_ids_and_old_v4_rows_still_resolve() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    assert_eq!(mug.get_version_num(), 7);
+
+    // A product created before the switch.
+    let old = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO products (id, name, slug, price, stock) VALUES ($1, 'Old Teapot', 'old-teapot', 4000, 3)",
+    )
+    .bind(old)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+    let response = app.get(&format!("/api/v1/products/{}", old), None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["name"], "Old Teapot");
+}
@@ -0,0 +1,697 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use common::{TestApp, TestResponse};
+
+/// `(stock, reserved)` of `product_id`, straight from the table.
+async fn stock(app: &TestApp, product_id: Uuid) -> (i32, i32) {
+    sqlx::query_as("SELECT stock, reserved FROM products WHERE id = $1")
+        .bind(product_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap()
+}
+
+/// Puts `quantity` of `product_id` in the cart of `token` and checks out.
+async fn checkout(app: &TestApp, token: &str, product_id: Uuid, quantity: i32) -> TestResponse {
+    let add = json!({ "product_id": product_id, "quantity": quantity });
+    let response = app.post("/api/v1/cart", Some(token), add).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    app.post("/api/v1/orders/checkout", Some(token), json!({}))
+        .await
+}
+
+fn order_id(response: &TestResponse) -> String {
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    response.body["data"]["order"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+#[tokio::test]
+async fn checkout_reserves_payment_takes_and_cancelling_releases() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+
+    let paid = order_id(&checkout(&app, &buyer, mug, 3).await);
+    assert_eq!(stock(&app, mug).await, (10, 3));
+    let cancelled = order_id(&checkout(&app, &buyer, mug, 2).await);
+    assert_eq!(stock(&app, mug).await, (10, 5));
+
+    let pay = format!("/api/v1/admin/orders/{}/pay", paid);
+    let response = app.post(&pay, Some(&admin), json!({})).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["status"], "paid");
+    assert_eq!(stock(&app, mug).await, (7, 2));
+
+    let cancel = format!("/api/v1/orders/{}/cancel", cancelled);
+    let response = app.post(&cancel, Some(&buyer), json!({})).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["status"], "cancelled");
+    assert_eq!(stock(&app, mug).await, (7, 0));
+
+    // Settled orders stay settled, and the stock with them.
+    let response = app.post(&pay, Some(&admin), json!({})).await;
+    assert_eq!(response.status, StatusCode::CONFLICT, "{}", response.body);
+    assert_eq!(response.body["data"]["error_code"], "ORDER_ALREADY_PAID");
+    let response = app
+        .post(
+            &format!("/api/v1/orders/{}/cancel", paid),
+            Some(&buyer),
+            json!({}),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT, "{}", response.body);
+    let response = app.post(&cancel, Some(&buyer), json!({})).await;
+    assert_eq!(response.body["data"]["error_code"], "INVALID_ORDER_STATUS");
+    let response = app
+        .post(
+            &format!("/api/v1/admin/orders/{}/pay", cancelled),
+            Some(&admin),
+            json!({}),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT, "{}", response.body);
+    assert_eq!(stock(&app, mug).await, (7, 0));
+
+    // Only the buyer cancels, only an admin marks paid.
+    let other = app.register("other@example.com").await;
+    let open = order_id(&checkout(&app, &buyer, mug, 1).await);
+    let response = app
+        .post(
+            &format!("/api/v1/orders/{}/cancel", open),
+            Some(&other),
+            json!({}),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND, "{}", response.body);
+    let response = app
+        .post(
+            &format!("/api/v1/admin/orders/{}/pay", open),
+            Some(&buyer),
+            json!({}),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN, "{}", response.body);
+    assert_eq!(stock(&app, mug).await, (7, 1));
+}
+
+#[tokio::test]
+async fn reserved_units_cannot_be_sold_twice() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let first = app.register("first@example.com").await;
+    let second = app.register("second@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 2).await;
+
+    order_id(&checkout(&app, &first, mug, 2).await);
+    let response = checkout(&app, &second, mug, 1).await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+    assert_eq!(response.body["data"]["error_code"], "INSUFFICIENT_STOCK");
+    assert_eq!(stock(&app, mug).await, (2, 2));
+
+    // Reserved units count as sold out, and stock cannot drop below them.
+    let response = app.get("/api/v1/products?in_stock=false", None).await;
+    assert_eq!(response.body["data"]["items"][0]["id"], mug.to_string());
+    let response = app
+        .request(
+            Method::PUT,
+            &format!("/api/v1/products/{}", mug),
+            Some(&admin),
+            Some(json!({ "stock": 1 })),
+        )
+        .await;
+    assert_eq!(
+        response.status,
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "{}",
+        response.body
+    );
+    assert_eq!(response.body["data"]["errors"][0]["code"], "below_reserved");
+    let result = sqlx::query("UPDATE products SET reserved = reserved + 1 WHERE id = $1")
+        .bind(mug)
+        .execute(&app.pool)
+        .await;
+    assert!(result.is_err(), "reserved must not exceed stock");
+}
+
+#[tokio::test]
+async fn concurrent_checkouts_of_the_last_unit_sell_it_once() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 1).await;
+    let mut buyers = Vec::new();
+    for i in 0..4 {
+        let token = app.register(&format!("buyer{}@example.com", i)).await;
+        let add = json!({ "product_id": mug, "quantity": 1 });
+        app.post("/api/v1/cart", Some(&token), add).await;
+        buyers.push(token);
+    }
+
+    let responses = futures::future::join_all(
+        buyers
+            .iter()
+            .map(|token| app.post("/api/v1/orders/checkout", Some(token), json!({}))),
+    )
+    .await;
+    let statuses: Vec<StatusCode> = responses.iter().map(|r| r.status).collect();
+    assert_eq!(
+        statuses
+            .iter()
+            .filter(|s| **s == StatusCode::CREATED)
+            .count(),
+        1,
+        "{:?}",
+        statuses
+    );
+    assert!(
+        responses
+            .iter()
+            .filter(|r| r.status != StatusCode::CREATED)
+            .all(|r| r.body["data"]["error_code"] == "INSUFFICIENT_STOCK"),
+        "{:?}",
+        statuses
+    );
+    assert_eq!(stock(&app, mug).await, (1, 1));
+    let orders: i64 = sqlx::query_scalar("SELECT count(*) FROM orders")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(orders, 1);
+}
+
+#[tokio::test]
+async fn low_stock_counts_reserved_units_as_gone() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    app.create_product(&admin, "Teapot", 4_000, 3).await;
+    app.create_product(&admin, "Kettle", 6_000, 50).await;
+
+    let low = |threshold: i32| {
+        let app = &app;
+        let admin = &admin;
+        async move {
+            let uri = format!("/api/v1/admin/inventory/low-stock?threshold={}", threshold);
+            let response = app.get(&uri, Some(admin)).await;
+            assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+            response.body
+        }
+    };
+    let names = |body: &Value| -> Vec<String> {
+        body["data"]["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    assert_eq!(names(&low(5).await), ["Teapot"]);
+    order_id(&checkout(&app, &buyer, mug, 8).await);
+    let body = low(5).await;
+    assert_eq!(names(&body), ["Ceramic Mug", "Teapot"]);
+    assert_eq!(body["data"]["items"][0]["available"], 2);
+    assert_eq!(body["data"]["items"][0]["stock"], 10);
+    assert_eq!(body["data"]["items"][0]["reserved"], 8);
+    assert_eq!(body["meta"]["total"], 2);
+    assert_eq!(names(&low(3).await), ["Ceramic Mug"]);
+    assert_eq!(names(&low(0).await), Vec::<String>::new());
+
+    let response = app
+        .get(
+            "/api/v1/admin/inventory/low-stock?threshold=-1",
+            Some(&admin),
+        )
+        .await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+    let response = app
+        .get("/api/v1/admin/inventory/low-stock", Some(&buyer))
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN, "{}", response.body);
+}
+
+/// `(delta, reason)` of every stock movement of `product_id`, oldest first.
+async fn ledger(app: &TestApp, admin: &str, product_id: Uuid) -> Vec<(i64, String)> {
+    let uri = format!(
+        "/api/v1/admin/products/{}/stock-movements?per_page=100",
+        product_id
+    );
+    let response = app.get(&uri, Some(admin)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    response.body["data"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| {
+            (
+                m["delta"].as_i64().unwrap(),
+                m["reason"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+async fn adjust(app: &TestApp, token: &str, product_id: Uuid, body: Value) -> TestResponse {
+    let uri = format!("/api/v1/admin/products/{}/inventory", product_id);
+    app.post(&uri, Some(token), body).await
+}
+
+#[tokio::test]
+async fn the_stock_ledger_sums_to_the_stock() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+
+    let delivery = Uuid::new_v4();
+    let body = json!({ "delta": 5, "reason": "restock", "reference_id": delivery });
+    let response = adjust(&app, &admin, mug, body).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["stock"], 15);
+    let response = adjust(
+        &app,
+        &admin,
+        mug,
+        json!({ "delta": -2, "reason": "damage" }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let paid = order_id(&checkout(&app, &buyer, mug, 3).await);
+    let pay = format!("/api/v1/admin/orders/{}/pay", paid);
+    let response = app.post(&pay, Some(&admin), json!({})).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    // Reserving and releasing does not move stock.
+    let cancelled = order_id(&checkout(&app, &buyer, mug, 2).await);
+    let cancel = format!("/api/v1/orders/{}/cancel", cancelled);
+    let response = app.post(&cancel, Some(&buyer), json!({})).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app
+        .request(
+            Method::PUT,
+            &format!("/api/v1/products/{}", mug),
+            Some(&admin),
+            Some(json!({ "stock": 20 })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let response = adjust(&app, &admin, mug, json!({ "delta": 1, "reason": "return" })).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let movements = ledger(&app, &admin, mug).await;
+    let reasons: Vec<&str> = movements.iter().map(|(_, r)| r.as_str()).collect();
+    assert_eq!(
+        reasons,
+        [
+            "restock",
+            "restock",
+            "damage",
+            "sale",
+            "correction",
+            "return"
+        ]
+    );
+    let deltas: Vec<i64> = movements.iter().map(|(d, _)| *d).collect();
+    assert_eq!(deltas, [10, 5, -2, -3, 10, 1]);
+    assert_eq!(stock(&app, mug).await, (21, 0));
+    assert_eq!(deltas.iter().sum::<i64>(), 21);
+
+    let (sale_reference, restock_reference): (Option<Uuid>, Option<Uuid>) = sqlx::query_as(
+        r#"
+        SELECT (SELECT reference_id FROM stock_movements WHERE reason = 'sale'),
+               (SELECT reference_id FROM stock_movements WHERE reason = 'restock' AND delta = 5)
+        "#,
+    )
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(sale_reference.unwrap().to_string(), paid);
+    assert_eq!(restock_reference, Some(delivery));
+
+    let response = app
+        .get(
+            &format!("/api/v1/admin/products/{}/stock-movements?per_page=4", mug),
+            Some(&admin),
+        )
+        .await;
+    assert_eq!(response.body["data"]["items"].as_array().unwrap().len(), 4);
+    assert_eq!(response.body["meta"]["total"], 6);
+    assert_eq!(response.body["meta"]["has_next"], true);
+}
+
+#[tokio::test]
+async fn inventory_adjustments_need_a_fitting_reason() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 5).await;
+    order_id(&checkout(&app, &buyer, mug, 3).await);
+
+    for (body, field, code) in [
+        (
+            json!({ "delta": 0, "reason": "correction" }),
+            "delta",
+            "zero",
+        ),
+        (json!({ "delta": -1, "reason": "sale" }), "reason", "sale"),
+        (
+            json!({ "delta": -1, "reason": "restock" }),
+            "delta",
+            "negative",
+        ),
+        (
+            json!({ "delta": 1, "reason": "damage" }),
+            "delta",
+            "positive",
+        ),
+        (
+            json!({ "delta": -3, "reason": "damage" }),
+            "delta",
+            "below_reserved",
+        ),
+        (
+            json!({ "delta": i32::MAX, "reason": "restock" }),
+            "delta",
+            "too_large",
+        ),
+    ] {
+        let response = adjust(&app, &admin, mug, body.clone()).await;
+        assert_eq!(
+            response.status,
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "{}: {}",
+            body,
+            response.body
+        );
+        let error = &response.body["data"]["errors"][0];
+        assert_eq!(
+            (error["field"].as_str(), error["code"].as_str()),
+            (Some(field), Some(code))
+        );
+    }
+    let response = adjust(&app, &admin, mug, json!({ "delta": 1, "reason": "gift" })).await;
+    assert_eq!(
+        response.status,
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "{}",
+        response.body
+    );
+    let response = adjust(&app, &admin, mug, json!({ "delta": 1 })).await;
+    assert_eq!(
+        response.status,
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "{}",
+        response.body
+    );
+    let body = json!({ "delta": 1, "reason": "restock" });
+    let response = adjust(&app, &buyer, mug, body.clone()).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN, "{}", response.body);
+    let response = adjust(&app, &admin, Uuid::new_v4(), body).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND, "{}", response.body);
+
+    // Only the opening restock made it into the ledger.
+    assert_eq!(
+        ledger(&app, &admin, mug).await,
+        [(5, "restock".to_string())]
+    );
+    assert_eq!(stock(&app, mug).await, (5, 3));
+    let missing = format!("/api/v1/admin/products/{}/stock-movements", Uuid::new_v4());
+    assert_eq!(
+        app.get(&missing, Some(&admin)).await.status,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+async fn a_rejected_adjustment_leaves_the_product_untouched_and_unlocked() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 5).await;
+    order_id(&checkout(&app, &buyer, mug, 2).await);
+
+    let response = adjust(
+        &app,
+        &admin,
+        mug,
+        json!({ "delta": -4, "reason": "damage" }),
+    )
+    .await;
+    assert_eq!(
+        response.status,
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "{}",
+        response.body
+    );
+    assert_eq!(response.body["data"]["errors"][0]["code"], "below_reserved");
+    assert_eq!(stock(&app, mug).await, (5, 2));
+
+    // No transaction was left open holding the row.
+    let idle: i64 = sqlx::query_scalar(
+        "SELECT count(*) FROM pg_stat_activity WHERE datname = current_database() AND state LIKE 'idle in transaction%'",
+    )
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(idle, 0);
+    let mut tx = app.pool.begin().await.unwrap();
+    sqlx::query("SELECT id FROM products WHERE id = $1 FOR UPDATE NOWAIT")
+        .bind(mug)
+        .execute(&mut *tx)
+        .await
+        .expect("the product row is still locked");
+    tx.rollback().await.unwrap();
+
+    let response = adjust(
+        &app,
+        &admin,
+        mug,
+        json!({ "delta": -3, "reason": "damage" }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(stock(&app, mug).await, (2, 2));
+}
+
+#[tokio::test]
+async fn concurrent_adjustments_never_take_stock_below_the_reserved_units() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 8).await;
+    order_id(&checkout(&app, &buyer, mug, 2).await);
+
+    let responses = futures::future::join_all((0..10).map(|_| {
+        adjust(
+            &app,
+            &admin,
+            mug,
+            json!({ "delta": -1, "reason": "damage" }),
+        )
+    }))
+    .await;
+    let accepted = responses
+        .iter()
+        .filter(|r| r.status == StatusCode::OK)
+        .count();
+    assert_eq!(accepted, 6);
+    assert!(
+        responses
+            .iter()
+            .filter(|r| r.status != StatusCode::OK)
+            .all(|r| r.body["data"]["errors"][0]["code"] == "below_reserved")
+    );
+    assert_eq!(stock(&app, mug).await, (2, 2));
+    let movements = ledger(&app, &admin, mug).await;
+    assert_eq!(movements.iter().map(|(d, _)| d).sum::<i64>(), 2);
+}
+
+async fn force(app: &TestApp, token: &str, order_id: &str, body: Value) -> TestResponse {
+    let uri = format!("/api/v1/admin/orders/{}/force-status", order_id);
+    app.post(&uri, Some(token), body).await
+}
+
+#[tokio::test]
+async fn force_cancelling_a_paid_order_flags_a_refund_and_can_restock() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let id = order_id(&checkout(&app, &buyer, mug, 3).await);
+    let response = app
+        .post(
+            &format!("/api/v1/admin/orders/{}/pay", id),
+            Some(&admin),
+            json!({}),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert!(response.body["data"]["paid_at"].is_string());
+    assert_eq!(stock(&app, mug).await, (7, 0));
+
+    let body = json!({ "status": "cancelled", "reason": "  Parcel lost by the carrier ", "restock": true });
+    let response = force(&app, &admin, &id, body).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["status"], "cancelled");
+    assert_eq!(response.body["data"]["refund_needed"], true);
+    assert_eq!(stock(&app, mug).await, (10, 0));
+    assert_eq!(
+        ledger(&app, &admin, mug).await,
+        [
+            (10, "restock".to_string()),
+            (-3, "sale".to_string()),
+            (3, "return".to_string())
+        ]
+    );
+
+    app.state.audit.flush().await;
+    let (actor, details): (Uuid, Value) = sqlx::query_as(
+        "SELECT actor_id, details FROM audit_log WHERE action = 'order.force_status' AND entity_id = $1",
+    )
+    .bind(id.parse::<Uuid>().unwrap())
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    let (admin_id,): (Uuid,) =
+        sqlx::query_as("SELECT id FROM users WHERE email = 'admin@example.com'")
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(actor, admin_id);
+    assert_eq!(
+        details,
+        json!({
+            "reason": "Parcel lost by the carrier",
+            "skipped_transition": { "from": "paid", "to": "cancelled" },
+            "refund_needed": true,
+            "restocked": true,
+            "total_amount": 3_750,
+        })
+    );
+
+    // Cancelled stays cancelled.
+    let body = json!({ "status": "completed", "reason": "found it" });
+    let response = force(&app, &admin, &id, body).await;
+    assert_eq!(response.status, StatusCode::CONFLICT, "{}", response.body);
+}
+
+#[tokio::test]
+async fn forcing_a_pending_order_settles_its_reservation() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+
+    let completed = order_id(&checkout(&app, &buyer, mug, 2).await);
+    let cancelled = order_id(&checkout(&app, &buyer, mug, 3).await);
+    assert_eq!(stock(&app, mug).await, (10, 5));
+
+    let body = json!({ "status": "completed", "reason": "Paid in the shop" });
+    let response = force(&app, &admin, &completed, body).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let order = &response.body["data"];
+    assert_eq!(order["status"], "completed");
+    assert!(order["paid_at"].is_string() && order["completed_at"].is_string());
+    assert_eq!(order["refund_needed"], false);
+    assert_eq!(stock(&app, mug).await, (8, 3));
+
+    // Restock means nothing to a pending order: its units were never taken.
+    let body = json!({ "status": "cancelled", "reason": "Fraud", "restock": true });
+    let response = force(&app, &admin, &cancelled, body).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["refund_needed"], false);
+    assert_eq!(stock(&app, mug).await, (8, 0));
+
+    // Completing a paid order needs no stock change; without restock a cancelled paid order
+    // keeps its units out.
+    let paid = order_id(&checkout(&app, &buyer, mug, 1).await);
+    app.post(
+        &format!("/api/v1/admin/orders/{}/pay", paid),
+        Some(&admin),
+        json!({}),
+    )
+    .await;
+    let body = json!({ "status": "cancelled", "reason": "Chargeback" });
+    let response = force(&app, &admin, &paid, body).await;
+    assert_eq!(response.body["data"]["refund_needed"], true);
+    assert_eq!(stock(&app, mug).await, (7, 0));
+}
+
+#[tokio::test]
+async fn forcing_needs_an_admin_a_reason_and_a_supported_status() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let id = order_id(&checkout(&app, &buyer, mug, 1).await);
+
+    let body = json!({ "status": "cancelled", "reason": "mine" });
+    let response = force(&app, &buyer, &id, body).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    let body = json!({ "status": "paid", "reason": " " });
+    let response = force(&app, &admin, &id, body).await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    let codes: Vec<_> = response.body["data"]["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| (e["field"].clone(), e["code"].clone()))
+        .collect();
+    assert_eq!(
+        codes,
+        [
+            (json!("status"), json!("unsupported")),
+            (json!("reason"), json!("required"))
+        ]
+    );
+    let body = json!({ "status": "cancelled", "reason": "x".repeat(501) });
+    let response = force(&app, &admin, &id, body).await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let body = json!({ "status": "cancelled", "reason": "gone" });
+    let response = force(&app, &admin, &Uuid::new_v4().to_string(), body).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(stock(&app, mug).await, (10, 1));
+}
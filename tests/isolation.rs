@@ -0,0 +1,48 @@
+//! Every `TestApp` owns its database, so tests can run side by side without seeing each other.
+
+mod common;
+
+use axum::http::StatusCode;
+use sqlx::{Connection, PgConnection};
+
+use common::TestApp;
+
+/// Registers the same admin and product in `app`; on a shared database the second app to get
+/// here would hit the unique email and slug.
+async fn seed(app: &TestApp) -> i64 {
+    let admin = app.register_admin("admin@example.com").await;
+    app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let products = app.get("/api/v1/products", None).await;
+    assert_eq!(products.status, StatusCode::OK, "{}", products.body);
+    products.body["meta"]["total"].as_i64().unwrap()
+}
+
+#[tokio::test]
+async fn concurrent_apps_do_not_share_data() {
+    let (Some(first), Some(second)) = tokio::join!(TestApp::spawn(), TestApp::spawn()) else {
+        return;
+    };
+    assert_ne!(first.database, second.database);
+
+    let (first_total, second_total) = tokio::join!(seed(&first), seed(&second));
+    assert_eq!((first_total, second_total), (1, 1));
+}
+
+#[tokio::test]
+async fn the_database_is_dropped_with_the_app() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let database = app.database.clone();
+    let server = app.server_options();
+    drop(app);
+
+    let mut conn = PgConnection::connect_with(&server).await.unwrap();
+    let left: Option<String> =
+        sqlx::query_scalar("SELECT datname FROM pg_database WHERE datname = $1")
+            .bind(&database)
+            .fetch_optional(&mut conn)
+            .await
+            .unwrap();
+    assert_eq!(left, None);
+}
@@ -0,0 +1,207 @@
+mod common;
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use axum::http::StatusCode;
+use axum_ecommerce_api::{
+    jobs::{Job, PruneGuestCarts, Scheduler},
+    state::AppState,
+};
+use common::TestApp;
+use serde_json::json;
+
+const TICK: Duration = Duration::from_millis(20);
+
+/// Counts its runs; fails, panics or hangs on the runs it is told to.
+struct FakeJob {
+    name: &'static str,
+    runs: Arc<AtomicU32>,
+    behaviour: fn(u32) -> Outcome,
+}
+
+#[derive(Clone, Copy)]
+enum Outcome {
+    Succeed,
+    Fail,
+    Panic,
+    Hang,
+}
+
+impl FakeJob {
+    fn new(name: &'static str, behaviour: fn(u32) -> Outcome) -> (Self, Arc<AtomicU32>) {
+        let runs = Arc::new(AtomicU32::new(0));
+        let job = Self {
+            name,
+            runs: runs.clone(),
+            behaviour,
+        };
+        (job, runs)
+    }
+}
+
+#[async_trait]
+impl Job for FakeJob {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn interval(&self) -> Duration {
+        TICK
+    }
+
+    async fn run(&self, _state: &AppState) -> anyhow::Result<()> {
+        let run = self.runs.fetch_add(1, Ordering::SeqCst) + 1;
+        match (self.behaviour)(run) {
+            Outcome::Succeed => Ok(()),
+            Outcome::Fail => anyhow::bail!("run {} failed", run),
+            Outcome::Panic => panic!("run {} panicked", run),
+            Outcome::Hang => {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Waits up to a few seconds for `runs` to reach `at_least`.
+async fn wait_for(runs: &AtomicU32, at_least: u32) {
+    for _ in 0..200 {
+        if runs.load(Ordering::SeqCst) >= at_least {
+            return;
+        }
+        tokio::time::sleep(TICK).await;
+    }
+    panic!(
+        "only {} runs, wanted {}",
+        runs.load(Ordering::SeqCst),
+        at_least
+    );
+}
+
+#[tokio::test]
+async fn jobs_run_on_their_interval_and_failures_do_not_stop_them() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let (steady, steady_runs) = FakeJob::new("steady", |_| Outcome::Succeed);
+    let (flaky, flaky_runs) = FakeJob::new("flaky", |run| match run {
+        1 => Outcome::Fail,
+        2 => Outcome::Panic,
+        3 => Outcome::Hang,
+        _ => Outcome::Succeed,
+    });
+    let jobs = Scheduler::new()
+        .with_job(steady)
+        .with_job(flaky)
+        .start(app.state.clone());
+
+    wait_for(&steady_runs, 5).await;
+    wait_for(&flaky_runs, 4).await;
+    // The fourth run has started; give it time to be recorded.
+    tokio::time::sleep(TICK * 3).await;
+    drop(jobs);
+
+    let steady = app.state.jobs.status("steady").unwrap();
+    assert!(steady.runs >= 5);
+    assert_eq!(steady.failures, 0);
+    assert_eq!(steady.last_error, None);
+    assert!(steady.last_run_at.is_some());
+
+    let flaky = app.state.jobs.status("flaky").unwrap();
+    assert!(flaky.runs >= 4, "{:?}", flaky);
+    assert_eq!(flaky.failures, 3, "fail, panic and timeout each count");
+    // A success clears the error of the runs before it.
+    assert_eq!(flaky.last_error, None);
+}
+
+#[tokio::test]
+async fn the_last_error_is_kept_until_a_run_succeeds() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let (broken, runs) = FakeJob::new("broken", |_| Outcome::Fail);
+    let jobs = Scheduler::new().with_job(broken).start(app.state.clone());
+    wait_for(&runs, 2).await;
+    tokio::time::sleep(TICK).await;
+    drop(jobs);
+
+    let status = app.state.jobs.status("broken").unwrap();
+    assert_eq!(status.failures, status.runs);
+    assert!(
+        status.last_error.as_deref().unwrap().ends_with("failed"),
+        "{:?}",
+        status
+    );
+}
+
+#[tokio::test]
+async fn admins_see_the_jobs_and_their_last_run() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let user = app.register("user@example.com").await;
+
+    let response = app.get("/api/v1/admin/jobs", Some(&admin)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["items"], json!([]));
+
+    let (steady, runs) = FakeJob::new("steady", |_| Outcome::Succeed);
+    let _jobs = Scheduler::new()
+        .with_job(steady)
+        .with_job(PruneGuestCarts)
+        .start(app.state.clone());
+    wait_for(&runs, 1).await;
+    tokio::time::sleep(TICK).await;
+
+    let response = app.get("/api/v1/admin/jobs", Some(&admin)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let items = response.body["data"]["items"].as_array().unwrap();
+    let names: Vec<_> = items.iter().map(|job| job["name"].clone()).collect();
+    assert_eq!(names, [json!("prune_guest_carts"), json!("steady")]);
+    // The hourly job has not come round yet.
+    assert_eq!(items[0]["interval_secs"], 3600);
+    assert_eq!(items[0]["runs"], 0);
+    assert_eq!(items[0]["last_run_at"], json!(null));
+    assert!(items[1]["runs"].as_u64().unwrap() >= 1);
+    assert!(items[1]["last_run_at"].is_string());
+    assert_eq!(items[1]["last_error"], json!(null));
+
+    let response = app.get("/api/v1/admin/jobs", Some(&user)).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN, "{}", response.body);
+}
+
+#[tokio::test]
+async fn pruning_deletes_only_expired_guest_carts() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let fresh = app.post("/api/v1/cart/session", None, json!({})).await;
+    let stale = app.post("/api/v1/cart/session", None, json!({})).await;
+    let stale = stale.body["data"]["token"].as_str().unwrap();
+    sqlx::query(
+        "UPDATE cart_sessions SET created_at = NOW() - interval '31 days' WHERE token::text = $1",
+    )
+    .bind(stale)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    PruneGuestCarts.run(&app.state).await.unwrap();
+
+    let left: Vec<(String,)> = sqlx::query_as("SELECT token::text FROM cart_sessions")
+        .fetch_all(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        left,
+        [(fresh.body["data"]["token"].as_str().unwrap().to_string(),)]
+    );
+}
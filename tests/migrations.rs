@@ -0,0 +1,161 @@
+//! Migration tracking, reverting and resetting through `db::MIGRATOR`.
+
+mod common;
+
+use axum::http::StatusCode;
+use axum_ecommerce_api::db::{MIGRATOR, migration_status, reset_database};
+use serde_json::json;
+use sqlx::migrate::MigrateError;
+
+use common::TestApp;
+
+async fn applied_count(app: &TestApp) -> i64 {
+    sqlx::query_scalar("SELECT count(*) FROM _sqlx_migrations")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn running_again_applies_nothing() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let before = applied_count(&app).await;
+
+    MIGRATOR.run(&app.pool).await.unwrap();
+
+    assert_eq!(applied_count(&app).await, before);
+    let states = migration_status(&app.pool).await.unwrap();
+    assert_eq!(states.len() as i64, before);
+    assert!(
+        states.iter().all(|m| m.applied && !m.changed),
+        "{:?}",
+        states
+    );
+}
+
+#[tokio::test]
+async fn an_edited_applied_migration_is_refused() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    // Same effect as editing the file after it ran: the recorded checksum no longer matches.
+    sqlx::query("UPDATE _sqlx_migrations SET checksum = '\\x00' WHERE version = 1")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let error = MIGRATOR.run(&app.pool).await.unwrap_err();
+    assert!(
+        matches!(error, MigrateError::VersionMismatch(1)),
+        "{:?}",
+        error
+    );
+    let states = migration_status(&app.pool).await.unwrap();
+    assert!(states[0].changed);
+    assert!(states[1..].iter().all(|m| !m.changed));
+}
+
+#[tokio::test]
+async fn a_fresh_database_has_everything_pending() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    sqlx::query("DROP TABLE _sqlx_migrations")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let states = migration_status(&app.pool).await.unwrap();
+    assert!(!states.is_empty());
+    assert!(states.iter().all(|m| !m.applied), "{:?}", states);
+}
+
+#[tokio::test]
+async fn functions_triggers_and_do_blocks_apply_in_filename_order() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let mut fixtures = sqlx::migrate!("./tests/fixtures/migrations");
+    // The app's own migrations are already recorded in the same table.
+    fixtures.set_ignore_missing(true);
+
+    fixtures.run(&app.pool).await.unwrap();
+
+    let notes: Vec<(String, Option<String>)> =
+        sqlx::query_as("SELECT body, touched_by FROM migration_fixture_notes ORDER BY id")
+            .fetch_all(&app.pool)
+            .await
+            .unwrap();
+    let touched = Some("trigger; with a semicolon".to_string());
+    assert_eq!(
+        notes,
+        vec![
+            ("seeded; from a DO block".to_string(), touched.clone()),
+            ("it's; quoted".to_string(), touched),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn reverting_everything_and_applying_again_round_trips() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+
+    MIGRATOR.undo(&app.pool, 0).await.unwrap();
+    let tables: i64 = sqlx::query_scalar(
+        "SELECT count(*) FROM pg_tables WHERE schemaname = 'public' AND tablename <> '_sqlx_migrations'",
+    )
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(tables, 0);
+    assert!(
+        migration_status(&app.pool)
+            .await
+            .unwrap()
+            .iter()
+            .all(|m| !m.applied)
+    );
+
+    MIGRATOR.run(&app.pool).await.unwrap();
+    app.register_admin("admin@example.com").await;
+}
+
+#[tokio::test]
+async fn the_app_works_on_a_freshly_reset_database() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+
+    reset_database(&app.pool).await.unwrap();
+
+    let products = app.get("/api/v1/products", None).await;
+    assert_eq!(
+        products.body["data"]["items"],
+        json!([]),
+        "{}",
+        products.body
+    );
+    let admin = app.register_admin("admin@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let user = app.register("shopper@example.com").await;
+    let added = app
+        .post(
+            "/api/v1/cart",
+            Some(&user),
+            json!({ "product_id": mug, "quantity": 2 }),
+        )
+        .await;
+    assert!(added.status.is_success(), "{}", added.body);
+    let order = app
+        .post("/api/v1/orders/checkout", Some(&user), json!({}))
+        .await;
+    assert_eq!(order.status, StatusCode::CREATED, "{}", order.body);
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use axum::http::StatusCode;
+use axum_ecommerce_api::money::{Currency, Money};
+use proptest::prelude::*;
+use serde_json::json;
+
+use common::TestApp;
+
+proptest! {
+    #[test]
+    fn addition_matches_wide_arithmetic(a: i64, b: i64) {
+        let wide = i128::from(a) + i128::from(b);
+        let expected = i64::try_from(wide).ok().map(Money::from_minor);
+        prop_assert_eq!(Money::from_minor(a).checked_add(Money::from_minor(b)), expected);
+    }
+
+    #[test]
+    fn multiplication_matches_wide_arithmetic(price: i64, quantity in 0..=i64::from(i32::MAX)) {
+        let wide = i128::from(price) * i128::from(quantity);
+        let expected = i64::try_from(wide).ok().map(Money::from_minor);
+        prop_assert_eq!(Money::from_minor(price).checked_mul(quantity), expected);
+    }
+
+    #[test]
+    fn sums_fail_exactly_when_the_total_leaves_i64(amounts in prop::collection::vec(any::<i64>(), 0..8)) {
+        let mut wide: i128 = 0;
+        let mut overflowed = false;
+        for amount in &amounts {
+            wide += i128::from(*amount);
+            overflowed |= i64::try_from(wide).is_err();
+        }
+        let sum = Money::checked_sum(amounts.iter().copied().map(Money::from_minor));
+        if overflowed {
+            prop_assert_eq!(sum, None);
+        } else {
+            prop_assert_eq!(sum, Some(Money::from_minor(wide as i64)));
+        }
+    }
+
+    #[test]
+    fn serializes_as_the_bare_integer(minor: i64) {
+        let money = Money::from_minor(minor);
+        prop_assert_eq!(json!(money), json!(minor));
+        prop_assert_eq!(serde_json::from_value::<Money>(json!(minor)).unwrap(), money);
+    }
+}
+
+#[test]
+fn amounts_format_in_the_given_currency() {
+    let money = Money::from_minor(123_450);
+    assert_eq!(money.format(&Currency::USD), "$1,234.50");
+    assert_eq!(money.format(&Currency::EUR), "€1.234,50");
+    assert_eq!(
+        Money::from_minor(550_000).format(&Currency::IDR),
+        "Rp 550.000"
+    );
+    assert_eq!(Money::from_minor(5).format(&Currency::GBP), "£0.05");
+    assert_eq!(Money::from_minor(-1_250).format(&Currency::USD), "-$12.50");
+    assert_eq!(
+        Money::from_minor(i64::MIN).format(&Currency::JPY),
+        "-¥9,223,372,036,854,775,808"
+    );
+}
+
+#[tokio::test]
+async fn prices_carry_a_display_string() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 123_450, 10).await;
+
+    let response = app.get(&format!("/api/v1/products/{}", mug), None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["price"], 123_450);
+    assert_eq!(response.body["data"]["price_display"], "$1,234.50");
+
+    let add = json!({ "product_id": mug, "quantity": 2 });
+    app.post("/api/v1/cart", Some(&buyer), add).await;
+    let response = app.get("/api/v1/cart", Some(&buyer)).await;
+    assert_eq!(response.body["data"]["summary"]["subtotal"], 246_900);
+    assert_eq!(
+        response.body["data"]["summary"]["subtotal_display"],
+        "$2,469.00"
+    );
+
+    let response = app
+        .post("/api/v1/orders/checkout", Some(&buyer), json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let order = &response.body["data"]["order"];
+    assert_eq!(order["total_amount"], 246_900);
+    assert_eq!(order["total_amount_display"], "$2,469.00");
+    assert_eq!(
+        response.body["data"]["items"][0]["price_display"],
+        "$1,234.50"
+    );
+}
+
+#[tokio::test]
+async fn totals_past_i64_are_rejected_not_wrapped() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let gold = app
+        .create_product(&admin, "Gold Bar", i64::MAX / 2, 10)
+        .await;
+    let add = json!({ "product_id": gold, "quantity": 3 });
+    let response = app.post("/api/v1/cart", Some(&buyer), add).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app.get("/api/v1/cart", Some(&buyer)).await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+
+    let response = app
+        .post("/api/v1/orders/checkout", Some(&buyer), json!({}))
+        .await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+    let orders: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM orders")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(orders, 0);
+}
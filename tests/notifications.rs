@@ -0,0 +1,215 @@
+//! Order emails go out through the `Notifier` in the state once the order has changed.
+
+mod common;
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use axum_ecommerce_api::{
+    money::Money,
+    notifier::{NoticeItem, Notifier, OrderEvent, OrderNotice, render},
+    state::AppState,
+};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use common::{TestApp, TestResponse};
+
+/// Keeps every call instead of sending anything.
+#[derive(Default)]
+struct RecordingNotifier(Mutex<Vec<(OrderEvent, OrderNotice)>>);
+
+impl RecordingNotifier {
+    fn record(&self, event: OrderEvent, notice: &OrderNotice) -> anyhow::Result<()> {
+        self.0.lock().unwrap().push((event, notice.clone()));
+        Ok(())
+    }
+
+    /// Waits for the `n`th call, as notifications are sent in the background.
+    async fn wait_for(&self, n: usize) -> Vec<(OrderEvent, OrderNotice)> {
+        for _ in 0..250 {
+            let calls = self.0.lock().unwrap().clone();
+            if calls.len() >= n {
+                return calls;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!(
+            "expected {} notifications, got {:?}",
+            n,
+            self.0.lock().unwrap()
+        );
+    }
+}
+
+#[async_trait]
+impl Notifier for RecordingNotifier {
+    async fn order_created(&self, notice: &OrderNotice) -> anyhow::Result<()> {
+        self.record(OrderEvent::Created, notice)
+    }
+
+    async fn order_paid(&self, notice: &OrderNotice) -> anyhow::Result<()> {
+        self.record(OrderEvent::Paid, notice)
+    }
+
+    async fn order_shipped(&self, notice: &OrderNotice) -> anyhow::Result<()> {
+        self.record(OrderEvent::Shipped, notice)
+    }
+}
+
+async fn post(router: &Router, uri: &str, token: &str, body: Value) -> TestResponse {
+    let request = Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    common::send(router, request).await
+}
+
+#[tokio::test]
+async fn customers_hear_about_their_order_being_placed_paid_and_shipped() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let recorder = Arc::new(RecordingNotifier::default());
+    let state = AppState {
+        notifier: recorder.clone(),
+        ..app.state.clone()
+    };
+    let router = axum_ecommerce_api::app(&app.config, state);
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let teapot = app.create_product(&admin, "Teapot", 4_000, 10).await;
+    for (product_id, quantity) in [(mug, 2), (teapot, 1)] {
+        let add = json!({ "product_id": product_id, "quantity": quantity });
+        app.post("/api/v1/cart", Some(&buyer), add).await;
+    }
+
+    let response = post(&router, "/api/v1/orders/checkout", &buyer, json!({})).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let order_id: Uuid = response.body["data"]["order"]["id"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    let calls = recorder.wait_for(1).await;
+    let (event, notice) = &calls[0];
+    assert_eq!(*event, OrderEvent::Created);
+    assert_eq!(
+        *notice,
+        OrderNotice {
+            order_id,
+            email: "buyer@example.com".into(),
+            recipient_name: None,
+            total_amount: Money::from_minor(6_500),
+            items: vec![
+                NoticeItem {
+                    product_name: "Ceramic Mug".into(),
+                    quantity: 2,
+                    price: Money::from_minor(1_250),
+                },
+                NoticeItem {
+                    product_name: "Teapot".into(),
+                    quantity: 1,
+                    price: Money::from_minor(4_000),
+                },
+            ],
+        }
+    );
+
+    let pay = format!("/api/v1/admin/orders/{}/pay", order_id);
+    let response = post(&router, &pay, &admin, json!({})).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let calls = recorder.wait_for(2).await;
+    assert_eq!(calls[1].0, OrderEvent::Paid);
+    assert_eq!(calls[1].1, calls[0].1);
+
+    let force = format!("/api/v1/admin/orders/{}/force-status", order_id);
+    let body = json!({ "status": "completed", "reason": "handed to the courier" });
+    let response = post(&router, &force, &admin, body).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let calls = recorder.wait_for(3).await;
+    assert_eq!(calls[2].0, OrderEvent::Shipped);
+    assert_eq!(calls[2].1.order_id, order_id);
+}
+
+#[tokio::test]
+async fn failed_requests_and_cancellations_send_nothing() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let recorder = Arc::new(RecordingNotifier::default());
+    let state = AppState {
+        notifier: recorder.clone(),
+        ..app.state.clone()
+    };
+    let router = axum_ecommerce_api::app(&app.config, state);
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+
+    // An empty cart makes no order.
+    let response = post(&router, "/api/v1/orders/checkout", &buyer, json!({})).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let add = json!({ "product_id": mug, "quantity": 1 });
+    app.post("/api/v1/cart", Some(&buyer), add).await;
+    let response = post(&router, "/api/v1/orders/checkout", &buyer, json!({})).await;
+    let order_id = response.body["data"]["order"]["id"].as_str().unwrap();
+    recorder.wait_for(1).await;
+    let force = format!("/api/v1/admin/orders/{}/force-status", order_id);
+    let body = json!({ "status": "cancelled", "reason": "customer called" });
+    let response = post(&router, &force, &admin, body).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let events: Vec<OrderEvent> = recorder
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(event, _)| *event)
+        .collect();
+    assert_eq!(events, [OrderEvent::Created]);
+}
+
+#[test]
+fn emails_name_the_order_its_lines_and_the_total() {
+    let order_id = Uuid::nil();
+    let notice = OrderNotice {
+        order_id,
+        email: "buyer@example.com".into(),
+        recipient_name: Some("Budi".into()),
+        total_amount: Money::from_minor(6_500),
+        items: vec![NoticeItem {
+            product_name: "Ceramic Mug".into(),
+            quantity: 2,
+            price: Money::from_minor(1_250),
+        }],
+    };
+    let (subject, body) = render(OrderEvent::Shipped, &notice);
+    assert_eq!(subject, format!("Your order is on its way {}", order_id));
+    assert!(body.starts_with("Hi Budi,"), "{}", body);
+    assert!(body.contains(&order_id.to_string()), "{}", body);
+    assert!(body.contains("2 x Ceramic Mug at $12.50"), "{}", body);
+    assert!(body.contains("Total: $65.00"), "{}", body);
+
+    let anonymous = OrderNotice {
+        recipient_name: None,
+        ..notice
+    };
+    let (subject, body) = render(OrderEvent::Created, &anonymous);
+    assert!(subject.starts_with("We received your order"));
+    assert!(body.starts_with("Hi there,"), "{}", body);
+}
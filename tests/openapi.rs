@@ -0,0 +1,168 @@
+mod common;
+
+use std::{collections::HashSet, env, process::Command};
+
+use axum::http::{Method, StatusCode};
+use axum_ecommerce_api::routes::doc::openapi_spec;
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use common::TestApp;
+
+/// `path` with every `{param}` filled in, e.g. `/api/products/{id}` -> `/api/products/<uuid>`.
+fn concrete(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            if segment.starts_with('{') {
+                Uuid::nil().to_string()
+            } else {
+                segment.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[test]
+fn exported_spec_is_json_with_the_product_paths_and_server() {
+    let path = env::temp_dir().join(format!("openapi-{}.json", Uuid::new_v4().simple()));
+    let status = Command::new(env!("CARGO_BIN_EXE_openapi"))
+        .arg(&path)
+        .env("APP_PUBLIC_URL", "https://shop.example.com")
+        .status()
+        .expect("cannot run the openapi binary");
+    assert!(status.success());
+
+    let spec: Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert!(
+        spec["paths"]["/api/v1/products"]["get"].is_object(),
+        "{}",
+        spec["paths"]
+    );
+    assert_eq!(spec["servers"][0]["url"], "https://shop.example.com");
+}
+
+#[tokio::test]
+async fn every_documented_path_is_served() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let spec = openapi_spec(&app.config.public_url);
+
+    // No route answers OPTIONS, so a served path says 405 where an unknown one hits the 404.
+    let response = app
+        .request(Method::OPTIONS, "/api/no-such-thing", None, None)
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    for path in spec.paths.paths.keys() {
+        let response = app
+            .request(Method::OPTIONS, &concrete(path), None, None)
+            .await;
+        assert_eq!(
+            response.status,
+            StatusCode::METHOD_NOT_ALLOWED,
+            "{} is documented but not routed: {}",
+            path,
+            response.body
+        );
+    }
+}
+
+#[tokio::test]
+async fn every_documented_get_reaches_a_handler_for_an_admin() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let product = json!({ "name": "Ceramic Mug", "description": "", "price": 1_250, "stock": 5, "sku": "MUG-1" });
+    let response = app.post("/api/products", Some(&admin), product).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let product = response.body["data"].clone();
+    let add = json!({ "product_id": product["id"], "quantity": 1 });
+    app.post("/api/cart", Some(&admin), add).await;
+    let response = app
+        .request(Method::POST, "/api/orders/checkout", Some(&admin), None)
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let order_id = response.body["data"]["order"]["id"].clone();
+    app.state.audit.flush().await;
+    let (audit_id,): (String,) = sqlx::query_as("SELECT id::text FROM audit_log LIMIT 1")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    let (user_id,): (String,) = sqlx::query_as("SELECT id::text FROM users LIMIT 1")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+
+    // Real ids throughout, so a 404 can only mean the documented path is not routed.
+    let value = |parent: &str, param: &str| match (parent, param) {
+        (_, "{slug}") => product["slug"].clone(),
+        (_, "{sku}") => product["sku"].clone(),
+        (_, "{product_id}") | ("products", "{id}") => product["id"].clone(),
+        ("orders", "{id}") => order_id.clone(),
+        ("audit-logs", "{id}") => json!(audit_id),
+        ("users", "{id}") => json!(user_id),
+        _ => panic!("no fixture for {} after /{}", param, parent),
+    };
+    let spec = openapi_spec(&app.config.public_url);
+    assert!(spec.paths.paths.contains_key("/api/v1/admin/orders/{id}"));
+    for (path, item) in &spec.paths.paths {
+        if item.get.is_none() {
+            continue;
+        }
+        let segments: Vec<&str> = path.split('/').collect();
+        let uri = segments
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| {
+                if segment.starts_with('{') {
+                    value(segments[i - 1], segment)
+                        .as_str()
+                        .unwrap()
+                        .to_string()
+                } else {
+                    segment.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let response = app.get(&uri, Some(&admin)).await;
+        assert_ne!(
+            response.status,
+            StatusCode::NOT_FOUND,
+            "GET {} (documented as {}): {}",
+            uri,
+            path,
+            response.body
+        );
+    }
+}
+
+/// Run with `INSTA_UPDATE=always` (or `cargo insta review`) after an intended spec change.
+#[test]
+fn spec_matches_the_snapshot() {
+    // The build info changes with every commit.
+    insta::assert_json_snapshot!(openapi_spec("http://127.0.0.1:3000"), {
+        r#".info["x-build"]"# => "[build]",
+    });
+}
+
+#[test]
+fn every_operation_has_a_unique_operation_id() {
+    let spec = serde_json::to_value(openapi_spec("http://127.0.0.1:3000")).unwrap();
+    let mut seen = HashSet::new();
+    for (path, item) in spec["paths"].as_object().unwrap() {
+        for (method, operation) in item.as_object().unwrap() {
+            let id = operation["operationId"].as_str().unwrap_or_default();
+            assert!(!id.is_empty(), "{} {} has no operationId", method, path);
+            assert!(
+                seen.insert(id.to_string()),
+                "operationId {} is used twice",
+                id
+            );
+        }
+    }
+}
@@ -0,0 +1,697 @@
+mod common;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use axum_ecommerce_api::{
+    models::OrderStatus, money::Money, routes::orders::OrderLimits, state::AppState,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use common::TestApp;
+
+const ORDERS: &str = "/api/v1/orders";
+
+/// Checks out one `product_id` for `token` and returns the new order's id.
+async fn checkout(app: &TestApp, token: &str, product_id: Uuid) -> Uuid {
+    let add = json!({ "product_id": product_id, "quantity": 1 });
+    app.post("/api/v1/cart", Some(token), add).await;
+    let response = app
+        .post(&format!("{}/checkout", ORDERS), Some(token), json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    response.body["data"]["order"]["id"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn statuses_serialize_in_lowercase() {
+    assert_eq!(json!(OrderStatus::Pending), json!("pending"));
+    assert_eq!(json!(OrderStatus::Completed), json!("completed"));
+    let paid: OrderStatus = serde_json::from_value(json!("paid")).unwrap();
+    assert_eq!(paid, OrderStatus::Paid);
+    assert!(paid.is_paid() && !OrderStatus::Pending.is_paid());
+    assert!(serde_json::from_value::<OrderStatus>(json!("shipped")).is_err());
+}
+
+#[tokio::test]
+async fn orders_filter_by_status_and_reject_unknown_ones() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let first = checkout(&app, &buyer, mug).await;
+    let second = checkout(&app, &buyer, mug).await;
+
+    // Paying an order moves it between the filters.
+    sqlx::query("UPDATE orders SET status = $2 WHERE id = $1")
+        .bind(first)
+        .bind(OrderStatus::Paid)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let response = app
+        .get(&format!("{}?status=paid", ORDERS), Some(&buyer))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let items = response.body["data"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["id"], first.to_string());
+    assert_eq!(items[0]["status"], "paid");
+    assert_eq!(response.body["meta"]["total"], 1);
+    let response = app
+        .get(&format!("{}?status=pending", ORDERS), Some(&buyer))
+        .await;
+    assert_eq!(response.body["data"]["items"][0]["id"], second.to_string());
+    let response = app.get(ORDERS, Some(&buyer)).await;
+    assert_eq!(response.body["meta"]["total"], 2);
+
+    let response = app
+        .get(&format!("{}?status=shipped", ORDERS), Some(&buyer))
+        .await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+
+    // The database refuses statuses the enum does not know.
+    let result = sqlx::query("UPDATE orders SET status = 'shipped' WHERE id = $1")
+        .bind(second)
+        .execute(&app.pool)
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn listings_carry_item_counts_and_embed_items_on_request() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let other = app.register("other@example.com").await;
+    let mug = app
+        .create_product(&admin, "Ceramic Mug", 1_250, 1_000)
+        .await;
+    let teapot = app.create_product(&admin, "Teapot", 4_000, 1_000).await;
+    let kettle = app.create_product(&admin, "Kettle", 6_000, 1_000).await;
+
+    // 20 orders of one to three lines each, newest last.
+    let products = [mug, teapot, kettle];
+    let mut expected = Vec::new();
+    for i in 0..20 {
+        let lines = i % 3 + 1;
+        for product_id in &products[..lines] {
+            let add = json!({ "product_id": product_id, "quantity": 2 });
+            app.post("/api/v1/cart", Some(&buyer), add).await;
+        }
+        let response = app
+            .post(&format!("{}/checkout", ORDERS), Some(&buyer), json!({}))
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+        let first_name = response.body["data"]["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .min_by_key(|item| item["id"].as_str().unwrap().to_string())
+            .map(|item| item["product_id"].clone())
+            .unwrap();
+        expected.push((
+            response.body["data"]["order"]["id"].clone(),
+            lines,
+            response.body["data"]["items"].clone(),
+            first_name,
+        ));
+    }
+    checkout(&app, &other, mug).await;
+
+    let response = app
+        .get(&format!("{}?per_page=20", ORDERS), Some(&buyer))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["meta"]["total"], 20);
+    let items = response.body["data"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 20);
+    let name = |product_id: &serde_json::Value| match product_id.as_str().unwrap() {
+        id if id == mug.to_string() => "Ceramic Mug",
+        id if id == teapot.to_string() => "Teapot",
+        _ => "Kettle",
+    };
+    for (listed, (id, lines, _, first)) in items.iter().zip(expected.iter().rev()) {
+        assert_eq!(&listed["id"], id);
+        assert_eq!(listed["item_count"], *lines);
+        assert_eq!(listed["first_item_name"], name(first));
+        assert!(listed.get("items").is_none(), "{}", listed);
+    }
+
+    let response = app
+        .get(
+            &format!("{}?include=items&per_page=20", ORDERS),
+            Some(&buyer),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let items = response.body["data"]["items"].as_array().unwrap();
+    for (listed, (id, _, lines, _)) in items.iter().zip(expected.iter().rev()) {
+        assert_eq!(&listed["id"], id);
+        let mut embedded = listed["items"].as_array().unwrap().clone();
+        let mut lines = lines.as_array().unwrap().clone();
+        embedded.sort_by_key(|item| item["id"].as_str().unwrap().to_string());
+        lines.sort_by_key(|item| item["id"].as_str().unwrap().to_string());
+        assert_eq!(embedded, lines);
+    }
+
+    // Admins see everyone's orders the same way.
+    let response = app
+        .get(
+            "/api/v1/admin/orders?include=items&status=pending",
+            Some(&admin),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["meta"]["total"], 21);
+    let newest = &response.body["data"]["items"][0];
+    assert_eq!(newest["item_count"], 1);
+    assert_eq!(newest["first_item_name"], "Ceramic Mug");
+    assert_eq!(newest["items"].as_array().unwrap().len(), 1);
+
+    let response = app
+        .get(&format!("{}?include=reviews", ORDERS), Some(&buyer))
+        .await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+}
+
+#[tokio::test]
+async fn order_listings_are_paginated() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let buyer_id: Uuid = sqlx::query_scalar("SELECT id FROM users WHERE email = $1")
+        .bind("buyer@example.com")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO orders (id, user_id, total_amount, status, created_at)
+        SELECT gen_random_uuid(), $1, n * 100, 'completed', now() - make_interval(days => n)
+        FROM generate_series(1, 12) AS n
+        "#,
+    )
+    .bind(buyer_id)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let user_orders = format!("/api/v1/admin/users/{}/orders", buyer_id);
+    for (uri, token) in [
+        (ORDERS, &buyer),
+        ("/api/v1/admin/orders", &admin),
+        (user_orders.as_str(), &admin),
+    ] {
+        // Ten by default, newest first.
+        let response = app.get(uri, Some(token)).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+        let items = response.body["data"]["items"].as_array().unwrap();
+        assert_eq!(items.len(), 10, "{}", uri);
+        assert_eq!(items[0]["total_amount"], 100);
+        let meta = &response.body["meta"];
+        assert_eq!(
+            (&meta["total"], &meta["total_pages"]),
+            (&json!(12), &json!(2))
+        );
+        assert_eq!(meta["has_next"], true);
+
+        let page = format!("{}?per_page=5&page=3&include=items", uri);
+        let response = app.get(&page, Some(token)).await;
+        let items = response.body["data"]["items"].as_array().unwrap();
+        let totals: Vec<_> = items.iter().map(|o| o["total_amount"].clone()).collect();
+        assert_eq!(totals, [json!(1_100), json!(1_200)], "{}", uri);
+        assert_eq!(items[0]["items"], json!([]));
+        assert_eq!(response.body["meta"]["has_next"], false);
+
+        let uncounted = format!("{}?per_page=5&with_total=false", uri);
+        let response = app.get(&uncounted, Some(token)).await;
+        assert_eq!(response.body["meta"]["total"], json!(null), "{}", uri);
+        assert_eq!(response.body["meta"]["has_next"], true);
+    }
+}
+
+#[tokio::test]
+async fn a_fifty_line_cart_checks_out_in_one_go() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mut lines = Vec::new();
+    for i in 0..50 {
+        let price = 100 + i64::from(i) * 10;
+        let quantity = i % 3 + 1;
+        let product_id = app
+            .create_product(&admin, &format!("Product {:02}", i), price, 5)
+            .await;
+        let add = json!({ "product_id": product_id, "quantity": quantity });
+        app.post("/api/v1/cart", Some(&buyer), add).await;
+        lines.push((product_id, quantity, price));
+    }
+
+    let response = app
+        .post(&format!("{}/checkout", ORDERS), Some(&buyer), json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let total: i64 = lines.iter().map(|(_, q, p)| i64::from(*q) * p).sum();
+    assert_eq!(response.body["data"]["order"]["total_amount"], total);
+    let items = response.body["data"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 50);
+    for (item, (product_id, quantity, price)) in items.iter().zip(&lines) {
+        assert_eq!(item["product_id"], product_id.to_string());
+        assert_eq!(item["quantity"], *quantity);
+        assert_eq!(item["price"], *price);
+    }
+
+    let reserved: Vec<(Uuid, i32)> =
+        sqlx::query_as("SELECT id, reserved FROM products WHERE reserved > 0")
+            .fetch_all(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(reserved.len(), 50);
+    for (product_id, quantity, _) in &lines {
+        assert!(reserved.contains(&(*product_id, *quantity)));
+    }
+    let response = app.get("/api/v1/cart", Some(&buyer)).await;
+    assert_eq!(response.body["data"]["items"], json!([]));
+}
+
+#[tokio::test]
+async fn one_short_line_fails_the_whole_checkout() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mut short = None;
+    for i in 0..50 {
+        let product_id = app
+            .create_product(&admin, &format!("Product {:02}", i), 100, 5)
+            .await;
+        let add = json!({ "product_id": product_id, "quantity": 2 });
+        app.post("/api/v1/cart", Some(&buyer), add).await;
+        if i == 37 {
+            short = Some(product_id);
+        }
+    }
+    // Someone else takes most of one product after it went into the cart.
+    sqlx::query("UPDATE products SET reserved = 4 WHERE id = $1")
+        .bind(short)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let response = app
+        .post(&format!("{}/checkout", ORDERS), Some(&buyer), json!({}))
+        .await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+    assert_eq!(response.body["data"]["error_code"], "INSUFFICIENT_STOCK");
+    assert!(
+        response.body["message"]
+            .as_str()
+            .unwrap()
+            .contains(&short.unwrap().to_string())
+    );
+    let (orders, items, reserved): (i64, i64, i64) = sqlx::query_as(
+        "SELECT (SELECT count(*) FROM orders), (SELECT count(*) FROM order_items), \
+         (SELECT COALESCE(SUM(reserved), 0) FROM products)",
+    )
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!((orders, items, reserved), (0, 0, 4));
+    let response = app.get("/api/v1/cart", Some(&buyer)).await;
+    assert_eq!(response.body["data"]["items"].as_array().unwrap().len(), 50);
+}
+
+#[tokio::test]
+async fn order_details_load_every_line_or_none() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let other = app.register("other@example.com").await;
+    let mut products = Vec::new();
+    for (i, price) in [300, 150, 75].into_iter().enumerate() {
+        let product_id = app
+            .create_product(&admin, &format!("Product {}", i), price, 10)
+            .await;
+        let add = json!({ "product_id": product_id, "quantity": i + 1 });
+        app.post("/api/v1/cart", Some(&buyer), add).await;
+        products.push(product_id);
+    }
+    let response = app
+        .post(&format!("{}/checkout", ORDERS), Some(&buyer), json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let order_id = response.body["data"]["order"]["id"].as_str().unwrap();
+    let created = response.body["data"].clone();
+
+    for (uri, token) in [
+        (format!("{}/{}", ORDERS, order_id), &buyer),
+        (format!("/api/v1/admin/orders/{}", order_id), &admin),
+    ] {
+        let response = app.get(&uri, Some(token)).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+        assert_eq!(response.body["data"]["order"], created["order"]);
+        let mut items = response.body["data"]["items"].as_array().unwrap().clone();
+        let mut expected = created["items"].as_array().unwrap().clone();
+        let by_id = |item: &serde_json::Value| item["id"].as_str().unwrap().to_string();
+        items.sort_by_key(by_id);
+        expected.sort_by_key(by_id);
+        assert_eq!(items, expected);
+        assert_eq!(items.len(), 3);
+    }
+    let response = app
+        .get(&format!("{}/{}", ORDERS, order_id), Some(&other))
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    // An order without lines still comes back, with an empty item list.
+    let (buyer_id,): (Uuid,) = sqlx::query_as("SELECT id FROM users WHERE email = $1")
+        .bind("buyer@example.com")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    let empty = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO orders (id, user_id, total_amount, status) VALUES ($1, $2, 0, 'pending')",
+    )
+    .bind(empty)
+    .bind(buyer_id)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+    for (uri, token) in [
+        (format!("{}/{}", ORDERS, empty), &buyer),
+        (format!("/api/v1/admin/orders/{}", empty), &admin),
+    ] {
+        let response = app.get(&uri, Some(token)).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+        assert_eq!(response.body["data"]["order"]["id"], empty.to_string());
+        assert_eq!(response.body["data"]["items"], json!([]));
+    }
+    let response = app
+        .get(
+            &format!("/api/v1/admin/orders/{}", Uuid::new_v4()),
+            Some(&admin),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+/// `(id, customer_email)` of the orders `/admin/orders/search` returns for `query`.
+async fn search(app: &TestApp, admin: &str, query: &str) -> Vec<(String, String)> {
+    let uri = format!("/api/v1/admin/orders/search?{}", query);
+    let response = app.get(&uri, Some(admin)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    response.body["data"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|o| {
+            (
+                o["id"].as_str().unwrap().to_string(),
+                o["customer_email"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn admins_find_orders_by_a_fragment_of_the_customer_email() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let jane = app.register("Jane.Doe@example.com").await;
+    let john = app.register("john_smith@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 50).await;
+    let first = checkout(&app, &jane, mug).await;
+    let second = checkout(&app, &jane, mug).await;
+    checkout(&app, &john, mug).await;
+
+    let jane_email = "Jane.Doe@example.com".to_string();
+    assert_eq!(
+        search(&app, &admin, "email=jane.doe@").await,
+        [
+            (second.to_string(), jane_email.clone()),
+            (first.to_string(), jane_email.clone())
+        ]
+    );
+    let page = app
+        .get(
+            "/api/v1/admin/orders/search?email=JANE&per_page=1&page=2",
+            Some(&admin),
+        )
+        .await;
+    assert_eq!(page.body["data"]["items"][0]["id"], first.to_string());
+    assert_eq!(page.body["data"]["items"][0]["item_count"], 1);
+    assert_eq!(page.body["meta"]["total"], 2);
+    assert_eq!(search(&app, &admin, "email=example.com").await.len(), 3);
+    // `_` and `%` match themselves only.
+    assert_eq!(search(&app, &admin, "email=n_s").await.len(), 1);
+    assert!(search(&app, &admin, "email=e_d").await.is_empty());
+    assert!(search(&app, &admin, "email=%25").await.is_empty());
+    assert!(
+        search(&app, &admin, "email=jane&status=paid")
+            .await
+            .is_empty()
+    );
+
+    let response = app
+        .get("/api/v1/admin/orders/search?email=%20", Some(&admin))
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    let response = app
+        .get("/api/v1/admin/orders/search?email=jane", Some(&jane))
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    // Buyers' own listings stay without emails.
+    let response = app.get(ORDERS, Some(&jane)).await;
+    assert!(
+        response.body["data"]["items"][0]
+            .get("customer_email")
+            .is_none()
+    );
+}
+
+async fn order_count(app: &TestApp) -> i64 {
+    sqlx::query_scalar("SELECT count(*) FROM orders")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn an_overflowing_total_is_refused() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let product_id = app.create_product(&admin, "Priceless", 100, 10).await;
+    let add = json!({ "product_id": product_id, "quantity": 3 });
+    app.post("/api/v1/cart", Some(&buyer), add).await;
+    // Three of these do not fit in an i64.
+    sqlx::query("UPDATE products SET price = $1 WHERE id = $2")
+        .bind(i64::MAX / 2)
+        .bind(product_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let response = app
+        .post(&format!("{}/checkout", ORDERS), Some(&buyer), json!({}))
+        .await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+    assert!(
+        response.body["message"]
+            .as_str()
+            .unwrap()
+            .contains("order total exceeds limits")
+    );
+    assert_eq!(order_count(&app).await, 0);
+}
+
+#[tokio::test]
+async fn checkout_enforces_the_configured_order_limits() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let cheap = app.create_product(&admin, "Cheap", 100, 50).await;
+    let pricey = app.create_product(&admin, "Pricey", 4_000, 50).await;
+    let state = AppState {
+        order_limits: OrderLimits {
+            max_total: Money::from_minor(5_000),
+            max_items: 3,
+        },
+        ..app.state.clone()
+    };
+    let router = axum_ecommerce_api::app(&app.config, state);
+    let checkout = || {
+        Request::builder()
+            .method("POST")
+            .uri(format!("{}/checkout", ORDERS))
+            .header(header::AUTHORIZATION, format!("Bearer {}", buyer))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from("{}"))
+            .unwrap()
+    };
+
+    // Four units, although only 400 in total.
+    let add = json!({ "product_id": cheap, "quantity": 4 });
+    app.post("/api/v1/cart", Some(&buyer), add).await;
+    let response = common::send(&router, checkout()).await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+    assert!(
+        response.body["message"]
+            .as_str()
+            .unwrap()
+            .contains("at most 3")
+    );
+
+    // Three units, but 8,100 in total.
+    for (product_id, quantity) in [(cheap, 1), (pricey, 2)] {
+        let add = json!({ "product_id": product_id, "quantity": quantity });
+        app.post("/api/v1/cart", Some(&buyer), add).await;
+    }
+    let response = common::send(&router, checkout()).await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+    assert!(
+        response.body["message"]
+            .as_str()
+            .unwrap()
+            .contains("order total exceeds limits")
+    );
+    assert_eq!(order_count(&app).await, 0);
+
+    let add = json!({ "product_id": pricey, "quantity": 1 });
+    app.post("/api/v1/cart", Some(&buyer), add).await;
+    let response = common::send(&router, checkout()).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    assert_eq!(response.body["data"]["order"]["total_amount"], 4_100);
+}
+
+#[tokio::test]
+async fn simultaneous_checkouts_of_one_cart_make_one_order() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let product_id = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let add = json!({ "product_id": product_id, "quantity": 2 });
+    app.post("/api/v1/cart", Some(&buyer), add).await;
+
+    let uri = format!("{}/checkout", ORDERS);
+    let (first, second) = tokio::join!(
+        app.post(&uri, Some(&buyer), json!({})),
+        app.post(&uri, Some(&buyer), json!({})),
+    );
+    let (winner, loser) = if first.status == StatusCode::CREATED {
+        (first, second)
+    } else {
+        (second, first)
+    };
+    assert_eq!(winner.status, StatusCode::CREATED, "{}", winner.body);
+    // The loser either ran into the winner's lock or found the cart already emptied.
+    let code = &loser.body["data"]["error_code"];
+    assert!(
+        (loser.status == StatusCode::CONFLICT && code == "CHECKOUT_IN_PROGRESS")
+            || (loser.status == StatusCode::BAD_REQUEST && code == "CART_EMPTY"),
+        "{} {}",
+        loser.status,
+        loser.body
+    );
+    assert_eq!(order_count(&app).await, 1);
+    let reserved: i32 = sqlx::query_scalar("SELECT reserved FROM products WHERE id = $1")
+        .bind(product_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(reserved, 2);
+}
+
+#[tokio::test]
+async fn a_checkout_still_running_turns_the_next_one_away() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let product_id = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let add = json!({ "product_id": product_id, "quantity": 1 });
+    app.post("/api/v1/cart", Some(&buyer), add).await;
+    let user_id: Uuid = sqlx::query_scalar("SELECT id FROM users WHERE email = $1")
+        .bind("buyer@example.com")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+
+    // Hold the lock a checkout of this user takes, as one in flight would.
+    let mut running = app.pool.begin().await.unwrap();
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended('checkout:' || $1::text, 0))")
+        .bind(user_id)
+        .execute(&mut *running)
+        .await
+        .unwrap();
+    let response = app
+        .post(&format!("{}/checkout", ORDERS), Some(&buyer), json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT, "{}", response.body);
+    assert_eq!(response.body["data"]["error_code"], "CHECKOUT_IN_PROGRESS");
+    assert_eq!(order_count(&app).await, 0);
+
+    running.rollback().await.unwrap();
+    let response = app
+        .post(&format!("{}/checkout", ORDERS), Some(&buyer), json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+}
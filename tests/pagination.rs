@@ -0,0 +1,266 @@
+mod common;
+
+use axum::http::StatusCode;
+use axum_ecommerce_api::response::Meta;
+use serde_json::json;
+
+use common::TestApp;
+
+/// `(total_pages, has_next, has_prev)` of a page.
+fn pages(meta: Meta) -> (Option<i64>, Option<bool>, Option<bool>) {
+    (meta.total_pages, meta.has_next, meta.has_prev)
+}
+
+#[test]
+fn page_counts_round_up_at_the_boundaries() {
+    // Nothing at all: no pages, nothing either side.
+    assert_eq!(
+        pages(Meta::new(1, 10, 0)),
+        (Some(0), Some(false), Some(false))
+    );
+    // An exact multiple fills the last page and adds no empty one.
+    assert_eq!(
+        pages(Meta::new(1, 10, 20)),
+        (Some(2), Some(true), Some(false))
+    );
+    assert_eq!(
+        pages(Meta::new(2, 10, 20)),
+        (Some(2), Some(false), Some(true))
+    );
+    // One row more spills onto a page of its own.
+    assert_eq!(
+        pages(Meta::new(2, 10, 21)),
+        (Some(3), Some(true), Some(true))
+    );
+    assert_eq!(
+        pages(Meta::new(3, 10, 21)),
+        (Some(3), Some(false), Some(true))
+    );
+    // Past the end there is nothing next, but still a way back.
+    assert_eq!(
+        pages(Meta::new(5, 10, 21)),
+        (Some(3), Some(false), Some(true))
+    );
+}
+
+#[test]
+fn unpaginated_lists_fill_in_what_they_know() {
+    // Whole lists pass `per_page = total`.
+    assert_eq!(
+        pages(Meta::new(1, 0, 0)),
+        (Some(0), Some(false), Some(false))
+    );
+    assert_eq!(
+        pages(Meta::new(1, 7, 7)),
+        (Some(1), Some(false), Some(false))
+    );
+
+    let meta = Meta::empty();
+    assert_eq!((meta.page, meta.per_page, meta.total), (None, None, None));
+    assert_eq!(pages(meta), (None, None, None));
+}
+
+#[test]
+fn pages_read_one_row_ahead_to_know_whats_next() {
+    // A full page plus the extra row: there is more, and the extra row goes.
+    let mut items: Vec<i32> = (0..11).collect();
+    let meta = Meta::for_page(1, 10, None, &mut items);
+    assert_eq!(items.len(), 10);
+    assert_eq!(pages(meta.clone()), (None, Some(true), Some(false)));
+    assert_eq!(meta.total, None);
+    // Exactly a full page: that was the last one.
+    let mut items: Vec<i32> = (0..10).collect();
+    let meta = Meta::for_page(3, 10, None, &mut items);
+    assert_eq!(items.len(), 10);
+    assert_eq!(pages(meta), (None, Some(false), Some(true)));
+    // With a count, the totals come back.
+    let mut items: Vec<i32> = (0..11).collect();
+    let meta = Meta::for_page(1, 10, Some(25), &mut items);
+    assert_eq!(meta.total, Some(25));
+    assert_eq!(pages(meta), (Some(3), Some(true), Some(false)));
+}
+
+#[tokio::test]
+async fn listings_can_skip_the_count() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    for i in 0..5 {
+        app.create_product(&admin, &format!("Mug {}", i), 1_000, 3)
+            .await;
+    }
+
+    let page = |page: i64| {
+        let app = &app;
+        async move {
+            let uri = format!("/api/v1/products?per_page=2&page={}&with_total=false", page);
+            let response = app.get(&uri, None).await;
+            assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+            response.body
+        }
+    };
+    for (number, len, has_next) in [(1, 2, true), (2, 2, true), (3, 1, false), (4, 0, false)] {
+        let body = page(number).await;
+        assert_eq!(body["data"]["items"].as_array().unwrap().len(), len);
+        assert_eq!(body["meta"]["has_next"], has_next, "page {}", number);
+        assert_eq!(body["meta"]["has_prev"], number > 1);
+        assert_eq!(body["meta"]["total"], json!(null));
+        assert_eq!(body["meta"]["total_pages"], json!(null));
+    }
+    // Exactly full last page: no phantom next page, and no cursor to one.
+    let response = app
+        .get("/api/v1/products?per_page=5&with_total=false", None)
+        .await;
+    assert_eq!(response.body["meta"]["has_next"], false);
+    assert!(response.body["meta"].get("next_cursor").is_none());
+
+    // The count stays the default.
+    let response = app.get("/api/v1/products?per_page=2", None).await;
+    assert_eq!(response.body["meta"]["total"], 5);
+    assert_eq!(response.body["meta"]["total_pages"], 3);
+
+    // Every paginated listing takes the flag.
+    app.register("buyer@example.com").await;
+    app.state.audit.flush().await;
+    for uri in [
+        "/api/v1/admin/users?per_page=1&with_total=false",
+        "/api/v1/admin/audit-logs?per_page=1&with_total=false",
+        "/api/v1/admin/inventory/low-stock?threshold=10&per_page=1&with_total=false",
+    ] {
+        let response = app.get(uri, Some(&admin)).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+        assert_eq!(response.body["meta"]["total"], json!(null), "{}", uri);
+        assert_eq!(response.body["meta"]["has_next"], true, "{}", uri);
+        assert_eq!(response.body["data"]["items"].as_array().unwrap().len(), 1);
+    }
+    let response = app.get("/api/v1/products?with_total=maybe", None).await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+}
+
+/// The `(field, code)` pairs of a 400 for bad query parameters.
+fn invalid_params(response: &common::TestResponse) -> Vec<(String, String)> {
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+    assert_eq!(response.body["data"]["error_code"], "BAD_REQUEST");
+    response.body["data"]["errors"]
+        .as_array()
+        .unwrap_or_else(|| panic!("no field errors: {}", response.body))
+        .iter()
+        .map(|e| {
+            (
+                e["field"].as_str().unwrap().to_string(),
+                e["code"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn malformed_query_parameters_are_named_in_the_error() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let invalid = |field: &str| vec![(field.to_string(), "invalid".to_string())];
+    for (query, field) in [
+        ("page=abc", "page"),
+        ("per_page=1.5", "per_page"),
+        ("page=99999999999999999999", "page"),
+        ("sort_by=banana", "sort_by"),
+        ("order=sideways", "order"),
+        ("in_stock=perhaps", "in_stock"),
+        ("min_price=cheap", "min_price"),
+    ] {
+        for prefix in ["/api/v1", "/api"] {
+            let uri = format!("{}/products?{}", prefix, query);
+            let response = app.get(&uri, None).await;
+            assert_eq!(invalid_params(&response), invalid(field), "{}", uri);
+            let message = response.body["message"].as_str().unwrap();
+            assert!(message.contains(field), "{}: {}", uri, message);
+        }
+    }
+    let response = app.get("/api/v1/products?sort_by=banana", None).await;
+    let detail = response.body["data"]["errors"][0]["message"]
+        .as_str()
+        .unwrap();
+    assert!(detail.contains("unknown variant `banana`"), "{}", detail);
+    let response = app.get("/api/v1/products?page=abc", None).await;
+    assert!(
+        response.body["meta"]["request_id"].is_string(),
+        "{}",
+        response.body
+    );
+
+    // Other listings go through the same extractor.
+    let admin = app.register_admin("admin@example.com").await;
+    let response = app
+        .get("/api/v1/admin/users?with_total=sometimes", Some(&admin))
+        .await;
+    assert_eq!(invalid_params(&response), invalid("with_total"));
+}
+
+#[tokio::test]
+async fn out_of_range_pages_are_refused() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let out_of_range = |fields: &[&str]| -> Vec<(String, String)> {
+        fields
+            .iter()
+            .map(|f| (f.to_string(), "out_of_range".to_string()))
+            .collect()
+    };
+    for (query, fields) in [
+        ("page=0", &["page"][..]),
+        ("page=-3", &["page"]),
+        ("per_page=0", &["per_page"]),
+        ("per_page=-5", &["per_page"]),
+        ("per_page=101", &["per_page"]),
+        ("per_page=500", &["per_page"]),
+        ("page=0&per_page=-5", &["page", "per_page"]),
+        ("page=9223372036854775807", &["page"]),
+    ] {
+        for uri in [
+            format!("/api/v1/products?{}", query),
+            format!("/api/v1/admin/users?{}", query),
+            format!("/api/v1/admin/audit-logs?{}", query),
+        ] {
+            let response = app.get(&uri, Some(&admin)).await;
+            assert_eq!(invalid_params(&response), out_of_range(fields), "{}", uri);
+        }
+    }
+
+    // The maximum itself is fine.
+    for uri in [
+        "/api/v1/products?per_page=100",
+        "/api/v1/admin/users?per_page=100",
+    ] {
+        let response = app.get(uri, Some(&admin)).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+        assert_eq!(response.body["meta"]["per_page"], 100, "{}", uri);
+    }
+    let response = app
+        .get("/api/v1/admin/users?per_page=101", Some(&admin))
+        .await;
+    assert!(
+        response.body["message"]
+            .as_str()
+            .unwrap()
+            .contains("per_page"),
+        "{}",
+        response.body
+    );
+    let response = app.get("/api/v1/products?page=2&per_page=1", None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["meta"]["page"], 2);
+}
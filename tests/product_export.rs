@@ -0,0 +1,229 @@
+mod common;
+
+use axum::http::{StatusCode, header};
+use chrono::{Duration, Utc};
+use serde_json::{Value, json};
+
+use common::TestApp;
+
+/// Data rows of an exported CSV document, without the header.
+fn csv_rows(body: &Value) -> Vec<csv::StringRecord> {
+    let text = body.as_str().expect("export is not text");
+    csv::Reader::from_reader(text.as_bytes())
+        .records()
+        .map(|record| record.unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn export_streams_every_matching_product() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    // Enough rows to take several chunks, a third of them unpublished.
+    sqlx::query(
+        "INSERT INTO products (id, name, slug, price, stock, is_published) \
+         SELECT gen_random_uuid(), \
+                CASE WHEN n % 2 = 0 THEN 'Mug ' ELSE 'Plate ' END || n, \
+                'product-' || n, n * 10, n % 7, n % 3 <> 0 \
+         FROM generate_series(1, 1300) AS n",
+    )
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let response = app
+        .get(
+            "/api/admin/products/export?q=mug&min_price=2000",
+            Some(&admin),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        response.headers[header::CONTENT_TYPE],
+        "text/csv; charset=utf-8"
+    );
+    let disposition = response.headers[header::CONTENT_DISPOSITION]
+        .to_str()
+        .unwrap();
+    assert!(
+        disposition.starts_with("attachment; filename=\"products-"),
+        "{}",
+        disposition
+    );
+
+    let rows = csv_rows(&response.body);
+    let expected: i64 = sqlx::query_scalar(
+        "SELECT count(*) FROM products WHERE name ILIKE '%mug%' AND price >= 2000",
+    )
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert!(expected > 500, "{}", expected);
+    assert_eq!(rows.len() as i64, expected);
+    assert!(rows.iter().all(|row| row[1].starts_with("Mug ")));
+    // Prices stay in minor units, not formatted for display.
+    for row in &rows {
+        let n: i64 = row[1].trim_start_matches("Mug ").parse().unwrap();
+        assert_eq!(&row[5], (n * 10).to_string(), "{:?}", row);
+    }
+    // Every row once, with no chunk repeated or skipped.
+    let mut ids: Vec<&str> = rows.iter().map(|row| row.get(0).unwrap()).collect();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), rows.len());
+
+    let response = app.get("/api/admin/products/export", Some(&admin)).await;
+    assert_eq!(csv_rows(&response.body).len(), 1300);
+
+    let user = app.register("user@example.com").await;
+    let response = app.get("/api/admin/products/export", Some(&user)).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    // Each export is audited with the filters it was asked for; the refused one is not.
+    app.state.audit.flush().await;
+    let details: Vec<(Value,)> = sqlx::query_as(
+        "SELECT details FROM audit_log WHERE action = 'product.export' ORDER BY created_at, id",
+    )
+    .fetch_all(&app.pool)
+    .await
+    .unwrap();
+    let filters = |q: Value, min_price: Value| {
+        json!({
+            "q": q, "min_price": min_price, "max_price": null,
+            "category": null, "sku": null, "in_stock": null,
+        })
+    };
+    assert_eq!(
+        details,
+        [
+            (filters(json!("mug"), json!(2000)),),
+            (filters(Value::Null, Value::Null),),
+        ]
+    );
+}
+
+const LOW_STOCK_EXPORT: &str = "/api/v1/admin/inventory/low-stock/export";
+
+#[tokio::test]
+async fn low_stock_export_matches_the_listing_row_for_row() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    // Several chunks' worth, with repeated names and availabilities to test the keyset.
+    sqlx::query(
+        "INSERT INTO products (id, name, slug, sku, price, stock) \
+         SELECT gen_random_uuid(), 'Item ' || n % 40, 'item-' || n, 'SKU-' || n, 100, n % 9 \
+         FROM generate_series(1, 1400) AS n",
+    )
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let response = app
+        .get(&format!("{}?threshold=6", LOW_STOCK_EXPORT), Some(&admin))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let disposition = response.headers[header::CONTENT_DISPOSITION]
+        .to_str()
+        .unwrap();
+    assert!(disposition.starts_with("attachment; filename=\"low-stock-"));
+    let text = response.body.as_str().unwrap();
+    assert!(
+        text.starts_with("sku,name,stock,reserved,threshold,last_30d_units_sold\n"),
+        "{}",
+        text
+    );
+    let exported: Vec<(String, String)> = csv_rows(&response.body)
+        .iter()
+        .map(|row| (row[0].to_string(), row[1].to_string()))
+        .collect();
+    assert!(csv_rows(&response.body).iter().all(|row| &row[4] == "6"));
+
+    let mut listed = Vec::new();
+    for page in 1.. {
+        let uri = format!(
+            "/api/v1/admin/inventory/low-stock?threshold=6&per_page=100&page={}",
+            page
+        );
+        let response = app.get(&uri, Some(&admin)).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+        for item in response.body["data"]["items"].as_array().unwrap() {
+            listed.push((
+                item["sku"].as_str().unwrap().to_string(),
+                item["name"].as_str().unwrap().to_string(),
+            ));
+        }
+        if response.body["meta"]["has_next"] != true {
+            break;
+        }
+    }
+    assert!(listed.len() > 900, "{}", listed.len());
+    assert_eq!(exported, listed);
+}
+
+#[tokio::test]
+async fn low_stock_export_counts_paid_sales_of_the_last_30_days() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 20).await;
+    app.create_product(&admin, "Teapot", 4_000, 50).await;
+
+    // (quantity, status, days ago): only the paid ones within 30 days count.
+    for (quantity, status, days_ago) in [
+        (2, "paid", 1),
+        (3, "completed", 29),
+        (4, "paid", 31),
+        (5, "pending", 0),
+        (1, "cancelled", 0),
+    ] {
+        let add = json!({ "product_id": mug, "quantity": quantity });
+        app.post("/api/v1/cart", Some(&buyer), add).await;
+        let response = app
+            .post("/api/v1/orders/checkout", Some(&buyer), json!({}))
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+        let id = response.body["data"]["order"]["id"]
+            .as_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        sqlx::query("UPDATE orders SET status = $2 WHERE id = $1")
+            .bind(id)
+            .bind(status)
+            .execute(&app.pool)
+            .await
+            .unwrap();
+        app.backdate("orders", id, Utc::now() - Duration::days(days_ago))
+            .await;
+    }
+    // The pending order still holds 5 of the 20: 15 available.
+    let response = app
+        .get(&format!("{}?threshold=16", LOW_STOCK_EXPORT), Some(&admin))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let rows = csv_rows(&response.body);
+    assert_eq!(rows.len(), 1);
+    let row: Vec<&str> = rows[0].iter().collect();
+    assert_eq!(row, ["", "Ceramic Mug", "20", "15", "16", "5"]);
+
+    app.state.audit.flush().await;
+    let details: Vec<(Value,)> =
+        sqlx::query_as("SELECT details FROM audit_log WHERE action = 'product.low_stock_export'")
+            .fetch_all(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(details, [(json!({ "threshold": 16 }),)]);
+
+    let response = app
+        .get(&format!("{}?threshold=-1", LOW_STOCK_EXPORT), Some(&admin))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    let response = app.get(LOW_STOCK_EXPORT, Some(&buyer)).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+}
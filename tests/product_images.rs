@@ -0,0 +1,200 @@
+mod common;
+
+use std::path::Path;
+
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode, header},
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use common::{TestApp, TestResponse};
+
+const BOUNDARY: &str = "test-boundary";
+
+/// Uploads `bytes` as the `file` field with `content_type`, and `alt` when given.
+async fn upload(
+    app: &TestApp,
+    token: &str,
+    product: Uuid,
+    content_type: &str,
+    bytes: &[u8],
+    alt: Option<&str>,
+) -> TestResponse {
+    let mut body = Vec::new();
+    if let Some(alt) = alt {
+        body.extend_from_slice(
+            format!(
+                "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"alt\"\r\n\r\n{alt}\r\n"
+            )
+            .as_bytes(),
+        );
+    }
+    body.extend_from_slice(
+        format!(
+            "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"upload\"\r\n\
+             Content-Type: {content_type}\r\n\r\n"
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(bytes);
+    body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/api/products/{}/images", product))
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(
+            header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={BOUNDARY}"),
+        )
+        .body(Body::from(body))
+        .unwrap();
+    app.send(request).await
+}
+
+/// Where the local storage keeps the file behind `url`.
+fn stored_path(app: &TestApp, url: &str) -> std::path::PathBuf {
+    let key = url
+        .strip_prefix(&format!("{}/", app.config.upload_base_url))
+        .unwrap();
+    Path::new(&app.config.upload_dir).join(key)
+}
+
+#[tokio::test]
+async fn images_are_uploaded_ordered_and_deleted() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+
+    let front = upload(&app, &admin, mug, "image/png", b"front", Some("Front")).await;
+    assert_eq!(front.status, StatusCode::OK, "{}", front.body);
+    assert_eq!(front.body["data"]["alt"], "Front");
+    assert_eq!(front.body["data"]["position"], 0);
+    let front_url = front.body["data"]["url"].as_str().unwrap().to_string();
+    assert!(front_url.ends_with(".png"), "{}", front_url);
+    assert!(front.body["data"].get("storage_key").is_none());
+    let front_file = stored_path(&app, &front_url);
+    assert_eq!(std::fs::read(&front_file).unwrap(), b"front");
+
+    let back = upload(&app, &admin, mug, "image/jpeg", b"back", None).await;
+    assert_eq!(back.status, StatusCode::OK, "{}", back.body);
+    assert_eq!(back.body["data"]["position"], 1);
+    assert_eq!(back.body["data"]["alt"], json!(null));
+
+    // The product carries its images in position order; moving one reorders them.
+    let product = format!("/api/products/{}", mug);
+    let response = app.get(&product, None).await;
+    let alts: Vec<_> = response.body["data"]["images"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|image| image["alt"].clone())
+        .collect();
+    assert_eq!(alts, [json!("Front"), json!(null)]);
+
+    let front_id = front.body["data"]["id"].as_str().unwrap();
+    let back_id = back.body["data"]["id"].as_str().unwrap();
+    let patch = json!({ "position": 5, "alt": "Front view" });
+    let response = app
+        .request(
+            Method::PATCH,
+            &format!("{}/images/{}", product, front_id),
+            Some(&admin),
+            Some(patch),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let response = app.get(&product, None).await;
+    let ids: Vec<_> = response.body["data"]["images"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|image| image["id"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(ids, [back_id, front_id]);
+    assert_eq!(response.body["data"]["images"][1]["alt"], "Front view");
+
+    // Deleting removes both the row and the file.
+    let response = app
+        .request(
+            Method::DELETE,
+            &format!("{}/images/{}", product, front_id),
+            Some(&admin),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert!(!front_file.exists());
+    let response = app.get(&product, None).await;
+    assert_eq!(response.body["data"]["images"].as_array().unwrap().len(), 1);
+    let response = app
+        .request(
+            Method::DELETE,
+            &format!("{}/images/{}", product, front_id),
+            Some(&admin),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn uploads_are_validated_and_admin_only() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let user = app.register("user@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+
+    let response = upload(&app, &user, mug, "image/png", b"png", None).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    let response = upload(&app, &admin, Uuid::new_v4(), "image/png", b"png", None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    for (content_type, bytes, message) in [
+        ("text/plain", &b"hello"[..], "jpeg, png, webp or gif"),
+        ("image/png", &b""[..], "must not be empty"),
+        ("image/png", &[0u8; 2 * 1024 * 1024 + 1][..], "at most"),
+    ] {
+        let response = upload(&app, &admin, mug, content_type, bytes, None).await;
+        assert_eq!(
+            response.status,
+            StatusCode::BAD_REQUEST,
+            "{}",
+            response.body
+        );
+        assert!(
+            response.body["message"].as_str().unwrap().contains(message),
+            "{}",
+            response.body
+        );
+    }
+
+    // Far past the body limit the upload is refused before it is read.
+    let response = upload(
+        &app,
+        &admin,
+        mug,
+        "image/png",
+        &[0u8; 3 * 1024 * 1024],
+        None,
+    )
+    .await;
+    assert!(
+        response.status == StatusCode::BAD_REQUEST
+            || response.status == StatusCode::PAYLOAD_TOO_LARGE,
+        "{}",
+        response.status
+    );
+
+    let images: i64 = sqlx::query_scalar("SELECT count(*) FROM product_images")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(images, 0);
+}
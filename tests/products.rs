@@ -0,0 +1,1328 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::http::{Method, StatusCode};
+use axum_ecommerce_api::{app as build_app, cache::ProductCache};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use common::TestApp;
+
+/// Names of the products a listing at `uri` returns to `token`.
+async fn listed_names(app: &TestApp, uri: &str, token: Option<&str>) -> Vec<String> {
+    let response = app.get(uri, token).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    response.body["data"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn product_writes_are_admin_only() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let user = app.register("user@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let product = format!("/api/products/{}", mug);
+    let body = json!({
+        "name": "Teapot",
+        "description": "Holds four cups",
+        "price": 4_000,
+        "stock": 3,
+    });
+    let update = json!({ "price": 1 });
+
+    for (token, status) in [
+        (None, StatusCode::BAD_REQUEST),
+        (Some(user.as_str()), StatusCode::FORBIDDEN),
+    ] {
+        let response = app.post("/api/products", token, body.clone()).await;
+        assert_eq!(response.status, status, "{}", response.body);
+        let response = app
+            .request(Method::PUT, &product, token, Some(update.clone()))
+            .await;
+        assert_eq!(response.status, status, "{}", response.body);
+        let response = app.request(Method::DELETE, &product, token, None).await;
+        assert_eq!(response.status, status, "{}", response.body);
+    }
+    let count: i64 = sqlx::query_scalar("SELECT count(*) FROM products")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+    let response = app.get(&product, None).await;
+    assert_eq!(response.body["data"]["price"], 1_250);
+
+    let response = app
+        .request(Method::PUT, &product, Some(&admin), Some(update))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let response = app
+        .request(Method::DELETE, &product, Some(&admin), None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+}
+
+#[tokio::test]
+async fn unpublished_products_are_hidden_from_users_but_not_admins() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let user = app.register("user@example.com").await;
+    app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let draft = json!({
+        "name": "Teapot",
+        "description": "Coming soon",
+        "price": 4_000,
+        "stock": 3,
+        "is_published": false,
+    });
+    let response = app.post("/api/products", Some(&admin), draft).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    assert_eq!(response.body["data"]["is_published"], false);
+    let teapot = response.body["data"]["id"].as_str().unwrap().to_string();
+    let product = format!("/api/products/{}", teapot);
+
+    // Shoppers can't list, open, cart or favorite it, even when asking for drafts.
+    for token in [None, Some(user.as_str())] {
+        for uri in ["/api/products", "/api/products?include_unpublished=true"] {
+            assert_eq!(listed_names(&app, uri, token).await, ["Ceramic Mug"]);
+        }
+        for uri in [product.clone(), "/api/products/slug/teapot".to_string()] {
+            let response = app.get(&uri, token).await;
+            assert_eq!(response.status, StatusCode::NOT_FOUND, "{}", uri);
+        }
+    }
+    let add = json!({ "product_id": teapot, "quantity": 1 });
+    let response = app.post("/api/cart", Some(&user), add.clone()).await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+    let favorite = json!({ "product_id": teapot });
+    let response = app
+        .post("/api/favorites", Some(&user), favorite.clone())
+        .await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+
+    // Admins see it when they ask for drafts, and can open and edit it.
+    assert_eq!(
+        listed_names(&app, "/api/products", Some(&admin)).await,
+        ["Ceramic Mug"]
+    );
+    let mut names =
+        listed_names(&app, "/api/products?include_unpublished=true", Some(&admin)).await;
+    names.sort();
+    assert_eq!(names, ["Ceramic Mug", "Teapot"]);
+    for uri in [&product, "/api/products/slug/teapot"] {
+        let response = app.get(uri, Some(&admin)).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    }
+    let edit = json!({ "description": "Holds four cups" });
+    let response = app
+        .request(Method::PUT, &product, Some(&admin), Some(edit))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["description"], "Holds four cups");
+    assert_eq!(response.body["data"]["is_published"], false);
+
+    // Publishing makes it available to everyone.
+    let publish = json!({ "is_published": true });
+    let response = app
+        .request(Method::PUT, &product, Some(&admin), Some(publish))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let response = app.get(&product, Some(&user)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let response = app.post("/api/cart", Some(&user), add).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let response = app.post("/api/favorites", Some(&user), favorite).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+}
+
+#[tokio::test]
+async fn every_combination_of_search_and_price_filters_lists_and_counts_alike() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let catalog = [
+        ("Red Mug", 500),
+        ("Blue Mug", 1_500),
+        ("Red Teapot", 2_500),
+        ("Green Kettle", 3_500),
+    ];
+    for (name, price) in catalog {
+        app.create_product(&admin, name, price, 5).await;
+    }
+
+    for q in [None, Some("red")] {
+        for min_price in [None, Some(1_000)] {
+            for max_price in [None, Some(3_000)] {
+                let mut expected: Vec<&str> = catalog
+                    .iter()
+                    .filter(|(name, price)| {
+                        q.is_none_or(|q| name.to_lowercase().contains(q))
+                            && min_price.is_none_or(|min| *price >= min)
+                            && max_price.is_none_or(|max| *price <= max)
+                    })
+                    .map(|(name, _)| *name)
+                    .collect();
+                expected.sort();
+
+                let mut query = vec!["per_page=100".to_string()];
+                query.extend(q.map(|q| format!("q={}", q)));
+                query.extend(min_price.map(|min| format!("min_price={}", min)));
+                query.extend(max_price.map(|max| format!("max_price={}", max)));
+                let uri = format!("/api/products?{}", query.join("&"));
+                let response = app.get(&uri, None).await;
+                assert_eq!(
+                    response.status,
+                    StatusCode::OK,
+                    "{}: {}",
+                    uri,
+                    response.body
+                );
+                let mut names: Vec<&str> = response.body["data"]["items"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|item| item["name"].as_str().unwrap())
+                    .collect();
+                names.sort();
+                assert_eq!(names, expected, "{}", uri);
+                assert_eq!(response.body["meta"]["total"], expected.len(), "{}", uri);
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn search_takes_wildcards_literally() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    for name in [
+        "50% Off Mug",
+        "500 Piece Puzzle",
+        "a_b Sticker",
+        "Abba Poster",
+    ] {
+        app.create_product(&admin, name, 1_000, 5).await;
+    }
+
+    let names = listed_names(&app, "/api/products?q=50%25", None).await;
+    assert_eq!(names, ["50% Off Mug"]);
+    let names = listed_names(&app, "/api/products?q=a_b", None).await;
+    assert_eq!(names, ["a_b Sticker"]);
+    let response = app.get("/api/products?q=a_b", None).await;
+    assert_eq!(response.body["meta"]["total"], 1);
+}
+
+#[tokio::test]
+async fn products_are_filtered_by_category_and_categories_in_use_need_force() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let user = app.register("user@example.com").await;
+    let categories = "/api/admin/categories";
+    let response = app
+        .post(categories, Some(&user), json!({ "name": "Mugs" }))
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    let response = app
+        .post(categories, Some(&admin), json!({ "name": "Mugs & Cups" }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let mugs = response.body["data"].clone();
+    assert_eq!(mugs["slug"], "mugs-cups");
+    let response = app
+        .post(
+            categories,
+            Some(&admin),
+            json!({ "name": "Teapots", "slug": "pots" }),
+        )
+        .await;
+    let pots = response.body["data"].clone();
+    let response = app
+        .post(
+            categories,
+            Some(&admin),
+            json!({ "name": "More Pots", "slug": "pots" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    for (name, category) in [
+        ("Ceramic Mug", &mugs),
+        ("Tin Cup", &mugs),
+        ("Teapot", &pots),
+    ] {
+        let body = json!({
+            "name": name,
+            "description": "",
+            "price": 1_000,
+            "stock": 5,
+            "category_id": category["id"],
+        });
+        let response = app.post("/api/products", Some(&admin), body).await;
+        assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+        assert_eq!(response.body["data"]["category"]["slug"], category["slug"]);
+    }
+    app.create_product(&admin, "Kettle", 1_000, 5).await;
+
+    let by_slug = listed_names(&app, "/api/products?category=mugs-cups&sort_by=name", None).await;
+    assert_eq!(by_slug, ["Ceramic Mug", "Tin Cup"]);
+    let by_id = format!("/api/products?category={}", pots["id"].as_str().unwrap());
+    assert_eq!(listed_names(&app, &by_id, None).await, ["Teapot"]);
+    assert!(
+        listed_names(&app, "/api/products?category=none", None)
+            .await
+            .is_empty()
+    );
+
+    // A category with products is only deleted on request, detaching them.
+    let mugs_uri = format!("{}/{}", categories, mugs["id"].as_str().unwrap());
+    let response = app
+        .request(Method::DELETE, &mugs_uri, Some(&admin), None)
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT, "{}", response.body);
+    let response = app
+        .request(
+            Method::DELETE,
+            &format!("{}?force=true", mugs_uri),
+            Some(&admin),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let detached: i64 =
+        sqlx::query_scalar("SELECT count(*) FROM products WHERE category_id IS NULL")
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(detached, 3);
+    let response = app
+        .request(Method::DELETE, &mugs_uri, Some(&admin), None)
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+/// Creates a product with the given extra fields and returns the response data.
+async fn create_with(app: &TestApp, admin: &str, name: &str, extra: Value) -> Value {
+    let mut body = json!({
+        "name": name,
+        "description": format!("{} for tests", name),
+        "price": 1_000,
+        "stock": 5,
+    });
+    body.as_object_mut()
+        .unwrap()
+        .extend(extra.as_object().unwrap().clone());
+    let response = app.post("/api/products", Some(admin), body).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    response.body["data"].clone()
+}
+
+#[tokio::test]
+async fn slugs_are_unique_stable_and_looked_up() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let first = create_with(&app, &admin, "Axum Hoodie", json!({})).await;
+    let second = create_with(&app, &admin, "Axum  hoodie!", json!({})).await;
+    let third = create_with(&app, &admin, "axum-hoodie", json!({})).await;
+    assert_eq!(first["slug"], "axum-hoodie");
+    assert_eq!(second["slug"], "axum-hoodie-2");
+    assert_eq!(third["slug"], "axum-hoodie-3");
+
+    let response = app.get("/api/products/slug/axum-hoodie-2", None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["id"], second["id"]);
+    let response = app.get("/api/products/slug/no-such-thing", None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    // Renaming keeps the slug; only an explicit one replaces it, if it is free.
+    let uri = format!("/api/products/{}", first["id"].as_str().unwrap());
+    let response = app
+        .request(
+            Method::PUT,
+            &uri,
+            Some(&admin),
+            Some(json!({ "name": "Rust Hoodie" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["slug"], "axum-hoodie");
+    let response = app
+        .request(
+            Method::PUT,
+            &uri,
+            Some(&admin),
+            Some(json!({ "slug": "axum-hoodie-3" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT, "{}", response.body);
+    let response = app
+        .request(
+            Method::PUT,
+            &uri,
+            Some(&admin),
+            Some(json!({ "slug": "Rust Hoodie" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["slug"], "rust-hoodie");
+    let response = app.get("/api/products/slug/rust-hoodie", None).await;
+    assert_eq!(response.body["data"]["name"], "Rust Hoodie");
+}
+
+#[tokio::test]
+async fn skus_are_unique_and_looked_up() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let mug = create_with(&app, &admin, "Ceramic Mug", json!({ "sku": " MUG-001 " })).await;
+    assert_eq!(mug["sku"], "MUG-001");
+    let teapot = create_with(&app, &admin, "Teapot", json!({ "sku": "POT-001" })).await;
+    create_with(&app, &admin, "Kettle", json!({})).await;
+
+    // A taken SKU is a conflict, on create and on update alike.
+    let body =
+        json!({ "name": "Tin Cup", "description": "", "price": 500, "stock": 1, "sku": "MUG-001" });
+    let response = app.post("/api/products", Some(&admin), body).await;
+    assert_eq!(response.status, StatusCode::CONFLICT, "{}", response.body);
+    assert!(
+        response.body["message"]
+            .as_str()
+            .unwrap()
+            .contains("MUG-001"),
+        "{}",
+        response.body
+    );
+    let uri = format!("/api/products/{}", teapot["id"].as_str().unwrap());
+    let response = app
+        .request(
+            Method::PUT,
+            &uri,
+            Some(&admin),
+            Some(json!({ "sku": "MUG-001" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT, "{}", response.body);
+    let body =
+        json!({ "name": "Tin Cup", "description": "", "price": 500, "stock": 1, "sku": "  " });
+    let response = app.post("/api/products", Some(&admin), body).await;
+    assert_eq!(
+        response.status,
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "{}",
+        response.body
+    );
+    let error = &response.body["data"]["errors"][0];
+    assert_eq!(
+        (error["field"].as_str(), error["code"].as_str()),
+        (Some("sku"), Some("empty"))
+    );
+
+    let response = app.get("/api/products/sku/MUG-001", None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["id"], mug["id"]);
+    let response = app.get("/api/products/sku/MUG-999", None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        listed_names(&app, "/api/products?sku=POT-001", None).await,
+        ["Teapot"]
+    );
+}
+
+#[tokio::test]
+async fn an_update_keeps_the_sku_unless_it_is_cleared() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let mug = create_with(&app, &admin, "Ceramic Mug", json!({ "sku": "MUG-001" })).await;
+    let teapot = create_with(&app, &admin, "Teapot", json!({ "sku": "POT-001" })).await;
+    let update = |product: &Value| format!("/api/products/{}", product["id"].as_str().unwrap());
+
+    let response = app
+        .request(
+            Method::PUT,
+            &update(&mug),
+            Some(&admin),
+            Some(json!({ "stock": 7 })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["sku"], "MUG-001");
+
+    // `null` and an empty string both clear it.
+    for (product, sku) in [(&mug, json!(null)), (&teapot, json!("  "))] {
+        let response = app
+            .request(
+                Method::PUT,
+                &update(product),
+                Some(&admin),
+                Some(json!({ "sku": sku })),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+        assert_eq!(response.body["data"]["sku"], Value::Null, "{}", sku);
+    }
+    let response = app.get("/api/products/sku/MUG-001", None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    // The freed SKU can be given to another product.
+    let response = app
+        .request(
+            Method::PUT,
+            &update(&teapot),
+            Some(&admin),
+            Some(json!({ "sku": "MUG-001" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["sku"], "MUG-001");
+}
+
+#[tokio::test]
+async fn products_are_filtered_and_sorted_by_stock() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    for (name, stock) in [("Mug", 3), ("Teapot", 0), ("Kettle", 12), ("Cup", 0)] {
+        app.create_product(&admin, name, 1_000, stock).await;
+    }
+
+    let uri = "/api/products?in_stock=true&sort_by=stock&order=desc";
+    assert_eq!(listed_names(&app, uri, None).await, ["Kettle", "Mug"]);
+    let response = app.get(uri, None).await;
+    assert_eq!(response.body["meta"]["total"], 2);
+    let uri = "/api/products?in_stock=false&sort_by=name";
+    assert_eq!(listed_names(&app, uri, None).await, ["Cup", "Teapot"]);
+    let response = app.get(uri, None).await;
+    assert_eq!(response.body["meta"]["total"], 2);
+    let uri = "/api/products?sort_by=stock";
+    let names = listed_names(&app, uri, None).await;
+    assert_eq!(names[2..], ["Mug", "Kettle"]);
+}
+
+/// `(field, code)` of each error in a validation failure.
+fn field_errors(response: &common::TestResponse) -> Vec<(String, String)> {
+    assert_eq!(
+        response.status,
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "{}",
+        response.body
+    );
+    assert_eq!(response.body["data"]["error_code"], "VALIDATION_FAILED");
+    response.body["data"]["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| {
+            (
+                error["field"].as_str().unwrap().to_string(),
+                error["code"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn invalid_product_fields_are_refused_field_by_field() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let uri = format!("/api/products/{}", mug);
+    let valid = json!({ "name": "Teapot", "description": "", "price": 4_000, "stock": 3 });
+    let too_long = "x".repeat(256);
+
+    for (field, value, code) in [
+        ("name", json!(""), "required"),
+        ("name", json!("   "), "required"),
+        ("name", json!(too_long), "too_long"),
+        ("price", json!(-5), "negative"),
+        ("stock", json!(-10), "negative"),
+    ] {
+        let mut body = valid.clone();
+        body[field] = value.clone();
+        let response = app.post("/api/products", Some(&admin), body).await;
+        assert_eq!(
+            field_errors(&response),
+            [(field.to_string(), code.to_string())]
+        );
+        let response = app
+            .request(
+                Method::PUT,
+                &uri,
+                Some(&admin),
+                Some(json!({ field: value })),
+            )
+            .await;
+        assert_eq!(
+            field_errors(&response),
+            [(field.to_string(), code.to_string())]
+        );
+    }
+
+    // Every bad field is reported at once, and nothing is written.
+    let body = json!({ "name": "", "description": "", "price": -1, "stock": -1 });
+    let response = app.post("/api/products", Some(&admin), body).await;
+    assert_eq!(field_errors(&response).len(), 3);
+    let count: i64 = sqlx::query_scalar("SELECT count(*) FROM products")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+    let response = app.get(&uri, None).await;
+    assert_eq!(response.body["data"]["name"], "Ceramic Mug");
+    assert_eq!(response.body["data"]["price"], 1_250);
+
+    // The table refuses them too, should anything get past the handlers.
+    let err = sqlx::query("UPDATE products SET stock = -1 WHERE id = $1")
+        .bind(mug)
+        .execute(&app.pool)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.as_database_error().unwrap().code().as_deref(),
+        Some("23514")
+    );
+}
+
+#[tokio::test]
+async fn descriptions_are_kept_set_or_cleared() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let uri = format!("/api/products/{}", mug);
+    let description = async |body: Value| {
+        let response = app
+            .request(Method::PUT, &uri, Some(&admin), Some(body))
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+        response.body["data"]["description"].clone()
+    };
+
+    // Absent keeps it, a string sets it, null clears it.
+    assert_eq!(
+        description(json!({ "price": 1_300 })).await,
+        "Ceramic Mug for tests"
+    );
+    assert_eq!(
+        description(json!({ "description": "Holds 300 ml" })).await,
+        "Holds 300 ml"
+    );
+    assert_eq!(
+        description(json!({ "description": null })).await,
+        Value::Null
+    );
+    assert_eq!(description(json!({ "name": "Mug" })).await, Value::Null);
+
+    let response = app.get(&uri, None).await;
+    assert_eq!(response.body["data"]["description"], Value::Null);
+    assert_eq!(response.body["data"]["price"], 1_300);
+}
+
+#[tokio::test]
+async fn price_changes_are_recorded_in_order() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_000, 10).await;
+    let admin_id: uuid::Uuid =
+        sqlx::query_scalar("SELECT id FROM users WHERE email = 'admin@example.com'")
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    let product = format!("/api/products/{}", mug);
+    let history = format!("/api/admin/products/{}/price-history", mug);
+
+    for price in [1_200, 900] {
+        let response = app
+            .request(
+                Method::PUT,
+                &product,
+                Some(&admin),
+                Some(json!({ "price": price })),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    }
+    // Nothing new when the price is left alone, or set to what it already is.
+    for update in [
+        json!({ "name": "Stoneware Mug", "stock": 4 }),
+        json!({ "price": 900 }),
+    ] {
+        let response = app
+            .request(Method::PUT, &product, Some(&admin), Some(update))
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    }
+
+    let response = app.get(&history, Some(&admin)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let rows = response.body["data"]["items"].as_array().unwrap();
+    let prices: Vec<(Value, Value)> = rows
+        .iter()
+        .map(|row| (row["old_price"].clone(), row["new_price"].clone()))
+        .collect();
+    assert_eq!(
+        prices,
+        [
+            (Value::Null, json!(1_000)),
+            (json!(1_000), json!(1_200)),
+            (json!(1_200), json!(900)),
+        ]
+    );
+    assert!(
+        rows.iter()
+            .all(|row| row["changed_by"] == admin_id.to_string())
+    );
+    assert_eq!(response.body["meta"]["total"], 3);
+
+    // Paged, oldest first.
+    let response = app
+        .get(&format!("{}?per_page=2&page=2", history), Some(&admin))
+        .await;
+    assert_eq!(response.body["data"]["items"][0]["new_price"], 900);
+
+    let user = app.register("user@example.com").await;
+    let response = app.get(&history, Some(&user)).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn deleting_an_ordered_product_keeps_its_orders_whole() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let teapot = app.create_product(&admin, "Teapot", 4_000, 10).await;
+    let add = json!({ "product_id": mug, "quantity": 1 });
+    app.post("/api/cart", Some(&buyer), add).await;
+    let response = app
+        .request(Method::POST, "/api/orders/checkout", Some(&buyer), None)
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let order = response.body["data"]["order"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let uri = format!("/api/products/{}", mug);
+    let response = app.request(Method::DELETE, &uri, Some(&admin), None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let response = app.get(&uri, None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND, "{}", response.body);
+
+    // The order still has its line, and paying it still takes the reserved unit.
+    let response = app
+        .get(&format!("/api/orders/{}", order), Some(&buyer))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(
+        response.body["data"]["items"][0]["product_id"],
+        mug.to_string()
+    );
+    let pay = format!("/api/v1/admin/orders/{}/pay", order);
+    let response = app.post(&pay, Some(&admin), json!({})).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let stock: (i32, i32) = sqlx::query_as("SELECT stock, reserved FROM products WHERE id = $1")
+        .bind(mug)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(stock, (9, 0));
+
+    let uri = format!("/api/products/{}", teapot);
+    let response = app.request(Method::DELETE, &uri, Some(&admin), None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let response = app.request(Method::DELETE, &uri, Some(&admin), None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+/// Follows `next_cursor` from `first` to the end, returning the names in the order seen.
+/// `between` runs once, after the first page.
+async fn walk_cursor(app: &TestApp, first: &str, between: impl AsyncFnOnce()) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut uri = first.to_string();
+    let mut between = Some(between);
+    loop {
+        let response = app.get(&uri, None).await;
+        assert_eq!(
+            response.status,
+            StatusCode::OK,
+            "{}: {}",
+            uri,
+            response.body
+        );
+        names.extend(
+            response.body["data"]["items"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|item| item["name"].as_str().unwrap().to_string()),
+        );
+        if let Some(between) = between.take() {
+            between().await;
+        }
+        let Some(cursor) = response.body["meta"]["next_cursor"].as_str() else {
+            assert_eq!(response.body["meta"]["has_next"], false);
+            return names;
+        };
+        uri = format!("{}&cursor={}", first, cursor);
+    }
+}
+
+#[tokio::test]
+async fn cursor_pages_have_no_gaps_or_repeats() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    // Prices repeat, so the id has to break ties.
+    sqlx::query(
+        "INSERT INTO products (id, name, slug, price, stock) \
+         SELECT gen_random_uuid(), 'Product ' || n, 'product-' || n, (n % 7) * 100, n \
+         FROM generate_series(1, 50) AS n",
+    )
+    .execute(&app.pool)
+    .await
+    .unwrap();
+    let mut all: Vec<String> = (1..=50).map(|n| format!("Product {}", n)).collect();
+    all.sort();
+
+    for order in ["asc", "desc"] {
+        // A product added mid-walk shows up at most once and moves nothing else.
+        let first = format!("/api/products?sort_by=price&order={}&per_page=7", order);
+        let insert = async || {
+            sqlx::query(
+                "INSERT INTO products (id, name, slug, price, stock) \
+                 VALUES (gen_random_uuid(), 'Late ' || $1, 'late-' || $1, 300, 1)",
+            )
+            .bind(order)
+            .execute(&app.pool)
+            .await
+            .unwrap();
+        };
+        let mut names = walk_cursor(&app, &first, insert).await;
+        let late = format!("Late {}", order);
+        let late = names.iter().filter(|name| **name == late).count();
+        assert!(late <= 1, "{:?}", names);
+        names.retain(|name| !name.starts_with("Late "));
+        let mut prices: Vec<i64> = names
+            .iter()
+            .map(|name| name["Product ".len()..].parse::<i64>().unwrap() % 7)
+            .collect();
+        if order == "desc" {
+            prices.reverse();
+        }
+        assert!(prices.is_sorted(), "{}: {:?}", order, names);
+        names.sort();
+        assert_eq!(names, all, "{}", order);
+    }
+
+    // Page numbers keep working alongside.
+    let response = app.get("/api/products?page=8&per_page=7", None).await;
+    assert_eq!(response.body["data"]["items"].as_array().unwrap().len(), 3);
+    let response = app.get("/api/products?cursor=not-a-cursor", None).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+/// Price of `product_id` as `GET /products/{id}` reports it.
+async fn served_price(app: &TestApp, product_id: Uuid) -> Value {
+    let response = app
+        .get(&format!("/api/products/{}", product_id), None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    response.body["data"]["price"].clone()
+}
+
+/// Changes a price behind the application's back.
+async fn reprice_in_db(app: &TestApp, product_id: Uuid, price: i64) {
+    sqlx::query("UPDATE products SET price = $2 WHERE id = $1")
+        .bind(product_id)
+        .bind(price)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn product_reads_are_cached_until_written_or_expired() {
+    let Some(mut app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_000, 10).await;
+    let uri = format!("/api/products/{}", mug);
+
+    assert_eq!(served_price(&app, mug).await, 1_000);
+    reprice_in_db(&app, mug, 2_000).await;
+    assert_eq!(served_price(&app, mug).await, 1_000);
+    let response = app.get("/api/admin/cache/stats", Some(&admin)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["hits"], 1);
+    assert_eq!(response.body["data"]["misses"], 1);
+    let response = app.get("/api/admin/cache/stats", None).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    // Writes through the API drop the entry.
+    let response = app
+        .request(Method::PUT, &uri, Some(&admin), Some(json!({ "stock": 9 })))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(served_price(&app, mug).await, 2_000);
+    let response = app.request(Method::DELETE, &uri, Some(&admin), None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let response = app.get(&uri, None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    // Entries also expire on their own.
+    app.state.product_cache = ProductCache::new(10, Duration::from_millis(200));
+    app.router = build_app(&app.config, app.state.clone());
+    let teapot = app.create_product(&admin, "Teapot", 4_000, 10).await;
+    assert_eq!(served_price(&app, teapot).await, 4_000);
+    reprice_in_db(&app, teapot, 4_500).await;
+    assert_eq!(served_price(&app, teapot).await, 4_000);
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(served_price(&app, teapot).await, 4_500);
+}
+
+#[tokio::test]
+async fn category_writes_reach_cached_products() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let response = app
+        .post(
+            "/api/admin/categories",
+            Some(&admin),
+            json!({ "name": "Mugs" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let category = format!(
+        "/api/admin/categories/{}",
+        response.body["data"]["id"].as_str().unwrap()
+    );
+    let mug = create_with(
+        &app,
+        &admin,
+        "Ceramic Mug",
+        json!({ "category_id": response.body["data"]["id"] }),
+    )
+    .await;
+    let uri = format!("/api/products/{}", mug["id"].as_str().unwrap());
+    let served_category = async || app.get(&uri, None).await.body["data"]["category"].clone();
+    assert_eq!(served_category().await["name"], "Mugs");
+
+    // Renaming the category drops the cached product, so the new name shows at once.
+    let response = app
+        .request(
+            Method::PUT,
+            &category,
+            Some(&admin),
+            Some(json!({ "name": "Cups" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(served_category().await["name"], "Cups");
+
+    // So does deleting it with force, which detaches the product.
+    let response = app
+        .request(
+            Method::DELETE,
+            &format!("{}?force=true", category),
+            Some(&admin),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(served_category().await, Value::Null);
+    let response = app.get(&uri, None).await;
+    assert_eq!(response.body["data"]["category_id"], Value::Null);
+}
+
+#[tokio::test]
+async fn products_are_sorted_by_several_keys_in_order() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    for (name, price) in [
+        ("Mug", 1_000),
+        ("Bowl", 2_000),
+        ("Cup", 1_000),
+        ("Atlas", 2_000),
+    ] {
+        app.create_product(&admin, name, price, 5).await;
+    }
+    let sorted =
+        async |query: &str| listed_names(&app, &format!("/api/products?{}", query), None).await;
+
+    assert_eq!(
+        sorted("sort=price:asc,name:asc").await,
+        ["Cup", "Mug", "Atlas", "Bowl"]
+    );
+    assert_eq!(
+        sorted("sort=price:desc,%20name:desc").await,
+        ["Bowl", "Atlas", "Mug", "Cup"]
+    );
+    // The direction defaults to ascending.
+    assert_eq!(
+        sorted("sort=price,name:desc").await,
+        ["Mug", "Cup", "Bowl", "Atlas"]
+    );
+    // `sort_by`/`order` win over `sort`.
+    assert_eq!(
+        sorted("sort=price:asc,name:asc&sort_by=name&order=desc").await,
+        ["Mug", "Cup", "Bowl", "Atlas"]
+    );
+
+    // Anything off the whitelist is refused, never spliced into the query.
+    for query in [
+        "sort=created_at;DROP%20TABLE%20products",
+        "sort=created_at:asc;DROP%20TABLE%20products",
+        "sort=password_hash:asc",
+        "sort=price:sideways",
+        "sort=price:asc,price:desc",
+    ] {
+        let response = app.get(&format!("/api/products?{}", query), None).await;
+        assert_eq!(
+            response.status,
+            StatusCode::BAD_REQUEST,
+            "{}: {}",
+            query,
+            response.body
+        );
+    }
+    assert_eq!(
+        sorted("sort=name:asc").await,
+        ["Atlas", "Bowl", "Cup", "Mug"]
+    );
+}
+
+#[tokio::test]
+async fn updates_move_updated_at_but_never_created_at() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let product = format!("/api/v1/products/{}", mug);
+    let created = app.get(&product, None).await.body["data"].clone();
+    assert_eq!(created["updated_at"], created["created_at"]);
+
+    let edit = json!({ "price": 1_500 });
+    let response = app
+        .request(Method::PUT, &product, Some(&admin), Some(edit))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let updated = &response.body["data"];
+    assert_eq!(updated["created_at"], created["created_at"]);
+    let timestamp = |value: &Value| -> chrono::DateTime<chrono::Utc> {
+        value.as_str().unwrap().parse().unwrap()
+    };
+    assert!(timestamp(&updated["updated_at"]) > timestamp(&created["updated_at"]));
+
+    // Not even a direct write can rewrite the creation time.
+    sqlx::query("UPDATE products SET created_at = '2000-01-01T00:00:00Z' WHERE id = $1")
+        .bind(mug)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let (created_at,): (chrono::DateTime<chrono::Utc>,) =
+        sqlx::query_as("SELECT created_at FROM products WHERE id = $1")
+            .bind(mug)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(created_at, timestamp(&created["created_at"]));
+}
+
+/// Names of the products `/admin/products` lists for `deleted`.
+async fn admin_listed(app: &TestApp, admin: &str, deleted: &str) -> Vec<String> {
+    let uri = format!("/api/v1/admin/products?deleted={}", deleted);
+    listed_names(app, &uri, Some(admin)).await
+}
+
+#[tokio::test]
+async fn deleted_products_can_be_listed_and_restored_by_admins() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    app.create_product(&admin, "Teapot", 4_000, 3).await;
+    let add = json!({ "product_id": mug, "quantity": 1 });
+    app.post("/api/v1/cart", Some(&buyer), add).await;
+
+    let product = format!("/api/v1/products/{}", mug);
+    let response = app
+        .request(Method::DELETE, &product, Some(&admin), None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let response = app
+        .request(Method::DELETE, &product, Some(&admin), None)
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    // Gone for everyone outside /admin/products, and from the buyer's cart.
+    for token in [None, Some(buyer.as_str()), Some(admin.as_str())] {
+        let response = app.get(&product, token).await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        let response = app.get("/api/v1/products/slug/ceramic-mug", token).await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+    }
+    let uri = "/api/v1/products?include_unpublished=true";
+    assert_eq!(listed_names(&app, uri, Some(&admin)).await, ["Teapot"]);
+    let cart = app.get("/api/v1/cart", Some(&buyer)).await;
+    assert_eq!(cart.body["data"]["items"], json!([]));
+
+    assert_eq!(admin_listed(&app, &admin, "only").await, ["Ceramic Mug"]);
+    assert_eq!(admin_listed(&app, &admin, "exclude").await, ["Teapot"]);
+    assert_eq!(
+        admin_listed(&app, &admin, "include").await,
+        ["Ceramic Mug", "Teapot"]
+    );
+    let response = app
+        .get("/api/v1/admin/products?deleted=only", Some(&admin))
+        .await;
+    assert!(response.body["data"]["items"][0]["deleted_at"].is_string());
+
+    let restore = format!("/api/v1/admin/products/{}/restore", mug);
+    let response = app.post(&restore, Some(&admin), json!({})).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["is_published"], false);
+    assert!(response.body["data"].get("deleted_at").is_none());
+    let response = app.post(&restore, Some(&admin), json!({})).await;
+    assert_eq!(response.status, StatusCode::CONFLICT, "{}", response.body);
+    // Restored but unpublished: admins see it, shoppers don't until it is published.
+    assert_eq!(app.get(&product, Some(&admin)).await.status, StatusCode::OK);
+    assert_eq!(app.get(&product, None).await.status, StatusCode::NOT_FOUND);
+
+    app.request(Method::DELETE, &product, Some(&admin), None)
+        .await;
+    let response = app
+        .post(&restore, Some(&admin), json!({ "publish": true }))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(app.get(&product, None).await.status, StatusCode::OK);
+
+    app.state.audit.flush().await;
+    let actions: Vec<(String,)> = sqlx::query_as(
+        "SELECT action FROM audit_log WHERE action IN ('product.delete', 'product.restore', 'product.admin_list') ORDER BY created_at",
+    )
+    .fetch_all(&app.pool)
+    .await
+    .unwrap();
+    let actions: Vec<&str> = actions.iter().map(|(a,)| a.as_str()).collect();
+    assert_eq!(
+        actions,
+        [
+            "product.delete",
+            "product.admin_list",
+            "product.admin_list",
+            "product.admin_list",
+            "product.admin_list",
+            "product.restore",
+            "product.delete",
+            "product.restore"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn a_live_product_with_the_same_sku_or_slug_blocks_a_restore() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let user = app.register("user@example.com").await;
+    let body = |name: &str, sku: &str| json!({ "name": name, "description": "", "price": 1_250, "stock": 5, "sku": sku });
+    let response = app
+        .post(
+            "/api/v1/products",
+            Some(&admin),
+            body("Ceramic Mug", "MUG-1"),
+        )
+        .await;
+    let old = response.body["data"]["id"].as_str().unwrap().to_string();
+    app.request(
+        Method::DELETE,
+        &format!("/api/v1/products/{}", old),
+        Some(&admin),
+        None,
+    )
+    .await;
+
+    // The SKU and slug are free again once their product is deleted.
+    let response = app
+        .post("/api/v1/products", Some(&admin), body("Blue Mug", "MUG-1"))
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let sku_holder = response.body["data"]["id"].as_str().unwrap().to_string();
+    let restore = format!("/api/v1/admin/products/{}/restore", old);
+    let response = app.post(&restore, Some(&admin), json!({})).await;
+    assert_eq!(response.status, StatusCode::CONFLICT, "{}", response.body);
+    assert!(
+        response.body["message"].as_str().unwrap().contains("MUG-1"),
+        "{}",
+        response.body
+    );
+
+    app.request(
+        Method::DELETE,
+        &format!("/api/v1/products/{}", sku_holder),
+        Some(&admin),
+        None,
+    )
+    .await;
+    let response = app
+        .post(
+            "/api/v1/products",
+            Some(&admin),
+            body("Ceramic Mug", "MUG-2"),
+        )
+        .await;
+    assert_eq!(response.body["data"]["slug"], "ceramic-mug");
+    let response = app.post(&restore, Some(&admin), json!({})).await;
+    assert_eq!(response.status, StatusCode::CONFLICT, "{}", response.body);
+    assert!(
+        response.body["message"]
+            .as_str()
+            .unwrap()
+            .contains("ceramic-mug"),
+        "{}",
+        response.body
+    );
+
+    for response in [
+        app.get("/api/v1/admin/products", Some(&user)).await,
+        app.post(&restore, Some(&user), json!({})).await,
+    ] {
+        assert_eq!(response.status, StatusCode::FORBIDDEN);
+    }
+    let unknown = format!("/api/v1/admin/products/{}/restore", Uuid::new_v4());
+    let response = app.post(&unknown, Some(&admin), json!({})).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn suggestions_match_names_prefixes_first() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    for name in [
+        "Teapot Holder",
+        "Hot Chocolate",
+        "Phone Case",
+        "Honey Jar",
+        "Ceramic Mug",
+        "100%_Cotton Tee",
+    ] {
+        app.create_product(&admin, name, 1_000, 5).await;
+    }
+    create_with(&app, &admin, "Hoodie", json!({ "is_published": false })).await;
+    let hourglass = app.create_product(&admin, "Hourglass", 1_000, 5).await;
+    let uri = format!("/api/products/{}", hourglass);
+    app.request(Method::DELETE, &uri, Some(&admin), None).await;
+
+    let suggest = "/api/v1/products/suggest";
+    let names = listed_names(&app, &format!("{}?q=ho", suggest), None).await;
+    assert_eq!(
+        names,
+        ["Honey Jar", "Hot Chocolate", "Phone Case", "Teapot Holder"]
+    );
+    let names = listed_names(&app, &format!("{}?q=%20HO%20&limit=2", suggest), None).await;
+    assert_eq!(names, ["Honey Jar", "Hot Chocolate"]);
+    // Admins get the same published-only list.
+    let names = listed_names(&app, &format!("{}?q=hoo", suggest), Some(&admin)).await;
+    assert!(names.is_empty(), "{:?}", names);
+
+    // Wildcards in the query are taken literally.
+    let names = listed_names(&app, &format!("{}?q=0%25_", suggest), None).await;
+    assert_eq!(names, ["100%_Cotton Tee"]);
+    let names = listed_names(&app, &format!("{}?q=%25_", suggest), None).await;
+    assert_eq!(names, ["100%_Cotton Tee"]);
+    let names = listed_names(&app, &format!("{}?q=e_c", suggest), None).await;
+    assert!(names.is_empty(), "{:?}", names);
+
+    // Only the id, name and slug come back.
+    let response = app.get(&format!("{}?q=mug", suggest), None).await;
+    let item = &response.body["data"]["items"][0];
+    let mut keys: Vec<&str> = item
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+    keys.sort();
+    assert_eq!(keys, ["id", "name", "slug"]);
+    assert_eq!(item["slug"], "ceramic-mug");
+}
+
+#[tokio::test]
+async fn short_queries_suggest_nothing_and_the_limit_is_capped() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    for n in 1..=12 {
+        app.create_product(&admin, &format!("Mug {:02}", n), 1_000, 5)
+            .await;
+    }
+
+    for q in ["", "m", "%20%20m%20", "%20%20"] {
+        let uri = format!("/api/v1/products/suggest?q={}", q);
+        assert!(listed_names(&app, &uri, None).await.is_empty(), "{}", q);
+    }
+    let names = listed_names(&app, "/api/v1/products/suggest", None).await;
+    assert!(names.is_empty());
+
+    let default = listed_names(&app, "/api/v1/products/suggest?q=mu", None).await;
+    assert_eq!(default.len(), 8);
+    assert_eq!(default[0], "Mug 01");
+    let capped = listed_names(&app, "/api/v1/products/suggest?q=mu&limit=50", None).await;
+    assert_eq!(capped.len(), 10);
+    let response = app
+        .get("/api/v1/products/suggest?q=mu&limit=lots", None)
+        .await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+}
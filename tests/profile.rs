@@ -0,0 +1,175 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use common::{PASSWORD, TestApp};
+
+const ME: &str = "/api/v1/auth/me";
+
+#[tokio::test]
+async fn registration_takes_an_optional_name_and_phone() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let body = json!({
+        "email": "jane@example.com",
+        "password": PASSWORD,
+        "full_name": "  Jane Doe ",
+        "phone": "+62 812-3456-7890",
+    });
+    let response = app.post("/api/v1/auth/register", None, body).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let token = app.login("jane@example.com").await;
+
+    let response = app.get(ME, Some(&token)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let profile = &response.body["data"];
+    assert_eq!(profile["email"], "jane@example.com");
+    assert_eq!(profile["full_name"], "Jane Doe");
+    assert_eq!(profile["phone"], "+6281234567890");
+    assert!(profile.get("password_hash").is_none());
+
+    // Both stay optional.
+    let token = app.register("bare@example.com").await;
+    let response = app.get(ME, Some(&token)).await;
+    assert_eq!(response.body["data"]["full_name"], json!(null));
+    assert_eq!(response.body["data"]["phone"], json!(null));
+}
+
+#[tokio::test]
+async fn the_profile_can_be_updated_and_cleared() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let token = app.register("jane@example.com").await;
+    let body = json!({ "full_name": "Jane Doe", "phone": "(021) 555 0199" });
+    let response = app.request(Method::PUT, ME, Some(&token), Some(body)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["full_name"], "Jane Doe");
+    assert_eq!(response.body["data"]["phone"], "0215550199");
+    let response = app.get(ME, Some(&token)).await;
+    assert_eq!(response.body["data"]["phone"], "0215550199");
+
+    // PUT replaces the whole profile.
+    let body = json!({ "full_name": "Jane Q. Doe" });
+    let response = app.request(Method::PUT, ME, Some(&token), Some(body)).await;
+    assert_eq!(response.body["data"]["full_name"], "Jane Q. Doe");
+    assert_eq!(response.body["data"]["phone"], json!(null));
+
+    let response = app.get(ME, None).await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+}
+
+#[tokio::test]
+async fn invalid_profiles_are_rejected_field_by_field() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let token = app.register("jane@example.com").await;
+    for phone in [
+        "12345",
+        "+1 555 0100 0000 0000 1",
+        "call me",
+        "++62812345678",
+    ] {
+        let body = json!({ "full_name": "x".repeat(101), "phone": phone });
+        let response = app.request(Method::PUT, ME, Some(&token), Some(body)).await;
+        assert_eq!(
+            response.status,
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "{}",
+            response.body
+        );
+        let fields: Vec<_> = response.body["data"]["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["field"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(fields, ["full_name", "phone"], "{}", phone);
+    }
+
+    let body = json!({
+        "email": "bad@example.com",
+        "password": PASSWORD,
+        "phone": "12-34",
+    });
+    let response = app.post("/api/v1/auth/register", None, body).await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Nothing was stored.
+    let response = app.get(ME, Some(&token)).await;
+    assert_eq!(response.body["data"]["full_name"], json!(null));
+}
+
+#[tokio::test]
+async fn orders_keep_the_recipient_from_checkout_time() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    let body = json!({ "full_name": "Jane Doe", "phone": "+6281234567890" });
+    app.request(Method::PUT, ME, Some(&buyer), Some(body)).await;
+
+    let add = json!({ "product_id": mug, "quantity": 1 });
+    app.post("/api/v1/cart", Some(&buyer), add).await;
+    let response = app
+        .post("/api/v1/orders/checkout", Some(&buyer), json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let order_id = response.body["data"]["order"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert_eq!(response.body["data"]["order"]["recipient_name"], "Jane Doe");
+    assert_eq!(
+        response.body["data"]["order"]["recipient_phone"],
+        "+6281234567890"
+    );
+
+    // Later profile edits leave the order alone.
+    let body = json!({ "full_name": "Someone Else" });
+    app.request(Method::PUT, ME, Some(&buyer), Some(body)).await;
+    let response = app
+        .get(&format!("/api/v1/orders/{}", order_id), Some(&buyer))
+        .await;
+    assert_eq!(response.body["data"]["order"]["recipient_name"], "Jane Doe");
+    assert_eq!(
+        response.body["data"]["order"]["recipient_phone"],
+        "+6281234567890"
+    );
+}
+
+#[tokio::test]
+async fn admins_list_users_with_their_profiles() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let body = json!({ "full_name": "Jane Doe", "phone": "+6281234567890" });
+    app.request(Method::PUT, ME, Some(&buyer), Some(body)).await;
+
+    let response = app
+        .get("/api/v1/admin/users?per_page=1", Some(&admin))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let items = response.body["data"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["email"], "buyer@example.com");
+    assert_eq!(items[0]["full_name"], "Jane Doe");
+    assert_eq!(items[0]["phone"], "+6281234567890");
+    assert!(items[0].get("password_hash").is_none());
+    assert_eq!(response.body["meta"]["total"], 2);
+
+    let response = app.get("/api/v1/admin/users", Some(&buyer)).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,201 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use chrono::{Duration, Utc};
+use serde_json::json;
+use uuid::Uuid;
+
+use common::TestApp;
+
+/// Checks out `lines` for `token`, moves the order to `status` and returns its id.
+async fn order(app: &TestApp, token: &str, lines: &[(Uuid, i32)], status: &str) -> Uuid {
+    for (product_id, quantity) in lines {
+        let add = json!({ "product_id": product_id, "quantity": quantity });
+        app.post("/api/cart", Some(token), add).await;
+    }
+    let response = app
+        .request(Method::POST, "/api/orders/checkout", Some(token), None)
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let id = response.body["data"]["order"]["id"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    sqlx::query("UPDATE orders SET status = $2 WHERE id = $1")
+        .bind(id)
+        .bind(status)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    id
+}
+
+/// `(name, units_sold)` of the best sellers at `uri`.
+async fn popular(app: &TestApp, uri: &str) -> Vec<(String, i64)> {
+    let response = app.get(uri, None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    response.body["data"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| {
+            (
+                p["name"].as_str().unwrap().to_string(),
+                p["units_sold"].as_i64().unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn popular_products_rank_paid_units_in_the_window() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_000, 100).await;
+    let teapot = app.create_product(&admin, "Teapot", 4_000, 100).await;
+    let kettle = app.create_product(&admin, "Kettle", 9_000, 100).await;
+    let cup = app.create_product(&admin, "Tin Cup", 500, 100).await;
+
+    order(&app, &buyer, &[(mug, 2), (teapot, 1)], "paid").await;
+    order(&app, &buyer, &[(teapot, 4)], "completed").await;
+    order(&app, &buyer, &[(mug, 1)], "paid").await;
+    // Unpaid orders don't count, however large.
+    order(&app, &buyer, &[(kettle, 50)], "pending").await;
+    order(&app, &buyer, &[(cup, 50)], "cancelled").await;
+    // Nor do sales before the window.
+    let old = order(&app, &buyer, &[(kettle, 9)], "paid").await;
+    app.backdate("orders", old, Utc::now() - Duration::days(40))
+        .await;
+
+    assert_eq!(
+        popular(&app, "/api/products/popular").await,
+        [("Teapot".to_string(), 5), ("Ceramic Mug".to_string(), 3)]
+    );
+    assert_eq!(
+        popular(&app, "/api/products/popular?limit=1").await,
+        [("Teapot".to_string(), 5)]
+    );
+    assert_eq!(
+        popular(&app, "/api/products/popular?days=60").await,
+        [
+            ("Kettle".to_string(), 9),
+            ("Teapot".to_string(), 5),
+            ("Ceramic Mug".to_string(), 3)
+        ]
+    );
+    // The limit is capped rather than refused.
+    assert_eq!(
+        popular(&app, "/api/products/popular?limit=500&days=60")
+            .await
+            .len(),
+        3
+    );
+}
+
+const TOP_PRODUCTS: &str = "/api/v1/admin/reports/top-products";
+
+/// `(name, units_sold, revenue, buyers)` of the rows of the top-products report at `query`.
+async fn top_products(app: &TestApp, admin: &str, query: &str) -> Vec<(String, i64, i64, i64)> {
+    let response = app
+        .get(&format!("{}{}", TOP_PRODUCTS, query), Some(admin))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    response.body["data"]["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| {
+            (
+                p["name"].as_str().unwrap().to_string(),
+                p["units_sold"].as_i64().unwrap(),
+                p["revenue"].as_i64().unwrap(),
+                p["buyers"].as_i64().unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn top_products_sum_paid_revenue_in_the_window() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let other = app.register("other@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_000, 100).await;
+    let teapot = app.create_product(&admin, "Teapot", 4_000, 100).await;
+    let kettle = app.create_product(&admin, "Kettle", 9_000, 100).await;
+    app.create_product(&admin, "Tin Cup", 500, 100).await;
+
+    order(&app, &buyer, &[(mug, 2), (teapot, 1)], "paid").await;
+    order(&app, &other, &[(mug, 3)], "completed").await;
+    order(&app, &buyer, &[(mug, 1)], "paid").await;
+    // An unpaid order doesn't count, however large.
+    order(&app, &other, &[(kettle, 50)], "pending").await;
+    let old = order(&app, &buyer, &[(kettle, 1)], "paid").await;
+    app.backdate("orders", old, "2020-01-15T00:00:00Z".parse().unwrap())
+        .await;
+
+    let row = |name: &str, units, revenue, buyers| (name.to_string(), units, revenue, buyers);
+    assert_eq!(
+        top_products(&app, &admin, "").await,
+        [
+            row("Kettle", 1, 9_000, 1),
+            row("Ceramic Mug", 6, 6_000, 2),
+            row("Teapot", 1, 4_000, 1),
+        ]
+    );
+    assert_eq!(
+        top_products(&app, &admin, "?from=2021-01-01T00:00:00Z").await,
+        [row("Ceramic Mug", 6, 6_000, 2), row("Teapot", 1, 4_000, 1)]
+    );
+    assert_eq!(
+        top_products(&app, &admin, "?to=2021-01-01T00:00:00Z&include_zero=true").await,
+        [
+            row("Kettle", 1, 9_000, 1),
+            row("Ceramic Mug", 0, 0, 0),
+            row("Teapot", 0, 0, 0),
+            row("Tin Cup", 0, 0, 0),
+        ]
+    );
+    assert_eq!(
+        top_products(&app, &admin, "?limit=1").await,
+        [row("Kettle", 1, 9_000, 1)]
+    );
+
+    let response = app
+        .get(&format!("{}?include_zero=true", TOP_PRODUCTS), Some(&admin))
+        .await;
+    assert_eq!(
+        response.body["data"]["items"][3]["revenue_display"],
+        "$0.00"
+    );
+}
+
+#[tokio::test]
+async fn top_products_is_for_admins_with_an_ordered_window() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+
+    let response = app.get(TOP_PRODUCTS, Some(&buyer)).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    let backwards = format!(
+        "{}?from=2024-02-01T00:00:00Z&to=2024-01-01T00:00:00Z",
+        TOP_PRODUCTS
+    );
+    let response = app.get(&backwards, Some(&admin)).await;
+    assert_eq!(
+        response.status,
+        StatusCode::BAD_REQUEST,
+        "{}",
+        response.body
+    );
+}
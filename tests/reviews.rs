@@ -0,0 +1,106 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use common::TestApp;
+
+/// Checks out `quantity` of `product_id` for `token` and moves the order to `status`.
+async fn order(app: &TestApp, token: &str, product_id: Uuid, quantity: i32, status: &str) {
+    let add = json!({ "product_id": product_id, "quantity": quantity });
+    app.post("/api/cart", Some(token), add).await;
+    let response = app
+        .request(Method::POST, "/api/orders/checkout", Some(token), None)
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let order_id: Uuid = response.body["data"]["order"]["id"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    sqlx::query("UPDATE orders SET status = $2 WHERE id = $1")
+        .bind(order_id)
+        .bind(status)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+}
+
+async fn review(app: &TestApp, token: &str, product_id: Uuid, rating: i32) -> (StatusCode, Value) {
+    let body = json!({ "rating": rating, "comment": format!("{} stars", rating) });
+    let response = app
+        .post(
+            &format!("/api/products/{}/reviews", product_id),
+            Some(token),
+            body,
+        )
+        .await;
+    (response.status, response.body)
+}
+
+#[tokio::test]
+async fn only_buyers_review_once_and_ratings_are_averaged() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let jane = app.register("jane@example.com").await;
+    let john = app.register("john@example.com").await;
+    let browser = app.register("browser@example.com").await;
+    let waiting = app.register("waiting@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 10).await;
+    order(&app, &jane, mug, 1, "paid").await;
+    order(&app, &john, mug, 1, "completed").await;
+    order(&app, &waiting, mug, 1, "pending").await;
+
+    // Only a paid order earns a review.
+    assert_eq!(
+        review(&app, &browser, mug, 5).await.0,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        review(&app, &waiting, mug, 5).await.0,
+        StatusCode::FORBIDDEN
+    );
+    for rating in [0, 6] {
+        assert_eq!(
+            review(&app, &jane, mug, rating).await.0,
+            StatusCode::BAD_REQUEST
+        );
+    }
+    let (status, body) = review(&app, &jane, mug, 4).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (status, body) = review(&app, &jane, mug, 1).await;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+    let (status, body) = review(&app, &john, mug, 5).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let johns_review = body["data"]["id"].as_str().unwrap().to_string();
+    let unknown = review(&app, &jane, Uuid::new_v4(), 3).await;
+    assert_eq!(unknown.0, StatusCode::NOT_FOUND);
+
+    let product = format!("/api/products/{}", mug);
+    let response = app.get(&product, None).await;
+    assert_eq!(response.body["data"]["review_count"], 2);
+    assert_eq!(response.body["data"]["average_rating"], 4.5);
+    let response = app
+        .get(&format!("{}/reviews?per_page=1", product), None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["data"]["items"][0]["comment"], "5 stars");
+    assert_eq!(response.body["meta"]["total"], 2);
+    assert_eq!(response.body["meta"]["has_next"], true);
+
+    // The author or an admin may delete a review; nobody else.
+    let uri = format!("{}/reviews/{}", product, johns_review);
+    let response = app.request(Method::DELETE, &uri, Some(&jane), None).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    let response = app.request(Method::DELETE, &uri, Some(&admin), None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let response = app.request(Method::DELETE, &uri, Some(&john), None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app.get(&product, None).await;
+    assert_eq!(response.body["data"]["review_count"], 1);
+    assert_eq!(response.body["data"]["average_rating"], 4.0);
+}
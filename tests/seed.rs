@@ -0,0 +1,126 @@
+mod common;
+
+use axum::http::StatusCode;
+use axum_ecommerce_api::{
+    db::DbPool,
+    seed::{self, SEED_PASSWORD, Seeded, Volumes},
+};
+use common::TestApp;
+use serde_json::json;
+
+async fn count(pool: &DbPool, sql: &str) -> i64 {
+    let (n,): (i64,) = sqlx::query_as(sql).fetch_one(pool).await.unwrap();
+    n
+}
+
+#[tokio::test]
+async fn seeding_fills_the_requested_volumes_once() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let volumes = Volumes {
+        products: 40,
+        users: 6,
+        orders: 25,
+    };
+
+    let seeded = seed::seed(&app.pool, volumes).await.unwrap();
+    assert_eq!(seeded.products, 40);
+    assert_eq!(seeded.users, 6);
+    assert_eq!(seeded.orders, 25);
+    assert_eq!(count(&app.pool, "SELECT count(*) FROM products").await, 40);
+    assert_eq!(count(&app.pool, "SELECT count(*) FROM users").await, 6);
+    assert_eq!(count(&app.pool, "SELECT count(*) FROM orders").await, 25);
+    assert_eq!(
+        count(&app.pool, "SELECT count(*) FROM cart_items").await,
+        seeded.cart_items as i64
+    );
+
+    // Every order has items and its total adds up.
+    let mismatched = r#"
+        SELECT count(*) FROM orders o
+        WHERE o.total_amount <> (
+            SELECT coalesce(sum(i.quantity * i.price), -1) FROM order_items i WHERE i.order_id = o.id
+        )
+    "#;
+    assert_eq!(count(&app.pool, mismatched).await, 0);
+    let statuses: Vec<(String,)> = sqlx::query_as("SELECT DISTINCT status FROM orders")
+        .fetch_all(&app.pool)
+        .await
+        .unwrap();
+    assert!(
+        statuses
+            .iter()
+            .all(|(s,)| s.as_str() == "paid" || s.as_str() == "pending")
+    );
+
+    // A second run has nothing left to do.
+    let again = seed::seed(&app.pool, volumes).await.unwrap();
+    assert_eq!(again, Seeded::default());
+    assert_eq!(count(&app.pool, "SELECT count(*) FROM products").await, 40);
+    assert_eq!(count(&app.pool, "SELECT count(*) FROM orders").await, 25);
+
+    // Raising a volume tops it up.
+    let more = seed::seed(
+        &app.pool,
+        Volumes {
+            products: 50,
+            ..volumes
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(more.products, 10);
+
+    // Seeded products are served like any other.
+    let response = app.get("/api/v1/products?page=2&limit=20", None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["meta"]["page"], 2);
+}
+
+#[tokio::test]
+async fn clean_removes_only_seeded_data() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let own = app.create_product(&admin, "Own Product", 1_000, 5).await;
+    seed::seed(
+        &app.pool,
+        Volumes {
+            products: 10,
+            users: 3,
+            orders: 5,
+        },
+    )
+    .await
+    .unwrap();
+
+    seed::clean(&app.pool).await.unwrap();
+
+    assert_eq!(count(&app.pool, "SELECT count(*) FROM users").await, 1);
+    assert_eq!(count(&app.pool, "SELECT count(*) FROM orders").await, 0);
+    let (left,): (String,) = sqlx::query_as("SELECT id::text FROM products")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(left, own.to_string());
+}
+
+#[tokio::test]
+async fn bootstrap_creates_accounts_that_can_log_in() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    seed::bootstrap(&app.pool).await.unwrap();
+    seed::bootstrap(&app.pool).await.unwrap();
+
+    assert_eq!(count(&app.pool, "SELECT count(*) FROM users").await, 2);
+    assert_eq!(count(&app.pool, "SELECT count(*) FROM products").await, 4);
+    let login = json!({ "email": "admin@example.com", "password": SEED_PASSWORD });
+    let response = app.post("/api/v1/auth/login", None, login).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let token = response.body["data"]["token"].as_str().unwrap();
+    let response = app.get("/api/v1/admin/jobs", Some(token)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+}
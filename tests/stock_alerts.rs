@@ -0,0 +1,172 @@
+mod common;
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{Json, Router, extract::State, http::StatusCode, routing::post};
+use axum_ecommerce_api::{
+    jobs::{Job, LowStockAlerts},
+    stock_alerts,
+};
+use serde_json::{Value, json};
+use uuid::Uuid;
+
+use common::TestApp;
+
+const ALERTS: &str = "/api/v1/admin/stock-alerts";
+
+/// Puts `quantity` of `product_id` in the cart of `token` and checks out.
+async fn checkout(app: &TestApp, token: &str, product_id: Uuid, quantity: i32) {
+    let add = json!({ "product_id": product_id, "quantity": quantity });
+    app.post("/api/v1/cart", Some(token), add).await;
+    let response = app
+        .post("/api/v1/orders/checkout", Some(token), json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+}
+
+async fn alerts(app: &TestApp, admin: &str, query: &str) -> Vec<Value> {
+    let response = app.get(&format!("{}{}", ALERTS, query), Some(admin)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    response.body["data"]["items"].as_array().unwrap().clone()
+}
+
+#[tokio::test]
+async fn a_stock_drop_raises_one_alert_until_restocked() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 6).await;
+    app.create_product(&admin, "Teapot", 4_000, 50).await;
+
+    assert!(stock_alerts::scan(&app.pool, 5).await.unwrap().is_empty());
+    checkout(&app, &buyer, mug, 2).await;
+    let raised = stock_alerts::scan(&app.pool, 5).await.unwrap();
+    assert_eq!(raised.len(), 1);
+    assert_eq!((raised[0].product_id, raised[0].available), (mug, 4));
+    assert_eq!(raised[0].product_name, "Ceramic Mug");
+
+    // Still low, lower even: the open alert covers it.
+    assert!(stock_alerts::scan(&app.pool, 5).await.unwrap().is_empty());
+    checkout(&app, &buyer, mug, 1).await;
+    assert!(stock_alerts::scan(&app.pool, 5).await.unwrap().is_empty());
+    let listed = alerts(&app, &admin, "").await;
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0]["threshold"], 5);
+    assert!(listed[0]["acknowledged_at"].is_null());
+
+    let ack = format!("{}/{}/ack", ALERTS, raised[0].id);
+    let response = app.post(&ack, Some(&admin), json!({})).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert!(response.body["data"]["acknowledged_at"].is_string());
+    let response = app.post(&ack, Some(&admin), json!({})).await;
+    assert_eq!(response.status, StatusCode::CONFLICT, "{}", response.body);
+    assert!(stock_alerts::scan(&app.pool, 5).await.unwrap().is_empty());
+    assert_eq!(alerts(&app, &admin, "?acknowledged=false").await.len(), 0);
+
+    // Restocking resolves it; the next drop is a new alert.
+    let restock = json!({ "delta": 10, "reason": "restock" });
+    app.post(
+        &format!("/api/v1/admin/products/{}/inventory", mug),
+        Some(&admin),
+        restock,
+    )
+    .await;
+    assert!(stock_alerts::scan(&app.pool, 5).await.unwrap().is_empty());
+    assert_eq!(alerts(&app, &admin, "?resolved=true").await.len(), 1);
+    checkout(&app, &buyer, mug, 10).await;
+    assert_eq!(stock_alerts::scan(&app.pool, 5).await.unwrap().len(), 1);
+    let open = alerts(&app, &admin, "?resolved=false").await;
+    assert_eq!(open.len(), 1);
+    assert_eq!(open[0]["available"], 3);
+    assert_eq!(alerts(&app, &admin, "").await.len(), 2);
+}
+
+#[tokio::test]
+async fn alert_endpoints_are_for_admins() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+
+    let response = app.get(ALERTS, Some(&buyer)).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    let ack = format!("{}/{}/ack", ALERTS, Uuid::new_v4());
+    let response = app.post(&ack, Some(&buyer), json!({})).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    let response = app.post(&ack, Some(&admin), json!({})).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+/// Serves a webhook on a free local port, collecting the bodies it is sent.
+async fn webhook() -> (String, Arc<Mutex<Vec<Value>>>) {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let router = Router::new()
+        .route(
+            "/hook",
+            post(
+                |State(received): State<Arc<Mutex<Vec<Value>>>>, Json(body): Json<Value>| async move {
+                    received.lock().unwrap().push(body);
+                    StatusCode::NO_CONTENT
+                },
+            ),
+        )
+        .with_state(received.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    (url, received)
+}
+
+#[tokio::test]
+async fn the_job_posts_new_alerts_to_the_webhook() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 6).await;
+    let (url, received) = webhook().await;
+    let job = LowStockAlerts {
+        threshold: 5,
+        interval: Duration::from_secs(60),
+        webhook_url: Some(url),
+        client: reqwest::Client::new(),
+    };
+
+    job.run(&app.state).await.unwrap();
+    checkout(&app, &buyer, mug, 2).await;
+    job.run(&app.state).await.unwrap();
+    job.run(&app.state).await.unwrap();
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 1, "{:?}", received);
+    let sent = received[0]["alerts"].as_array().unwrap();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0]["product_id"], mug.to_string());
+    assert_eq!(sent[0]["available"], 4);
+}
+
+#[tokio::test]
+async fn a_failing_webhook_fails_the_run_but_keeps_the_alert() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    app.create_product(&admin, "Ceramic Mug", 1_250, 1).await;
+    let job = LowStockAlerts {
+        threshold: 5,
+        interval: Duration::from_secs(60),
+        // Nothing listens on port 1.
+        webhook_url: Some("http://127.0.0.1:1/hook".to_string()),
+        client: reqwest::Client::new(),
+    };
+
+    assert!(job.run(&app.state).await.is_err());
+    assert_eq!(alerts(&app, &admin, "").await.len(), 1);
+}
@@ -0,0 +1,96 @@
+//! The HTTPS listener, with a self-signed certificate made on the spot.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use axum::{Router, routing::get};
+use axum_ecommerce_api::{
+    routes::health::live,
+    server::{load_tls, serve, tls_server_config},
+};
+use rcgen::{CertifiedKey, generate_simple_self_signed};
+use uuid::Uuid;
+
+/// Writes a fresh self-signed certificate for `localhost` and its key; returns both paths.
+fn self_signed(dir: &Path) -> (CertifiedKey, PathBuf, PathBuf) {
+    let certified = generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let cert_path = dir.join(format!("{}.crt", Uuid::new_v4()));
+    let key_path = dir.join(format!("{}.key", Uuid::new_v4()));
+    fs::write(&cert_path, certified.cert.pem()).unwrap();
+    fs::write(&key_path, certified.key_pair.serialize_pem()).unwrap();
+    (certified, cert_path, key_path)
+}
+
+#[tokio::test]
+async fn serves_https_with_the_configured_certificate() {
+    let dir = std::env::temp_dir();
+    let (certified, cert_path, key_path) = self_signed(&dir);
+    let tls = load_tls(&cert_path, &key_path).unwrap();
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let app = Router::new().route("/live", get(live));
+    let server = tokio::spawn(serve(listener, app, Some(tls), std::future::pending()));
+
+    let client = reqwest::Client::builder()
+        .add_root_certificate(
+            reqwest::Certificate::from_pem(certified.cert.pem().as_bytes()).unwrap(),
+        )
+        .build()
+        .unwrap();
+    let response = client
+        .get(format!("https://localhost:{}/live", port))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["status"], "ok");
+
+    // A client that does not trust the certificate gets no further than the handshake.
+    let untrusting = reqwest::Client::new()
+        .get(format!("https://localhost:{}/live", port))
+        .send()
+        .await;
+    assert!(untrusting.is_err());
+
+    server.abort();
+    fs::remove_file(cert_path).ok();
+    fs::remove_file(key_path).ok();
+}
+
+#[test]
+fn unusable_key_material_is_reported_by_file() {
+    let dir = std::env::temp_dir();
+    let (_, cert_path, key_path) = self_signed(&dir);
+    let (_, other_cert, other_key) = self_signed(&dir);
+
+    let error = tls_server_config(&cert_path, &other_key).unwrap_err();
+    assert!(
+        format!("{:#}", error).contains("does not fit certificate"),
+        "{:#}",
+        error
+    );
+
+    let missing = dir.join("missing.key");
+    let error = tls_server_config(&cert_path, &missing).unwrap_err();
+    assert!(
+        format!("{:#}", error).contains("cannot read TLS private key"),
+        "{:#}",
+        error
+    );
+
+    // A key where the certificate should be.
+    let error = tls_server_config(&key_path, &key_path).unwrap_err();
+    assert!(
+        format!("{:#}", error).contains("no certificates"),
+        "{:#}",
+        error
+    );
+
+    for path in [cert_path, key_path, other_cert, other_key] {
+        fs::remove_file(path).ok();
+    }
+}
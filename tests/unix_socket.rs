@@ -0,0 +1,85 @@
+//! Serving over a unix socket, as behind a local nginx.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::{fs, os::unix::fs::PermissionsExt, time::Duration};
+
+use axum::{
+    body::{Body, to_bytes},
+    http::{Request, StatusCode},
+};
+use axum_ecommerce_api::{app, server::serve_unix};
+use hyper_util::rt::TokioIo;
+use serde_json::Value;
+use tokio::{net::UnixStream, sync::oneshot};
+
+use common::TestApp;
+
+#[tokio::test]
+async fn health_answers_over_the_socket_which_is_removed_on_shutdown() {
+    let Some(test_app) = TestApp::spawn().await else {
+        return;
+    };
+    let path = std::env::temp_dir().join(format!("{}.sock", test_app.database));
+    // Left behind by a crashed run; the server replaces it.
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+
+    let (stop, stopped) = oneshot::channel::<()>();
+    let router = app(&test_app.config, test_app.state.clone());
+    let server = tokio::spawn({
+        let path = path.clone();
+        async move {
+            serve_unix(&path, 0o600, router, async {
+                stopped.await.ok();
+            })
+            .await
+        }
+    });
+    let stream = loop {
+        if let Ok(stream) = UnixStream::connect(&path).await {
+            break stream;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    };
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+        .await
+        .unwrap();
+    let connection = tokio::spawn(connection);
+    let request = Request::builder()
+        .uri("/health")
+        .header("host", "localhost")
+        .body(Body::empty())
+        .unwrap();
+    let response = sender.send_request(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = to_bytes(Body::new(response.into_body()), usize::MAX)
+        .await
+        .unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["data"]["database"]["status"], "up");
+    drop(sender);
+    connection.await.unwrap().unwrap();
+
+    stop.send(()).unwrap();
+    server.await.unwrap().unwrap();
+    assert!(!path.exists(), "socket was not removed on shutdown");
+}
+
+#[tokio::test]
+async fn a_regular_file_at_the_socket_path_is_left_alone() {
+    let path = std::env::temp_dir().join(format!("{}.sock", uuid::Uuid::new_v4()));
+    fs::write(&path, "not a socket").unwrap();
+
+    let router = axum::Router::new();
+    let error = serve_unix(&path, 0o600, router, async {})
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("is not a socket"), "{:#}", error);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "not a socket");
+    fs::remove_file(path).ok();
+}
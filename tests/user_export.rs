@@ -0,0 +1,251 @@
+//! `GET /auth/me/export` hands a user everything stored about them, once an hour.
+
+mod common;
+
+use axum::{
+    body::{Body, to_bytes},
+    http::{Method, Request, StatusCode, header},
+};
+use serde_json::json;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use common::TestApp;
+
+const EXPORT: &str = "/api/v1/auth/me/export";
+
+#[tokio::test]
+async fn the_export_holds_every_section_of_the_users_data() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let admin = app.register_admin("admin@example.com").await;
+    let buyer = app.register("buyer@example.com").await;
+    let other = app.register("other@example.com").await;
+    let mug = app.create_product(&admin, "Ceramic Mug", 1_250, 50).await;
+    let teapot = app.create_product(&admin, "Teapot", 4_000, 50).await;
+    let kettle = app.create_product(&admin, "Kettle", 6_000, 50).await;
+
+    let body = json!({ "full_name": "Budi Santoso" });
+    let response = app
+        .request(Method::PUT, "/api/v1/auth/me", Some(&buyer), Some(body))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let add = json!({ "product_id": mug, "quantity": 2 });
+    app.post("/api/v1/cart", Some(&buyer), add).await;
+    let response = app
+        .post("/api/v1/orders/checkout", Some(&buyer), json!({}))
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let checked_out = response.body["data"]["order"]["id"].clone();
+    let add = json!({ "product_id": teapot, "quantity": 1 });
+    app.post("/api/v1/cart", Some(&buyer), add).await;
+    let add = json!({ "product_id": kettle, "quantity": 3 });
+    let line = app.post("/api/v1/cart", Some(&buyer), add).await;
+    let save = format!(
+        "/api/v1/cart/{}/save",
+        line.body["data"]["id"].as_str().unwrap()
+    );
+    let response = app.request(Method::POST, &save, Some(&buyer), None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let favorite = json!({ "product_id": teapot });
+    let response = app.post("/api/v1/favorites", Some(&buyer), favorite).await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    // Someone else's data stays out of it.
+    let add = json!({ "product_id": mug, "quantity": 1 });
+    app.post("/api/v1/cart", Some(&other), add).await;
+    app.post("/api/v1/orders/checkout", Some(&other), json!({}))
+        .await;
+
+    // More orders than fit on one page, so the export has to page through them.
+    let buyer_id: Uuid = sqlx::query_scalar("SELECT id FROM users WHERE email = $1")
+        .bind("buyer@example.com")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO orders (id, user_id, total_amount, status, created_at)
+        SELECT gen_random_uuid(), $1, n * 100, 'completed', now() - make_interval(days => n)
+        FROM generate_series(1, 250) AS n
+        "#,
+    )
+    .bind(buyer_id)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+    app.state.audit.flush().await;
+
+    let response = app.get(EXPORT, Some(&buyer)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.headers[header::CONTENT_TYPE], "application/json");
+    let disposition = response.headers[header::CONTENT_DISPOSITION]
+        .to_str()
+        .unwrap();
+    assert!(
+        disposition.starts_with("attachment; filename=\"user-data-"),
+        "{}",
+        disposition
+    );
+    let export = &response.body;
+    assert!(export["exported_at"].is_string(), "{}", export);
+
+    assert_eq!(export["profile"]["id"], json!(buyer_id));
+    assert_eq!(export["profile"]["email"], "buyer@example.com");
+    assert_eq!(export["profile"]["full_name"], "Budi Santoso");
+    assert!(export["profile"].get("password_hash").is_none());
+
+    let cart = export["cart"]["items"].as_array().unwrap();
+    assert_eq!(cart.len(), 1);
+    assert_eq!(cart[0]["product_name"], "Teapot");
+    let saved = export["saved_for_later"]["items"].as_array().unwrap();
+    assert_eq!(saved.len(), 1);
+    assert_eq!(saved[0]["product_name"], "Kettle");
+    assert_eq!(saved[0]["quantity"], 3);
+
+    let favorites = export["favorites"].as_array().unwrap();
+    assert_eq!(favorites.len(), 1);
+    assert_eq!(favorites[0]["product_id"], json!(teapot));
+    assert_eq!(favorites[0]["product_name"], "Teapot");
+
+    let orders = export["orders"].as_array().unwrap();
+    assert_eq!(orders.len(), 251);
+    assert!(
+        orders
+            .iter()
+            .all(|o| o["order"]["user_id"] == json!(buyer_id))
+    );
+    let mut ids: Vec<&str> = orders
+        .iter()
+        .map(|o| o["order"]["id"].as_str().unwrap())
+        .collect();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), 251, "an order was exported twice");
+    // Oldest first, so the checkout made above comes last.
+    let last = &orders[250];
+    assert_eq!(last["order"]["id"], checked_out);
+    assert_eq!(last["items"][0]["product_id"], json!(mug));
+    assert_eq!(last["items"][0]["quantity"], 2);
+
+    let audit_log = export["audit_log"].as_array().unwrap();
+    let actions: Vec<&str> = audit_log
+        .iter()
+        .map(|e| e["action"].as_str().unwrap())
+        .collect();
+    for action in ["user.register", "user.login", "order.create"] {
+        assert!(
+            actions.contains(&action),
+            "{} missing: {:?}",
+            action,
+            actions
+        );
+    }
+    assert!(audit_log.iter().all(|e| e["actor_id"] == json!(buyer_id)));
+
+    app.state.audit.flush().await;
+    let exports: i64 = sqlx::query_scalar(
+        "SELECT count(*) FROM audit_log WHERE action = 'user.data_export' AND actor_id = $1",
+    )
+    .bind(buyer_id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(exports, 1);
+}
+
+#[tokio::test]
+async fn a_user_may_export_once_an_hour() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let buyer = app.register("buyer@example.com").await;
+    let other = app.register("other@example.com").await;
+
+    let response = app.get(EXPORT, Some(&buyer)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["orders"], json!([]));
+    assert_eq!(response.body["favorites"], json!([]));
+
+    let response = app.get(EXPORT, Some(&buyer)).await;
+    assert_eq!(
+        response.status,
+        StatusCode::TOO_MANY_REQUESTS,
+        "{}",
+        response.body
+    );
+    assert_eq!(response.body["data"]["error_code"], "RATE_LIMITED");
+    let retry_after: u64 = response.headers[header::RETRY_AFTER]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((3_500..=3_600).contains(&retry_after), "{}", retry_after);
+
+    // The limit is per user.
+    let response = app.get(EXPORT, Some(&other)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    // An hour later it is allowed again.
+    sqlx::query(
+        "UPDATE users SET last_data_export_at = now() - interval '61 minutes' WHERE email = $1",
+    )
+    .bind("buyer@example.com")
+    .execute(&app.pool)
+    .await
+    .unwrap();
+    let response = app.get(EXPORT, Some(&buyer)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app.get(EXPORT, None).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+async fn rename_table(app: &TestApp, from: &str, to: &str) {
+    sqlx::query(&format!("ALTER TABLE {} RENAME TO {}", from, to))
+        .execute(&app.pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn a_failed_export_does_not_use_up_the_hour() {
+    let Some(app) = TestApp::spawn().await else {
+        return;
+    };
+    let buyer = app.register("buyer@example.com").await;
+
+    // Failing before anything is sent.
+    rename_table(&app, "favorites", "favorites_away").await;
+    let response = app.get(EXPORT, Some(&buyer)).await;
+    assert_eq!(
+        response.status,
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "{}",
+        response.body
+    );
+    rename_table(&app, "favorites_away", "favorites").await;
+
+    // Failing halfway through the download.
+    rename_table(&app, "audit_log", "audit_log_away").await;
+    let request = Request::get(EXPORT)
+        .header(header::AUTHORIZATION, format!("Bearer {}", buyer))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.router.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(to_bytes(response.into_body(), usize::MAX).await.is_err());
+    rename_table(&app, "audit_log_away", "audit_log").await;
+
+    let last: Option<chrono::DateTime<chrono::Utc>> =
+        sqlx::query_scalar("SELECT last_data_export_at FROM users WHERE email = $1")
+            .bind("buyer@example.com")
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(last, None);
+    let response = app.get(EXPORT, Some(&buyer)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let response = app.get(EXPORT, Some(&buyer)).await;
+    assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+}